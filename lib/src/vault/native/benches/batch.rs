@@ -0,0 +1,54 @@
+//! Batch versus per-item sealing latency.
+//!
+//! Run with `cargo bench --bench batch`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use vault_core::{vault_free, vault_seal, vault_seal_batch, VaultBuffer, VaultSlice};
+
+const KEY: [u8; 32] = [0x42; 32];
+
+fn entries(count: usize) -> Vec<Vec<u8>> {
+    (0..count).map(|i| format!("{{\"id\":{i},\"note\":\"entry\"}}").into_bytes()).collect()
+}
+
+fn bench_seal_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("seal_100_entries");
+    let entries = entries(100);
+    let items: Vec<VaultSlice> = entries
+        .iter()
+        .map(|e| VaultSlice { ptr: e.as_ptr(), len: e.len() as u32 })
+        .collect();
+
+    group.bench_function(BenchmarkId::new("per_item", entries.len()), |b| {
+        b.iter(|| unsafe {
+            for entry in &entries {
+                let sealed = vault_seal(KEY.as_ptr(), entry.as_ptr(), entry.len() as u32);
+                black_box(&sealed);
+                vault_free(sealed.data, sealed.len);
+            }
+        })
+    });
+
+    group.bench_function(BenchmarkId::new("batch", entries.len()), |b| {
+        let mut out: Vec<VaultBuffer> = Vec::with_capacity(items.len());
+        b.iter(|| unsafe {
+            let rc = vault_seal_batch(
+                KEY.as_ptr(),
+                KEY.len() as u32,
+                items.as_ptr(),
+                items.len() as u32,
+                out.as_mut_ptr(),
+            );
+            assert_eq!(rc, 0);
+            out.set_len(items.len());
+            for sealed in out.drain(..) {
+                vault_free(sealed.data, sealed.len);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_seal_batch);
+criterion_main!(benches);
@@ -0,0 +1,182 @@
+//! Core primitive costs: Argon2 parameter matrix and working-memory wipe,
+//! seal/unseal throughput, per-call versus reused cipher contexts, and
+//! CSPRNG throughput.
+//!
+//! Run with `cargo bench --bench primitives`.
+//!
+//! ## Reading the Argon2 numbers for mobile calibration
+//!
+//! Desktop timings are a lower bound. Phones are typically 3-5x slower per
+//! lane, run fewer lanes at full speed, and throttle under sustained load,
+//! so benchmark there (or scale generously) before choosing parameters.
+//! Time grows roughly linearly in both `m_cost` and `t_cost`; `p_cost`
+//! only helps up to the number of fast cores. Aim for 200-500 ms on the
+//! slowest supported device, and prefer raising `m_cost` over `t_cost`
+//! while memory allows, since memory is what makes GPU attacks expensive.
+
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use vault_core::{
+    vault_cipher_free, vault_cipher_new, vault_cipher_seal, vault_derive_key_ex, vault_free, vault_random, vault_seal,
+    vault_unseal,
+};
+
+const KEY: [u8; 32] = [0x42; 32];
+const SALT: [u8; 16] = [0x07; 16];
+const PASSPHRASE: &[u8] = b"correct horse battery staple";
+
+/// `vault_derive_key_ex` flag skipping the working-memory wipe
+const DERIVE_SKIP_MEMORY_WIPE: u32 = 1;
+
+/// (m_cost KiB, t_cost, p_cost)
+const ARGON2_MATRIX: [(u32, u32, u32); 6] = [
+    (19456, 2, 1),
+    (32768, 3, 1),
+    (65536, 3, 1),
+    (65536, 3, 4),
+    (131072, 3, 4),
+    (65536, 6, 4),
+];
+
+fn bench_derive_key(c: &mut Criterion) {
+    let mut group = c.benchmark_group("derive_key");
+    group.sample_size(10).measurement_time(Duration::from_secs(10));
+
+    for (m_cost, t_cost, p_cost) in ARGON2_MATRIX {
+        let id = BenchmarkId::from_parameter(format!("m{m_cost}_t{t_cost}_p{p_cost}"));
+        group.bench_function(id, |b| {
+            b.iter(|| unsafe {
+                let key = vault_derive_key_ex(
+                    PASSPHRASE.as_ptr(),
+                    PASSPHRASE.len() as u32,
+                    SALT.as_ptr(),
+                    std::ptr::null(),
+                    0,
+                    m_cost,
+                    t_cost,
+                    p_cost,
+                    0,
+                    0,
+                );
+                assert_eq!(key.error, 0);
+                vault_free(key.data, key.len);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Cost of wiping the working memory, at the default 64 MiB.
+fn bench_derive_memory_wipe(c: &mut Criterion) {
+    let mut group = c.benchmark_group("derive_memory_wipe");
+    group.sample_size(10).measurement_time(Duration::from_secs(10));
+
+    for (name, flags) in [("wiped", 0), ("unwiped", DERIVE_SKIP_MEMORY_WIPE)] {
+        group.bench_function(name, |b| {
+            b.iter(|| unsafe {
+                let key = vault_derive_key_ex(
+                    PASSPHRASE.as_ptr(),
+                    PASSPHRASE.len() as u32,
+                    SALT.as_ptr(),
+                    std::ptr::null(),
+                    0,
+                    65536,
+                    3,
+                    4,
+                    0,
+                    flags,
+                );
+                assert_eq!(key.error, 0);
+                vault_free(key.data, key.len);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_seal_unseal(c: &mut Criterion) {
+    let mut group = c.benchmark_group("seal_unseal");
+
+    for size in [64usize, 1024, 1024 * 1024] {
+        let plaintext = vec![0xA5u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("seal", size), &plaintext, |b, plaintext| {
+            b.iter(|| unsafe {
+                let sealed = vault_seal(KEY.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+                assert_eq!(sealed.error, 0);
+                black_box(&sealed);
+                vault_free(sealed.data, sealed.len);
+            })
+        });
+
+        // Seal once outside the timed loop, free once after it
+        let sealed = unsafe { vault_seal(KEY.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32) };
+        assert_eq!(sealed.error, 0);
+        group.bench_with_input(BenchmarkId::new("unseal", size), &sealed, |b, sealed| {
+            b.iter(|| unsafe {
+                let unsealed = vault_unseal(KEY.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(unsealed.error, 0);
+                black_box(&unsealed);
+                vault_free(unsealed.data, unsealed.len);
+            })
+        });
+        unsafe { vault_free(sealed.data, sealed.len) };
+    }
+
+    group.finish();
+}
+
+fn bench_cipher_context(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cipher_context");
+
+    // Small items, where per-call key setup is a visible share of the cost
+    let plaintext = [0xA5u8; 64];
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("per_call", |b| {
+        b.iter(|| unsafe {
+            let sealed = vault_seal(KEY.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            black_box(&sealed);
+            vault_free(sealed.data, sealed.len);
+        })
+    });
+
+    let ctx = unsafe { vault_cipher_new(KEY.as_ptr(), KEY.len() as u32) };
+    assert!(!ctx.is_null());
+    group.bench_function("reused", |b| {
+        b.iter(|| unsafe {
+            let sealed = vault_cipher_seal(ctx, plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            black_box(&sealed);
+            vault_free(sealed.data, sealed.len);
+        })
+    });
+    unsafe { vault_cipher_free(ctx) };
+
+    group.finish();
+}
+
+fn bench_random(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random");
+
+    for size in [32usize, 4096, 1024 * 1024] {
+        let mut buf = vec![0u8; size];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(BenchmarkId::from_parameter(size), |b| {
+            b.iter(|| unsafe {
+                assert_eq!(vault_random(buf.as_mut_ptr(), buf.len() as u32), 0);
+                black_box(&buf);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_derive_key, bench_derive_memory_wipe, bench_seal_unseal, bench_cipher_context, bench_random);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+//! Record build settings for `vault_build_profile`.
+
+fn main() {
+    for var in ["OPT_LEVEL", "TARGET"] {
+        let value = std::env::var(var).unwrap_or_default();
+        println!("cargo:rustc-env=VAULT_BUILD_{var}={value}");
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,20 @@
+//! Fuzz `unseal_safe` with arbitrary keys and blobs.
+//!
+//! Input layout: `key_len (1) || key || sealed`. A `key_len` longer than the
+//! remaining input takes everything, so every input is well-formed for the
+//! harness and any crash is the library's.
+//!
+//! Run with `cargo +nightly fuzz run unseal` from `native/`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vault_core::unseal_safe;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&key_len, rest)) = data.split_first() else {
+        return;
+    };
+    let (key, sealed) = rest.split_at((key_len as usize).min(rest.len()));
+    let _ = unseal_safe(key, sealed);
+});
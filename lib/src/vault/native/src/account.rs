@@ -0,0 +1,112 @@
+//! Account Identifiers
+//!
+//! A short, checksummed identifier for an account, derived from its public
+//! key, for display and for typing or reading back. Every build (native,
+//! web) computes it here so the identifier matches byte for byte.
+//!
+//! ## Algorithm
+//!
+//! 1. `id = BLAKE3-derive_key("vault_core 2025-01 account id", pubkey)[..16]`
+//! 2. `checksum = BLAKE3-derive_key("vault_core 2025-01 account id checksum", id)[..4]`
+//! 3. Render `id || checksum` (20 bytes) as unpadded Crockford base32
+//!    (see the `base32` module): exactly 32 uppercase ASCII characters.
+//!
+//! The checksum catches transcription errors when an identifier is typed
+//! back in; it adds no security. The identifier is public and must never be
+//! used as key material.
+
+use std::slice;
+
+use crate::base32::base32_encode;
+
+use super::*;
+
+/// Identifier length in bytes, before the checksum
+const ACCOUNT_ID_SIZE: usize = 16;
+
+/// Checksum length in bytes
+const ACCOUNT_CHECKSUM_SIZE: usize = 4;
+
+const ACCOUNT_ID_CONTEXT: &str = "vault_core 2025-01 account id";
+
+const ACCOUNT_CHECKSUM_CONTEXT: &str = "vault_core 2025-01 account id checksum";
+
+/// `id || checksum` for a public key.
+pub(crate) fn account_id_bytes(pubkey: &[u8]) -> [u8; ACCOUNT_ID_SIZE + ACCOUNT_CHECKSUM_SIZE] {
+    let mut out = [0u8; ACCOUNT_ID_SIZE + ACCOUNT_CHECKSUM_SIZE];
+    out[..ACCOUNT_ID_SIZE].copy_from_slice(&blake3::derive_key(ACCOUNT_ID_CONTEXT, pubkey)[..ACCOUNT_ID_SIZE]);
+    let checksum = blake3::derive_key(ACCOUNT_CHECKSUM_CONTEXT, &out[..ACCOUNT_ID_SIZE]);
+    out[ACCOUNT_ID_SIZE..].copy_from_slice(&checksum[..ACCOUNT_CHECKSUM_SIZE]);
+    out
+}
+
+/// Compute the display identifier for an account's public key.
+///
+/// See the module documentation for the exact algorithm.
+///
+/// # Safety
+///
+/// - `pubkey` must be valid for `pubkey_len` bytes (non-empty)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-character ASCII identifier, or error code
+#[no_mangle]
+pub unsafe extern "C" fn vault_account_id(pubkey: *const u8, pubkey_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if pubkey.is_null() || pubkey_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let pubkey_slice = slice::from_raw_parts(pubkey, pubkey_len as usize);
+
+        match base32_encode(&account_id_bytes(pubkey_slice)) {
+            Ok(id) => VaultBuffer::success(id),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn account_id(pubkey: &[u8]) -> String {
+        let result = vault_account_id(pubkey.as_ptr(), pubkey.len() as u32);
+        assert_eq!(result.error, 0);
+        let id = String::from_utf8(slice::from_raw_parts(result.data, result.len as usize).to_vec()).unwrap();
+        vault_free(result.data, result.len);
+        id
+    }
+
+    #[test]
+    fn test_account_id_known_vector() {
+        let pubkey: Vec<u8> = (0..32).collect();
+
+        unsafe {
+            let id = account_id(&pubkey);
+            assert_eq!(id.len(), 32);
+            assert_eq!(id, "EB457NBBZ46EH804ZWQKMPS9BGPCYJVK");
+            assert_eq!(vault_account_id(ptr::null(), 32).error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_account_id_bit_flip() {
+        let mut pubkey = [0x42u8; 32];
+        let before = account_id_bytes(&pubkey);
+        pubkey[31] ^= 1;
+        let after = account_id_bytes(&pubkey);
+
+        assert_ne!(before[..ACCOUNT_ID_SIZE], after[..ACCOUNT_ID_SIZE]);
+        assert_ne!(before[ACCOUNT_ID_SIZE..], after[ACCOUNT_ID_SIZE..]);
+        unsafe {
+            assert_ne!(account_id(&[0x42u8; 32]), account_id(&pubkey));
+        }
+    }
+}
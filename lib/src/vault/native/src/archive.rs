@@ -0,0 +1,284 @@
+//! Sealed-Entry Archives
+//!
+//! Many sealed entries framed as one blob, so backup files do not need
+//! hand-written length prefixes on every platform. The archive only frames
+//! the entries; each one is still a sealed blob to pass to `vault_unseal`
+//! (or whichever function produced it), and nothing here is encrypted or
+//! authenticated on its own.
+//!
+//! ## Format (version 1)
+//!
+//! `magic (4, "VLTA") || version (1) || count (4) || index || entries`
+//!
+//! The index holds `offset (4) || length (4)` per entry, offsets counted
+//! from the start of the archive. Entries follow the index back to back in
+//! index order, and the archive ends exactly after the last one. All
+//! integers are little-endian.
+
+use std::slice;
+
+use super::*;
+
+/// Archive magic
+const ARCHIVE_MAGIC: [u8; 4] = *b"VLTA";
+
+/// Current archive version
+const ARCHIVE_VERSION: u8 = 1;
+
+/// Size of everything before the index
+const ARCHIVE_HEADER_SIZE: usize = 4 + 1 + 4;
+
+/// Size of one index entry: offset (4) || length (4)
+const ARCHIVE_INDEX_ENTRY_SIZE: usize = 8;
+
+/// Read a little-endian `u32` at `offset`.
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0u8; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
+
+/// Encode an archive of non-empty entries.
+fn pack_archive(entries: &[&[u8]]) -> VaultResult<Vec<u8>> {
+    let index_end = entries
+        .len()
+        .checked_mul(ARCHIVE_INDEX_ENTRY_SIZE)
+        .and_then(|n| n.checked_add(ARCHIVE_HEADER_SIZE))
+        .ok_or(ERR_INVALID_INPUT)?;
+    let total = entries
+        .iter()
+        .try_fold(index_end, |total, entry| total.checked_add(entry.len()))
+        .filter(|&total| total <= u32::MAX as usize)
+        .ok_or(ERR_INVALID_INPUT)?;
+
+    let mut output = output_buffer(total)?;
+    output.extend_from_slice(&ARCHIVE_MAGIC);
+    output.push(ARCHIVE_VERSION);
+    output.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    let mut offset = index_end;
+    for entry in entries {
+        output.extend_from_slice(&(offset as u32).to_le_bytes());
+        output.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+        offset += entry.len();
+    }
+    for entry in entries {
+        output.extend_from_slice(entry);
+    }
+    Ok(output)
+}
+
+/// Validate an archive's header and index and borrow entry `index`.
+///
+/// The whole index is checked on every read, so a damaged archive is
+/// reported as such whichever entry is asked for.
+fn archive_entry(archive: &[u8], index: u32) -> VaultResult<&[u8]> {
+    if archive.len() < ARCHIVE_HEADER_SIZE || archive[..4] != ARCHIVE_MAGIC {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    if archive[4] != ARCHIVE_VERSION {
+        return Err(ERR_UNSUPPORTED_VERSION);
+    }
+    let count = read_u32(archive, 5) as usize;
+    let index_end = count
+        .checked_mul(ARCHIVE_INDEX_ENTRY_SIZE)
+        .and_then(|n| n.checked_add(ARCHIVE_HEADER_SIZE))
+        .filter(|&end| end <= archive.len())
+        .ok_or(ERR_CORRUPT_DATA)?;
+
+    let mut expected_offset = index_end;
+    let mut found = None;
+    for i in 0..count {
+        let at = ARCHIVE_HEADER_SIZE + i * ARCHIVE_INDEX_ENTRY_SIZE;
+        let offset = read_u32(archive, at) as usize;
+        let len = read_u32(archive, at + 4) as usize;
+        if offset != expected_offset || len == 0 || archive.len() - offset < len {
+            return Err(ERR_CORRUPT_DATA);
+        }
+        if i == index as usize {
+            found = Some(&archive[offset..offset + len]);
+        }
+        expected_offset = offset + len;
+    }
+    if expected_offset != archive.len() {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    found.ok_or(ERR_INVALID_INPUT)
+}
+
+/// Pack sealed entries into one archive.
+///
+/// # Format
+///
+/// Output: see the module docs.
+///
+/// # Safety
+///
+/// - `entries` must point to `count` valid `VaultSlice`s, each non-empty
+///   (may be null when `count` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the archive, or `ERR_INVALID_INPUT` for a null or
+/// empty entry, or an archive that would exceed 4 GiB
+#[no_mangle]
+pub unsafe extern "C" fn vault_archive_build(entries: *const VaultSlice, count: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if entries.is_null() && count != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let entries: &[VaultSlice] = if count == 0 { &[] } else { slice::from_raw_parts(entries, count as usize) };
+        if entries.iter().any(|entry| entry.ptr.is_null() || entry.len == 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let entry_slices: Vec<&[u8]> = entries.iter().map(|entry| slice::from_raw_parts(entry.ptr, entry.len as usize)).collect();
+        match pack_archive(&entry_slices) {
+            Ok(archive) => VaultBuffer::success(archive),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Locate one sealed entry in an archive built by `vault_archive_build`.
+///
+/// The entry is not copied: `out_ptr` is set to point inside `archive` and
+/// is only valid while `archive` is.
+///
+/// # Safety
+///
+/// - `archive` must be valid for `archive_len` bytes
+/// - `out_ptr` and `out_len` must be writable
+///
+/// # Returns
+///
+/// 0 on success, `ERR_INVALID_INPUT` for an `index` past the last entry,
+/// `ERR_UNSUPPORTED_VERSION` for an unknown archive version, or
+/// `ERR_CORRUPT_DATA` for a bad magic or an inconsistent index
+#[no_mangle]
+pub unsafe extern "C" fn vault_archive_read(
+    archive: *const u8,
+    archive_len: u32,
+    index: u32,
+    out_ptr: *mut *const u8,
+    out_len: *mut u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if archive.is_null() || out_ptr.is_null() || out_len.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let archive_slice = slice::from_raw_parts(archive, archive_len as usize);
+
+        match archive_entry(archive_slice, index) {
+            Ok(entry) => {
+                *out_ptr = entry.as_ptr();
+                *out_len = entry.len() as u32;
+                0
+            }
+            Err(code) => code,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn build(entries: &[&[u8]]) -> Vec<u8> {
+        let slices: Vec<VaultSlice> = entries.iter().map(|e| VaultSlice { ptr: e.as_ptr(), len: e.len() as u32 }).collect();
+        let result = vault_archive_build(slices.as_ptr(), slices.len() as u32);
+        assert_eq!(result.error, 0);
+        let archive = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        archive
+    }
+
+    unsafe fn read(archive: &[u8], index: u32) -> Result<Vec<u8>, i32> {
+        let mut entry_ptr = ptr::null();
+        let mut entry_len = 0u32;
+        let rc = vault_archive_read(archive.as_ptr(), archive.len() as u32, index, &mut entry_ptr, &mut entry_len);
+        if rc != 0 {
+            return Err(rc);
+        }
+        Ok(slice::from_raw_parts(entry_ptr, entry_len as usize).to_vec())
+    }
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintexts: [&[u8]; 3] = [b"first entry", b"second", b"the third and longest entry"];
+
+        unsafe {
+            let sealed: Vec<Vec<u8>> = plaintexts
+                .iter()
+                .map(|p| {
+                    let s = vault_seal(key.as_ptr(), p.as_ptr(), p.len() as u32);
+                    assert_eq!(s.error, 0);
+                    let blob = slice::from_raw_parts(s.data, s.len as usize).to_vec();
+                    vault_free(s.data, s.len);
+                    blob
+                })
+                .collect();
+            let entries: Vec<&[u8]> = sealed.iter().map(Vec::as_slice).collect();
+            let archive = build(&entries);
+            assert_eq!(&archive[..4], b"VLTA");
+
+            for (i, plaintext) in plaintexts.iter().enumerate() {
+                let entry = read(&archive, i as u32).unwrap();
+                assert_eq!(entry, sealed[i]);
+                let opened = vault_unseal(key.as_ptr(), entry.as_ptr(), entry.len() as u32);
+                assert_eq!(opened.error, 0);
+                assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), *plaintext);
+                vault_free(opened.data, opened.len);
+            }
+            assert_eq!(read(&archive, 3), Err(ERR_INVALID_INPUT));
+
+            let empty = build(&[]);
+            assert_eq!(empty.len(), ARCHIVE_HEADER_SIZE);
+            assert_eq!(read(&empty, 0), Err(ERR_INVALID_INPUT));
+
+            let bad = [VaultSlice { ptr: key.as_ptr(), len: 0 }];
+            assert_eq!(vault_archive_build(bad.as_ptr(), 1).error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_archive_corrupt_header_rejected() {
+        unsafe {
+            let archive = build(&[b"entry one", b"entry two", b"entry three"]);
+
+            let mut bad_magic = archive.clone();
+            bad_magic[0] ^= 0xFF;
+            assert_eq!(read(&bad_magic, 0), Err(ERR_CORRUPT_DATA));
+
+            let mut bad_version = archive.clone();
+            bad_version[4] = 9;
+            assert_eq!(read(&bad_version, 0), Err(ERR_UNSUPPORTED_VERSION));
+
+            // A count the index cannot fit, and one entry too many or too few
+            for count in [u32::MAX, 4, 2] {
+                let mut bad_count = archive.clone();
+                bad_count[5..9].copy_from_slice(&count.to_le_bytes());
+                assert_eq!(read(&bad_count, 0), Err(ERR_CORRUPT_DATA), "count {count}");
+            }
+
+            // Off-by-one offset and length
+            let mut bad_offset = archive.clone();
+            bad_offset[ARCHIVE_HEADER_SIZE + 8] += 1;
+            assert_eq!(read(&bad_offset, 0), Err(ERR_CORRUPT_DATA));
+            let mut bad_len = archive.clone();
+            bad_len[ARCHIVE_HEADER_SIZE + 4] += 1;
+            assert_eq!(read(&bad_len, 2), Err(ERR_CORRUPT_DATA));
+
+            assert_eq!(read(&archive[..archive.len() - 1], 0), Err(ERR_CORRUPT_DATA));
+            assert_eq!(read(&archive[..3], 0), Err(ERR_CORRUPT_DATA));
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Crockford Base32
+//!
+//! Encoding for recovery keys that users write down and type back in. The
+//! Crockford alphabet (`0-9 A-Z` without `I L O U`) avoids characters that
+//! are easily confused, and decoding forgives the usual transcription
+//! slips: lowercase is accepted, `O` reads as `0`, `I` and `L` read as `1`,
+//! and spaces and hyphens are ignored.
+//!
+//! Encoding is unpadded, most significant bit first. Character mapping uses
+//! arithmetic on masks rather than table lookups or branches on the value,
+//! so the secret bytes being encoded do not influence timing. Where the
+//! separators are is not treated as secret.
+
+use std::slice;
+
+use super::*;
+
+/// All-ones when `lo <= c <= hi`, otherwise zero, without branching on `c`.
+pub(crate) fn range_mask(c: u8, lo: u8, hi: u8) -> u8 {
+    let c = c as i16;
+    let below = (c - lo as i16) >> 8; // -1 when c < lo
+    let above = (hi as i16 - c) >> 8; // -1 when c > hi
+    !(below | above) as u8
+}
+
+/// Map a 5-bit value to its Crockford character.
+pub(crate) fn encode_symbol(v: u8) -> u8 {
+    // Start from '0' + v, then step over ':'..'@' and the skipped letters
+    let mut c = b'0' + v;
+    c += 7 & range_mask(v, 10, 31);
+    c += 1 & range_mask(v, 18, 31); // I
+    c += 1 & range_mask(v, 20, 31); // L
+    c += 1 & range_mask(v, 22, 31); // O
+    c += 1 & range_mask(v, 27, 31); // U
+    c
+}
+
+/// Map a character to its 5-bit value, or `None` if it is not in the alphabet.
+pub(crate) fn decode_symbol(c: u8) -> Option<u8> {
+    let c = c - (0x20 & range_mask(c, b'a', b'z'));
+
+    let mut value = 0u8;
+    let mut valid = 0u8;
+    for (lo, hi, base) in [
+        (b'0', b'9', 0),
+        (b'A', b'H', 10),
+        (b'J', b'K', 18),
+        (b'M', b'N', 20),
+        (b'P', b'T', 22),
+        (b'V', b'Z', 27),
+    ] {
+        let mask = range_mask(c, lo, hi);
+        value |= mask & c.wrapping_sub(lo).wrapping_add(base);
+        valid |= mask;
+    }
+    // Transcription aliases
+    let o = range_mask(c, b'O', b'O');
+    let one = range_mask(c, b'I', b'I') | range_mask(c, b'L', b'L');
+    value |= one & 1;
+    valid |= o | one;
+
+    (valid != 0).then_some(value)
+}
+
+/// Encode bytes as unpadded Crockford base32.
+pub(crate) fn base32_encode(data: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut out = output_buffer((data.len() * 8).div_ceil(5))?;
+    let mut acc = 0u16;
+    let mut bits = 0;
+    for &byte in data {
+        acc = (acc << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(encode_symbol(((acc >> bits) & 0x1F) as u8));
+        }
+    }
+    if bits > 0 {
+        out.push(encode_symbol(((acc << (5 - bits)) & 0x1F) as u8));
+    }
+    acc.zeroize();
+    Ok(out)
+}
+
+/// Decode Crockford base32, skipping spaces and hyphens.
+pub(crate) fn base32_decode(text: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut out = Secret::with_capacity(text.len() * 5 / 8)?;
+    let mut acc = Secret::new(0u16);
+    let mut bits = 0;
+    for &c in text {
+        if c == b' ' || c == b'-' {
+            continue;
+        }
+        let value = decode_symbol(c).ok_or(ERR_INVALID_INPUT)?;
+        *acc = (*acc << 5) | value as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((*acc >> bits) as u8);
+        }
+    }
+    // Leftover bits are encoder padding: fewer than 5, and all zero
+    if bits >= 5 || *acc & ((1 << bits) - 1) != 0 {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(out.into_inner())
+}
+
+/// Encode bytes as Crockford base32 text (ASCII, no padding).
+///
+/// # Safety
+///
+/// - `data` must be valid for `len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_base32_encode(data: *const u8, len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if data.is_null() || len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let data_slice = slice::from_raw_parts(data, len as usize);
+
+        match base32_encode(data_slice) {
+            Ok(text) => VaultBuffer::success(text),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decode Crockford base32 text typed by a user.
+///
+/// Case-insensitive; spaces and hyphens are ignored; `O` decodes as `0` and
+/// `I`/`L` as `1`.
+///
+/// # Safety
+///
+/// - `text` must be valid for `len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the decoded bytes, or `ERR_INVALID_INPUT` for a
+/// character outside the alphabet or a length no encoding produces
+#[no_mangle]
+pub unsafe extern "C" fn vault_base32_decode(text: *const u8, len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if text.is_null() || len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let text_slice = slice::from_raw_parts(text, len as usize);
+
+        match base32_decode(text_slice) {
+            Ok(data) => VaultBuffer::success(data),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base32_alphabet() {
+        let alphabet: Vec<u8> = (0..32).map(encode_symbol).collect();
+        assert_eq!(alphabet, b"0123456789ABCDEFGHJKMNPQRSTVWXYZ");
+        for (v, &c) in alphabet.iter().enumerate() {
+            assert_eq!(decode_symbol(c), Some(v as u8));
+            assert_eq!(decode_symbol(c.to_ascii_lowercase()), Some(v as u8));
+        }
+        assert_eq!(decode_symbol(b'U'), None);
+        assert_eq!(decode_symbol(b'!'), None);
+    }
+
+    #[test]
+    fn test_base32_roundtrip() {
+        let key: Vec<u8> = (0..32).map(|i| i * 7 + 3).collect();
+
+        unsafe {
+            for len in 1..=key.len() {
+                let encoded = vault_base32_encode(key.as_ptr(), len as u32);
+                assert_eq!(encoded.error, 0);
+                let text = slice::from_raw_parts(encoded.data, encoded.len as usize).to_vec();
+                vault_free(encoded.data, encoded.len);
+
+                // Typed back lowercase in hyphenated groups of four
+                let typed: Vec<u8> = text
+                    .chunks(4)
+                    .collect::<Vec<_>>()
+                    .join(&b'-')
+                    .to_ascii_lowercase();
+                let decoded = vault_base32_decode(typed.as_ptr(), typed.len() as u32);
+                assert_eq!(decoded.error, 0);
+                assert_eq!(slice::from_raw_parts(decoded.data, decoded.len as usize), &key[..len]);
+                vault_free(decoded.data, decoded.len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_base32_known_vector() {
+        assert_eq!(base32_encode(b"foobar").unwrap(), b"CSQPYRK1E8");
+        assert_eq!(base32_decode(b"csqp yrk1-e8").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn test_base32_ambiguous_normalized() {
+        // O -> 0, I/L -> 1, in either case
+        let canonical = base32_decode(b"1400").unwrap();
+        for typed in [&b"I4O0"[..], b"i4oo", b"L4o0", b"l40O"] {
+            assert_eq!(base32_decode(typed).unwrap(), canonical);
+        }
+    }
+
+    #[test]
+    fn test_base32_rejects_invalid() {
+        assert_eq!(base32_decode(b"CSQU"), Err(ERR_INVALID_INPUT));
+        assert_eq!(base32_decode(b"CS_Q"), Err(ERR_INVALID_INPUT));
+        // Three symbols is never a whole number of bytes
+        assert_eq!(base32_decode(b"CSQ"), Err(ERR_INVALID_INPUT));
+        // Nonzero padding bits
+        assert_eq!(base32_decode(b"01"), Err(ERR_INVALID_INPUT));
+        assert_eq!(base32_decode(b" - "), Ok(Vec::new()));
+    }
+}
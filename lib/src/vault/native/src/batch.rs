@@ -0,0 +1,344 @@
+//! Batch Operations
+//!
+//! Sealing hundreds of small entries one call at a time pays an FFI crossing
+//! and an allocation round-trip per entry. These functions take an array of
+//! `VaultSlice` inputs and fill a caller-provided array of `VaultBuffer`s.
+//!
+//! Every output is produced exactly as the single-item function would
+//! produce it (independent random nonce per item), so each can be freed and
+//! unsealed individually.
+
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::kdf::argon2_params_arg;
+
+use super::*;
+
+/// Upper bound on derivation threads regardless of memory budget
+const MAX_KDF_THREADS: usize = 4;
+
+/// Per-item outcome of a parallel derivation; `None` until a worker reaches it
+type DerivedKey = Option<VaultResult<Secret<[u8; KEY_SIZE]>>>;
+
+/// Number of derivations to run at once within `budget_kib` of Argon2 memory.
+fn kdf_workers(count: usize, m_cost: u32, budget_kib: u32) -> usize {
+    let by_memory = (budget_kib / m_cost.max(1)) as usize;
+    by_memory.min(MAX_KDF_THREADS).min(count)
+}
+
+/// Free every buffer already written, leaving error buffers in their place.
+pub(crate) unsafe fn release(out: &mut [VaultBuffer], code: i32) {
+    for buffer in out.iter_mut() {
+        vault_free(buffer.data, buffer.len);
+        *buffer = VaultBuffer::error(code);
+    }
+}
+
+/// Seal many plaintexts under one key in a single call.
+///
+/// Writes one `VaultBuffer` per item into `out_buffers`, each in the
+/// `vault_seal` format. On any failure all buffers produced so far are freed,
+/// every slot holds an error buffer, and the error code is returned.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `items` must point to `item_count` valid `VaultSlice`s
+/// - `out_buffers` must be writable for `item_count` `VaultBuffer`s
+/// - Each returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// 0 on success, negative error code on failure
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_batch(
+    key: *const u8,
+    key_len: u32,
+    items: *const VaultSlice,
+    item_count: u32,
+    out_buffers: *mut VaultBuffer,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if items.is_null() || out_buffers.is_null() || item_count == 0 {
+            return ERR_INVALID_INPUT;
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+
+        let items = slice::from_raw_parts(items, item_count as usize);
+        let out = slice::from_raw_parts_mut(out_buffers, item_count as usize);
+        for buffer in out.iter_mut() {
+            *buffer = VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            if item.ptr.is_null() && item.len != 0 {
+                release(&mut out[..i], ERR_INVALID_INPUT);
+                return ERR_INVALID_INPUT;
+            }
+            let plaintext: &[u8] = if item.len == 0 { &[] } else { slice::from_raw_parts(item.ptr, item.len as usize) };
+
+            match seal_blob(key_slice, plaintext) {
+                Ok(sealed) => out[i] = VaultBuffer::success(sealed),
+                Err(code) => {
+                    release(&mut out[..i], code);
+                    return code;
+                }
+            }
+            if out[i].error != 0 {
+                let code = out[i].error;
+                release(&mut out[..=i], code);
+                return code;
+            }
+        }
+
+        0
+    })
+}
+
+/// Derive many keys concurrently, each with its own passphrase and salt.
+///
+/// Runs up to `MAX_KDF_THREADS` Argon2id derivations at a time, further
+/// limited so that no more than `memory_budget_kib` of Argon2 working
+/// memory (`m_cost` KiB each) is live at once. Writes one 32-byte key per
+/// item into `out_buffers`. On any failure every key produced is freed,
+/// every slot holds an error buffer, and the error code is returned.
+///
+/// # Safety
+///
+/// - `passphrases` must point to `count` valid `VaultSlice`s
+/// - `salts` must point to `count * 16` bytes (one salt per passphrase)
+/// - `out_buffers` must be writable for `count` `VaultBuffer`s
+/// - Each returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_INVALID_INPUT` if the budget cannot fit a single
+/// derivation, or the first error encountered
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_keys_parallel(
+    passphrases: *const VaultSlice,
+    salts: *const u8,
+    count: u32,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    memory_budget_kib: u32,
+    out_buffers: *mut VaultBuffer,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrases.is_null() || salts.is_null() || out_buffers.is_null() || count == 0 {
+            return ERR_INVALID_INPUT;
+        }
+        if let Err(code) = argon2_params_arg(m_cost, t_cost, p_cost) {
+            return code;
+        }
+        let workers = kdf_workers(count as usize, m_cost, memory_budget_kib);
+        if workers == 0 {
+            return ERR_INVALID_INPUT;
+        }
+
+        let items = slice::from_raw_parts(passphrases, count as usize);
+        let salts = slice::from_raw_parts(salts, count as usize * SALT_SIZE);
+        let out = slice::from_raw_parts_mut(out_buffers, count as usize);
+        for buffer in out.iter_mut() {
+            *buffer = VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if items.iter().any(|item| item.ptr.is_null() || item.len == 0) {
+            return ERR_INVALID_INPUT;
+        }
+        let passphrases: Vec<&[u8]> = items
+            .iter()
+            .map(|item| slice::from_raw_parts(item.ptr, item.len as usize))
+            .collect();
+
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<DerivedKey>> = Mutex::new((0..passphrases.len()).map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= passphrases.len() {
+                        break;
+                    }
+                    let salt = &salts[i * SALT_SIZE..(i + 1) * SALT_SIZE];
+                    let result = argon2id(passphrases[i], salt, m_cost, t_cost, p_cost);
+                    results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(result);
+                });
+            }
+        });
+
+        let results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        if let Some(code) = results.iter().find_map(|r| match r {
+            Some(Err(code)) => Some(*code),
+            None => Some(ERR_KDF_FAILED),
+            Some(Ok(_)) => None,
+        }) {
+            return code;
+        }
+
+        for (i, result) in results.into_iter().enumerate() {
+            if let Some(Ok(key)) = result {
+                out[i] = match output_copy(key.as_ref()) {
+                    Ok(key) => VaultBuffer::success(key),
+                    Err(code) => VaultBuffer::error(code),
+                };
+                if out[i].error != 0 {
+                    let code = out[i].error;
+                    release(&mut out[..=i], code);
+                    return code;
+                }
+            }
+        }
+        0
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_batch_roundtrip() {
+        let key = [0x42u8; 32];
+        let entries: Vec<Vec<u8>> = (0..100u32)
+            .map(|i| format!("entry {i}").into_bytes())
+            .collect();
+        let items: Vec<VaultSlice> = entries
+            .iter()
+            .map(|e| VaultSlice { ptr: e.as_ptr(), len: e.len() as u32 })
+            .collect();
+        let mut out: Vec<VaultBuffer> = (0..items.len()).map(|_| VaultBuffer::error(0)).collect();
+
+        unsafe {
+            let rc = vault_seal_batch(key.as_ptr(), 32, items.as_ptr(), items.len() as u32, out.as_mut_ptr());
+            assert_eq!(rc, 0);
+
+            for (entry, sealed) in entries.iter().zip(&out) {
+                assert_eq!(sealed.error, 0);
+                let unsealed = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(unsealed.error, 0);
+                assert_eq!(slice::from_raw_parts(unsealed.data, unsealed.len as usize), &entry[..]);
+                vault_free(unsealed.data, unsealed.len);
+            }
+
+            // Independent nonces
+            let first = slice::from_raw_parts(out[0].data.add(FORMAT_HEADER_SIZE), NONCE_SIZE);
+            let second = slice::from_raw_parts(out[1].data.add(FORMAT_HEADER_SIZE), NONCE_SIZE);
+            assert_ne!(first, second);
+
+            for sealed in &out {
+                vault_free(sealed.data, sealed.len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_derive_keys_parallel_matches_single() {
+        let passphrases: Vec<Vec<u8>> = (0..4u8).map(|i| format!("profile {i}").into_bytes()).collect();
+        let items: Vec<VaultSlice> = passphrases
+            .iter()
+            .map(|p| VaultSlice { ptr: p.as_ptr(), len: p.len() as u32 })
+            .collect();
+        let salts: Vec<u8> = (0..4u8).flat_map(|i| [i; SALT_SIZE]).collect();
+        let mut out: Vec<VaultBuffer> = (0..4).map(|_| VaultBuffer::error(0)).collect();
+
+        unsafe {
+            let rc = vault_derive_keys_parallel(items.as_ptr(), salts.as_ptr(), 4, 1024, 1, 1, 4096, out.as_mut_ptr());
+            assert_eq!(rc, 0);
+
+            for (i, key) in out.iter().enumerate() {
+                assert_eq!(key.error, 0);
+                let salt = &salts[i * SALT_SIZE..(i + 1) * SALT_SIZE];
+                let single = vault_derive_key_ex(
+                    passphrases[i].as_ptr(),
+                    passphrases[i].len() as u32,
+                    salt.as_ptr(),
+                    ptr::null(),
+                    0,
+                    1024,
+                    1,
+                    1,
+                    ARGON2_VARIANT_ID,
+                    0,
+                );
+                assert_eq!(single.error, 0);
+                assert_eq!(
+                    slice::from_raw_parts(key.data, KEY_SIZE),
+                    slice::from_raw_parts(single.data, KEY_SIZE)
+                );
+                vault_free(single.data, single.len);
+                vault_free(key.data, key.len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_derive_keys_parallel_memory_budget() {
+        // Budget, not the thread cap, limits concurrency
+        assert_eq!(kdf_workers(8, 65536, 65536 * 2), 2);
+        assert_eq!(kdf_workers(8, 1024, u32::MAX), MAX_KDF_THREADS);
+        assert_eq!(kdf_workers(2, 1024, u32::MAX), 2);
+        assert_eq!(kdf_workers(8, 65536, 65535), 0);
+
+        let passphrase = b"one";
+        let items = [VaultSlice { ptr: passphrase.as_ptr(), len: 3 }; 2];
+        let salts = [0u8; 2 * SALT_SIZE];
+        let mut out = [VaultBuffer::error(0), VaultBuffer::error(0)];
+
+        unsafe {
+            // A budget below one derivation is refused outright
+            let rc = vault_derive_keys_parallel(items.as_ptr(), salts.as_ptr(), 2, 1024, 1, 1, 1023, out.as_mut_ptr());
+            assert_eq!(rc, ERR_INVALID_INPUT);
+
+            // Costs are checked once, before any worker starts
+            let rc = vault_derive_keys_parallel(items.as_ptr(), salts.as_ptr(), 2, 1024, 0, 1, 4096, out.as_mut_ptr());
+            assert_eq!(rc, ERR_INVALID_INPUT);
+
+            // Exactly one derivation's worth runs them one at a time
+            let rc = vault_derive_keys_parallel(items.as_ptr(), salts.as_ptr(), 2, 1024, 1, 1, 1024, out.as_mut_ptr());
+            assert_eq!(rc, 0);
+            for key in &out {
+                vault_free(key.data, key.len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_seal_batch_failure_releases_outputs() {
+        let key = [0x42u8; 32];
+        let good = b"fine";
+        let items = [
+            VaultSlice { ptr: good.as_ptr(), len: good.len() as u32 },
+            VaultSlice { ptr: ptr::null(), len: 4 },
+        ];
+        let mut out = [VaultBuffer::error(0), VaultBuffer::error(0)];
+
+        unsafe {
+            let rc = vault_seal_batch(key.as_ptr(), 32, items.as_ptr(), 2, out.as_mut_ptr());
+            assert_eq!(rc, ERR_INVALID_INPUT);
+        }
+        assert!(out.iter().all(|b| b.data.is_null() && b.error == ERR_INVALID_INPUT));
+
+        // A null pointer with zero length is an empty entry, not a failure
+        let items = [VaultSlice { ptr: ptr::null(), len: 0 }];
+        unsafe {
+            assert_eq!(vault_seal_batch(key.as_ptr(), 32, items.as_ptr(), 1, out.as_mut_ptr()), 0);
+            let opened = vault_unseal(key.as_ptr(), out[0].data, out[0].len);
+            assert_eq!((opened.error, opened.len), (0, 0));
+            vault_free(out[0].data, out[0].len);
+        }
+    }
+}
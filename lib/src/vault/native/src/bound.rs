@@ -0,0 +1,167 @@
+//! Device-Bound Seals
+//!
+//! A sealed blob that only opens when the caller supplies the same device
+//! identifier it was sealed with. The identifier is authenticated as
+//! associated data, not mixed into the key.
+//!
+//! ## Threat model
+//!
+//! This stops a copied blob being opened by an app instance that cannot
+//! produce the original device id, for example after a backup is restored
+//! to another phone. It is only as strong as the id is hard to obtain: an
+//! id read from a hardware-backed attestation or secure-element key works,
+//! while a readable value such as a device model or a stored UUID can be
+//! copied along with the blob. The id is not secret key material; anyone
+//! holding the key and the id can open the blob anywhere.
+//!
+//! ## Format
+//!
+//! `format (1, 0x0A) || nonce (24) || ciphertext || tag (16)`
+//!
+//! Associated data is `format || device_id`.
+
+use std::slice;
+
+use super::*;
+
+/// Associated data for a bound blob: format byte, then the device id.
+fn bound_aad(device_id: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(FORMAT_HEADER_SIZE + device_id.len());
+    aad.push(FORMAT_BOUND);
+    aad.extend_from_slice(device_id);
+    aad
+}
+
+/// Seal data so it only opens with the same device id.
+///
+/// # Format
+///
+/// Output: `format (1) || nonce (24) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - `device_id` must be valid for `device_id_len` bytes (non-empty)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_bound(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    device_id: *const u8,
+    device_id_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if (plaintext.is_null() && plaintext_len != 0) || device_id.is_null() || device_id_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+        let device_id_slice = slice::from_raw_parts(device_id, device_id_len as usize);
+
+        let sealed = match xchacha_seal(key_slice, plaintext_slice, &bound_aad(device_id_slice)) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(FORMAT_HEADER_SIZE + sealed.len()) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.push(FORMAT_BOUND);
+        output.extend_from_slice(&sealed);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt data sealed with `vault_seal_bound` on the same device.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - `device_id` must be valid for `device_id_len` bytes (non-empty)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, or `ERR_DECRYPT_FAILED` for a wrong
+/// key or a different device id
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_bound(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+    device_id: *const u8,
+    device_id_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+        if sealed.is_null() || device_id.is_null() || device_id_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+        let device_id_slice = slice::from_raw_parts(device_id, device_id_len as usize);
+
+        let (header, body) = sealed_slice.split_at(FORMAT_HEADER_SIZE);
+        if header[0] != FORMAT_BOUND {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+
+        match xchacha_open(key_slice, body, &bound_aad(device_id_slice)) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bound_other_device_fails() {
+        let key = [0x42u8; 32];
+        let plaintext = b"device-only secret";
+        let device_a = b"attestation-key-A";
+        let device_b = b"attestation-key-B";
+
+        unsafe {
+            let sealed = vault_seal_bound(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32, device_a.as_ptr(), 17);
+            assert_eq!(sealed.error, 0);
+
+            let same = vault_unseal_bound(key.as_ptr(), 32, sealed.data, sealed.len, device_a.as_ptr(), 17);
+            assert_eq!(same.error, 0);
+            assert_eq!(slice::from_raw_parts(same.data, same.len as usize), plaintext);
+            vault_free(same.data, same.len);
+
+            let other = vault_unseal_bound(key.as_ptr(), 32, sealed.data, sealed.len, device_b.as_ptr(), 17);
+            assert_eq!(other.error, ERR_DECRYPT_FAILED);
+
+            // Not an ordinary sealed blob either
+            let plain = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+            assert_eq!(plain.error, ERR_UNSUPPORTED_VERSION);
+
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+}
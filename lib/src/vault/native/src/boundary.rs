@@ -0,0 +1,140 @@
+//! FFI Panic Boundary
+//!
+//! Every exported function runs its body through `ffi_boundary`, so a panic
+//! (a bug, or a slice length that does not match its pointer) comes back to
+//! the caller as `ERR_INTERNAL_PANIC` instead of unwinding into C, Dart or
+//! Swift frames, which is undefined behavior and takes the host app down.
+//!
+//! Secrets held in `Secret` and `Zeroizing` are wiped as the panic unwinds.
+//! The panic message is kept for `vault_last_error_message`. Catching needs
+//! unwinding: a build with `panic = "abort"` still aborts the process.
+//!
+//! A caught panic means the library hit a bug. The call's outputs are
+//! unspecified, but nothing shared is left half-updated: the tables behind
+//! locks recover from poisoning, and every other state is per call.
+
+use std::any::Any;
+use std::ffi::c_char;
+use std::panic::{self, AssertUnwindSafe};
+
+use super::*;
+
+/// What an exported function returns when its body panics
+pub(crate) trait PanicReturn {
+    fn panicked() -> Self;
+}
+
+impl PanicReturn for VaultBuffer {
+    fn panicked() -> Self {
+        VaultBuffer::error(ERR_INTERNAL_PANIC)
+    }
+}
+
+impl PanicReturn for VaultStatus {
+    fn panicked() -> Self {
+        VaultStatus { code: ERR_INTERNAL_PANIC, detail: 0 }
+    }
+}
+
+impl PanicReturn for i32 {
+    fn panicked() -> Self {
+        ERR_INTERNAL_PANIC
+    }
+}
+
+impl PanicReturn for i64 {
+    fn panicked() -> Self {
+        ERR_INTERNAL_PANIC as i64
+    }
+}
+
+/// Counts and sizes, for which 0 already means "none"
+impl PanicReturn for u32 {
+    fn panicked() -> Self {
+        0
+    }
+}
+
+impl PanicReturn for () {
+    fn panicked() -> Self {}
+}
+
+/// Handle constructors return null on any failure
+impl<T> PanicReturn for *mut T {
+    fn panicked() -> Self {
+        ptr::null_mut()
+    }
+}
+
+impl PanicReturn for *const c_char {
+    fn panicked() -> Self {
+        ptr::null()
+    }
+}
+
+/// The message a panic was raised with.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "no message"
+    }
+}
+
+/// Run an exported function's body, turning a panic into an error return.
+pub(crate) fn ffi_boundary<R: PanicReturn>(body: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(result) => result,
+        Err(payload) => {
+            error_detail(ERR_INTERNAL_PANIC, format_args!("panicked: {}", panic_message(&*payload)));
+            R::panicked()
+        }
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error_message() -> String {
+        unsafe {
+            let message = vault_last_error_message();
+            let text = String::from_utf8(slice::from_raw_parts(message.data, message.len as usize).to_vec()).unwrap();
+            vault_free(message.data, message.len);
+            text
+        }
+    }
+
+    #[test]
+    fn test_panic_becomes_error() {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+
+        let buffer = ffi_boundary(|| -> VaultBuffer { panic!("bad length {}", 40) });
+        assert_eq!((buffer.error, buffer.len), (ERR_INTERNAL_PANIC, 0));
+        assert!(buffer.data.is_null());
+        assert_eq!(last_error_message(), "Internal error (a panic was caught): panicked: bad length 40");
+
+        assert_eq!(ffi_boundary(|| -> i32 { panic!("code") }), ERR_INTERNAL_PANIC);
+        assert_eq!(ffi_boundary(|| -> i64 { panic!("length") }), ERR_INTERNAL_PANIC as i64);
+        assert!(ffi_boundary(|| -> *mut VaultCipher { panic!("handle") }).is_null());
+        let status = ffi_boundary(|| -> VaultStatus { panic!("status") });
+        assert_eq!(status, VaultStatus { code: ERR_INTERNAL_PANIC, detail: 0 });
+        let slice: &[u8] = &[1, 2, 3];
+        ffi_boundary(|| {
+            let _ = slice[usize::MAX / 2];
+        });
+        assert!(last_error_message().contains("out of bounds"));
+
+        panic::set_hook(hook);
+
+        // Without a panic the body's own result comes through
+        assert_eq!(ffi_boundary(|| ERR_DECRYPT_FAILED), ERR_DECRYPT_FAILED);
+    }
+}
@@ -0,0 +1,146 @@
+//! Runtime Capabilities
+//!
+//! Lets the wallet discover what the loaded library supports, so it can
+//! adapt its UI and refuse operations up front instead of getting
+//! `ERR_INVALID_INPUT` back from a function this build does not offer. The
+//! list is fixed at compile time from the target and enabled features.
+//!
+//! ## Format
+//!
+//! UTF-8 JSON, one object with four arrays:
+//!
+//! ```json
+//! {
+//!   "ciphers": [{"name": "XChaCha20-Poly1305", "key": 32, "nonce": 24, "tag": 16, "format": 1}, ...],
+//!   "kdfs": [{"name": "Argon2id", "variant": 0, "m_cost": 65536, "t_cost": 3, "p_cost": 4}, ...],
+//!   "signatures": [{"name": "Ed25519", "public_key": 32, "signature": 64}],
+//!   "features": ["file", ...]
+//! }
+//! ```
+//!
+//! Sizes are in bytes; `format` is the sealed blob's format byte. The
+//! Argon2 costs are the current defaults (see
+//! `vault_set_default_argon2_params`). New keys may be added; readers should
+//! ignore keys they do not know.
+
+use std::fmt::Write;
+
+use crate::ed25519::{ED25519_PUBLIC_KEY_SIZE, ED25519_SIGNATURE_SIZE};
+use crate::ietf::IETF_NONCE_SIZE;
+use crate::kdf::default_argon2_params;
+use crate::secp256k1::{SECP256K1_PUBLIC_KEY_SIZE, SECP256K1_SIGNATURE_SIZE};
+use crate::siv::SIV_KEY_SIZE;
+
+use super::*;
+
+/// A cipher entry: name, key size, nonce size, tag size, format byte.
+const CIPHERS: &[(&str, usize, usize, usize, u8)] = &[
+    ("XChaCha20-Poly1305", KEY_SIZE, NONCE_SIZE, TAG_SIZE, FORMAT_XCHACHA),
+    ("AES-256-SIV", SIV_KEY_SIZE, 0, TAG_SIZE, FORMAT_SIV),
+    ("ChaCha20-Poly1305", KEY_SIZE, IETF_NONCE_SIZE, TAG_SIZE, FORMAT_IETF),
+];
+
+/// Argon2 variants accepted by the parameterized KDFs.
+const ARGON2_VARIANTS: &[(&str, u32)] = &[
+    ("Argon2id", ARGON2_VARIANT_ID),
+    ("Argon2i", ARGON2_VARIANT_I),
+    ("Argon2d", ARGON2_VARIANT_D),
+];
+
+/// A signature scheme entry: name, public key size, signature size.
+const SIGNATURES: &[(&str, usize, usize)] = &[
+    ("Ed25519", ED25519_PUBLIC_KEY_SIZE, ED25519_SIGNATURE_SIZE),
+    ("ECDSA-secp256k1", SECP256K1_PUBLIC_KEY_SIZE, SECP256K1_SIGNATURE_SIZE),
+];
+
+/// Optional functionality, present only in some builds.
+const FEATURES: &[(&str, bool)] = &[
+    ("file", cfg!(not(target_arch = "wasm32"))),
+    ("stream", cfg!(not(target_arch = "wasm32"))),
+    ("ephemeral", cfg!(not(target_arch = "wasm32"))),
+    ("wasm", cfg!(feature = "wasm")),
+    ("debug-guard", cfg!(feature = "debug-guard")),
+    ("timing-selfcheck", cfg!(feature = "timing-selfcheck")),
+];
+
+/// Render the capability document.
+fn capabilities_json() -> String {
+    let mut json = String::from("{\"ciphers\":[");
+    for (i, (name, key, nonce, tag, format)) in CIPHERS.iter().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(
+            json,
+            "{sep}{{\"name\":\"{name}\",\"key\":{key},\"nonce\":{nonce},\"tag\":{tag},\"format\":{format}}}"
+        );
+    }
+
+    json.push_str("],\"kdfs\":[");
+    let (m_cost, t_cost, p_cost) = default_argon2_params();
+    for (i, (name, variant)) in ARGON2_VARIANTS.iter().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(
+            json,
+            "{sep}{{\"name\":\"{name}\",\"variant\":{variant},\"m_cost\":{m_cost},\"t_cost\":{t_cost},\"p_cost\":{p_cost}}}"
+        );
+    }
+    json.push_str(",{\"name\":\"HKDF-SHA256\"}");
+
+    json.push_str("],\"signatures\":[");
+    for (i, (name, public_key, signature)) in SIGNATURES.iter().enumerate() {
+        let sep = if i == 0 { "" } else { "," };
+        let _ = write!(json, "{sep}{{\"name\":\"{name}\",\"public_key\":{public_key},\"signature\":{signature}}}");
+    }
+
+    json.push_str("],\"features\":[");
+    let enabled: Vec<String> = FEATURES
+        .iter()
+        .filter(|(_, on)| *on)
+        .map(|(name, _)| format!("\"{name}\""))
+        .collect();
+    json.push_str(&enabled.join(","));
+    json.push_str("]}");
+    json
+}
+
+/// List the ciphers, KDFs and signature schemes this build supports.
+///
+/// See the module documentation for the JSON layout.
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_capabilities() -> VaultBuffer {
+    ffi_boundary(|| {
+        VaultBuffer::success(capabilities_json().into_bytes())
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_default_build() {
+        let _defaults = crate::kdf::lock_default_params();
+        unsafe {
+            let result = vault_capabilities();
+            assert_eq!(result.error, 0);
+            let json = std::str::from_utf8(slice::from_raw_parts(result.data, result.len as usize))
+                .unwrap()
+                .to_owned();
+            vault_free(result.data, result.len);
+
+            assert!(json.starts_with('{') && json.ends_with('}'));
+            assert!(json.contains("{\"name\":\"XChaCha20-Poly1305\",\"key\":32,\"nonce\":24,\"tag\":16,\"format\":1}"));
+            assert!(json.contains("{\"name\":\"Argon2id\",\"variant\":0,\"m_cost\":65536,\"t_cost\":3,\"p_cost\":4}"));
+            assert!(json.contains("\"signatures\":[{\"name\":\"Ed25519\",\"public_key\":32,\"signature\":64},"));
+            assert!(json.contains("{\"name\":\"ECDSA-secp256k1\",\"public_key\":33,\"signature\":64}"));
+            assert_eq!(json.contains("\"file\""), cfg!(not(target_arch = "wasm32")));
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! Key Sanity Checks
+//!
+//! `vault_seal` accepts any 32 bytes as a key, including an all-zero buffer
+//! left behind by an integration bug (an uninitialized array, a key that
+//! was wiped too early). `vault_seal_checked` refuses such keys with
+//! `ERR_WEAK_KEY`.
+//!
+//! This catches mistakes, not weak randomness: only keys made of a single
+//! repeated byte value are rejected, which a random key hits with
+//! probability 2^-248.
+
+use std::slice;
+
+use super::*;
+
+/// True for a key that is one byte value repeated (all zero, all 0xFF, ...).
+///
+/// Looks at every byte regardless of where the first difference is, so the
+/// time taken says nothing about the key.
+fn is_degenerate_key(key: &[u8]) -> bool {
+    key.iter().fold(0u8, |diff, &b| diff | (b ^ key[0])) == 0
+}
+
+/// `vault_seal`, but refusing a key made of one repeated byte.
+///
+/// # Format
+///
+/// Output: same as `vault_seal`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the sealed data, `ERR_WEAK_KEY` for a degenerate
+/// key, or another error code
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_checked(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if plaintext.is_null() && plaintext_len != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if is_degenerate_key(key_slice) {
+            return VaultBuffer::error(ERR_WEAK_KEY);
+        }
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        match seal_blob(key_slice, plaintext_slice) {
+            Ok(sealed) => VaultBuffer::success(sealed),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_checked_rejects_degenerate_keys() {
+        let plaintext = b"checked";
+
+        unsafe {
+            for key in [[0u8; 32], [0xFFu8; 32], [0x42u8; 32]] {
+                let result = vault_seal_checked(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32);
+                assert_eq!(result.error, ERR_WEAK_KEY);
+                assert!(result.data.is_null());
+            }
+
+            let mut key = [0u8; 32];
+            assert_eq!(vault_random(key.as_mut_ptr(), 32), 0);
+            let sealed = vault_seal_checked(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+
+            let opened = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+            vault_free(sealed.data, sealed.len);
+
+            assert_eq!(vault_seal_checked(key.as_ptr(), 16, plaintext.as_ptr(), 7).error, ERR_BAD_KEY_SIZE);
+        }
+    }
+}
@@ -0,0 +1,455 @@
+//! Reusable Cipher Contexts
+//!
+//! `vault_seal` and `vault_unseal` key a fresh cipher on every call. When
+//! many items are sealed under one key, a `VaultCipher` keys it once and is
+//! reused; its blobs are in the `vault_seal` format and interchangeable
+//! with it.
+//!
+//! A context is not internally locked: use it from one thread at a time
+//! (moving it between threads is fine). The key is wiped when the context
+//! is freed with `vault_cipher_free`.
+//!
+//! ## Counter Mode
+//!
+//! A context from `vault_cipher_new_counter` puts a monotonic counter in
+//! the first 8 bytes of each nonce and fills the other 16 at random, so no
+//! two seals from one context can share a nonce however many it makes. The
+//! counter is authenticated with the message and can be read back with
+//! `vault_cipher_unseal_counter` to put messages in order. The context
+//! refuses to seal once the counter is used up rather than wrapping.
+//!
+//! Counter-mode blobs carry their own format byte:
+//!
+//! `format (1, 0x0D) || counter (8, LE) || random (16) || ciphertext || tag (16)`
+//!
+//! Two contexts started at the same counter under one key still get
+//! distinct nonces from the random part, but their counters no longer
+//! order their messages.
+
+use std::cell::Cell;
+use std::fmt;
+use std::slice;
+
+use super::*;
+
+/// Size of the counter at the start of a counter-mode nonce
+const COUNTER_SIZE: usize = 8;
+
+/// Keyed XChaCha20-Poly1305 context (opaque to callers)
+pub struct VaultCipher {
+    cipher: XChaCha20Poly1305,
+    nonces: NonceSource,
+}
+
+/// Where a context's nonces come from.
+enum NonceSource {
+    Random,
+    /// The next counter to use, or `None` once the last one is spent
+    Counter(Cell<Option<u64>>),
+}
+
+/// Seal under `counter || 16 random bytes` in the counter-mode format.
+fn counter_seal(cipher: &XChaCha20Poly1305, counter: u64, plaintext: &[u8]) -> VaultResult<Vec<u8>> {
+    let header = [FORMAT_COUNTER];
+    let mut nonce_bytes = Secret::new([0u8; NONCE_SIZE]);
+    nonce_bytes[..COUNTER_SIZE].copy_from_slice(&counter.to_le_bytes());
+    random_bytes(&mut nonce_bytes[COUNTER_SIZE..])?;
+
+    let mut output = Secret::with_capacity(FORMAT_HEADER_SIZE + NONCE_SIZE + plaintext.len() + TAG_SIZE)?;
+    output.extend_from_slice(&header);
+    output.extend_from_slice(nonce_bytes.as_ref());
+    output.extend_from_slice(plaintext);
+    let tag = cipher
+        .encrypt_in_place_detached(XNonce::from_slice(nonce_bytes.as_ref()), &header, &mut output[FORMAT_HEADER_SIZE + NONCE_SIZE..])
+        .map_err(|_| ERR_INVALID_INPUT)?;
+    output.extend_from_slice(&tag);
+
+    Ok(output.into_inner())
+}
+
+/// Never prints the key.
+impl fmt::Debug for VaultCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VaultCipher(***redacted***)")
+    }
+}
+
+/// Create a cipher context for a 32-byte key.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - The returned pointer must be released with `vault_cipher_free`
+///
+/// # Returns
+///
+/// A new context, or null for a null or wrong-length key
+#[no_mangle]
+pub unsafe extern "C" fn vault_cipher_new(key: *const u8, key_len: u32) -> *mut VaultCipher {
+    ffi_boundary(|| {
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(_) => return ptr::null_mut(),
+        };
+        match XChaCha20Poly1305::new_from_slice(key_slice) {
+            Ok(cipher) => Box::into_raw(Box::new(VaultCipher { cipher, nonces: NonceSource::Random })),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Create a counter-mode cipher context for a 32-byte key.
+///
+/// Its first seal uses `start_counter`, and each later one the next value.
+/// To continue a sequence in a new context, start it one past the highest
+/// counter read back so far.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - The returned pointer must be released with `vault_cipher_free`
+///
+/// # Returns
+///
+/// A new context, or null for a null or wrong-length key
+#[no_mangle]
+pub unsafe extern "C" fn vault_cipher_new_counter(key: *const u8, key_len: u32, start_counter: u64) -> *mut VaultCipher {
+    ffi_boundary(|| {
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(_) => return ptr::null_mut(),
+        };
+        match XChaCha20Poly1305::new_from_slice(key_slice) {
+            Ok(cipher) => Box::into_raw(Box::new(VaultCipher {
+                cipher,
+                nonces: NonceSource::Counter(Cell::new(Some(start_counter))),
+            })),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Encrypt under a context's key, in the `vault_seal` format, or the
+/// counter-mode format for a context from `vault_cipher_new_counter`.
+///
+/// In counter mode each call takes the next counter, even if sealing then
+/// fails, so a counter is never used twice.
+///
+/// # Safety
+///
+/// - `ctx` must come from `vault_cipher_new` or `vault_cipher_new_counter`,
+///   not yet be freed, and not be in use on another thread
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the sealed blob, or `ERR_INVALID_INPUT` once a
+/// counter-mode context has used its last counter (`u64::MAX`)
+#[no_mangle]
+pub unsafe extern "C" fn vault_cipher_seal(
+    ctx: *const VaultCipher,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if ctx.is_null() || (plaintext.is_null() && plaintext_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        if let NonceSource::Counter(next) = &(*ctx).nonces {
+            let counter = match next.get() {
+                Some(c) => c,
+                None => return VaultBuffer::error(ERR_INVALID_INPUT),
+            };
+            next.set(counter.checked_add(1));
+            return match counter_seal(&(*ctx).cipher, counter, plaintext_slice) {
+                Ok(sealed) => VaultBuffer::success(sealed),
+                Err(code) => VaultBuffer::error(code),
+            };
+        }
+
+        let header = [FORMAT_XCHACHA];
+        let sealed = match xchacha_seal_with(&(*ctx).cipher, plaintext_slice, &header) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(FORMAT_HEADER_SIZE + sealed.len()) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&sealed);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt a `vault_seal`-format or counter-mode blob under a context's key.
+///
+/// Either kind of context opens either kind of blob.
+///
+/// # Safety
+///
+/// - `ctx` must come from `vault_cipher_new` or `vault_cipher_new_counter`,
+///   not yet be freed, and not be in use on another thread
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_cipher_unseal(
+    ctx: *const VaultCipher,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+        if ctx.is_null() || sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, body) = sealed_slice.split_at(FORMAT_HEADER_SIZE);
+        if header[0] != FORMAT_XCHACHA && header[0] != FORMAT_COUNTER {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+
+        match xchacha_open_with(&(*ctx).cipher, body, header) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt a counter-mode blob and report the counter it was sealed at.
+///
+/// The counter is part of the nonce, so it is only written once the blob
+/// has authenticated.
+///
+/// # Safety
+///
+/// - `ctx` must come from `vault_cipher_new` or `vault_cipher_new_counter`,
+///   not yet be freed, and not be in use on another thread
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - `out_counter` must be writable
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, or `ERR_UNSUPPORTED_VERSION` for a
+/// blob not sealed in counter mode
+#[no_mangle]
+pub unsafe extern "C" fn vault_cipher_unseal_counter(
+    ctx: *const VaultCipher,
+    sealed: *const u8,
+    sealed_len: u32,
+    out_counter: *mut u64,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+        if ctx.is_null() || sealed.is_null() || out_counter.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, body) = sealed_slice.split_at(FORMAT_HEADER_SIZE);
+        if header[0] != FORMAT_COUNTER {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+
+        match xchacha_open_with(&(*ctx).cipher, body, header) {
+            Ok(plaintext) => {
+                let mut counter = [0u8; COUNTER_SIZE];
+                counter.copy_from_slice(&body[..COUNTER_SIZE]);
+                *out_counter = u64::from_le_bytes(counter);
+                VaultBuffer::success(plaintext)
+            }
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Release a cipher context, wiping its key.
+///
+/// # Safety
+///
+/// - `ctx` must come from `vault_cipher_new` or `vault_cipher_new_counter`
+///   and not yet be freed (null is ignored)
+#[no_mangle]
+pub unsafe extern "C" fn vault_cipher_free(ctx: *mut VaultCipher) {
+    ffi_boundary(|| {
+        if !ctx.is_null() {
+            drop(Box::from_raw(ctx));
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cipher_many_seals() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let ctx = vault_cipher_new(key.as_ptr(), 32);
+            assert!(!ctx.is_null());
+
+            let mut nonces = Vec::new();
+            for i in 0..64u32 {
+                let plaintext = format!("item {i}");
+                let sealed = vault_cipher_seal(ctx, plaintext.as_ptr(), plaintext.len() as u32);
+                assert_eq!(sealed.error, 0);
+                let blob = slice::from_raw_parts(sealed.data, sealed.len as usize);
+                nonces.push(blob[FORMAT_HEADER_SIZE..FORMAT_HEADER_SIZE + NONCE_SIZE].to_vec());
+
+                let unsealed = vault_cipher_unseal(ctx, sealed.data, sealed.len);
+                assert_eq!(unsealed.error, 0);
+                assert_eq!(slice::from_raw_parts(unsealed.data, unsealed.len as usize), plaintext.as_bytes());
+                vault_free(unsealed.data, unsealed.len);
+
+                // Same format as the per-call path
+                let plain_path = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(plain_path.error, 0);
+                vault_free(plain_path.data, plain_path.len);
+
+                vault_free(sealed.data, sealed.len);
+            }
+
+            nonces.sort();
+            nonces.dedup();
+            assert_eq!(nonces.len(), 64);
+
+            vault_cipher_free(ctx);
+        }
+    }
+
+    #[test]
+    fn test_cipher_errors() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            assert!(vault_cipher_new(key.as_ptr(), 16).is_null());
+            assert!(vault_cipher_new(ptr::null(), 32).is_null());
+            assert_eq!(vault_cipher_seal(ptr::null(), key.as_ptr(), 1).error, ERR_INVALID_INPUT);
+
+            let ctx = vault_cipher_new(key.as_ptr(), 32);
+            let empty = vault_cipher_seal(ctx, ptr::null(), 0);
+            assert_eq!(empty.error, 0);
+            let opened = vault_cipher_unseal(ctx, empty.data, empty.len);
+            assert_eq!((opened.error, opened.len), (0, 0));
+            assert_eq!(vault_cipher_seal(ctx, ptr::null(), 1).error, ERR_INVALID_INPUT);
+            vault_free(empty.data, empty.len);
+            vault_cipher_free(ctx);
+
+            let other = vault_cipher_new([0x43u8; 32].as_ptr(), 32);
+            let sealed = vault_seal(key.as_ptr(), b"x".as_ptr(), 1);
+            assert_eq!(vault_cipher_unseal(other, sealed.data, sealed.len).error, ERR_DECRYPT_FAILED);
+            assert_eq!(vault_cipher_unseal(other, sealed.data, 8).error, ERR_CORRUPT_DATA);
+            let mut counter = 0u64;
+            assert_eq!(vault_cipher_unseal_counter(other, sealed.data, 8, &mut counter).error, ERR_CORRUPT_DATA);
+            vault_free(sealed.data, sealed.len);
+            vault_cipher_free(other);
+            vault_cipher_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_cipher_counter_increasing() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let ctx = vault_cipher_new_counter(key.as_ptr(), 32, 1000);
+            assert!(!ctx.is_null());
+
+            let mut blobs = Vec::new();
+            for i in 0..16u32 {
+                let plaintext = format!("message {i}");
+                let sealed = vault_cipher_seal(ctx, plaintext.as_ptr(), plaintext.len() as u32);
+                assert_eq!(sealed.error, 0);
+                assert_eq!(*sealed.data, FORMAT_COUNTER);
+                blobs.push((plaintext, sealed));
+            }
+
+            let mut last = None;
+            for (plaintext, sealed) in &blobs {
+                let mut counter = 0u64;
+                let opened = vault_cipher_unseal_counter(ctx, sealed.data, sealed.len, &mut counter);
+                assert_eq!(opened.error, 0);
+                assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext.as_bytes());
+                vault_free(opened.data, opened.len);
+                assert!(last.is_none_or(|last| counter > last));
+                last = Some(counter);
+
+                // A plain context opens it too
+                let plain_ctx = vault_cipher_new(key.as_ptr(), 32);
+                let opened = vault_cipher_unseal(plain_ctx, sealed.data, sealed.len);
+                assert_eq!(opened.error, 0);
+                vault_free(opened.data, opened.len);
+                vault_cipher_free(plain_ctx);
+            }
+            assert_eq!(last, Some(1015));
+
+            // The counter is authenticated
+            let (_, sealed) = &blobs[0];
+            let mut tampered = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+            tampered[FORMAT_HEADER_SIZE] ^= 0x01;
+            let mut counter = 0u64;
+            let result = vault_cipher_unseal_counter(ctx, tampered.as_ptr(), tampered.len() as u32, &mut counter);
+            assert_eq!(result.error, ERR_DECRYPT_FAILED);
+            assert_eq!(counter, 0);
+
+            for (_, sealed) in blobs {
+                vault_free(sealed.data, sealed.len);
+            }
+            vault_cipher_free(ctx);
+        }
+    }
+
+    #[test]
+    fn test_cipher_counter_refuses_wrap() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let ctx = vault_cipher_new_counter(key.as_ptr(), 32, u64::MAX - 1);
+            for expected in [u64::MAX - 1, u64::MAX] {
+                let sealed = vault_cipher_seal(ctx, b"x".as_ptr(), 1);
+                assert_eq!(sealed.error, 0);
+                let mut counter = 0u64;
+                let opened = vault_cipher_unseal_counter(ctx, sealed.data, sealed.len, &mut counter);
+                assert_eq!(opened.error, 0);
+                assert_eq!(counter, expected);
+                vault_free(opened.data, opened.len);
+                vault_free(sealed.data, sealed.len);
+            }
+
+            // Used up: refuses instead of wrapping to 0
+            assert_eq!(vault_cipher_seal(ctx, b"x".as_ptr(), 1).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_cipher_seal(ctx, b"x".as_ptr(), 1).error, ERR_INVALID_INPUT);
+            vault_cipher_free(ctx);
+
+            // Random-mode blobs carry no counter
+            let plain_ctx = vault_cipher_new(key.as_ptr(), 32);
+            let sealed = vault_cipher_seal(plain_ctx, b"x".as_ptr(), 1);
+            let mut counter = 0u64;
+            let result = vault_cipher_unseal_counter(plain_ctx, sealed.data, sealed.len, &mut counter);
+            assert_eq!(result.error, ERR_UNSUPPORTED_VERSION);
+            vault_free(sealed.data, sealed.len);
+            vault_cipher_free(plain_ctx);
+        }
+    }
+}
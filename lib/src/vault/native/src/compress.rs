@@ -0,0 +1,223 @@
+//! Compressed Sealing
+//!
+//! DEFLATE-compresses plaintext before sealing it in the `vault_seal`
+//! layout, flagged by the high bit of the format byte (`0x81`). Intended for
+//! our own structured data such as backups: compressing attacker-influenced
+//! input alongside secrets leaks information through the ciphertext length,
+//! so never use it for data that mixes the two.
+//!
+//! Compression happens into a buffer the size of the plaintext; if the
+//! result would not be smaller, or `level` is 0, the data is stored
+//! uncompressed as an ordinary `0x01` blob that `vault_unseal` also reads.
+//! The compressor's internal dictionary is released without being wiped.
+//!
+//! ## Format
+//!
+//! `format (1, 0x81) || nonce (24) || ciphertext || tag (16)`, where the
+//! plaintext is `original length (4, LE) || deflate stream`.
+
+use std::iter;
+use std::slice;
+
+use miniz_oxide::deflate::core::{
+    compress, create_comp_flags_from_zip_params, CompressorOxide, TDEFLFlush, TDEFLStatus,
+};
+use miniz_oxide::inflate::decompress_slice_iter_to_slice;
+
+use super::*;
+
+/// Highest accepted compression level
+const MAX_COMPRESSION_LEVEL: u32 = 10;
+
+/// Compress to `len || deflate`, or `None` if that is not smaller than `plaintext`.
+fn deflate_payload(plaintext: &[u8], level: u32) -> Option<Zeroizing<Vec<u8>>> {
+    if plaintext.len() <= 4 || plaintext.len() > u32::MAX as usize {
+        return None;
+    }
+
+    let mut payload = Zeroizing::new(output_buffer(plaintext.len()).ok()?);
+    payload.resize(plaintext.len(), 0);
+    payload[..4].copy_from_slice(&(plaintext.len() as u32).to_le_bytes());
+
+    let mut compressor = Box::new(CompressorOxide::new(create_comp_flags_from_zip_params(level as i32, 0, 0)));
+    let (status, _, written) = compress(&mut compressor, plaintext, &mut payload[4..], TDEFLFlush::Finish);
+    if status != TDEFLStatus::Done {
+        return None;
+    }
+
+    payload.truncate(4 + written);
+    Some(payload)
+}
+
+/// Reverse [`deflate_payload`].
+fn inflate_payload(payload: &[u8]) -> VaultResult<Vec<u8>> {
+    if payload.len() < 4 {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let mut len_bytes = [0u8; 4];
+    len_bytes.copy_from_slice(&payload[..4]);
+    let original_len = u32::from_le_bytes(len_bytes) as usize;
+
+    // The length comes from the blob; an absurd one fails cleanly here
+    let mut plaintext = Zeroizing::new(output_buffer(original_len)?);
+    plaintext.resize(original_len, 0);
+    match decompress_slice_iter_to_slice(&mut plaintext, iter::once(&payload[4..]), false, true) {
+        Ok(n) if n == original_len => Ok(mem::take(&mut *plaintext)),
+        _ => Err(ERR_CORRUPT_DATA),
+    }
+}
+
+/// Compress, then encrypt with XChaCha20-Poly1305.
+///
+/// `level` is 0 (store) to 10 (smallest); 6 is a good default.
+///
+/// # Format
+///
+/// Output: `0x81 || nonce (24) || ciphertext || tag (16)` when compressed,
+/// otherwise an ordinary `vault_seal` blob
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_compressed(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    level: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if (plaintext.is_null() && plaintext_len != 0) || level > MAX_COMPRESSION_LEVEL {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let compressed = if level == 0 { None } else { deflate_payload(plaintext_slice, level) };
+        let result = match compressed {
+            Some(payload) => {
+                let header = [FORMAT_XCHACHA | FORMAT_COMPRESSED];
+                xchacha_seal(key_slice, &payload, &header).and_then(|sealed| {
+                    let mut output = output_buffer(FORMAT_HEADER_SIZE + sealed.len())?;
+                    output.extend_from_slice(&header);
+                    output.extend_from_slice(&sealed);
+                    Ok(output)
+                })
+            }
+            None => seal_blob(key_slice, plaintext_slice),
+        };
+
+        match result {
+            Ok(output) => VaultBuffer::success(output),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt a blob from `vault_seal_compressed` (or `vault_seal`),
+/// decompressing when the format byte says so.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_compressed(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, body) = sealed_slice.split_at(FORMAT_HEADER_SIZE);
+        let result = if header[0] == FORMAT_XCHACHA | FORMAT_COMPRESSED {
+            xchacha_open(key_slice, body, header).and_then(|payload| inflate_payload(&Zeroizing::new(payload)))
+        } else {
+            open_blob(key_slice, sealed_slice)
+        };
+
+        match result {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn roundtrip(key: &[u8], plaintext: &[u8], level: u32) -> Vec<u8> {
+        let sealed = vault_seal_compressed(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32, level);
+        assert_eq!(sealed.error, 0);
+        let blob = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+
+        let unsealed = vault_unseal_compressed(key.as_ptr(), 32, sealed.data, sealed.len);
+        assert_eq!(unsealed.error, 0);
+        assert_eq!(slice::from_raw_parts(unsealed.data, unsealed.len as usize), plaintext);
+
+        vault_free(unsealed.data, unsealed.len);
+        vault_free(sealed.data, sealed.len);
+        blob
+    }
+
+    #[test]
+    fn test_compressed_compressible() {
+        let key = [0x42u8; 32];
+        let plaintext = br#"{"account":"savings","balance":0,"memo":""},"#.repeat(200);
+
+        unsafe {
+            let blob = roundtrip(&key, &plaintext, 6);
+            assert_eq!(blob[0], FORMAT_XCHACHA | FORMAT_COMPRESSED);
+            assert!(blob.len() < plaintext.len() / 4);
+
+            // Level 0 stores an ordinary blob
+            let stored = roundtrip(&key, &plaintext, 0);
+            assert_eq!(stored[0], FORMAT_XCHACHA);
+            assert_eq!(stored.len(), FORMAT_HEADER_SIZE + NONCE_SIZE + plaintext.len() + TAG_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_compressed_incompressible() {
+        let key = [0x42u8; 32];
+        let mut plaintext = [0u8; 4096];
+        random_bytes(&mut plaintext).unwrap();
+
+        unsafe {
+            // Falls back to storing, so vault_unseal reads it too
+            let blob = roundtrip(&key, &plaintext, 9);
+            assert_eq!(blob[0], FORMAT_XCHACHA);
+
+            let unsealed = vault_unseal(key.as_ptr(), blob.as_ptr(), blob.len() as u32);
+            assert_eq!(unsealed.error, 0);
+            vault_free(unsealed.data, unsealed.len);
+        }
+    }
+}
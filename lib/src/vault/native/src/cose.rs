@@ -0,0 +1,371 @@
+//! COSE Export
+//!
+//! Seals as a CBOR `COSE_Encrypt0` message (RFC 9052), so backups can be
+//! inspected and opened with standard COSE libraries.
+//!
+//! XChaCha20-Poly1305 has no registered COSE algorithm identifier, so the
+//! export uses ChaCha20/Poly1305 (algorithm 24, RFC 9053) with a random
+//! 12-byte IV, the same cipher as the `ietf` module; its per-key message
+//! limit applies here too.
+//!
+//! ## Format
+//!
+//! ```text
+//! 16([                         / COSE_Encrypt0 tag /
+//!   << {1: 24} >>,             / protected: alg ChaCha20/Poly1305 /
+//!   {5: h'<iv (12)>'},         / unprotected: IV /
+//!   h'<ciphertext || tag (16)>'
+//! ])
+//! ```
+//!
+//! The associated data is the RFC 9052 `Enc_structure`
+//! `["Encrypt0", protected, h'']`. `vault_unseal_cose` accepts the message
+//! with or without the tag, requires the algorithm in the protected header,
+//! and rejects any other algorithm with `ERR_UNSUPPORTED_VERSION`.
+
+use std::slice;
+
+use crate::ietf::{ietf_decrypt, ietf_encrypt, IETF_NONCE_SIZE};
+
+use super::*;
+
+/// COSE algorithm identifier for ChaCha20/Poly1305 (RFC 9053)
+const COSE_ALG_CHACHA20_POLY1305: u64 = 24;
+
+/// COSE header labels
+const COSE_HEADER_ALG: u64 = 1;
+const COSE_HEADER_IV: u64 = 5;
+
+/// CBOR tag for COSE_Encrypt0
+const COSE_TAG_ENCRYPT0: u64 = 16;
+
+/// CBOR major types
+const CBOR_UINT: u8 = 0;
+const CBOR_NINT: u8 = 1;
+const CBOR_BYTES: u8 = 2;
+const CBOR_TEXT: u8 = 3;
+const CBOR_ARRAY: u8 = 4;
+const CBOR_MAP: u8 = 5;
+const CBOR_TAG: u8 = 6;
+
+/// Append a CBOR head: major type and argument, shortest form.
+fn cbor_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    match arg {
+        0..=23 => out.push(major | arg as u8),
+        24..=0xFF => out.extend_from_slice(&[major | 24, arg as u8]),
+        0x100..=0xFFFF => {
+            out.push(major | 25);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..=0xFFFF_FFFF => {
+            out.push(major | 26);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+/// Append a CBOR byte string.
+fn cbor_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    cbor_head(out, CBOR_BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// The protected header: `{1: 24}`.
+fn protected_header() -> Vec<u8> {
+    let mut header = Vec::new();
+    cbor_head(&mut header, CBOR_MAP, 1);
+    cbor_head(&mut header, CBOR_UINT, COSE_HEADER_ALG);
+    cbor_head(&mut header, CBOR_UINT, COSE_ALG_CHACHA20_POLY1305);
+    header
+}
+
+/// The RFC 9052 `Enc_structure` for a COSE_Encrypt0 with no external AAD.
+fn enc_structure(protected: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(16 + protected.len());
+    cbor_head(&mut aad, CBOR_ARRAY, 3);
+    cbor_head(&mut aad, CBOR_TEXT, 8);
+    aad.extend_from_slice(b"Encrypt0");
+    cbor_bytes(&mut aad, protected);
+    cbor_bytes(&mut aad, &[]);
+    aad
+}
+
+/// A definite-length CBOR reader over a byte slice.
+struct CborReader<'a> {
+    data: &'a [u8],
+}
+
+/// A header map value: only integers and strings appear in our headers.
+enum HeaderValue<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> CborReader<'a> {
+    fn take(&mut self, len: usize) -> VaultResult<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(ERR_CORRUPT_DATA);
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    /// Read a head, returning (major type, argument).
+    fn head(&mut self) -> VaultResult<(u8, u64)> {
+        let initial = self.take(1)?[0];
+        let arg = match initial & 0x1F {
+            n @ 0..=23 => n as u64,
+            24 => self.take(1)?[0] as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            // Indefinite lengths and reserved values
+            _ => return Err(ERR_CORRUPT_DATA),
+        };
+        Ok((initial >> 5, arg))
+    }
+
+    fn expect(&mut self, major: u8) -> VaultResult<u64> {
+        match self.head()? {
+            (m, arg) if m == major => Ok(arg),
+            _ => Err(ERR_CORRUPT_DATA),
+        }
+    }
+
+    fn bytes(&mut self) -> VaultResult<&'a [u8]> {
+        let len = self.expect(CBOR_BYTES)?;
+        self.take(usize::try_from(len).map_err(|_| ERR_CORRUPT_DATA)?)
+    }
+
+    /// Read a header map of integer labels to integer or string values.
+    fn header_map(&mut self) -> VaultResult<Vec<(i64, HeaderValue<'a>)>> {
+        let count = self.expect(CBOR_MAP)?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let label = self.int()?;
+            let value = match self.head()? {
+                (CBOR_UINT, n) => HeaderValue::Int(i64::try_from(n).map_err(|_| ERR_CORRUPT_DATA)?),
+                (CBOR_NINT, n) => HeaderValue::Int(-1 - i64::try_from(n).map_err(|_| ERR_CORRUPT_DATA)?),
+                (CBOR_BYTES | CBOR_TEXT, len) => {
+                    HeaderValue::Bytes(self.take(usize::try_from(len).map_err(|_| ERR_CORRUPT_DATA)?)?)
+                }
+                _ => return Err(ERR_CORRUPT_DATA),
+            };
+            entries.push((label, value));
+        }
+        Ok(entries)
+    }
+
+    fn int(&mut self) -> VaultResult<i64> {
+        match self.head()? {
+            (CBOR_UINT, n) => i64::try_from(n).map_err(|_| ERR_CORRUPT_DATA),
+            (CBOR_NINT, n) => Ok(-1 - i64::try_from(n).map_err(|_| ERR_CORRUPT_DATA)?),
+            _ => Err(ERR_CORRUPT_DATA),
+        }
+    }
+}
+
+/// Encode a COSE_Encrypt0 message.
+fn cose_seal(key: &[u8], plaintext: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut iv = [0u8; IETF_NONCE_SIZE];
+    random_bytes(&mut iv)?;
+
+    let protected = protected_header();
+    let ciphertext = ietf_encrypt(key, &iv, plaintext, &enc_structure(&protected))?;
+
+    let mut output = output_buffer(32 + protected.len() + ciphertext.len())?;
+    cbor_head(&mut output, CBOR_TAG, COSE_TAG_ENCRYPT0);
+    cbor_head(&mut output, CBOR_ARRAY, 3);
+    cbor_bytes(&mut output, &protected);
+    cbor_head(&mut output, CBOR_MAP, 1);
+    cbor_head(&mut output, CBOR_UINT, COSE_HEADER_IV);
+    cbor_bytes(&mut output, &iv);
+    cbor_bytes(&mut output, &ciphertext);
+    Ok(output)
+}
+
+/// Parse and decrypt a COSE_Encrypt0 message.
+fn cose_open(key: &[u8], message: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut reader = CborReader { data: message };
+    if message.first() == Some(&((CBOR_TAG << 5) | COSE_TAG_ENCRYPT0 as u8)) {
+        reader.head()?;
+    }
+    if reader.expect(CBOR_ARRAY)? != 3 {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let protected = reader.bytes()?;
+    let unprotected = reader.header_map()?;
+    let ciphertext = reader.bytes()?;
+    if !reader.data.is_empty() || ciphertext.len() < TAG_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    let alg = CborReader { data: protected }
+        .header_map()?
+        .into_iter()
+        .find(|(label, _)| *label == COSE_HEADER_ALG as i64)
+        .map(|(_, value)| value);
+    if !matches!(alg, Some(HeaderValue::Int(alg)) if alg == COSE_ALG_CHACHA20_POLY1305 as i64) {
+        return Err(ERR_UNSUPPORTED_VERSION);
+    }
+
+    let iv = match unprotected.iter().find(|(label, _)| *label == COSE_HEADER_IV as i64) {
+        Some((_, HeaderValue::Bytes(iv))) if iv.len() == IETF_NONCE_SIZE => *iv,
+        _ => return Err(ERR_CORRUPT_DATA),
+    };
+
+    ietf_decrypt(key, iv, ciphertext, &enc_structure(protected))
+}
+
+/// Encrypt into a CBOR COSE_Encrypt0 message (ChaCha20/Poly1305).
+///
+/// See the module documentation for the message layout.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_cose(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if plaintext.is_null() && plaintext_len != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        match cose_seal(key_slice, plaintext_slice) {
+            Ok(message) => VaultBuffer::success(message),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt a COSE_Encrypt0 message produced by `vault_seal_cose` or by
+/// another COSE implementation using ChaCha20/Poly1305.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `message` must be valid for `message_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_UNSUPPORTED_VERSION` for any
+/// other algorithm, `ERR_CORRUPT_DATA` for a malformed message, or
+/// `ERR_DECRYPT_FAILED`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_cose(
+    key: *const u8,
+    key_len: u32,
+    message: *const u8,
+    message_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if message.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let message_slice = slice::from_raw_parts(message, message_len as usize);
+
+        match cose_open(key_slice, message_slice) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cose_structure() {
+        let key = [0x42u8; 32];
+        let plaintext = b"inspectable backup";
+        let message = cose_seal(&key, plaintext).unwrap();
+
+        // 16([h'A1011818', {5: h'<12>'}, h'<ct || tag>'])
+        assert_eq!(&message[..8], &[0xD0, 0x83, 0x44, 0xA1, 0x01, 0x18, 0x18, 0xA1]);
+        assert_eq!(&message[8..10], &[0x05, 0x4C]);
+        assert_eq!(message[22], 0x40 | 24);
+        assert_eq!(message[23] as usize, plaintext.len() + TAG_SIZE);
+        assert_eq!(message.len(), 24 + plaintext.len() + TAG_SIZE);
+
+        // The whole buffer is one well-formed item
+        let mut reader = CborReader { data: &message };
+        assert_eq!(reader.head().unwrap(), (CBOR_TAG, COSE_TAG_ENCRYPT0));
+        assert_eq!(reader.expect(CBOR_ARRAY).unwrap(), 3);
+        reader.bytes().unwrap();
+        assert_eq!(reader.header_map().unwrap().len(), 1);
+        reader.bytes().unwrap();
+        assert!(reader.data.is_empty());
+    }
+
+    #[test]
+    fn test_cose_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintext = b"inspectable backup";
+
+        unsafe {
+            let sealed = vault_seal_cose(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            let message = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+
+            let opened = vault_unseal_cose(key.as_ptr(), 32, sealed.data, sealed.len);
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+            vault_free(sealed.data, sealed.len);
+
+            // Untagged is accepted; a wrong key is not
+            assert_eq!(cose_open(&key, &message[1..]).unwrap(), plaintext);
+            assert_eq!(cose_open(&[0x43u8; 32], &message), Err(ERR_DECRYPT_FAILED));
+        }
+    }
+
+    #[test]
+    fn test_cose_rejects_other_algorithm() {
+        let key = [0x42u8; 32];
+
+        // Protected header {1: 3} (A256GCM) instead of {1: 24}
+        let mut message = cose_seal(&key, b"aes expected").unwrap();
+        message.splice(2..7, [0x43, 0xA1, 0x01, 0x03]);
+        assert_eq!(cose_open(&key, &message), Err(ERR_UNSUPPORTED_VERSION));
+
+        // No algorithm at all
+        let mut message = cose_seal(&key, b"aes expected").unwrap();
+        message.splice(2..7, [0x41, 0xA0]);
+        assert_eq!(cose_open(&key, &message), Err(ERR_UNSUPPORTED_VERSION));
+
+        // Truncated and trailing data are malformed
+        let good = cose_seal(&key, b"x").unwrap();
+        assert_eq!(cose_open(&key, &good[..good.len() - 1]), Err(ERR_CORRUPT_DATA));
+        assert_eq!(cose_open(&key, &[&good[..], &[0]].concat()), Err(ERR_CORRUPT_DATA));
+    }
+}
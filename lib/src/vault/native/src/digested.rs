@@ -0,0 +1,207 @@
+//! Seals with a Plaintext Digest
+//!
+//! Backups record a digest of what was sealed so a later restore can be
+//! checked end to end, not just authenticated. `vault_seal_with_digest`
+//! hashes the plaintext as it copies it into the output buffer, so the
+//! caller's data is read once, and `vault_unseal_with_digest` recomputes
+//! the digest after decrypting and compares it with the stored one.
+//!
+//! Blobs are in the `vault_seal` format and interchangeable with it. The
+//! digest is BLAKE3 (32 bytes), the same as `vault_blake3` of the
+//! plaintext, and is not secret-keyed: store it alongside the blob only
+//! where revealing a hash of the plaintext is acceptable.
+
+use std::slice;
+
+use super::*;
+
+/// Size of the plaintext digest
+const DIGEST_SIZE: usize = 32;
+
+/// Plaintext copied and hashed per step while filling the output buffer
+const DIGEST_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Seal `plaintext` in the `vault_seal` format and return its BLAKE3 digest.
+fn seal_hashing(key: &[u8], plaintext: &[u8]) -> VaultResult<(Vec<u8>, [u8; DIGEST_SIZE])> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?;
+    let header = [FORMAT_XCHACHA];
+    let mut nonce_bytes = Secret::new([0u8; NONCE_SIZE]);
+    random_bytes(nonce_bytes.as_mut())?;
+
+    let mut output = Secret::with_capacity(FORMAT_HEADER_SIZE + NONCE_SIZE + plaintext.len() + TAG_SIZE)?;
+    output.extend_from_slice(&header);
+    output.extend_from_slice(nonce_bytes.as_ref());
+
+    // Hash each chunk while it is in cache from the copy
+    let mut hasher = blake3::Hasher::new();
+    for chunk in plaintext.chunks(DIGEST_CHUNK_SIZE) {
+        output.extend_from_slice(chunk);
+        hasher.update(chunk);
+    }
+
+    let tag = cipher
+        .encrypt_in_place_detached(XNonce::from_slice(nonce_bytes.as_ref()), &header, &mut output[FORMAT_HEADER_SIZE + NONCE_SIZE..])
+        .map_err(|_| ERR_INVALID_INPUT)?;
+    output.extend_from_slice(&tag);
+
+    Ok((output.into_inner(), *hasher.finalize().as_bytes()))
+}
+
+/// Seal data and write the BLAKE3 digest of the plaintext.
+///
+/// # Format
+///
+/// Output: `format (1) || nonce (24) || ciphertext || tag (16)`, as
+/// `vault_seal`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes (may be null when
+///   `plaintext_len` is 0)
+/// - `out_digest` must be writable for 32 bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the sealed blob; `out_digest` is only written on
+/// success
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_with_digest(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    out_digest: *mut u8,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_digest.is_null() || (plaintext.is_null() && plaintext_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        match seal_hashing(key_slice, plaintext_slice) {
+            Ok((sealed, digest)) => {
+                ptr::copy_nonoverlapping(digest.as_ptr(), out_digest, DIGEST_SIZE);
+                VaultBuffer::success(sealed)
+            }
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt a `vault_seal` blob and check the plaintext against a digest.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - `expected_digest` must point to 32 bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_DECRYPT_FAILED` for a wrong
+/// key or tampered blob, or `ERR_CORRUPT_DATA` if the blob decrypts but its
+/// plaintext does not match `expected_digest`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_with_digest(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+    expected_digest: *const u8,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() || expected_digest.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+        let expected = slice::from_raw_parts(expected_digest, DIGEST_SIZE);
+
+        let mut plaintext = match open_blob(key_slice, sealed_slice) {
+            Ok(p) => Zeroizing::new(p),
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if !ct_eq(blake3::hash(&plaintext).as_bytes(), expected) {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        VaultBuffer::success(mem::take(&mut *plaintext))
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_matches_blake3() {
+        let key = [0x42u8; 32];
+        // Spans several copy chunks
+        let plaintext: Vec<u8> = (0..DIGEST_CHUNK_SIZE * 2 + 100).map(|i| i as u8).collect();
+        let mut digest = [0u8; DIGEST_SIZE];
+
+        unsafe {
+            let sealed = vault_seal_with_digest(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32, digest.as_mut_ptr());
+            assert_eq!(sealed.error, 0);
+
+            let standalone = vault_blake3(plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(slice::from_raw_parts(standalone.data, standalone.len as usize), &digest);
+            vault_free(standalone.data, standalone.len);
+
+            // A plain vault_seal blob
+            let opened = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+
+            let opened = vault_unseal_with_digest(key.as_ptr(), 32, sealed.data, sealed.len, digest.as_ptr());
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+
+    #[test]
+    fn test_digest_mismatch_detected() {
+        let key = [0x42u8; 32];
+        let mut digest = [0u8; DIGEST_SIZE];
+
+        unsafe {
+            let sealed = vault_seal_with_digest(key.as_ptr(), 32, b"backup".as_ptr(), 6, digest.as_mut_ptr());
+            assert_eq!(sealed.error, 0);
+
+            // Authentic blob, but not the plaintext the digest was recorded for
+            digest[0] ^= 0x01;
+            let result = vault_unseal_with_digest(key.as_ptr(), 32, sealed.data, sealed.len, digest.as_ptr());
+            assert_eq!(result.error, ERR_CORRUPT_DATA);
+            assert!(result.data.is_null());
+
+            let wrong_key = [0x43u8; 32];
+            digest[0] ^= 0x01;
+            let result = vault_unseal_with_digest(wrong_key.as_ptr(), 32, sealed.data, sealed.len, digest.as_ptr());
+            assert_eq!(result.error, ERR_DECRYPT_FAILED);
+            vault_free(sealed.data, sealed.len);
+
+            let empty = vault_seal_with_digest(key.as_ptr(), 32, ptr::null(), 0, digest.as_mut_ptr());
+            assert_eq!(empty.error, 0);
+            assert_eq!(digest, *blake3::hash(b"").as_bytes());
+            vault_free(empty.data, empty.len);
+        }
+    }
+}
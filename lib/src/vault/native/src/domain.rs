@@ -0,0 +1,209 @@
+//! Domain-Separated Seals
+//!
+//! A blob sealed for one purpose must not open on the unseal path of
+//! another, even under the same key: otherwise a stored note could be
+//! replayed as a sync message, or a backup fed to the export importer. Each
+//! purpose has a domain id, and a fixed tag for that domain is authenticated
+//! as associated data, so unsealing under any other domain fails exactly as
+//! a wrong key does.
+//!
+//! ## Domains
+//!
+//! | Id | Purpose |
+//! |----|---------|
+//! | 1 | Stored notes and vault entries |
+//! | 2 | Sync messages between devices |
+//! | 3 | Backups |
+//! | 4 | Exports for other apps |
+//!
+//! ## Format
+//!
+//! `format (1, 0x0C) || nonce (24) || ciphertext || tag (16)`
+//!
+//! Associated data is `format || domain tag`. The domain id itself is not
+//! stored.
+
+use std::slice;
+
+use super::*;
+
+// Domain selectors accepted at the FFI boundary
+const DOMAIN_NOTE: u32 = 1;
+const DOMAIN_SYNC: u32 = 2;
+const DOMAIN_BACKUP: u32 = 3;
+const DOMAIN_EXPORT: u32 = 4;
+
+/// The fixed tag authenticated for a domain id.
+fn domain_tag(domain_id: u32) -> VaultResult<&'static [u8]> {
+    match domain_id {
+        DOMAIN_NOTE => Ok(b"vault_core 2025-01 domain note"),
+        DOMAIN_SYNC => Ok(b"vault_core 2025-01 domain sync"),
+        DOMAIN_BACKUP => Ok(b"vault_core 2025-01 domain backup"),
+        DOMAIN_EXPORT => Ok(b"vault_core 2025-01 domain export"),
+        _ => Err(ERR_INVALID_INPUT),
+    }
+}
+
+/// Associated data for a domain blob: format byte, then the domain tag.
+fn domain_aad(tag: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(FORMAT_HEADER_SIZE + tag.len());
+    aad.push(FORMAT_DOMAIN);
+    aad.extend_from_slice(tag);
+    aad
+}
+
+/// Seal data so it only opens under the same domain.
+///
+/// # Format
+///
+/// Output: `format (1) || nonce (24) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes (may be null when
+///   `plaintext_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the sealed blob, or `ERR_INVALID_INPUT` for an
+/// unknown `domain_id`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_domain(
+    key: *const u8,
+    key_len: u32,
+    domain_id: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if plaintext.is_null() && plaintext_len != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let tag = match domain_tag(domain_id) {
+            Ok(t) => t,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let sealed = match xchacha_seal(key_slice, plaintext_slice, &domain_aad(tag)) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(FORMAT_HEADER_SIZE + sealed.len()) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.push(FORMAT_DOMAIN);
+        output.extend_from_slice(&sealed);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt data sealed with `vault_seal_domain` under the same domain.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, or `ERR_DECRYPT_FAILED` for a wrong
+/// key or a blob sealed under a different domain
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_domain(
+    key: *const u8,
+    key_len: u32,
+    domain_id: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let tag = match domain_tag(domain_id) {
+            Ok(t) => t,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+        if sealed_slice.len() < FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+
+        let (header, body) = sealed_slice.split_at(FORMAT_HEADER_SIZE);
+        if header[0] != FORMAT_DOMAIN {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+
+        match xchacha_open(key_slice, body, &domain_aad(tag)) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_mismatch_fails() {
+        let key = [0x42u8; 32];
+        let plaintext = b"stored note";
+
+        unsafe {
+            let sealed = vault_seal_domain(key.as_ptr(), 32, DOMAIN_NOTE, plaintext.as_ptr(), 11);
+            assert_eq!(sealed.error, 0);
+            assert_eq!(*sealed.data, FORMAT_DOMAIN);
+
+            let same = vault_unseal_domain(key.as_ptr(), 32, DOMAIN_NOTE, sealed.data, sealed.len);
+            assert_eq!(same.error, 0);
+            assert_eq!(slice::from_raw_parts(same.data, same.len as usize), plaintext);
+            vault_free(same.data, same.len);
+
+            // Same key, different purpose
+            let other = vault_unseal_domain(key.as_ptr(), 32, DOMAIN_SYNC, sealed.data, sealed.len);
+            assert_eq!(other.error, ERR_DECRYPT_FAILED);
+            assert!(other.data.is_null());
+
+            // Nor does it open on the plain unseal path
+            let plain = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+            assert_eq!(plain.error, ERR_UNSUPPORTED_VERSION);
+
+            let unknown = vault_unseal_domain(key.as_ptr(), 32, 0, sealed.data, sealed.len);
+            assert_eq!(unknown.error, ERR_INVALID_INPUT);
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+
+    #[test]
+    fn test_domain_tags_distinct() {
+        let tags: Vec<_> = [DOMAIN_NOTE, DOMAIN_SYNC, DOMAIN_BACKUP, DOMAIN_EXPORT]
+            .iter()
+            .map(|&id| domain_tag(id).unwrap())
+            .collect();
+        for (i, a) in tags.iter().enumerate() {
+            assert!(tags[i + 1..].iter().all(|b| a != b));
+        }
+        assert_eq!(domain_tag(5), Err(ERR_INVALID_INPUT));
+    }
+}
@@ -0,0 +1,343 @@
+//! Ed25519 Signatures
+//!
+//! Signing for sync-protocol messages and Solana-style transactions, so the
+//! secret half of the key stays in wiped native memory instead of the Dart
+//! heap. Signatures are plain RFC 8032 Ed25519 (not Ed25519ph or ctx).
+//!
+//! ## Keypair Format
+//!
+//! ```text
+//! [seed: 32 bytes][public key: 32 bytes]
+//! ```
+//!
+//! The layout libsodium and Solana keypair files use. The seed is the
+//! secret; the public key is carried so signing can check the pair matches.
+//!
+//! Verification is strict: non-canonical encodings and small-order public
+//! keys are rejected, so a message has one valid signature per key.
+//!
+//! A seed can instead stay behind a key handle: import it with
+//! `vault_key_import_for` (purpose 2), or derive it with the SLIP-0010 functions of `hd`,
+//! and sign with `vault_ed25519_sign_with_handle`.
+
+use std::slice;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+
+use crate::keyhandle::{key_for, KeyPurpose};
+
+use super::*;
+
+/// Ed25519 seed size
+const ED25519_SEED_SIZE: usize = 32;
+
+/// Ed25519 public key size
+pub(crate) const ED25519_PUBLIC_KEY_SIZE: usize = 32;
+
+/// Seed followed by public key
+const ED25519_KEYPAIR_SIZE: usize = ED25519_SEED_SIZE + ED25519_PUBLIC_KEY_SIZE;
+
+/// Ed25519 signature size
+pub(crate) const ED25519_SIGNATURE_SIZE: usize = 64;
+
+/// Borrow a message argument (null is allowed only when empty).
+unsafe fn message_arg<'a>(message: *const u8, message_len: u32) -> VaultResult<&'a [u8]> {
+    if message_len == 0 {
+        return Ok(&[]);
+    }
+    if message.is_null() {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(slice::from_raw_parts(message, message_len as usize))
+}
+
+/// The public key for a seed.
+pub(crate) fn ed25519_public_key(seed: &[u8; ED25519_SEED_SIZE]) -> [u8; ED25519_PUBLIC_KEY_SIZE] {
+    SigningKey::from_bytes(seed).verifying_key().to_bytes()
+}
+
+/// Expand a 32-byte seed into an Ed25519 keypair.
+///
+/// # Format
+///
+/// See the module documentation: `seed || public key`. The public key is
+/// the last 32 bytes.
+///
+/// # Safety
+///
+/// - `seed` must point to exactly 32 bytes (`seed_len` must be 32)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 64-byte keypair, `ERR_BAD_KEY_SIZE`, or
+/// `ERR_INVALID_INPUT` for a null seed
+#[no_mangle]
+pub unsafe extern "C" fn vault_ed25519_keypair_from_seed(seed: *const u8, seed_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if seed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if seed_len as usize != ED25519_SEED_SIZE {
+            return VaultBuffer::error(ERR_BAD_KEY_SIZE);
+        }
+        let mut seed_copy = Secret::new([0u8; ED25519_SEED_SIZE]);
+        seed_copy.copy_from_slice(slice::from_raw_parts(seed, ED25519_SEED_SIZE));
+
+        let signing_key = SigningKey::from_bytes(&seed_copy);
+        let keypair = Secret::new(signing_key.to_keypair_bytes());
+        VaultBuffer::success(keypair.to_vec())
+    })
+}
+
+/// Sign a message with an Ed25519 keypair.
+///
+/// # Safety
+///
+/// - `keypair` must point to exactly 64 bytes (`keypair_len` must be 64)
+/// - `message` must be valid for `message_len` bytes (may be null when
+///   `message_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 64-byte signature, `ERR_BAD_KEY_SIZE`, or
+/// `ERR_INVALID_INPUT` for a null pointer or a public key that does not
+/// belong to the seed
+#[no_mangle]
+pub unsafe extern "C" fn vault_ed25519_sign(
+    keypair: *const u8,
+    keypair_len: u32,
+    message: *const u8,
+    message_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if keypair.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if keypair_len as usize != ED25519_KEYPAIR_SIZE {
+            return VaultBuffer::error(ERR_BAD_KEY_SIZE);
+        }
+        let message_slice = match message_arg(message, message_len) {
+            Ok(m) => m,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let mut keypair_copy = Secret::new([0u8; ED25519_KEYPAIR_SIZE]);
+        keypair_copy.copy_from_slice(slice::from_raw_parts(keypair, ED25519_KEYPAIR_SIZE));
+
+        let signing_key = match SigningKey::from_keypair_bytes(&keypair_copy) {
+            Ok(k) => k,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+        VaultBuffer::success(signing_key.sign(message_slice).to_bytes().to_vec())
+    })
+}
+
+/// Sign a message with the Ed25519 seed behind `key_handle`.
+///
+/// # Safety
+///
+/// - `message` must be valid for `message_len` bytes (may be null when
+///   `message_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 64-byte signature, or `ERR_INVALID_INPUT` for
+/// a null message or an unknown handle or one that is not an Ed25519 key
+#[no_mangle]
+pub unsafe extern "C" fn vault_ed25519_sign_with_handle(key_handle: u64, message: *const u8, message_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let message_slice = match message_arg(message, message_len) {
+            Ok(m) => m,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let seed = match key_for(key_handle, KeyPurpose::Ed25519) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let signing_key = SigningKey::from_bytes(&seed);
+        VaultBuffer::success(signing_key.sign(message_slice).to_bytes().to_vec())
+    })
+}
+
+/// The public key of the Ed25519 seed behind `key_handle`.
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte public key, or `ERR_INVALID_INPUT` for
+/// an unknown handle or one that is not an Ed25519 key
+#[no_mangle]
+pub unsafe extern "C" fn vault_ed25519_public_key(key_handle: u64) -> VaultBuffer {
+    ffi_boundary(|| {
+        let seed = match key_for(key_handle, KeyPurpose::Ed25519) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        VaultBuffer::success(ed25519_public_key(&seed).to_vec())
+    })
+}
+
+/// Verify an Ed25519 signature.
+///
+/// # Safety
+///
+/// - `public_key` must point to exactly 32 bytes (`public_key_len` must be 32)
+/// - `message` must be valid for `message_len` bytes (may be null when
+///   `message_len` is 0)
+/// - `signature` must point to exactly 64 bytes (`signature_len` must be 64)
+///
+/// # Returns
+///
+/// 0 if the signature is valid, `ERR_DECRYPT_FAILED` if it is not (including
+/// a public key that is not a valid point), `ERR_BAD_KEY_SIZE`, or
+/// `ERR_INVALID_INPUT` for a null pointer or a signature of the wrong length
+#[no_mangle]
+pub unsafe extern "C" fn vault_ed25519_verify(
+    public_key: *const u8,
+    public_key_len: u32,
+    message: *const u8,
+    message_len: u32,
+    signature: *const u8,
+    signature_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if public_key.is_null() || signature.is_null() || signature_len as usize != ED25519_SIGNATURE_SIZE {
+            return ERR_INVALID_INPUT;
+        }
+        if public_key_len as usize != ED25519_PUBLIC_KEY_SIZE {
+            return ERR_BAD_KEY_SIZE;
+        }
+        let message_slice = match message_arg(message, message_len) {
+            Ok(m) => m,
+            Err(code) => return code,
+        };
+        let mut public_bytes = [0u8; ED25519_PUBLIC_KEY_SIZE];
+        public_bytes.copy_from_slice(slice::from_raw_parts(public_key, ED25519_PUBLIC_KEY_SIZE));
+        let mut signature_bytes = [0u8; ED25519_SIGNATURE_SIZE];
+        signature_bytes.copy_from_slice(slice::from_raw_parts(signature, ED25519_SIGNATURE_SIZE));
+
+        let verifying_key = match VerifyingKey::from_bytes(&public_bytes) {
+            Ok(k) => k,
+            Err(_) => return ERR_DECRYPT_FAILED,
+        };
+        match verifying_key.verify_strict(message_slice, &Signature::from_bytes(&signature_bytes)) {
+            Ok(()) => 0,
+            Err(_) => ERR_DECRYPT_FAILED,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let bytes = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        bytes
+    }
+
+    #[test]
+    fn test_ed25519_rfc8032_vectors() {
+        // RFC 8032 section 7.1, tests 1 and 2
+        let vectors = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        ];
+
+        for (seed, public_key, message, signature) in vectors {
+            let (seed, public_key, message, signature) = (hex(seed), hex(public_key), hex(message), hex(signature));
+            unsafe {
+                let keypair = take(vault_ed25519_keypair_from_seed(seed.as_ptr(), 32));
+                assert_eq!(keypair[..32], seed[..]);
+                assert_eq!(keypair[32..], public_key[..]);
+
+                let signed = take(vault_ed25519_sign(keypair.as_ptr(), 64, message.as_ptr(), message.len() as u32));
+                assert_eq!(signed, signature);
+                let rc = vault_ed25519_verify(public_key.as_ptr(), 32, message.as_ptr(), message.len() as u32, signed.as_ptr(), 64);
+                assert_eq!(rc, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ed25519_sign_with_handle() {
+        // RFC 8032 section 7.1, test 2
+        let seed = hex("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb");
+        let message = hex("72");
+
+        unsafe {
+            let mut handle = 0u64;
+            assert_eq!(vault_key_import_for(seed.as_ptr(), 32, 2, &mut handle), 0);
+            assert_eq!(take(vault_ed25519_public_key(handle)), hex("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"));
+            assert_eq!(
+                take(vault_ed25519_sign_with_handle(handle, message.as_ptr(), 1)),
+                hex("92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00")
+            );
+            assert_eq!(vault_ed25519_sign_with_handle(handle, ptr::null(), 1).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_key_destroy(handle), 0);
+            assert_eq!(vault_ed25519_sign_with_handle(handle, message.as_ptr(), 1).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_ed25519_public_key(handle).error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_ed25519_rejections() {
+        let seed = [0x42u8; 32];
+        let message = b"sync message";
+
+        unsafe {
+            let keypair = take(vault_ed25519_keypair_from_seed(seed.as_ptr(), 32));
+            let mut signature = take(vault_ed25519_sign(keypair.as_ptr(), 64, message.as_ptr(), 12));
+            let public_key = &keypair[32..];
+
+            let verify = |message: &[u8], signature: &[u8]| {
+                vault_ed25519_verify(public_key.as_ptr(), 32, message.as_ptr(), message.len() as u32, signature.as_ptr(), 64)
+            };
+            assert_eq!(verify(message, &signature), 0);
+            assert_eq!(verify(b"sync messagE", &signature), ERR_DECRYPT_FAILED);
+            signature[63] ^= 0x01;
+            assert_eq!(verify(message, &signature), ERR_DECRYPT_FAILED);
+
+            // A public key that is not the seed's
+            let mut mismatched = keypair.clone();
+            mismatched[63] ^= 0x01;
+            assert_eq!(vault_ed25519_sign(mismatched.as_ptr(), 64, message.as_ptr(), 12).error, ERR_INVALID_INPUT);
+
+            assert_eq!(vault_ed25519_keypair_from_seed(seed.as_ptr(), 31).error, ERR_BAD_KEY_SIZE);
+            assert_eq!(vault_ed25519_sign(keypair.as_ptr(), 32, message.as_ptr(), 12).error, ERR_BAD_KEY_SIZE);
+            assert_eq!(vault_ed25519_sign(keypair.as_ptr(), 64, ptr::null(), 12).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_ed25519_verify(public_key.as_ptr(), 32, message.as_ptr(), 12, signature.as_ptr(), 63), ERR_INVALID_INPUT);
+            assert_eq!(vault_ed25519_verify(public_key.as_ptr(), 31, message.as_ptr(), 12, signature.as_ptr(), 64), ERR_BAD_KEY_SIZE);
+        }
+    }
+}
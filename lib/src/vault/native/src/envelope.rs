@@ -0,0 +1,149 @@
+//! Envelope Encryption
+//!
+//! Vault data is sealed under a random data-encryption key (DEK), and only
+//! the DEK is sealed under the passphrase-derived key-encryption key (KEK).
+//! Changing the passphrase then means re-wrapping one small blob rather
+//! than unsealing and resealing every item.
+//!
+//! A wrapped DEK is an ordinary `vault_seal` blob of the DEK under the KEK,
+//! so the initial one is made with `vault_derive_key` + `vault_seal`.
+
+use std::slice;
+
+use super::*;
+
+/// Derive a KEK with the default Argon2id parameters.
+fn derive_kek(passphrase: &[u8], salt: &[u8]) -> VaultResult<Secret<[u8; KEY_SIZE]>> {
+    argon2id(passphrase, salt, ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST)
+}
+
+/// Re-wrap a DEK under a new passphrase without returning it to the caller.
+///
+/// Derives the old and new KEKs, unwraps the DEK with the old one and seals
+/// it under the new one. The DEK only ever exists in wiped native memory.
+///
+/// # Format
+///
+/// Input and output: `vault_seal` blobs of the DEK under the respective KEK
+///
+/// # Safety
+///
+/// - `old_pass` must be valid for `old_pass_len` bytes
+/// - `new_pass` must be valid for `new_pass_len` bytes
+/// - `old_salt` and `new_salt` must each point to exactly 16 bytes
+/// - `wrapped_dek` must be valid for `wrapped_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the DEK wrapped under the new passphrase, or
+/// `ERR_DECRYPT_FAILED` if the old passphrase does not unwrap it
+#[no_mangle]
+pub unsafe extern "C" fn vault_change_passphrase(
+    old_pass: *const u8,
+    old_pass_len: u32,
+    old_salt: *const u8,
+    new_pass: *const u8,
+    new_pass_len: u32,
+    new_salt: *const u8,
+    wrapped_dek: *const u8,
+    wrapped_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if old_pass.is_null()
+            || old_salt.is_null()
+            || new_pass.is_null()
+            || new_salt.is_null()
+            || wrapped_dek.is_null()
+            || old_pass_len == 0
+            || new_pass_len == 0
+        {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let old_pass_slice = slice::from_raw_parts(old_pass, old_pass_len as usize);
+        let old_salt_slice = slice::from_raw_parts(old_salt, SALT_SIZE);
+        let new_pass_slice = slice::from_raw_parts(new_pass, new_pass_len as usize);
+        let new_salt_slice = slice::from_raw_parts(new_salt, SALT_SIZE);
+        let wrapped_slice = slice::from_raw_parts(wrapped_dek, wrapped_len as usize);
+
+        let old_kek = match derive_kek(old_pass_slice, old_salt_slice) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let dek = match open_blob(old_kek.as_ref(), wrapped_slice) {
+            Ok(d) => Zeroizing::new(d),
+            Err(code) => return VaultBuffer::error(code),
+        };
+        drop(old_kek);
+
+        let new_kek = match derive_kek(new_pass_slice, new_salt_slice) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        match seal_blob(new_kek.as_ref(), &dek) {
+            Ok(rewrapped) => VaultBuffer::success(rewrapped),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_PASS: &[u8] = b"old passphrase";
+    const NEW_PASS: &[u8] = b"new passphrase";
+    const OLD_SALT: [u8; SALT_SIZE] = [1u8; SALT_SIZE];
+    const NEW_SALT: [u8; SALT_SIZE] = [2u8; SALT_SIZE];
+
+    unsafe fn change(old_pass: &[u8], wrapped: &[u8]) -> VaultBuffer {
+        vault_change_passphrase(
+            old_pass.as_ptr(),
+            old_pass.len() as u32,
+            OLD_SALT.as_ptr(),
+            NEW_PASS.as_ptr(),
+            NEW_PASS.len() as u32,
+            NEW_SALT.as_ptr(),
+            wrapped.as_ptr(),
+            wrapped.len() as u32,
+        )
+    }
+
+    #[test]
+    fn test_change_passphrase() {
+        let dek = [0x77u8; KEY_SIZE];
+
+        let old_kek = derive_kek(OLD_PASS, &OLD_SALT).unwrap();
+        let wrapped = seal_blob(old_kek.as_ref(), &dek).unwrap();
+
+        unsafe {
+            let result = change(OLD_PASS, &wrapped);
+            assert_eq!(result.error, 0);
+            let rewrapped = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+            vault_free(result.data, result.len);
+
+            let new_kek = derive_kek(NEW_PASS, &NEW_SALT).unwrap();
+            assert_eq!(open_blob(new_kek.as_ref(), &rewrapped).unwrap(), dek);
+            assert_eq!(open_blob(old_kek.as_ref(), &rewrapped), Err(ERR_DECRYPT_FAILED));
+        }
+    }
+
+    #[test]
+    fn test_change_passphrase_wrong_old() {
+        let dek = [0x77u8; KEY_SIZE];
+
+        let old_kek = derive_kek(OLD_PASS, &OLD_SALT).unwrap();
+        let wrapped = seal_blob(old_kek.as_ref(), &dek).unwrap();
+
+        unsafe {
+            assert_eq!(change(b"not the old one", &wrapped).error, ERR_DECRYPT_FAILED);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Ephemeral Secrets with Auto-Expiry
+//!
+//! Clipboard-style holding of a secret the user is about to paste (a private
+//! key, a recovery phrase). The bytes are copied into native memory and a
+//! background timer wipes them when the TTL runs out, whether or not anyone
+//! reads them. After that, reads return `ERR_EXPIRED`.
+//!
+//! The secret is only reachable through its handle and behind a lock, and
+//! is zeroized on expiry, on `vault_ephemeral_clear` and on free. Its pages
+//! are locked against swap (`mlock`, or `VirtualLock` on Windows) before
+//! the secret is copied in. Locking is best-effort, since the OS may refuse
+//! it (for example over `RLIMIT_MEMLOCK`); `vault_ephemeral_locked` reports
+//! whether it succeeded. Locks cover whole pages and do not nest, so freeing
+//! one secret can unlock a page it shared with another.
+//!
+//! Not available on the web build (no threads).
+
+use std::slice;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use zeroize::Zeroize;
+
+use super::*;
+
+/// Lock `bytes` into RAM, returning whether the OS allowed it.
+#[cfg(unix)]
+fn lock_memory(bytes: &[u8]) -> bool {
+    unsafe { libc::mlock(bytes.as_ptr().cast(), bytes.len()) == 0 }
+}
+
+#[cfg(unix)]
+fn unlock_memory(bytes: &[u8]) {
+    unsafe {
+        libc::munlock(bytes.as_ptr().cast(), bytes.len());
+    }
+}
+
+/// Lock `bytes` into RAM, returning whether the OS allowed it.
+#[cfg(windows)]
+fn lock_memory(bytes: &[u8]) -> bool {
+    unsafe { windows_sys::Win32::System::Memory::VirtualLock(bytes.as_ptr().cast(), bytes.len()) != 0 }
+}
+
+#[cfg(windows)]
+fn unlock_memory(bytes: &[u8]) {
+    unsafe {
+        windows_sys::Win32::System::Memory::VirtualUnlock(bytes.as_ptr().cast(), bytes.len());
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn lock_memory(_bytes: &[u8]) -> bool {
+    false
+}
+
+#[cfg(not(any(unix, windows)))]
+fn unlock_memory(_bytes: &[u8]) {}
+
+/// Secret bytes in memory locked against swap when the OS allows it,
+/// wiped before they are unlocked and freed
+struct LockedSecret {
+    bytes: Vec<u8>,
+    locked: bool,
+}
+
+impl LockedSecret {
+    /// Lock a fresh buffer, then copy `data` into it.
+    fn new(data: &[u8]) -> VaultResult<Self> {
+        let mut bytes = output_buffer(data.len())?;
+        bytes.resize(data.len(), 0);
+        let locked = lock_memory(&bytes);
+        bytes.copy_from_slice(data);
+        Ok(Self { bytes, locked })
+    }
+}
+
+impl Drop for LockedSecret {
+    fn drop(&mut self) {
+        self.bytes.as_mut_slice().zeroize();
+        if self.locked {
+            unlock_memory(&self.bytes);
+        }
+    }
+}
+
+/// State shared between a handle and its timer thread.
+struct EphemeralState {
+    /// The secret, or `None` once wiped
+    secret: Mutex<Option<LockedSecret>>,
+    /// Signalled when the secret is cleared early, so the timer can exit
+    cleared: Condvar,
+    deadline: Instant,
+    /// Whether the secret's pages were locked
+    locked: bool,
+}
+
+impl EphemeralState {
+    fn lock(&self) -> MutexGuard<'_, Option<LockedSecret>> {
+        // A panic while holding the lock cannot leave the Option half-written
+        self.secret.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wipe the secret now and release the timer.
+    fn wipe(&self) {
+        self.lock().take();
+        self.cleared.notify_all();
+    }
+}
+
+/// Timer thread: wipe at the deadline unless cleared first.
+fn expire_at_deadline(state: Arc<EphemeralState>) {
+    let mut secret = state.lock();
+    while secret.is_some() {
+        let now = Instant::now();
+        if now >= state.deadline {
+            secret.take();
+            return;
+        }
+        secret = match state.cleared.wait_timeout(secret, state.deadline - now) {
+            Ok((guard, _)) => guard,
+            Err(e) => e.into_inner().0,
+        };
+    }
+}
+
+/// A secret held until its TTL expires (opaque to callers)
+pub struct VaultEphemeral {
+    state: Arc<EphemeralState>,
+}
+
+/// Copy a secret into native memory and wipe it after `ttl_millis`.
+///
+/// # Safety
+///
+/// - `data` must be valid for `len` bytes
+/// - The returned pointer must be released with `vault_ephemeral_free`
+///
+/// # Returns
+///
+/// A new handle, or null for a null or empty secret, a zero TTL, if memory
+/// runs out, or if the timer thread cannot be started
+#[no_mangle]
+pub unsafe extern "C" fn vault_ephemeral_store(data: *const u8, len: u32, ttl_millis: u64) -> *mut VaultEphemeral {
+    ffi_boundary(|| {
+        // Validate inputs
+        if data.is_null() || len == 0 || len > i32::MAX as u32 || ttl_millis == 0 {
+            return ptr::null_mut();
+        }
+
+        let deadline = match Instant::now().checked_add(Duration::from_millis(ttl_millis)) {
+            Some(d) => d,
+            None => return ptr::null_mut(),
+        };
+        let secret = match LockedSecret::new(slice::from_raw_parts(data, len as usize)) {
+            Ok(s) => s,
+            Err(_) => return ptr::null_mut(),
+        };
+        let state = Arc::new(EphemeralState {
+            locked: secret.locked,
+            secret: Mutex::new(Some(secret)),
+            cleared: Condvar::new(),
+            deadline,
+        });
+
+        let timer_state = Arc::clone(&state);
+        let spawned = thread::Builder::new()
+            .name("vault-ephemeral".into())
+            .spawn(move || expire_at_deadline(timer_state));
+        if spawned.is_err() {
+            // Nothing would wipe it on time; drop (and wipe) it now instead
+            state.wipe();
+            return ptr::null_mut();
+        }
+
+        Box::into_raw(Box::new(VaultEphemeral { state }))
+    })
+}
+
+/// Copy a stored secret out while its TTL has not expired.
+///
+/// # Safety
+///
+/// - `handle` must come from `vault_ephemeral_store` and not yet be freed
+/// - `out` must be writable for `out_cap` bytes
+///
+/// # Returns
+///
+/// The number of bytes written, `ERR_EXPIRED` once the TTL has passed or the
+/// secret was cleared, or -1 for a null argument or an `out_cap` smaller
+/// than the secret
+#[no_mangle]
+pub unsafe extern "C" fn vault_ephemeral_read(handle: *const VaultEphemeral, out: *mut u8, out_cap: u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if handle.is_null() || out.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+
+        let state = &(*handle).state;
+        let mut secret = state.lock();
+        // Do not rely on the timer having run yet
+        if Instant::now() >= state.deadline {
+            secret.take();
+        }
+        let bytes = match secret.as_ref() {
+            Some(secret) => &secret.bytes,
+            None => return ERR_EXPIRED,
+        };
+        if bytes.len() > out_cap as usize {
+            return ERR_INVALID_INPUT;
+        }
+
+        ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+        bytes.len() as i32
+    })
+}
+
+/// Report whether a stored secret's memory was locked against swap.
+///
+/// Locking is attempted once, when the secret is stored; when the OS
+/// refuses it the secret is still held, wiped and expired as usual.
+///
+/// # Safety
+///
+/// - `handle` must come from `vault_ephemeral_store` and not yet be freed
+///
+/// # Returns
+///
+/// 1 if the memory was locked, 0 if locking failed, or `ERR_INVALID_INPUT`
+/// for a null handle
+#[no_mangle]
+pub unsafe extern "C" fn vault_ephemeral_locked(handle: *const VaultEphemeral) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if handle.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let state = &(*handle).state;
+        state.locked as i32
+    })
+}
+
+/// Wipe a stored secret immediately; later reads return `ERR_EXPIRED`.
+///
+/// The handle stays valid until `vault_ephemeral_free`.
+///
+/// # Safety
+///
+/// - `handle` must come from `vault_ephemeral_store` and not yet be freed
+///   (null is ignored)
+#[no_mangle]
+pub unsafe extern "C" fn vault_ephemeral_clear(handle: *const VaultEphemeral) {
+    ffi_boundary(|| {
+        if !handle.is_null() {
+            (*handle).state.wipe();
+        }
+    })
+}
+
+/// Wipe a stored secret and release its handle.
+///
+/// # Safety
+///
+/// - `handle` must come from `vault_ephemeral_store` and not yet be freed
+///   (null is ignored)
+#[no_mangle]
+pub unsafe extern "C" fn vault_ephemeral_free(handle: *mut VaultEphemeral) {
+    ffi_boundary(|| {
+        if !handle.is_null() {
+            let handle = Box::from_raw(handle);
+            handle.state.wipe();
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"abandon ability able about above absent";
+
+    unsafe fn read(handle: *const VaultEphemeral) -> Result<Vec<u8>, i32> {
+        let mut out = vec![0u8; 64];
+        let n = vault_ephemeral_read(handle, out.as_mut_ptr(), out.len() as u32);
+        if n < 0 {
+            return Err(n);
+        }
+        out.truncate(n as usize);
+        Ok(out)
+    }
+
+    #[test]
+    fn test_ephemeral_read_before_ttl() {
+        unsafe {
+            let handle = vault_ephemeral_store(SECRET.as_ptr(), SECRET.len() as u32, 60_000);
+            assert!(!handle.is_null());
+            assert_eq!(read(handle).unwrap(), SECRET);
+            assert_eq!(read(handle).unwrap(), SECRET);
+
+            let mut small = [0u8; 4];
+            assert_eq!(vault_ephemeral_read(handle, small.as_mut_ptr(), 4), ERR_INVALID_INPUT);
+            vault_ephemeral_free(handle);
+
+            assert!(vault_ephemeral_store(SECRET.as_ptr(), 0, 1000).is_null());
+            assert!(vault_ephemeral_store(SECRET.as_ptr(), 8, 0).is_null());
+        }
+    }
+
+    #[test]
+    fn test_ephemeral_memory_locked() {
+        unsafe {
+            let handle = vault_ephemeral_store(SECRET.as_ptr(), SECRET.len() as u32, 60_000);
+            let locked = vault_ephemeral_locked(handle);
+            assert!(locked == 0 || locked == 1);
+
+            // Where locking succeeded, the kernel counts the locked pages
+            #[cfg(target_os = "linux")]
+            if locked == 1 {
+                let status = std::fs::read_to_string("/proc/self/status").unwrap();
+                let line = status.lines().find(|l| l.starts_with("VmLck:")).unwrap();
+                let kb: u64 = line.split_whitespace().nth(1).unwrap().parse().unwrap();
+                assert!(kb > 0, "{line}");
+            }
+
+            // Clearing keeps the report
+            vault_ephemeral_clear(handle);
+            assert_eq!(vault_ephemeral_locked(handle), locked);
+            vault_ephemeral_free(handle);
+            assert_eq!(vault_ephemeral_locked(ptr::null()), ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_ephemeral_expires_without_read() {
+        unsafe {
+            let handle = vault_ephemeral_store(SECRET.as_ptr(), SECRET.len() as u32, 20);
+            assert!(!handle.is_null());
+
+            // The timer wipes it even though nobody reads
+            let state = Arc::clone(&(*handle).state);
+            let give_up = Instant::now() + Duration::from_secs(5);
+            while state.lock().is_some() && Instant::now() < give_up {
+                thread::sleep(Duration::from_millis(5));
+            }
+            assert!(state.lock().is_none());
+
+            assert_eq!(read(handle), Err(ERR_EXPIRED));
+            vault_ephemeral_free(handle);
+        }
+    }
+
+    #[test]
+    fn test_ephemeral_clear_wipes() {
+        unsafe {
+            let handle = vault_ephemeral_store(SECRET.as_ptr(), SECRET.len() as u32, 60_000);
+            let state = Arc::clone(&(*handle).state);
+
+            vault_ephemeral_clear(handle);
+            assert!(state.lock().is_none());
+            assert_eq!(read(handle), Err(ERR_EXPIRED));
+
+            vault_ephemeral_free(handle);
+            vault_ephemeral_clear(ptr::null());
+            vault_ephemeral_free(ptr::null_mut());
+
+            // Only the timer thread's reference may outlive the handle, and
+            // it exits promptly once woken
+            let give_up = Instant::now() + Duration::from_secs(5);
+            while Arc::strong_count(&state) > 1 && Instant::now() < give_up {
+                thread::sleep(Duration::from_millis(5));
+            }
+            assert_eq!(Arc::strong_count(&state), 1);
+        }
+    }
+}
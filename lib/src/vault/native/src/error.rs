@@ -0,0 +1,335 @@
+//! Error Descriptions
+//!
+//! Every FFI function reports failure as one of the negative `ERR_*` codes.
+//! `vault_strerror` turns a code into a fixed English description for logs
+//! and diagnostics; UI text should still be chosen per code by the caller.
+//!
+//! Safe Rust entry points return `VaultError`, which maps one-to-one onto
+//! the codes.
+//!
+//! A code alone does not say which argument was wrong or how much memory
+//! could not be found, so each thread also keeps a description of its most
+//! recent failure, read with `vault_last_error_message`. Where the failing
+//! check knows more than its code (a key of the wrong length, Argon2 costs
+//! it rejected, an allocation that failed) the description says so. It
+//! never contains key material or caller data, only sizes, costs and codes.
+
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_char, CStr};
+use std::fmt;
+
+use super::*;
+
+/// Error from a safe Rust entry point; `code()` gives the FFI error code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultError {
+    InvalidInput,
+    DecryptFailed,
+    KdfFailed,
+    LockedOut,
+    UnsupportedVersion,
+    CorruptData,
+    Expired,
+    Io,
+    BadKeySize,
+    BadNonceSize,
+    BadSaltSize,
+    OutOfMemory,
+    WipeFailed,
+    RngFailed,
+    Busy,
+    WeakKey,
+    TimingLeak,
+    InternalPanic,
+    /// A code this version does not know
+    Unknown(i32),
+}
+
+impl VaultError {
+    /// The FFI error code for this error.
+    pub fn code(self) -> i32 {
+        match self {
+            Self::InvalidInput => ERR_INVALID_INPUT,
+            Self::DecryptFailed => ERR_DECRYPT_FAILED,
+            Self::KdfFailed => ERR_KDF_FAILED,
+            Self::LockedOut => ERR_LOCKED_OUT,
+            Self::UnsupportedVersion => ERR_UNSUPPORTED_VERSION,
+            Self::CorruptData => ERR_CORRUPT_DATA,
+            Self::Expired => ERR_EXPIRED,
+            Self::Io => ERR_IO,
+            Self::BadKeySize => ERR_BAD_KEY_SIZE,
+            Self::BadNonceSize => ERR_BAD_NONCE_SIZE,
+            Self::BadSaltSize => ERR_BAD_SALT_SIZE,
+            Self::OutOfMemory => ERR_OUT_OF_MEMORY,
+            Self::WipeFailed => ERR_WIPE_FAILED,
+            Self::RngFailed => ERR_RNG_FAILED,
+            Self::Busy => ERR_BUSY,
+            Self::WeakKey => ERR_WEAK_KEY,
+            Self::TimingLeak => ERR_TIMING_LEAK,
+            Self::InternalPanic => ERR_INTERNAL_PANIC,
+            Self::Unknown(code) => code,
+        }
+    }
+}
+
+impl From<i32> for VaultError {
+    fn from(code: i32) -> Self {
+        match code {
+            ERR_INVALID_INPUT => Self::InvalidInput,
+            ERR_DECRYPT_FAILED => Self::DecryptFailed,
+            ERR_KDF_FAILED => Self::KdfFailed,
+            ERR_LOCKED_OUT => Self::LockedOut,
+            ERR_UNSUPPORTED_VERSION => Self::UnsupportedVersion,
+            ERR_CORRUPT_DATA => Self::CorruptData,
+            ERR_EXPIRED => Self::Expired,
+            ERR_IO => Self::Io,
+            ERR_BAD_KEY_SIZE => Self::BadKeySize,
+            ERR_BAD_NONCE_SIZE => Self::BadNonceSize,
+            ERR_BAD_SALT_SIZE => Self::BadSaltSize,
+            ERR_OUT_OF_MEMORY => Self::OutOfMemory,
+            ERR_WIPE_FAILED => Self::WipeFailed,
+            ERR_RNG_FAILED => Self::RngFailed,
+            ERR_BUSY => Self::Busy,
+            ERR_WEAK_KEY => Self::WeakKey,
+            ERR_TIMING_LEAK => Self::TimingLeak,
+            ERR_INTERNAL_PANIC => Self::InternalPanic,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for VaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&error_text(self.code()).to_string_lossy())
+    }
+}
+
+impl std::error::Error for VaultError {}
+
+/// Description of an error code.
+pub(crate) fn error_text(code: i32) -> &'static CStr {
+    match code {
+        0 => c"Success",
+        ERR_INVALID_INPUT => c"Invalid input (null pointer or empty argument)",
+        ERR_DECRYPT_FAILED => c"Decryption failed (wrong key or corrupted data)",
+        ERR_KDF_FAILED => c"Key derivation failed",
+        ERR_LOCKED_OUT => c"Too many failed attempts",
+        ERR_UNSUPPORTED_VERSION => c"Unsupported sealed data format",
+        ERR_CORRUPT_DATA => c"Sealed data is truncated or malformed",
+        ERR_EXPIRED => c"Sealed data has expired",
+        ERR_IO => c"File could not be read or written",
+        ERR_BAD_KEY_SIZE => c"Key has the wrong length",
+        ERR_BAD_NONCE_SIZE => c"Nonce has the wrong length",
+        ERR_BAD_SALT_SIZE => c"Salt has the wrong length",
+        ERR_OUT_OF_MEMORY => c"Not enough memory",
+        ERR_WIPE_FAILED => c"Memory did not read back as zero after wiping",
+        ERR_RNG_FAILED => c"The system random number generator failed",
+        ERR_BUSY => c"Another unlock is already in progress",
+        ERR_WEAK_KEY => c"Key is a single repeated byte (likely uninitialized)",
+        ERR_TIMING_LEAK => c"Timing self-check found input-dependent timing",
+        ERR_INTERNAL_PANIC => c"Internal error (a panic was caught)",
+        _ => c"Unknown error",
+    }
+}
+
+thread_local! {
+    /// This thread's most recent failure: its code and description
+    static LAST_ERROR: RefCell<Option<(i32, String)>> = const { RefCell::new(None) };
+
+    /// Whether `LAST_ERROR` holds a detail for a failure not yet returned
+    static DETAIL_PENDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Record why the current call is failing with `code`, and return `code`.
+///
+/// The detail is kept when the call returns `code` through a `VaultBuffer`,
+/// and is shown after the code's description.
+pub(crate) fn error_detail(code: i32, detail: fmt::Arguments<'_>) -> i32 {
+    let message = format!("{}: {}", error_text(code).to_string_lossy(), detail);
+    LAST_ERROR.set(Some((code, message)));
+    DETAIL_PENDING.set(true);
+    code
+}
+
+/// Record a failure being returned to the caller with `code`.
+///
+/// A detail recorded for the same code while the call ran is kept;
+/// otherwise the description is the code's plain text.
+pub(crate) fn record_error(code: i32) {
+    let has_detail = DETAIL_PENDING.replace(false) && LAST_ERROR.with_borrow(|last| matches!(last, Some((c, _)) if *c == code));
+    if !has_detail {
+        LAST_ERROR.set(Some((code, error_text(code).to_string_lossy().into_owned())));
+    }
+}
+
+/// Drop a detail from a failure the current call recovered from.
+pub(crate) fn clear_error_detail() {
+    DETAIL_PENDING.set(false);
+}
+
+/// Describe the most recent failure on the calling thread.
+///
+/// Every function returning a `VaultBuffer` updates this when it fails;
+/// functions returning a bare code update it when they have a detail to add
+/// (a wrong key or nonce length, rejected Argon2 costs, a failed
+/// allocation). Successful calls leave it alone, so it can describe an
+/// earlier failure: read it right after the call that failed.
+///
+/// # Safety
+///
+/// Always safe to call. The returned buffer must be freed with `vault_free`.
+///
+/// # Returns
+///
+/// VaultBuffer containing the UTF-8 description (not NUL-terminated), or an
+/// empty buffer if nothing has failed on this thread
+#[no_mangle]
+pub unsafe extern "C" fn vault_last_error_message() -> VaultBuffer {
+    ffi_boundary(|| {
+        let message = LAST_ERROR.with_borrow(|last| last.as_ref().map(|(_, message)| message.clone().into_bytes()));
+        VaultBuffer::success(message.unwrap_or_default())
+    })
+}
+
+/// Describe an error code.
+///
+/// # Safety
+///
+/// Always safe to call. The returned string is NUL-terminated, statically
+/// allocated, and must not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn vault_strerror(code: i32) -> *const c_char {
+    ffi_boundary(|| {
+        error_text(code).as_ptr()
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strerror_distinct() {
+        let codes = [
+            ERR_INVALID_INPUT,
+            ERR_DECRYPT_FAILED,
+            ERR_KDF_FAILED,
+            ERR_LOCKED_OUT,
+            ERR_UNSUPPORTED_VERSION,
+            ERR_CORRUPT_DATA,
+            ERR_EXPIRED,
+            ERR_IO,
+            ERR_BAD_KEY_SIZE,
+            ERR_BAD_NONCE_SIZE,
+            ERR_BAD_SALT_SIZE,
+            ERR_OUT_OF_MEMORY,
+            ERR_WIPE_FAILED,
+            ERR_RNG_FAILED,
+            ERR_BUSY,
+            ERR_WEAK_KEY,
+            ERR_TIMING_LEAK,
+            ERR_INTERNAL_PANIC,
+        ];
+        let messages: Vec<&CStr> = codes.iter().map(|&c| error_text(c)).collect();
+
+        for (i, message) in messages.iter().enumerate() {
+            assert_ne!(*message, c"Unknown error");
+            assert!(!messages[..i].contains(message));
+        }
+        assert_eq!(error_text(-1000), c"Unknown error");
+
+        unsafe {
+            let text = CStr::from_ptr(vault_strerror(ERR_BAD_KEY_SIZE));
+            assert_eq!(text.to_str().unwrap(), "Key has the wrong length");
+        }
+    }
+
+    #[test]
+    fn test_vault_error_codes_roundtrip() {
+        for code in ERR_INTERNAL_PANIC..=ERR_INVALID_INPUT {
+            assert_eq!(VaultError::from(code).code(), code);
+            assert!(!matches!(VaultError::from(code), VaultError::Unknown(_)));
+        }
+        assert_eq!(VaultError::from(-1000), VaultError::Unknown(-1000));
+        assert_eq!(VaultError::BadKeySize.to_string(), "Key has the wrong length");
+    }
+
+    fn last_error_message() -> String {
+        unsafe {
+            let message = vault_last_error_message();
+            assert_eq!(message.error, 0);
+            let text = String::from_utf8(slice::from_raw_parts(message.data, message.len as usize).to_vec()).unwrap();
+            vault_free(message.data, message.len);
+            text
+        }
+    }
+
+    #[test]
+    fn test_last_error_message() {
+        let key = [0x42u8; 32];
+        let nonce = [0x07u8; NONCE_SIZE];
+        let mut out = [0u8; 16];
+
+        unsafe {
+            // Details name what was wrong
+            let result = vault_seal(key.as_ptr(), ptr::null(), 5);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+            assert_eq!(last_error_message(), "Invalid input (null pointer or empty argument)");
+
+            assert_eq!(vault_keystream(key.as_ptr(), 16, nonce.as_ptr(), 24, out.as_mut_ptr(), 16), ERR_BAD_KEY_SIZE);
+            assert_eq!(last_error_message(), "Key has the wrong length: key is 16 bytes, expected 32");
+            assert_eq!(vault_keystream(key.as_ptr(), 32, nonce.as_ptr(), 12, out.as_mut_ptr(), 16), ERR_BAD_NONCE_SIZE);
+            assert_eq!(last_error_message(), "Nonce has the wrong length: nonce is 12 bytes, expected 24");
+
+            let result = vault_derive_key_with_params(b"pass".as_ptr(), 4, [0u8; SALT_SIZE].as_ptr(), 8, 0, 1);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+            assert_eq!(last_error_message(), "Invalid input (null pointer or empty argument): t_cost 0 is below the minimum 1");
+
+            let result = vault_unseal(key.as_ptr(), [0x7Fu8; 64].as_ptr(), 64);
+            assert_eq!(result.error, ERR_UNSUPPORTED_VERSION);
+            assert_eq!(last_error_message(), "Unsupported sealed data format: format byte 0x7f is not recognized");
+
+            // A detail does not outlive the failure it belongs to
+            assert_eq!(vault_keystream(key.as_ptr(), 16, nonce.as_ptr(), 24, out.as_mut_ptr(), 16), ERR_BAD_KEY_SIZE);
+            let result = vault_timelock_seal(key.as_ptr(), 16, b"x".as_ptr(), 1, 1);
+            assert_eq!(result.error, ERR_BAD_KEY_SIZE);
+            assert_eq!(last_error_message(), "Key has the wrong length: key is 16 bytes, expected 32");
+            let sealed = vault_seal(key.as_ptr(), b"x".as_ptr(), 1);
+            assert_eq!(sealed.error, 0);
+            vault_free(sealed.data, sealed.len);
+            let result = vault_unseal(key.as_ptr(), [FORMAT_XCHACHA; 4].as_ptr(), 4);
+            assert_eq!(result.error, ERR_CORRUPT_DATA);
+            assert_eq!(last_error_message(), "Sealed data is truncated or malformed: sealed data is 4 bytes, the format needs at least 41");
+        }
+
+        // Each thread sees only its own failures
+        std::thread::spawn(|| unsafe {
+            let message = vault_last_error_message();
+            assert_eq!((message.error, message.len), (0, 0));
+        })
+        .join()
+        .unwrap();
+    }
+
+    #[test]
+    fn test_bad_key_size_reported() {
+        let short_key = [0x42u8; 16];
+        let plaintext = b"sized";
+
+        unsafe {
+            let result = vault_timelock_seal(short_key.as_ptr(), 16, plaintext.as_ptr(), 5, 1);
+            assert_eq!(result.error, ERR_BAD_KEY_SIZE);
+
+            let result = vault_seal_siv(short_key.as_ptr(), 16, plaintext.as_ptr(), 5, ptr::null(), 0);
+            assert_eq!(result.error, ERR_BAD_KEY_SIZE);
+
+            // A null key is still plain invalid input
+            let result = vault_timelock_seal(ptr::null(), 32, plaintext.as_ptr(), 5, 1);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Expiring Seals
+//!
+//! A sealed blob that carries its own authenticated `not_after` time, for
+//! shared links and session keys that should stop opening after a deadline.
+//!
+//! The current time is supplied by the caller, so the expiry is only as
+//! trustworthy as the caller's clock: anyone who can set the clock back can
+//! open an expired blob. Treat this as a policy check, not a cryptographic
+//! guarantee of deletion.
+//!
+//! ## Format
+//!
+//! `format (1, 0x04) || not_after (8, LE, unix seconds) || nonce (24) || ciphertext || tag (16)`
+//!
+//! The header (format, not_after) is authenticated as associated data.
+
+use std::slice;
+
+use super::*;
+
+/// Size of the authenticated header: format || not_after
+const EXPIRING_HEADER_SIZE: usize = FORMAT_HEADER_SIZE + 8;
+
+/// Seal data that `vault_unseal_expiring` refuses to open from `not_after_unix` on.
+///
+/// # Format
+///
+/// Output: `format (1) || not_after (8) || nonce (24) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_expiring(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    not_after_unix: i64,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if plaintext.is_null() && plaintext_len != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let mut header = Vec::with_capacity(EXPIRING_HEADER_SIZE);
+        header.push(FORMAT_EXPIRING);
+        header.extend_from_slice(&not_after_unix.to_le_bytes());
+
+        let sealed = match xchacha_seal(key_slice, plaintext_slice, &header) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(header.len() + sealed.len()) {
+            Ok(o) => o,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&sealed);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt data sealed with `vault_seal_expiring`, enforcing its expiry.
+///
+/// The blob is authenticated before the expiry is checked, so a tampered
+/// timestamp fails as `ERR_DECRYPT_FAILED`.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - `now_unix` must come from a clock the caller trusts
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_EXPIRED` if
+/// `now_unix >= not_after`, or `ERR_DECRYPT_FAILED`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_expiring(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+    now_unix: i64,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = EXPIRING_HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, body) = sealed_slice.split_at(EXPIRING_HEADER_SIZE);
+        if header[0] != FORMAT_EXPIRING {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+        let mut not_after_bytes = [0u8; 8];
+        not_after_bytes.copy_from_slice(&header[1..]);
+        let not_after = i64::from_le_bytes(not_after_bytes);
+
+        let mut plaintext = match xchacha_open(key_slice, body, header) {
+            Ok(p) => Zeroizing::new(p),
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if now_unix >= not_after {
+            return VaultBuffer::error(ERR_EXPIRED);
+        }
+
+        VaultBuffer::success(mem::take(&mut *plaintext))
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOT_AFTER: i64 = 1_760_000_000;
+
+    unsafe fn seal(key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let result = vault_seal_expiring(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32, NOT_AFTER);
+        assert_eq!(result.error, 0);
+        let sealed = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        sealed
+    }
+
+    unsafe fn unseal(key: &[u8], sealed: &[u8], now: i64) -> VaultBuffer {
+        vault_unseal_expiring(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, now)
+    }
+
+    #[test]
+    fn test_expiring_before_deadline() {
+        let key = [0x42u8; 32];
+        let plaintext = b"session token";
+
+        unsafe {
+            let sealed = seal(&key, plaintext);
+            let result = unseal(&key, &sealed, NOT_AFTER - 1);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), plaintext);
+            vault_free(result.data, result.len);
+        }
+    }
+
+    #[test]
+    fn test_expiring_after_deadline() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let sealed = seal(&key, b"session token");
+            assert_eq!(unseal(&key, &sealed, NOT_AFTER).error, ERR_EXPIRED);
+            assert_eq!(unseal(&key, &sealed, NOT_AFTER + 3600).error, ERR_EXPIRED);
+        }
+    }
+
+    #[test]
+    fn test_expiring_tampered_timestamp() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let mut sealed = seal(&key, b"session token");
+
+            // Push the deadline out by a year
+            let extended = NOT_AFTER + 365 * 24 * 3600;
+            sealed[1..9].copy_from_slice(&extended.to_le_bytes());
+
+            assert_eq!(unseal(&key, &sealed, NOT_AFTER + 1).error, ERR_DECRYPT_FAILED);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! File Sealing by Path
+//!
+//! Seals and unseals files without routing their contents through Dart.
+//! The input is memory-mapped and pushed through the chunked STREAM format
+//! (see `stream`), so peak memory is one chunk regardless of file size.
+//!
+//! Output is written to a temporary file beside `out_path` and renamed over
+//! it only once complete. On any error the temporary file is removed, so a
+//! failed call leaves an existing `out_path` untouched and a failed unseal
+//! never leaves behind unauthenticated plaintext.
+//!
+//! `in_path` and `out_path` must not name the same file, including through
+//! a hard or symbolic link: writing over a mapped input would crash the
+//! process, so this is refused with `ERR_INVALID_INPUT`.
+
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use memmap2::Mmap;
+
+use super::*;
+
+/// Distinguishes temporary output files made by this process
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Borrow a UTF-8 path argument.
+unsafe fn path_arg<'a>(path: *const u8, path_len: u32) -> VaultResult<&'a str> {
+    if path.is_null() || path_len == 0 {
+        return Err(ERR_INVALID_INPUT);
+    }
+    std::str::from_utf8(slice::from_raw_parts(path, path_len as usize)).map_err(|_| ERR_INVALID_INPUT)
+}
+
+/// Whether `out_path` already names the file opened as `input`.
+fn is_same_file(input: &Metadata, in_path: &str, out_path: &str) -> bool {
+    // Follows symbolic links; a missing output cannot be the input
+    let Ok(output) = fs::canonicalize(out_path).and_then(fs::metadata) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = in_path;
+        input.dev() == output.dev() && input.ino() == output.ino()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (input, output);
+        matches!((fs::canonicalize(in_path), fs::canonicalize(out_path)), (Ok(a), Ok(b)) if a == b)
+    }
+}
+
+/// A fresh temporary path in the same directory as `out_path`, so the
+/// final rename stays on one filesystem.
+fn temp_path_for(out_path: &str) -> PathBuf {
+    let out = Path::new(out_path);
+    let dir = match out.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let name = out.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let unique = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!(".{name}.{}.{unique}.tmp", std::process::id()))
+}
+
+/// Map `in_path`, run `transform` into a temporary file, and rename it over
+/// `out_path` only if everything succeeds.
+///
+/// `transform` writes whole chunks straight to the file: a `BufWriter` would
+/// hold decrypted plaintext in a buffer it frees without wiping.
+fn transform_file(
+    in_path: &str,
+    out_path: &str,
+    transform: impl FnOnce(&[u8], &mut File) -> VaultResult<()>,
+) -> VaultResult<()> {
+    let input = File::open(in_path).map_err(|_| ERR_IO)?;
+    let metadata = input.metadata().map_err(|_| ERR_IO)?;
+    if is_same_file(&metadata, in_path, out_path) {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("output path is the input file")));
+    }
+    let len = metadata.len();
+    // Zero-length files cannot be mapped on every platform
+    let map = if len == 0 {
+        None
+    } else {
+        // SAFETY: the file is opened read-only; concurrent truncation by
+        // another process is outside what this API guards against
+        Some(unsafe { Mmap::map(&input) }.map_err(|_| ERR_IO)?)
+    };
+    let data: &[u8] = map.as_deref().unwrap_or(&[]);
+
+    let temp_path = temp_path_for(out_path);
+    let mut output = OpenOptions::new().write(true).create_new(true).open(&temp_path).map_err(|_| ERR_IO)?;
+
+    let result = transform(data, &mut output).and_then(|()| {
+        output.sync_all().map_err(|_| ERR_IO)?;
+        fs::rename(&temp_path, out_path).map_err(|_| ERR_IO)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Seal the file at `in_path` into a new file at `out_path`.
+///
+/// # Format
+///
+/// Output file: the chunked stream format (`0x05`), see the `stream` module
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `in_path` and `out_path` must be valid UTF-8 for their lengths
+///
+/// # Returns
+///
+/// 0 on success, `ERR_IO` if the input cannot be read or the output written,
+/// or `ERR_INVALID_INPUT` if both paths name the same file
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_file(
+    key: *const u8,
+    key_len: u32,
+    in_path: *const u8,
+    in_path_len: u32,
+    out_path: *const u8,
+    out_path_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+        let (in_path, out_path) = match (path_arg(in_path, in_path_len), path_arg(out_path, out_path_len)) {
+            (Ok(i), Ok(o)) => (i, o),
+            _ => return ERR_INVALID_INPUT,
+        };
+
+        match transform_file(in_path, out_path, |data, out| stream_seal_to(key_slice, data, out)) {
+            Ok(()) => 0,
+            Err(code) => code,
+        }
+    })
+}
+
+/// Unseal a file produced by `vault_seal_file` into a new file at `out_path`.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `in_path` and `out_path` must be valid UTF-8 for their lengths
+///
+/// # Returns
+///
+/// 0 on success, `ERR_DECRYPT_FAILED` if any chunk fails to authenticate
+/// (no output is written), `ERR_IO`, or `ERR_INVALID_INPUT` if both paths
+/// name the same file
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_file(
+    key: *const u8,
+    key_len: u32,
+    in_path: *const u8,
+    in_path_len: u32,
+    out_path: *const u8,
+    out_path_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+        let (in_path, out_path) = match (path_arg(in_path, in_path_len), path_arg(out_path, out_path_len)) {
+            (Ok(i), Ok(o)) => (i, o),
+            _ => return ERR_INVALID_INPUT,
+        };
+
+        match transform_file(in_path, out_path, |data, out| stream_open_to(key_slice, data, out)) {
+            Ok(()) => 0,
+            Err(code) => code,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vault_core_{}_{name}", std::process::id()))
+    }
+
+    unsafe fn seal_file(key: &[u8], from: &Path, to: &Path) -> i32 {
+        let (from, to) = (from.to_str().unwrap(), to.to_str().unwrap());
+        vault_seal_file(key.as_ptr(), 32, from.as_ptr(), from.len() as u32, to.as_ptr(), to.len() as u32)
+    }
+
+    unsafe fn unseal_file(key: &[u8], from: &Path, to: &Path) -> i32 {
+        let (from, to) = (from.to_str().unwrap(), to.to_str().unwrap());
+        vault_unseal_file(key.as_ptr(), 32, from.as_ptr(), from.len() as u32, to.as_ptr(), to.len() as u32)
+    }
+
+    #[test]
+    fn test_file_roundtrip() {
+        let key = [0x42u8; 32];
+        let plain = temp_path("roundtrip.plain");
+        let sealed = temp_path("roundtrip.sealed");
+        let opened = temp_path("roundtrip.opened");
+
+        // Several chunks plus a partial one
+        let contents: Vec<u8> = (0..3 * 1024 * 1024 + 123).map(|i| (i % 251) as u8).collect();
+        fs::write(&plain, &contents).unwrap();
+
+        unsafe {
+            assert_eq!(seal_file(&key, &plain, &sealed), 0);
+            assert_eq!(unseal_file(&key, &sealed, &opened), 0);
+        }
+        assert_eq!(fs::read(&opened).unwrap(), contents);
+        assert_eq!(fs::read(&sealed).unwrap()[0], FORMAT_STREAM);
+
+        for path in [&plain, &sealed, &opened] {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_file_errors_clean_up() {
+        let key = [0x42u8; 32];
+        let wrong_key = [0x43u8; 32];
+        let plain = temp_path("errors.plain");
+        let sealed = temp_path("errors.sealed");
+        let opened = temp_path("errors.opened");
+        fs::write(&plain, vec![7u8; 200_000]).unwrap();
+
+        unsafe {
+            // Missing input, unwritable output
+            assert_eq!(seal_file(&key, &temp_path("missing"), &sealed), ERR_IO);
+            assert_eq!(seal_file(&key, &plain, &temp_path("no/such/dir/out")), ERR_IO);
+
+            // Wrong key: partial plaintext must not be left behind
+            assert_eq!(seal_file(&key, &plain, &sealed), 0);
+            assert_eq!(unseal_file(&wrong_key, &sealed, &opened), ERR_DECRYPT_FAILED);
+            assert!(!opened.exists());
+
+            // Truncated to a chunk boundary: the last-chunk flag is missing
+            let bytes = fs::read(&sealed).unwrap();
+            fs::write(&sealed, &bytes[..STREAM_HEADER_SIZE + STREAM_CHUNK_SIZE + TAG_SIZE]).unwrap();
+            assert_eq!(unseal_file(&key, &sealed, &opened), ERR_DECRYPT_FAILED);
+            assert!(!opened.exists());
+        }
+
+        for path in [&plain, &sealed] {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_file_same_path_refused() {
+        let key = [0x42u8; 32];
+        let plain = temp_path("same.plain");
+        let sealed = temp_path("same.sealed");
+        let opened = temp_path("same.opened");
+        let linked = temp_path("same.link");
+        let contents = vec![9u8; 100_000];
+        fs::write(&plain, &contents).unwrap();
+
+        unsafe {
+            assert_eq!(seal_file(&key, &plain, &plain), ERR_INVALID_INPUT);
+            fs::hard_link(&plain, &linked).unwrap();
+            assert_eq!(seal_file(&key, &plain, &linked), ERR_INVALID_INPUT);
+            let _ = fs::remove_file(&linked);
+            #[cfg(unix)]
+            {
+                std::os::unix::fs::symlink(&plain, &linked).unwrap();
+                assert_eq!(seal_file(&key, &plain, &linked), ERR_INVALID_INPUT);
+                let _ = fs::remove_file(&linked);
+            }
+            assert_eq!(fs::read(&plain).unwrap(), contents);
+
+            // A failed unseal leaves an existing output as it was
+            assert_eq!(seal_file(&key, &plain, &sealed), 0);
+            assert_eq!(unseal_file(&key, &sealed, &sealed), ERR_INVALID_INPUT);
+            fs::write(&opened, b"previous").unwrap();
+            assert_eq!(unseal_file(&[0x43u8; 32], &sealed, &opened), ERR_DECRYPT_FAILED);
+            assert_eq!(fs::read(&opened).unwrap(), b"previous");
+            assert_eq!(unseal_file(&key, &sealed, &opened), 0);
+            assert_eq!(fs::read(&opened).unwrap(), contents);
+        }
+
+        // No temporary files left beside the outputs
+        let prefix = format!(".vault_core_{}_same", std::process::id());
+        let entries = fs::read_dir(std::env::temp_dir()).unwrap().filter_map(Result::ok);
+        assert!(!entries.into_iter().any(|e| e.file_name().to_string_lossy().starts_with(&prefix)));
+
+        for path in [&plain, &sealed, &opened] {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Key Fingerprints
+//!
+//! A short, stable identifier for a key, for "is this the right vault?"
+//! checks and for comparing two devices' keys out of band. The fingerprint
+//! is a truncated BLAKE3 key derivation under a fixed context, so it is
+//! deterministic and cannot be reversed to the key.
+//!
+//! 64 bits is enough to tell keys apart by eye; it is not a commitment and
+//! must never be used as key material.
+
+use std::slice;
+
+use super::*;
+use crate::wordlist::ENGLISH;
+
+/// Fingerprint length in bytes
+const FINGERPRINT_SIZE: usize = 8;
+
+/// Words in the human-readable rendering (6 × 11 bits covers the 64-bit fingerprint)
+const FINGERPRINT_WORDS: usize = 6;
+
+const FINGERPRINT_CONTEXT: &str = "vault_core 2025-01 key fingerprint";
+
+/// Borrow a non-empty key of any length.
+unsafe fn key_bytes<'a>(key: *const u8, key_len: u32) -> VaultResult<&'a [u8]> {
+    if key.is_null() || key_len == 0 {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(slice::from_raw_parts(key, key_len as usize))
+}
+
+/// Full derived hash; the fingerprint is its prefix.
+fn fingerprint_hash(key: &[u8]) -> [u8; 32] {
+    blake3::derive_key(FINGERPRINT_CONTEXT, key)
+}
+
+/// Render the leading 66 bits of `hash` as BIP-39 words.
+fn fingerprint_words(hash: &[u8; 32]) -> String {
+    let mut wide = [0u8; 16];
+    wide[..9].copy_from_slice(&hash[..9]);
+    let value = u128::from_be_bytes(wide);
+
+    (0..FINGERPRINT_WORDS)
+        .map(|i| {
+            let index = (value >> (128 - 11 * (i + 1))) & 0x7FF;
+            ENGLISH[index as usize]
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Compute an 8-byte fingerprint of a key.
+///
+/// # Safety
+///
+/// - `key` must be valid for `key_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 8-byte fingerprint, or error code
+#[no_mangle]
+pub unsafe extern "C" fn vault_key_fingerprint(key: *const u8, key_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        let key_slice = match key_bytes(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let hash = fingerprint_hash(key_slice);
+        VaultBuffer::success(hash[..FINGERPRINT_SIZE].to_vec())
+    })
+}
+
+/// Render a key fingerprint as six space-separated BIP-39 words.
+///
+/// The words encode the same fingerprint as `vault_key_fingerprint`
+/// (plus two further bits of the same hash), in ASCII.
+///
+/// # Safety
+///
+/// - `key` must be valid for `key_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_key_fingerprint_words(key: *const u8, key_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        let key_slice = match key_bytes(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let hash = fingerprint_hash(key_slice);
+        VaultBuffer::success(fingerprint_words(&hash).into_bytes())
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn fingerprint(key: &[u8]) -> Vec<u8> {
+        let result = vault_key_fingerprint(key.as_ptr(), key.len() as u32);
+        assert_eq!(result.error, 0);
+        let out = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        out
+    }
+
+    #[test]
+    fn test_fingerprint_stable_and_distinct() {
+        let key1 = [0x42u8; 32];
+        let key2 = [0x43u8; 32];
+
+        unsafe {
+            let fp1 = fingerprint(&key1);
+            assert_eq!(fp1.len(), FINGERPRINT_SIZE);
+            assert_eq!(fp1, fingerprint(&key1));
+            assert_ne!(fp1, fingerprint(&key2));
+            assert_ne!(&fp1[..], &key1[..FINGERPRINT_SIZE]);
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_words() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let result = vault_key_fingerprint_words(key.as_ptr(), key.len() as u32);
+            assert_eq!(result.error, 0);
+            let text = std::str::from_utf8(slice::from_raw_parts(result.data, result.len as usize)).unwrap().to_owned();
+            vault_free(result.data, result.len);
+
+            let words: Vec<&str> = text.split(' ').collect();
+            assert_eq!(words.len(), FINGERPRINT_WORDS);
+            assert!(words.iter().all(|w| ENGLISH.contains(w)));
+
+            // First word is the top 11 bits of the fingerprint
+            let fp = fingerprint(&key);
+            let first = ((fp[0] as usize) << 3) | (fp[1] as usize >> 5);
+            assert_eq!(words[0], ENGLISH[first]);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Buffer Lifecycle Guard (`debug-guard` feature)
+//!
+//! Records every buffer handed out by `VaultBuffer::success` together with
+//! its length. `vault_free` consults the registry first, so freeing a
+//! pointer twice, freeing one the library never returned, or passing the
+//! wrong length is reported on stderr and ignored instead of corrupting the
+//! heap.
+//!
+//! Intended for CI and debug builds; every allocation and free takes a
+//! global lock. Without the feature `vault_free` keeps its direct path.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Live allocations: pointer address → length
+fn live() -> &'static Mutex<HashMap<usize, usize>> {
+    static LIVE: OnceLock<Mutex<HashMap<usize, usize>>> = OnceLock::new();
+    LIVE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Rejected frees on this thread, so parallel tests don't interfere
+    static VIOLATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Report a rejected free.
+fn violation(message: String) {
+    #[cfg(test)]
+    VIOLATIONS.with(|v| v.set(v.get() + 1));
+    eprintln!("vault_core: {message}");
+}
+
+/// Register a buffer returned to the caller.
+pub(crate) fn track(ptr: *mut u8, len: usize) {
+    live().lock().unwrap_or_else(|e| e.into_inner()).insert(ptr as usize, len);
+}
+
+/// Unregister a buffer about to be freed.
+///
+/// Returns false (and records a violation) if the buffer is not live with
+/// exactly this length, in which case it must not be freed.
+pub(crate) fn release(ptr: *mut u8, len: usize) -> bool {
+    let mut live = live().lock().unwrap_or_else(|e| e.into_inner());
+    match live.get(&(ptr as usize)) {
+        Some(&tracked) if tracked == len => {
+            live.remove(&(ptr as usize));
+            true
+        }
+        Some(&tracked) => {
+            violation(format!("vault_free({ptr:p}) with length {len}, allocated with {tracked}"));
+            false
+        }
+        None => {
+            violation(format!("vault_free({ptr:p}) on a buffer that is not live (double free?)"));
+            false
+        }
+    }
+}
+
+/// Rejected frees on the current thread.
+#[cfg(test)]
+fn violations() -> usize {
+    VIOLATIONS.with(|v| v.get())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_double_free_detected() {
+        let key = [0x42u8; 32];
+        let plaintext = b"freed once";
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+
+            let before = super::violations();
+            vault_free(sealed.data, sealed.len);
+            assert_eq!(super::violations(), before);
+
+            vault_free(sealed.data, sealed.len);
+            assert_eq!(super::violations(), before + 1);
+        }
+    }
+
+    #[test]
+    fn test_length_mismatch_detected() {
+        let key = [0x42u8; 32];
+        let plaintext = b"wrong length";
+        let mut buf = [0u8; 16];
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+
+            let before = super::violations();
+            vault_free(sealed.data, sealed.len - 1);
+            assert_eq!(super::violations(), before + 1);
+
+            // Still live, so the correct free goes through
+            vault_free(sealed.data, sealed.len);
+            assert_eq!(super::violations(), before + 1);
+
+            // Never returned by the library
+            vault_free(buf.as_mut_ptr(), buf.len() as u32);
+            assert_eq!(super::violations(), before + 2);
+        }
+    }
+}
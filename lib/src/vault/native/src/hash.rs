@@ -0,0 +1,253 @@
+//! Message Digests
+//!
+//! SHA-256 for digests that must match other tools (manifests, exported
+//! backups) and BLAKE3 where only this library reads them. Both are
+//! available one-shot and as an incremental context, so large inputs never
+//! need to be held in memory at once.
+//!
+//! A `VaultHasher` is single-use: after `vault_hasher_finalize` it rejects
+//! further updates and finalizes with `ERR_INVALID_INPUT`, and it must
+//! still be released with `vault_hasher_free`.
+
+use std::slice;
+
+use sha2::{Digest, Sha256};
+
+use super::*;
+
+/// Algorithm selector: SHA-256
+const HASH_SHA256: u32 = 0;
+
+/// Algorithm selector: BLAKE3 (32-byte output)
+const HASH_BLAKE3: u32 = 1;
+
+/// Incremental hashing context (opaque to callers)
+pub struct VaultHasher {
+    state: Option<HasherState>,
+}
+
+/// Never prints the state, which is derived from possibly secret input.
+impl fmt::Debug for VaultHasher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VaultHasher(***redacted***)")
+    }
+}
+
+enum HasherState {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl HasherState {
+    fn new(algo: u32) -> VaultResult<Self> {
+        match algo {
+            HASH_SHA256 => Ok(Self::Sha256(Sha256::new())),
+            HASH_BLAKE3 => Ok(Self::Blake3(Box::new(blake3::Hasher::new()))),
+            _ => Err(ERR_INVALID_INPUT),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(h) => h.update(data),
+            Self::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Borrow hash input, allowing null when `len` is 0.
+unsafe fn data_arg<'a>(data: *const u8, len: u32) -> VaultResult<&'a [u8]> {
+    match (data.is_null(), len) {
+        (true, 0) => Ok(&[]),
+        (true, _) => Err(ERR_INVALID_INPUT),
+        (false, _) => Ok(slice::from_raw_parts(data, len as usize)),
+    }
+}
+
+/// SHA-256 of a buffer.
+///
+/// # Safety
+///
+/// - `data` must be valid for `len` bytes (may be null when `len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_sha256(data: *const u8, len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        match data_arg(data, len) {
+            Ok(d) => VaultBuffer::success(Sha256::digest(d).to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// BLAKE3 of a buffer (32-byte output).
+///
+/// # Safety
+///
+/// - `data` must be valid for `len` bytes (may be null when `len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_blake3(data: *const u8, len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        match data_arg(data, len) {
+            Ok(d) => VaultBuffer::success(blake3::hash(d).as_bytes().to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Create an incremental hasher.
+///
+/// `algo`: 0 = SHA-256, 1 = BLAKE3.
+///
+/// # Safety
+///
+/// - The returned pointer must be released with `vault_hasher_free`
+///
+/// # Returns
+///
+/// A new hasher, or null for an unknown algorithm
+#[no_mangle]
+pub unsafe extern "C" fn vault_hasher_new(algo: u32) -> *mut VaultHasher {
+    ffi_boundary(|| {
+        match HasherState::new(algo) {
+            Ok(state) => Box::into_raw(Box::new(VaultHasher { state: Some(state) })),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Feed bytes into a hasher.
+///
+/// # Safety
+///
+/// - `hasher` must come from `vault_hasher_new` and not yet be freed
+/// - `data` must be valid for `len` bytes (may be null when `len` is 0)
+///
+/// # Returns
+///
+/// 0 on success, `ERR_INVALID_INPUT` if the hasher was already finalized
+#[no_mangle]
+pub unsafe extern "C" fn vault_hasher_update(hasher: *mut VaultHasher, data: *const u8, len: u32) -> i32 {
+    ffi_boundary(|| {
+        if hasher.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let data_slice = match data_arg(data, len) {
+            Ok(d) => d,
+            Err(code) => return code,
+        };
+
+        match (*hasher).state.as_mut() {
+            Some(state) => {
+                state.update(data_slice);
+                0
+            }
+            None => ERR_INVALID_INPUT,
+        }
+    })
+}
+
+/// Produce the digest and retire the hasher.
+///
+/// # Safety
+///
+/// - `hasher` must come from `vault_hasher_new` and not yet be freed
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte digest, or `ERR_INVALID_INPUT` if
+/// the hasher was already finalized
+#[no_mangle]
+pub unsafe extern "C" fn vault_hasher_finalize(hasher: *mut VaultHasher) -> VaultBuffer {
+    ffi_boundary(|| {
+        if hasher.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        match (*hasher).state.take() {
+            Some(state) => VaultBuffer::success(state.finalize()),
+            None => VaultBuffer::error(ERR_INVALID_INPUT),
+        }
+    })
+}
+
+/// Release a hasher, finalized or not.
+///
+/// # Safety
+///
+/// - `hasher` must come from `vault_hasher_new` (or be null)
+/// - Must not be called twice on the same pointer
+#[no_mangle]
+pub unsafe extern "C" fn vault_hasher_free(hasher: *mut VaultHasher) {
+    ffi_boundary(|| {
+        if hasher.is_null() {
+            return;
+        }
+        drop(Box::from_raw(hasher));
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let out = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        out
+    }
+
+    #[test]
+    fn test_hasher_chunks_match_one_shot() {
+        let message: Vec<u8> = (0..10_000u32).map(|i| (i * 7) as u8).collect();
+        let parts = [&message[..1], &message[1..4096], &message[4096..]];
+
+        unsafe {
+            for (algo, one_shot) in [
+                (HASH_SHA256, vault_sha256(message.as_ptr(), message.len() as u32)),
+                (HASH_BLAKE3, vault_blake3(message.as_ptr(), message.len() as u32)),
+            ] {
+                let hasher = vault_hasher_new(algo);
+                assert!(!hasher.is_null());
+                for part in parts {
+                    assert_eq!(vault_hasher_update(hasher, part.as_ptr(), part.len() as u32), 0);
+                }
+                assert_eq!(take(vault_hasher_finalize(hasher)), take(one_shot));
+                vault_hasher_free(hasher);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hasher_known_answer_and_reuse() {
+        unsafe {
+            // SHA-256("abc")
+            let digest = take(vault_sha256(b"abc".as_ptr(), 3));
+            assert_eq!(digest[..4], [0xba, 0x78, 0x16, 0xbf]);
+
+            let hasher = vault_hasher_new(HASH_SHA256);
+            take(vault_hasher_finalize(hasher));
+            assert_eq!(vault_hasher_update(hasher, b"x".as_ptr(), 1), ERR_INVALID_INPUT);
+            assert_eq!(vault_hasher_finalize(hasher).error, ERR_INVALID_INPUT);
+            vault_hasher_free(hasher);
+
+            assert!(vault_hasher_new(99).is_null());
+        }
+    }
+}
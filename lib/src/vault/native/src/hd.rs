@@ -0,0 +1,535 @@
+//! BIP-32 Hierarchical Deterministic Keys
+//!
+//! A BIP-39 seed (see `mnemonic`) becomes a master key handle, and a path
+//! such as `m/44'/0'/0'/0/0` derives a child handle from it. Each HD handle
+//! holds the secp256k1 private key together with its chain code, depth,
+//! parent fingerprint and child number; private keys and chain codes never
+//! leave native memory and are wiped with the handle (`vault_key_destroy`).
+//!
+//! An HD handle is an ordinary key handle, so a derived key signs directly
+//! with `vault_secp256k1_sign`. Only the extended *public* key is exported
+//! (`vault_hd_export_xpub`), for watch-only wallets.
+//!
+//! ## Paths
+//!
+//! ```text
+//! m/44'/0'/0'/0/0
+//! ```
+//!
+//! `m` names the handle's own node, so an account handle derives its
+//! addresses with `m/0/5`. Hardened indices take a `'`, `h` or `H` suffix;
+//! every index is below 2^31.
+//!
+//! Child derivation adds scalars with the constant-time `k256` arithmetic
+//! of the `secp256k1` module. A child key that BIP-32 declares invalid (chance
+//! about 1 in 2^127) is reported as `ERR_INVALID_INPUT`; the caller moves on
+//! to the next index, as the BIP directs.
+//!
+//! ## Ed25519 (SLIP-0010)
+//!
+//! `vault_hd_ed25519_master_from_seed` starts a SLIP-0010 Ed25519 tree, as
+//! Solana (`m/44'/501'/0'/0'`) and Stellar (`m/44'/148'/0'`) wallets use.
+//! The same `vault_hd_derive_path` walks it, but every index must be
+//! hardened: SLIP-0010 defines no public derivation for Ed25519, and so no
+//! xpub. Its keys are Ed25519 seeds that sign with
+//! `vault_ed25519_sign_with_handle`. Cardano's BIP32-Ed25519 (Icarus) is a
+//! different scheme and is not supported.
+//!
+//! A handle of one curve is refused by the other curve's signing functions,
+//! and no HD handle seals or can be wrapped (see `keyhandle`).
+
+use std::slice;
+
+use hmac::{Hmac, KeyInit, Mac};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::ed25519::ed25519_public_key;
+use crate::keyhandle::{held_for, insert_key, KeyPurpose};
+use crate::secp256k1::{public_key_for, tweak_add, SECP256K1_PUBLIC_KEY_SIZE};
+
+use super::*;
+
+/// HMAC key for a BIP-32 master node
+const HD_MASTER_HMAC_KEY: &[u8] = b"Bitcoin seed";
+
+/// HMAC key for a SLIP-0010 Ed25519 master node
+const HD_ED25519_HMAC_KEY: &[u8] = b"ed25519 seed";
+
+/// Smallest seed BIP-32 allows (128 bits)
+const HD_MIN_SEED_SIZE: usize = 16;
+
+/// Largest seed BIP-32 allows (512 bits)
+const HD_MAX_SEED_SIZE: usize = 64;
+
+/// First hardened child index
+const HD_HARDENED: u32 = 1 << 31;
+
+/// Chain code size
+const HD_CHAIN_CODE_SIZE: usize = 32;
+
+/// Version bytes of a mainnet `xpub`
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xB2, 0x1E];
+
+/// Serialized extended key size (before Base58Check)
+const XPUB_PAYLOAD_SIZE: usize = 78;
+
+/// The curve an HD tree derives keys for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum HdCurve {
+    /// BIP-32 secp256k1
+    Secp256k1,
+    /// SLIP-0010 Ed25519, hardened children only
+    Ed25519,
+}
+
+impl HdCurve {
+    fn name(self) -> &'static str {
+        match self {
+            HdCurve::Secp256k1 => "secp256k1",
+            HdCurve::Ed25519 => "Ed25519",
+        }
+    }
+
+    /// The purpose of the handles this tree's keys live behind.
+    fn purpose(self) -> KeyPurpose {
+        match self {
+            HdCurve::Secp256k1 => KeyPurpose::Secp256k1,
+            HdCurve::Ed25519 => KeyPurpose::Ed25519,
+        }
+    }
+}
+
+/// Everything but the private key that places a node in its tree
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub(crate) struct HdChain {
+    #[zeroize(skip)]
+    curve: HdCurve,
+    chain_code: [u8; HD_CHAIN_CODE_SIZE],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+}
+
+/// A node: its private key and chain state
+type HdNode = (Secret<[u8; KEY_SIZE]>, HdChain);
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> VaultResult<Secret<[u8; 64]>> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?;
+    for part in parts {
+        mac.update(part);
+    }
+    Ok(Secret::new(<[u8; 64]>::from(mac.finalize().into_bytes())))
+}
+
+/// The master node for a seed.
+fn master_node(seed: &[u8], curve: HdCurve) -> VaultResult<HdNode> {
+    let hmac_key = match curve {
+        HdCurve::Secp256k1 => HD_MASTER_HMAC_KEY,
+        HdCurve::Ed25519 => HD_ED25519_HMAC_KEY,
+    };
+    let i = hmac_sha512(hmac_key, &[seed])?;
+    let mut key = Secret::new([0u8; KEY_SIZE]);
+    key.copy_from_slice(&i[..32]);
+    if curve == HdCurve::Secp256k1 {
+        // Rejects a key of 0 or at least n; every Ed25519 seed is valid
+        public_key_for(&key).map_err(|code| error_detail(code, format_args!("seed gives an invalid master key")))?;
+    }
+
+    let mut chain = HdChain { curve, chain_code: [0u8; HD_CHAIN_CODE_SIZE], depth: 0, parent_fingerprint: [0u8; 4], child_number: 0 };
+    chain.chain_code.copy_from_slice(&i[32..]);
+    Ok((key, chain))
+}
+
+/// The public key a node's fingerprint is taken over (33 bytes for both curves).
+fn fingerprint_key(key: &[u8; KEY_SIZE], curve: HdCurve) -> VaultResult<[u8; SECP256K1_PUBLIC_KEY_SIZE]> {
+    match curve {
+        HdCurve::Secp256k1 => public_key_for(key),
+        HdCurve::Ed25519 => {
+            // SLIP-0010 prefixes the Ed25519 key with 0x00
+            let mut public_key = [0u8; SECP256K1_PUBLIC_KEY_SIZE];
+            public_key[1..].copy_from_slice(&ed25519_public_key(key));
+            Ok(public_key)
+        }
+    }
+}
+
+/// The child of a node at `index` (hardened from `HD_HARDENED` up).
+fn child_node(key: &[u8; KEY_SIZE], chain: &HdChain, index: u32) -> VaultResult<HdNode> {
+    if chain.curve == HdCurve::Ed25519 && index < HD_HARDENED {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("Ed25519 derives hardened children only; index {index} is not")));
+    }
+    let depth = chain
+        .depth
+        .checked_add(1)
+        .ok_or_else(|| error_detail(ERR_INVALID_INPUT, format_args!("path is deeper than 255 levels")))?;
+    let public_key = fingerprint_key(key, chain.curve)?;
+
+    // Hardened: 0x00 || key || index; normal: public key || index
+    let mut data = Secret::new([0u8; SECP256K1_PUBLIC_KEY_SIZE + 4]);
+    if index >= HD_HARDENED {
+        data[1..SECP256K1_PUBLIC_KEY_SIZE].copy_from_slice(key);
+    } else {
+        data[..SECP256K1_PUBLIC_KEY_SIZE].copy_from_slice(&public_key);
+    }
+    data[SECP256K1_PUBLIC_KEY_SIZE..].copy_from_slice(&index.to_be_bytes());
+    let i = hmac_sha512(&chain.chain_code, &[data.as_ref()])?;
+
+    let mut child_key = Secret::new([0u8; KEY_SIZE]);
+    child_key.copy_from_slice(&i[..32]);
+    if chain.curve == HdCurve::Secp256k1 {
+        child_key = tweak_add(key, &child_key)
+            .map_err(|code| error_detail(code, format_args!("child {index:#x} is invalid; use the next index")))?;
+    }
+
+    let mut child =
+        HdChain { curve: chain.curve, chain_code: [0u8; HD_CHAIN_CODE_SIZE], depth, parent_fingerprint: [0u8; 4], child_number: index };
+    child.chain_code.copy_from_slice(&i[32..]);
+    child.parent_fingerprint.copy_from_slice(&hash160(&public_key)[..4]);
+    Ok((child_key, child))
+}
+
+/// Parse `m/44'/0'/0'/0/0` into child indices.
+fn parse_path(path: &[u8]) -> VaultResult<Vec<u32>> {
+    let mut components = path.split(|&b| b == b'/');
+    if components.next() != Some(b"m") {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("path does not start with \"m\"")));
+    }
+
+    let mut indices = Vec::new();
+    for (position, component) in components.enumerate() {
+        let (digits, hardened) = match component.split_last() {
+            Some((b'\'' | b'h' | b'H', digits)) => (digits, HD_HARDENED),
+            _ => (component, 0),
+        };
+        let index = std::str::from_utf8(digits)
+            .ok()
+            .filter(|d| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|d| d.parse::<u32>().ok())
+            .filter(|&i| i < HD_HARDENED)
+            .ok_or_else(|| error_detail(ERR_INVALID_INPUT, format_args!("path component {} is not an index below 2^31", position + 1)))?;
+        indices.push(index | hardened);
+    }
+    Ok(indices)
+}
+
+/// The node behind `handle`, which must come from `vault_hd_*`.
+fn node_for(handle: u64) -> VaultResult<HdNode> {
+    let held = held_for(handle)?;
+    match &held.chain {
+        Some(chain) => Ok((Secret::new(held.key), chain.clone())),
+        None => Err(error_detail(ERR_INVALID_INPUT, format_args!("handle {handle} is not an HD key"))),
+    }
+}
+
+/// RIPEMD-160 of SHA-256, Bitcoin's key identifier.
+fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+/// The Base58Check `xpub` text of a secp256k1 node.
+fn xpub(key: &[u8; KEY_SIZE], chain: &HdChain) -> VaultResult<Vec<u8>> {
+    if chain.curve != HdCurve::Secp256k1 {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("{} HD keys have no xpub", chain.curve.name())));
+    }
+    let mut payload = [0u8; XPUB_PAYLOAD_SIZE];
+    payload[..4].copy_from_slice(&XPUB_VERSION);
+    payload[4] = chain.depth;
+    payload[5..9].copy_from_slice(&chain.parent_fingerprint);
+    payload[9..13].copy_from_slice(&chain.child_number.to_be_bytes());
+    payload[13..45].copy_from_slice(&chain.chain_code);
+    payload[45..].copy_from_slice(&public_key_for(key)?);
+    Ok(bs58::encode(payload).with_check().into_vec())
+}
+
+/// Derive the BIP-32 master key from a seed and keep it behind a handle.
+///
+/// The seed is usually the 64 bytes from `vault_mnemonic_to_seed`.
+///
+/// # Safety
+///
+/// - `seed` must be valid for `seed_len` bytes (16 to 64)
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, or `ERR_INVALID_INPUT` for a null pointer, a seed of the
+/// wrong size, or a seed whose master key is invalid
+#[no_mangle]
+pub unsafe extern "C" fn vault_hd_master_from_seed(seed: *const u8, seed_len: u32, out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| master_to_handle(seed, seed_len, out_handle, HdCurve::Secp256k1))
+}
+
+/// Derive the SLIP-0010 Ed25519 master key from a seed and keep it behind a handle.
+///
+/// Derive below it with `vault_hd_derive_path`, hardened indices only.
+///
+/// # Safety
+///
+/// - `seed` must be valid for `seed_len` bytes (16 to 64)
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, or `ERR_INVALID_INPUT` for a null pointer or a seed of the
+/// wrong size
+#[no_mangle]
+pub unsafe extern "C" fn vault_hd_ed25519_master_from_seed(seed: *const u8, seed_len: u32, out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| master_to_handle(seed, seed_len, out_handle, HdCurve::Ed25519))
+}
+
+unsafe fn master_to_handle(seed: *const u8, seed_len: u32, out_handle: *mut u64, curve: HdCurve) -> i32 {
+    // Validate inputs
+    if seed.is_null() || out_handle.is_null() {
+        return ERR_INVALID_INPUT;
+    }
+    if !(HD_MIN_SEED_SIZE..=HD_MAX_SEED_SIZE).contains(&(seed_len as usize)) {
+        return error_detail(ERR_INVALID_INPUT, format_args!("seed is {seed_len} bytes, expected {HD_MIN_SEED_SIZE} to {HD_MAX_SEED_SIZE}"));
+    }
+    let seed_slice = slice::from_raw_parts(seed, seed_len as usize);
+
+    match master_node(seed_slice, curve) {
+        Ok((key, chain)) => {
+            *out_handle = insert_key(key.as_ref(), curve.purpose(), Some(&chain));
+            0
+        }
+        Err(code) => code,
+    }
+}
+
+/// Derive the node at `path` below an HD handle and keep it behind a new handle.
+///
+/// # Format
+///
+/// UTF-8 text such as `m/44'/0'/0'/0/0`; see the module documentation.
+///
+/// # Safety
+///
+/// - `path` must be valid for `path_len` bytes
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, or `ERR_INVALID_INPUT` for a null pointer, an unknown or
+/// non-HD handle, a malformed path, a normal index below an Ed25519 handle,
+/// or an invalid child key
+#[no_mangle]
+pub unsafe extern "C" fn vault_hd_derive_path(handle: u64, path: *const u8, path_len: u32, out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if path.is_null() || out_handle.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let indices = match parse_path(slice::from_raw_parts(path, path_len as usize)) {
+            Ok(i) => i,
+            Err(code) => return code,
+        };
+        let (mut key, mut chain) = match node_for(handle) {
+            Ok(node) => node,
+            Err(code) => return code,
+        };
+
+        for index in indices {
+            (key, chain) = match child_node(&key, &chain, index) {
+                Ok(node) => node,
+                Err(code) => return code,
+            };
+        }
+        *out_handle = insert_key(key.as_ref(), chain.curve.purpose(), Some(&chain));
+        0
+    })
+}
+
+/// Export the extended public key of an HD handle.
+///
+/// # Format
+///
+/// Base58Check `xpub…` text (mainnet version bytes), not NUL-terminated.
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the xpub, or `ERR_INVALID_INPUT` for an unknown,
+/// non-HD or Ed25519 handle
+#[no_mangle]
+pub extern "C" fn vault_hd_export_xpub(handle: u64) -> VaultBuffer {
+    ffi_boundary(|| {
+        let (key, chain) = match node_for(handle) {
+            Ok(node) => node,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        match xpub(&key, &chain) {
+            Ok(text) => VaultBuffer::success(text),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let bytes = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        bytes
+    }
+
+    unsafe fn derive(handle: u64, path: &str) -> Result<u64, i32> {
+        let mut child = 0u64;
+        match vault_hd_derive_path(handle, path.as_ptr(), path.len() as u32, &mut child) {
+            0 => Ok(child),
+            code => Err(code),
+        }
+    }
+
+    unsafe fn export(handle: u64) -> String {
+        String::from_utf8(take(vault_hd_export_xpub(handle))).unwrap()
+    }
+
+    #[test]
+    fn test_hd_bip32_vector_1() {
+        let seed = hex("000102030405060708090a0b0c0d0e0f");
+        let chain = [
+            ("m", "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"),
+            ("m/0H", "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw"),
+            ("m/0H/1", "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"),
+            ("m/0H/1/2H", "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5"),
+            ("m/0H/1/2H/2", "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV"),
+            (
+                "m/0H/1/2H/2/1000000000",
+                "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+            ),
+        ];
+
+        unsafe {
+            let mut master = 0u64;
+            assert_eq!(vault_hd_master_from_seed(seed.as_ptr(), seed.len() as u32, &mut master), 0);
+            for (path, expected) in chain {
+                let node = derive(master, path).unwrap();
+                assert_eq!(export(node), expected, "{path}");
+                assert_eq!(vault_key_destroy(node), 0);
+            }
+
+            // `m` is the handle's own node, and every suffix spelling agrees
+            let account = derive(master, "m/0'/1").unwrap();
+            let leaf = derive(account, "m/2h/2").unwrap();
+            assert_eq!(export(leaf), chain[4].1);
+
+            // A derived handle signs like any imported secp256k1 key
+            let public_key = take(vault_secp256k1_public_key(leaf));
+            let hash = [0x5Au8; 32];
+            let signature = take(vault_secp256k1_sign(leaf, hash.as_ptr(), 32));
+            assert_eq!(vault_secp256k1_verify(public_key.as_ptr(), 33, hash.as_ptr(), 32, signature.as_ptr(), 64), 0);
+
+            for handle in [master, account, leaf] {
+                assert_eq!(vault_key_destroy(handle), 0);
+            }
+            assert_eq!(vault_hd_export_xpub(master).error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_hd_slip10_ed25519_vector_1() {
+        let seed = hex("000102030405060708090a0b0c0d0e0f");
+        // (path, chain code, private key, public key)
+        let chain = [
+            (
+                "m",
+                "90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+            ),
+            (
+                "m/0H",
+                "8b59aa11380b624e81507a27fedda59fea6d0b779a778918a2fd3590e16e9c69",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+            ),
+            (
+                "m/0H/1H/2H/2H/1000000000H",
+                "68789923a0cac2cd5a29172a475fe9e0fb14cd6adb5ad98a3fa70333e7afa230",
+                "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+                "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+            ),
+        ];
+
+        unsafe {
+            let mut master = 0u64;
+            assert_eq!(vault_hd_ed25519_master_from_seed(seed.as_ptr(), seed.len() as u32, &mut master), 0);
+            for (path, chain_code, private_key, public_key) in chain {
+                let node = derive(master, path).unwrap();
+                let (key, chain) = node_for(node).unwrap();
+                assert_eq!(chain.chain_code.to_vec(), hex(chain_code), "{path}");
+                assert_eq!(key.to_vec(), hex(private_key), "{path}");
+                assert_eq!(take(vault_ed25519_public_key(node)), hex(public_key), "{path}");
+                assert_eq!(vault_key_destroy(node), 0);
+            }
+
+            let node = derive(master, "m/44'/501'/0'/0'").unwrap();
+            let public_key = take(vault_ed25519_public_key(node));
+            let signature = take(vault_ed25519_sign_with_handle(node, b"transfer".as_ptr(), 8));
+            assert_eq!(vault_ed25519_verify(public_key.as_ptr(), 32, b"transfer".as_ptr(), 8, signature.as_ptr(), 64), 0);
+
+            // No normal children and no xpub, and each curve refuses the other's handles
+            assert_eq!(derive(master, "m/44'/501'/0'/0"), Err(ERR_INVALID_INPUT));
+            assert_eq!(vault_hd_export_xpub(node).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_secp256k1_sign(node, [0x5Au8; 32].as_ptr(), 32).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_secp256k1_public_key(node).error, ERR_INVALID_INPUT);
+            let mut bitcoin = 0u64;
+            assert_eq!(vault_hd_master_from_seed(seed.as_ptr(), seed.len() as u32, &mut bitcoin), 0);
+            assert_eq!(vault_ed25519_sign_with_handle(bitcoin, b"transfer".as_ptr(), 8).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_ed25519_public_key(bitcoin).error, ERR_INVALID_INPUT);
+
+            for handle in [master, node, bitcoin] {
+                assert_eq!(vault_key_destroy(handle), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hd_rejections() {
+        let seed = [0x11u8; 64];
+
+        unsafe {
+            let mut master = 0u64;
+            assert_eq!(vault_hd_master_from_seed(seed.as_ptr(), 15, &mut master), ERR_INVALID_INPUT);
+            assert_eq!(vault_hd_master_from_seed(seed.as_ptr(), 65, &mut master), ERR_INVALID_INPUT);
+            assert_eq!(vault_hd_master_from_seed(ptr::null(), 64, &mut master), ERR_INVALID_INPUT);
+            assert_eq!(vault_hd_master_from_seed(seed.as_ptr(), 64, &mut master), 0);
+
+            for path in ["", "44'/0'", "M/0", "m/", "m//0", "m/x", "m/-1", "m/+1", "m/2147483648", "m/0''", "m/0 "] {
+                assert_eq!(derive(master, path), Err(ERR_INVALID_INPUT), "{path:?}");
+            }
+            let hardest = derive(master, "m/2147483647'").unwrap();
+            assert_eq!(vault_key_destroy(hardest), 0);
+
+            // A plain key handle has no chain code
+            let mut plain = 0u64;
+            assert_eq!(vault_key_import([0x42u8; 32].as_ptr(), 32, &mut plain), 0);
+            assert_eq!(derive(plain, "m/0"), Err(ERR_INVALID_INPUT));
+            assert_eq!(vault_hd_export_xpub(plain).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_key_destroy(plain), 0);
+
+            assert_eq!(vault_key_destroy(master), 0);
+            assert_eq!(derive(master, "m/0"), Err(ERR_INVALID_INPUT));
+        }
+    }
+}
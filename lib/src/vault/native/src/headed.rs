@@ -0,0 +1,269 @@
+//! Records with a Cleartext Header
+//!
+//! A sealed entry whose small header (entry type, created-at, ...) stays
+//! readable without the key, for indexing and listing, while still being
+//! authenticated: the header is the associated data of the body's AEAD, so
+//! changing a single header byte makes the whole record fail to open.
+//!
+//! `vault_read_record_header` returns the header without checking it;
+//! only what `vault_unseal_record` returns has been verified.
+//!
+//! ## Format
+//!
+//! `format (1, 0x0B) || header_len (2, LE) || header || nonce (24) || ciphertext || tag (16)`
+//!
+//! Everything before the nonce is authenticated as associated data.
+
+use std::slice;
+
+use super::*;
+
+/// Longest header accepted, in bytes
+const MAX_RECORD_HEADER_SIZE: usize = u16::MAX as usize;
+
+/// Size of the fixed prefix: format || header_len
+const HEADED_PREFIX_SIZE: usize = FORMAT_HEADER_SIZE + 2;
+
+/// Split a headed record into (associated data, header, sealed body).
+fn split_headed(sealed: &[u8]) -> VaultResult<(&[u8], &[u8], &[u8])> {
+    if sealed.len() < HEADED_PREFIX_SIZE {
+        return Err(ERR_INVALID_INPUT);
+    }
+    if sealed[0] != FORMAT_HEADED {
+        return Err(ERR_UNSUPPORTED_VERSION);
+    }
+    let header_len = u16::from_le_bytes([sealed[1], sealed[2]]) as usize;
+    let aad_len = HEADED_PREFIX_SIZE + header_len;
+    if sealed.len() < aad_len + NONCE_SIZE + TAG_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    let (aad, body) = sealed.split_at(aad_len);
+    Ok((aad, &aad[HEADED_PREFIX_SIZE..], body))
+}
+
+/// Seal a body under a key with a cleartext, authenticated header.
+///
+/// # Format
+///
+/// Output: `format (1) || header_len (2) || header || nonce (24) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `header` must be valid for `header_len` bytes (at most 65535; may be
+///   null when `header_len` is 0)
+/// - `body` must be valid for `body_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_record(
+    key: *const u8,
+    key_len: u32,
+    header: *const u8,
+    header_len: u32,
+    body: *const u8,
+    body_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if body.is_null() || (header.is_null() && header_len != 0) || header_len as usize > MAX_RECORD_HEADER_SIZE {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let header_slice: &[u8] = if header_len == 0 { &[] } else { slice::from_raw_parts(header, header_len as usize) };
+        let body_slice = slice::from_raw_parts(body, body_len as usize);
+
+        let mut output = match output_buffer(HEADED_PREFIX_SIZE + header_slice.len() + NONCE_SIZE + body_slice.len() + TAG_SIZE) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.push(FORMAT_HEADED);
+        output.extend_from_slice(&(header_len as u16).to_le_bytes());
+        output.extend_from_slice(header_slice);
+
+        let sealed = match xchacha_seal(key_slice, body_slice, &output) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&sealed);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Verify a headed record and decrypt its body.
+///
+/// The verified header is written to `*out_header` (pass null to skip it).
+/// On failure `*out_header` holds an error buffer.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - `out_header` must be writable for one `VaultBuffer`, or null
+/// - Returned buffers must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the body, or `ERR_DECRYPT_FAILED` if the body or
+/// header was altered
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_record(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+    out_header: *mut VaultBuffer,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        let fail = |code| {
+            if !out_header.is_null() {
+                *out_header = VaultBuffer::error(code);
+            }
+            VaultBuffer::error(code)
+        };
+
+        // Validate inputs
+        if sealed.is_null() {
+            return fail(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return fail(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (aad, header, body) = match split_headed(sealed_slice) {
+            Ok(parts) => parts,
+            Err(code) => return fail(code),
+        };
+
+        match xchacha_open(key_slice, body, aad) {
+            Ok(plaintext) => {
+                if !out_header.is_null() {
+                    *out_header = VaultBuffer::success(header.to_vec());
+                }
+                VaultBuffer::success(plaintext)
+            }
+            Err(code) => fail(code),
+        }
+    })
+}
+
+/// Read the header of a headed record without the key.
+///
+/// The header is **not verified**: use it for indexing and display only,
+/// and rely on `vault_unseal_record` for anything that matters.
+///
+/// # Safety
+///
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_read_record_header(sealed: *const u8, sealed_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        match split_headed(sealed_slice) {
+            Ok((_, header, _)) => VaultBuffer::success(header.to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[u8] = b"type=note;created=2025-06-01";
+
+    unsafe fn seal(key: &[u8], header: &[u8], body: &[u8]) -> Vec<u8> {
+        let result = vault_seal_record(key.as_ptr(), 32, header.as_ptr(), header.len() as u32, body.as_ptr(), body.len() as u32);
+        assert_eq!(result.error, 0);
+        let sealed = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        sealed
+    }
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let out = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        out
+    }
+
+    #[test]
+    fn test_headed_roundtrip() {
+        let key = [0x42u8; 32];
+        let body = b"the secret part";
+
+        unsafe {
+            let sealed = seal(&key, HEADER, body);
+            assert_eq!(sealed[0], FORMAT_HEADED);
+            assert_eq!(&sealed[HEADED_PREFIX_SIZE..HEADED_PREFIX_SIZE + HEADER.len()], HEADER);
+
+            let mut header = VaultBuffer::error(0);
+            let opened = vault_unseal_record(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, &mut header);
+            assert_eq!(take(opened), body);
+            assert_eq!(take(header), HEADER);
+
+            // Skipping the header output and an empty header both work
+            let opened = vault_unseal_record(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, ptr::null_mut());
+            assert_eq!(take(opened), body);
+            let bare = vault_seal_record(key.as_ptr(), 32, ptr::null(), 0, body.as_ptr(), body.len() as u32);
+            assert_eq!(bare.error, 0);
+            let opened = vault_unseal_record(key.as_ptr(), 32, bare.data, bare.len, ptr::null_mut());
+            assert_eq!(take(opened), body);
+            vault_free(bare.data, bare.len);
+        }
+    }
+
+    #[test]
+    fn test_headed_header_tamper_detected() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let mut sealed = seal(&key, HEADER, b"body");
+            sealed[HEADED_PREFIX_SIZE + 5] ^= 1;
+
+            let mut header = VaultBuffer::error(0);
+            let opened = vault_unseal_record(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, &mut header);
+            assert_eq!(opened.error, ERR_DECRYPT_FAILED);
+            assert_eq!(header.error, ERR_DECRYPT_FAILED);
+            assert!(header.data.is_null());
+
+            // Moving bytes between header and body by changing header_len fails too
+            let mut sealed = seal(&key, HEADER, b"body");
+            sealed[1] -= 1;
+            let opened = vault_unseal_record(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, ptr::null_mut());
+            assert_eq!(opened.error, ERR_DECRYPT_FAILED);
+        }
+    }
+
+    #[test]
+    fn test_headed_read_header_without_key() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let sealed = seal(&key, HEADER, b"body");
+            assert_eq!(take(vault_read_record_header(sealed.as_ptr(), sealed.len() as u32)), HEADER);
+
+            let truncated = vault_read_record_header(sealed.as_ptr(), (HEADED_PREFIX_SIZE + HEADER.len()) as u32);
+            assert_eq!(truncated.error, ERR_CORRUPT_DATA);
+
+            let plain = vault_seal(key.as_ptr(), b"x".as_ptr(), 1);
+            assert_eq!(vault_read_record_header(plain.data, plain.len).error, ERR_UNSUPPORTED_VERSION);
+            vault_free(plain.data, plain.len);
+        }
+    }
+}
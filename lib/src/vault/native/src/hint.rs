@@ -0,0 +1,313 @@
+//! Seals with a Recovery Hint
+//!
+//! A sealed blob that carries a short passphrase hint ("the one from the
+//! blue notebook") alongside the payload. The hint is encrypted under its
+//! own subkey, so it can be revealed on its own without decrypting the
+//! payload, and is never stored in plaintext.
+//!
+//! The hint subkey is derived from the main key with HKDF-SHA256 under a
+//! dedicated info string. Callers that gate the hint behind a secondary check
+//! can hand `vault_peek_hint` the main key; the payload key itself never
+//! decrypts the hint, and the hint key never decrypts the payload.
+//!
+//! ## Format
+//!
+//! `format (1, 0x06) || hint_len (2, LE) || hint nonce (24) || hint ciphertext || hint tag (16) || nonce (24) || ciphertext || tag (16)`
+//!
+//! The header (format, hint_len) is authenticated with the hint. The payload
+//! authenticates the header and the whole sealed hint, so a hint cannot be
+//! swapped between blobs sealed under the same key.
+
+use std::slice;
+
+use crate::subkey::hkdf_sha256;
+
+use super::*;
+
+/// HKDF info for the hint subkey
+const HINT_KEY_INFO: &[u8] = b"vault_core 2025-01 recovery hint key";
+
+/// Longest hint accepted, in bytes
+const MAX_HINT_SIZE: usize = 256;
+
+/// Size of the authenticated header: format || hint_len
+const HINTED_HEADER_SIZE: usize = FORMAT_HEADER_SIZE + 2;
+
+/// Derive the subkey that encrypts the hint.
+fn hint_key(key: &[u8]) -> VaultResult<Zeroizing<Vec<u8>>> {
+    hkdf_sha256(key, &[], HINT_KEY_INFO, KEY_SIZE).map(Zeroizing::new)
+}
+
+/// Split a hinted blob into (header, sealed hint, sealed payload).
+fn split_hinted(sealed: &[u8]) -> VaultResult<(&[u8], &[u8], &[u8])> {
+    if sealed.len() < HINTED_HEADER_SIZE + 2 * (NONCE_SIZE + TAG_SIZE) {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    if sealed[0] != FORMAT_HINTED {
+        return Err(ERR_UNSUPPORTED_VERSION);
+    }
+    let hint_len = u16::from_le_bytes([sealed[1], sealed[2]]) as usize;
+    if hint_len > MAX_HINT_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    let (header, body) = sealed.split_at(HINTED_HEADER_SIZE);
+    let hint_sealed_len = NONCE_SIZE + hint_len + TAG_SIZE;
+    if body.len() < hint_sealed_len + NONCE_SIZE + TAG_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let (hint, payload) = body.split_at(hint_sealed_len);
+    Ok((header, hint, payload))
+}
+
+/// Seal data together with an encrypted recovery hint.
+///
+/// # Format
+///
+/// Output: `format (1) || hint_len (2) || sealed hint (24 + hint_len + 16) || nonce (24) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - `hint` must be valid for `hint_len` bytes, at most 256
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_with_hint(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    hint: *const u8,
+    hint_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if (plaintext.is_null() && plaintext_len != 0) || (hint.is_null() && hint_len != 0) || hint_len as usize > MAX_HINT_SIZE {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+        let hint_slice: &[u8] = if hint_len == 0 { &[] } else { slice::from_raw_parts(hint, hint_len as usize) };
+
+        let mut output = match output_buffer(HINTED_HEADER_SIZE + 2 * (NONCE_SIZE + TAG_SIZE) + hint_slice.len() + plaintext_slice.len()) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.push(FORMAT_HINTED);
+        output.extend_from_slice(&(hint_len as u16).to_le_bytes());
+
+        let sealed_hint = match hint_key(key_slice).and_then(|k| xchacha_seal(&k, hint_slice, &output)) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&sealed_hint);
+
+        let sealed_payload = match xchacha_seal(key_slice, plaintext_slice, &output) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&sealed_payload);
+
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt only the recovery hint of a `vault_seal_with_hint` blob.
+///
+/// The payload is neither authenticated nor decrypted.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the hint (possibly empty)
+#[no_mangle]
+pub unsafe extern "C" fn vault_peek_hint(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, hint, _) = match split_hinted(sealed_slice) {
+            Ok(parts) => parts,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        match hint_key(key_slice).and_then(|k| xchacha_open(&k, hint, header)) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt the payload of a `vault_seal_with_hint` blob.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_with_hint(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, hint, payload) = match split_hinted(sealed_slice) {
+            Ok(parts) => parts,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let aad_len = header.len() + hint.len();
+
+        match xchacha_open(key_slice, payload, &sealed_slice[..aad_len]) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn seal(key: &[u8], plaintext: &[u8], hint: &[u8]) -> Vec<u8> {
+        let result = vault_seal_with_hint(
+            key.as_ptr(),
+            32,
+            plaintext.as_ptr(),
+            plaintext.len() as u32,
+            hint.as_ptr(),
+            hint.len() as u32,
+        );
+        assert_eq!(result.error, 0);
+        let sealed = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        sealed
+    }
+
+    unsafe fn take(result: VaultBuffer) -> Vec<u8> {
+        assert_eq!(result.error, 0);
+        let out = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        out
+    }
+
+    #[test]
+    fn test_hint_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintext = b"wallet seed";
+        let hint = b"the one from the blue notebook";
+
+        unsafe {
+            let sealed = seal(&key, plaintext, hint);
+            assert_eq!(sealed[0], FORMAT_HINTED);
+            assert!(!sealed.windows(hint.len()).any(|w| w == hint));
+
+            let peeked = take(vault_peek_hint(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32));
+            assert_eq!(peeked, hint);
+
+            let opened = take(vault_unseal_with_hint(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32));
+            assert_eq!(opened, plaintext);
+
+            let wrong = [0x43u8; 32];
+            let result = vault_peek_hint(wrong.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32);
+            assert_eq!(result.error, ERR_DECRYPT_FAILED);
+        }
+    }
+
+    #[test]
+    fn test_hint_key_separated() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let subkey = hint_key(&key).unwrap();
+            assert_ne!(subkey.as_slice(), &key);
+
+            // Neither key opens the other section
+            let sealed = seal(&key, b"payload", b"hint");
+            let (header, hint, payload) = split_hinted(&sealed).unwrap();
+            assert_eq!(xchacha_open(&key, hint, header), Err(ERR_DECRYPT_FAILED));
+            let aad = &sealed[..header.len() + hint.len()];
+            assert_eq!(xchacha_open(&subkey, payload, aad), Err(ERR_DECRYPT_FAILED));
+        }
+    }
+
+    #[test]
+    fn test_hint_truncated_is_corrupt() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let sealed = seal(&key, b"payload", b"hint");
+            for len in [HINTED_HEADER_SIZE, HINTED_HEADER_SIZE + 2 * (NONCE_SIZE + TAG_SIZE) - 1, sealed.len() - 8] {
+                let peeked = vault_peek_hint(key.as_ptr(), 32, sealed.as_ptr(), len as u32);
+                assert_eq!(peeked.error, ERR_CORRUPT_DATA, "{len}");
+                let opened = vault_unseal_with_hint(key.as_ptr(), 32, sealed.as_ptr(), len as u32);
+                assert_eq!(opened.error, ERR_CORRUPT_DATA, "{len}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_hint_swap_rejected() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let first = seal(&key, b"payload one", b"hint A");
+            let second = seal(&key, b"payload two", b"hint B");
+
+            // Graft the second blob's hint onto the first blob's payload
+            let split = HINTED_HEADER_SIZE + NONCE_SIZE + 6 + TAG_SIZE;
+            let mut spliced = second[..split].to_vec();
+            spliced.extend_from_slice(&first[split..]);
+
+            let result = vault_unseal_with_hint(key.as_ptr(), 32, spliced.as_ptr(), spliced.len() as u32);
+            assert_eq!(result.error, ERR_DECRYPT_FAILED);
+        }
+    }
+
+    #[test]
+    fn test_hint_too_long() {
+        let key = [0x42u8; 32];
+        let hint = [b'h'; MAX_HINT_SIZE + 1];
+
+        unsafe {
+            let result = vault_seal_with_hint(key.as_ptr(), 32, b"x".as_ptr(), 1, hint.as_ptr(), hint.len() as u32);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! IETF ChaCha20-Poly1305 (12-byte nonce)
+//!
+//! RFC 8439 ChaCha20-Poly1305, as used by libsodium's
+//! `crypto_aead_chacha20poly1305_ietf` and many external formats, for
+//! importing and exporting data that cannot use XChaCha20.
+//!
+//! **Message limit:** nonces here are 96-bit and random, so collisions
+//! become a real risk much sooner than with the 192-bit XChaCha nonce. Seal
+//! no more than 2^32 messages under one key (the NIST SP 800-38D bound for
+//! random 96-bit nonces). Prefer `vault_seal` for anything stored by this
+//! library.
+//!
+//! ## Format
+//!
+//! `format (1, 0x09) || nonce (12) || ciphertext || tag (16)`
+//!
+//! The format byte is authenticated as associated data. The
+//! `nonce || ciphertext || tag` part is libsodium's combined output with
+//! that one byte of associated data.
+
+use std::slice;
+
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+use super::*;
+
+/// IETF ChaCha20-Poly1305 nonce size
+pub(crate) const IETF_NONCE_SIZE: usize = 12;
+
+/// Encrypt under an explicit nonce: `ciphertext || tag`.
+pub(crate) fn ietf_encrypt(key: &[u8], nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> VaultResult<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?;
+
+    let mut output = Secret::with_capacity(plaintext.len() + TAG_SIZE)?;
+    output.extend_from_slice(plaintext);
+    let tag = cipher
+        .encrypt_in_place_detached(Nonce::from_slice(nonce), aad, &mut output)
+        .map_err(|_| ERR_INVALID_INPUT)?;
+    output.extend_from_slice(&tag);
+    Ok(output.into_inner())
+}
+
+/// Decrypt `ciphertext || tag` under an explicit nonce.
+pub(crate) fn ietf_decrypt(key: &[u8], nonce: &[u8], sealed: &[u8], aad: &[u8]) -> VaultResult<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?;
+
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - TAG_SIZE);
+    let mut plaintext = Secret::copy_of(ciphertext)?;
+    cipher
+        .decrypt_in_place_detached(Nonce::from_slice(nonce), aad, &mut plaintext, Tag::from_slice(tag))
+        .map_err(|_| ERR_DECRYPT_FAILED)?;
+    Ok(plaintext.into_inner())
+}
+
+/// Encrypt with IETF ChaCha20-Poly1305 under a random 12-byte nonce.
+///
+/// See the module documentation for the per-key message limit.
+///
+/// # Format
+///
+/// Output: `format (1) || nonce (12) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_ietf(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if plaintext.is_null() && plaintext_len != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let mut nonce = [0u8; IETF_NONCE_SIZE];
+        if let Err(code) = random_bytes(&mut nonce) {
+            return VaultBuffer::error(code);
+        }
+
+        let header = [FORMAT_IETF];
+        let sealed = match ietf_encrypt(key_slice, &nonce, plaintext_slice, &header) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(FORMAT_HEADER_SIZE + IETF_NONCE_SIZE + sealed.len()) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&sealed);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt data sealed with `vault_seal_ietf`.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must contain: format (1) || nonce (12) || ciphertext || tag (16)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_ietf(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = FORMAT_HEADER_SIZE + IETF_NONCE_SIZE + TAG_SIZE;
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, body) = sealed_slice.split_at(FORMAT_HEADER_SIZE);
+        if header[0] != FORMAT_IETF {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+        let (nonce, ciphertext) = body.split_at(IETF_NONCE_SIZE);
+
+        match ietf_decrypt(key_slice, nonce, ciphertext, header) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_ietf_rfc8439_vector() {
+        // RFC 8439 section 2.8.2, also libsodium's aead_chacha20poly1305 ietf test
+        let key: Vec<u8> = (0x80..=0x9f).collect();
+        let nonce = hex("070000004041424344454647");
+        let aad = hex("50515253c0c1c2c3c4c5c6c7");
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let expected = hex(
+            "d31a8d34648e60db7b86afbc53ef7ec2 a4aded51296e08fea9e2b5a736ee62d6
+             3dbea45e8ca9671282fafb69da92728b 1a71de0a9e060b2905d6a5b67ecd3b36
+             92ddbd7f2d778b8c9803aee328091b58 fab324e4fad675945585808b4831d7bc
+             3ff4def08e4b7a9de576d26586cec64b 6116
+             1ae10b594f09e26a7e902ecbd0600691",
+        );
+
+        assert_eq!(ietf_encrypt(&key, &nonce, plaintext, &aad).unwrap(), expected);
+        assert_eq!(ietf_decrypt(&key, &nonce, &expected, &aad).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_ietf_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintext = b"exported for an external tool";
+
+        unsafe {
+            let sealed = vault_seal_ietf(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            assert_eq!(sealed.len as usize, FORMAT_HEADER_SIZE + IETF_NONCE_SIZE + plaintext.len() + TAG_SIZE);
+            assert_eq!(*sealed.data, FORMAT_IETF);
+
+            let unsealed = vault_unseal_ietf(key.as_ptr(), 32, sealed.data, sealed.len);
+            assert_eq!(unsealed.error, 0);
+            assert_eq!(slice::from_raw_parts(unsealed.data, unsealed.len as usize), plaintext);
+            vault_free(unsealed.data, unsealed.len);
+
+            // The XChaCha path refuses it by format byte
+            let other = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+            assert_eq!(other.error, ERR_UNSUPPORTED_VERSION);
+
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Caller-Buffer Sealing
+//!
+//! `vault_seal_into` and `vault_unseal_into` write into a buffer the caller
+//! owns instead of returning a `VaultBuffer`, and can work in place so a
+//! large secret never exists in two buffers at once. Blobs are in the
+//! `vault_seal` format and interchangeable with it. Size the buffers with
+//! `vault_seal_overhead` and `vault_max_plaintext`.
+//!
+//! ## Aliasing
+//!
+//! Input and output may either not overlap at all, or start at the same
+//! address (in place):
+//!
+//! - Seal in place: the plaintext sits at the start of a buffer of
+//!   `plaintext_len + 41` bytes; it is moved up past the format byte and
+//!   nonce and encrypted where it lands, and the tag fills the last 16
+//!   bytes.
+//! - Unseal in place: the sealed blob is authenticated and decrypted where
+//!   it is, the plaintext is moved down to the start, and the remaining
+//!   41 bytes of the buffer are zeroed.
+//!
+//! Any other overlap is rejected with `ERR_INVALID_INPUT` before anything
+//! is read or written, since the output would overwrite input not yet read.
+
+use std::slice;
+
+use crate::ietf::IETF_NONCE_SIZE;
+
+use super::*;
+
+/// Bytes a seal adds: format || nonce || tag
+const SEAL_OVERHEAD: usize = FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+
+/// Offset of the ciphertext in a sealed blob
+const CIPHERTEXT_OFFSET: usize = FORMAT_HEADER_SIZE + NONCE_SIZE;
+
+/// How an output region relates to an input region.
+#[derive(Debug, PartialEq, Eq)]
+enum Aliasing {
+    Disjoint,
+    InPlace,
+    Partial,
+}
+
+fn aliasing(input: *const u8, input_len: usize, output: *const u8, output_len: usize) -> Aliasing {
+    let (input, output) = (input as usize, output as usize);
+    if input == output {
+        Aliasing::InPlace
+    } else if input < output.saturating_add(output_len) && output < input.saturating_add(input_len) {
+        Aliasing::Partial
+    } else {
+        Aliasing::Disjoint
+    }
+}
+
+/// Encrypt `buf[CIPHERTEXT_OFFSET..len - TAG_SIZE]` in place and fill in the
+/// header, nonce and tag around it.
+fn seal_in_buffer(cipher: &XChaCha20Poly1305, buf: &mut [u8], nonce: &[u8; NONCE_SIZE]) -> VaultResult<()> {
+    let header = [FORMAT_XCHACHA];
+
+    let tag_offset = buf.len() - TAG_SIZE;
+    let tag = cipher
+        .encrypt_in_place_detached(XNonce::from_slice(nonce), &header, &mut buf[CIPHERTEXT_OFFSET..tag_offset])
+        .map_err(|_| ERR_INVALID_INPUT)?;
+
+    buf[0] = FORMAT_XCHACHA;
+    buf[FORMAT_HEADER_SIZE..CIPHERTEXT_OFFSET].copy_from_slice(nonce);
+    buf[tag_offset..].copy_from_slice(&tag);
+    Ok(())
+}
+
+/// Encrypt into a caller-provided buffer, in the `vault_seal` format.
+///
+/// `plaintext` may equal `out` (in place); see the module documentation.
+///
+/// # Format
+///
+/// Output: `format (1) || nonce (24) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - `out` must be writable for `out_len` bytes, which must equal
+///   `plaintext_len + 41`
+///
+/// # Returns
+///
+/// 0 on success, or a negative error code (`ERR_INVALID_INPUT` for a wrong
+/// `out_len` or a partial overlap); argument errors leave `out` untouched
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_into(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    out: *mut u8,
+    out_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        let plaintext_len = plaintext_len as usize;
+        if (plaintext.is_null() && plaintext_len != 0) || out.is_null() || out_len as usize != plaintext_len + SEAL_OVERHEAD {
+            return ERR_INVALID_INPUT;
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+        let mode = aliasing(plaintext, plaintext_len, out, out_len as usize);
+        if mode == Aliasing::Partial {
+            return ERR_INVALID_INPUT;
+        }
+
+        let cipher = match XChaCha20Poly1305::new_from_slice(key_slice) {
+            Ok(c) => c,
+            Err(_) => return ERR_INVALID_INPUT,
+        };
+        let mut nonce = Secret::new([0u8; NONCE_SIZE]);
+        if let Err(code) = random_bytes(nonce.as_mut()) {
+            return code;
+        }
+
+        let buf = slice::from_raw_parts_mut(out, out_len as usize);
+        let body = CIPHERTEXT_OFFSET..CIPHERTEXT_OFFSET + plaintext_len;
+        if mode == Aliasing::InPlace {
+            buf.copy_within(..plaintext_len, CIPHERTEXT_OFFSET);
+        } else if plaintext_len != 0 {
+            buf[body].copy_from_slice(slice::from_raw_parts(plaintext, plaintext_len));
+        }
+
+        match seal_in_buffer(&cipher, buf, &nonce) {
+            Ok(()) => 0,
+            Err(code) => {
+                buf.zeroize();
+                code
+            }
+        }
+    })
+}
+
+/// Decrypt a `vault_seal`-format blob into a caller-provided buffer.
+///
+/// `sealed` may equal `out` (in place); the whole sealed buffer is then
+/// overwritten, plaintext first and zeros after. See the module
+/// documentation.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - `out` must be writable for `out_len` bytes, which must equal
+///   `sealed_len - 41` (when in place, the whole `sealed_len` bytes are
+///   written)
+///
+/// # Returns
+///
+/// 0 on success, or a negative error code (`ERR_INVALID_INPUT` for a wrong
+/// `out_len` or a partial overlap). On `ERR_DECRYPT_FAILED` an in-place
+/// buffer still holds the sealed blob and a separate `out` is zeroed.
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_into(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+    out: *mut u8,
+    out_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        let sealed_len = sealed_len as usize;
+        if sealed.is_null() || out.is_null() || sealed_len < SEAL_OVERHEAD || out_len as usize != sealed_len - SEAL_OVERHEAD {
+            return ERR_INVALID_INPUT;
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+        let mode = aliasing(sealed, sealed_len, out, out_len as usize);
+        if mode == Aliasing::Partial {
+            return ERR_INVALID_INPUT;
+        }
+        if *sealed != FORMAT_XCHACHA {
+            return ERR_UNSUPPORTED_VERSION;
+        }
+        let cipher = match XChaCha20Poly1305::new_from_slice(key_slice) {
+            Ok(c) => c,
+            Err(_) => return ERR_INVALID_INPUT,
+        };
+
+        // Copy out the nonce and tag before the buffer can be overwritten
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len);
+        let plaintext_len = out_len as usize;
+        let tag_offset = CIPHERTEXT_OFFSET + plaintext_len;
+        let nonce = *XNonce::from_slice(&sealed_slice[FORMAT_HEADER_SIZE..CIPHERTEXT_OFFSET]);
+        let tag = *Tag::from_slice(&sealed_slice[tag_offset..]);
+        let header = [FORMAT_XCHACHA];
+
+        if mode == Aliasing::InPlace {
+            let buf = slice::from_raw_parts_mut(out, sealed_len);
+            if cipher
+                .decrypt_in_place_detached(&nonce, &header, &mut buf[CIPHERTEXT_OFFSET..tag_offset], &tag)
+                .is_err()
+            {
+                return ERR_DECRYPT_FAILED;
+            }
+            buf.copy_within(CIPHERTEXT_OFFSET..tag_offset, 0);
+            buf[plaintext_len..].zeroize();
+        } else {
+            let buf = slice::from_raw_parts_mut(out, plaintext_len);
+            buf.copy_from_slice(&sealed_slice[CIPHERTEXT_OFFSET..tag_offset]);
+            if cipher.decrypt_in_place_detached(&nonce, &header, buf, &tag).is_err() {
+                buf.zeroize();
+                return ERR_DECRYPT_FAILED;
+            }
+        }
+        0
+    })
+}
+
+/// Bytes a one-shot seal adds for a cipher, by format byte.
+fn cipher_overhead(cipher_id: u32) -> Option<usize> {
+    let format = u8::try_from(cipher_id).ok()?;
+    match format {
+        FORMAT_XCHACHA => Some(SEAL_OVERHEAD),
+        FORMAT_SIV => Some(FORMAT_HEADER_SIZE + TAG_SIZE),
+        FORMAT_IETF => Some(FORMAT_HEADER_SIZE + IETF_NONCE_SIZE + TAG_SIZE),
+        _ => None,
+    }
+}
+
+/// Total bytes a seal adds to the plaintext for a cipher.
+///
+/// `cipher_id` is the cipher's format byte, as listed by
+/// `vault_capabilities` (1 = XChaCha20-Poly1305, 2 = AES-256-SIV,
+/// 9 = ChaCha20-Poly1305). Size output buffers from this rather than from
+/// hardcoded constants.
+///
+/// # Returns
+///
+/// The overhead in bytes, or 0 for an unknown cipher
+#[no_mangle]
+pub extern "C" fn vault_seal_overhead(cipher_id: u32) -> u32 {
+    ffi_boundary(|| {
+        cipher_overhead(cipher_id).map_or(0, |overhead| overhead as u32)
+    })
+}
+
+/// Largest plaintext whose sealed form fits in `out_cap` bytes.
+///
+/// See `vault_seal_overhead` for `cipher_id`.
+///
+/// # Returns
+///
+/// The plaintext capacity in bytes, `ERR_UNSUPPORTED_VERSION` for an
+/// unknown cipher, or `ERR_INVALID_INPUT` if `out_cap` cannot hold even an
+/// empty seal
+#[no_mangle]
+pub extern "C" fn vault_max_plaintext(out_cap: u32, cipher_id: u32) -> i64 {
+    ffi_boundary(|| {
+        let overhead = match cipher_overhead(cipher_id) {
+            Some(o) => o,
+            None => return ERR_UNSUPPORTED_VERSION as i64,
+        };
+        match (out_cap as usize).checked_sub(overhead) {
+            Some(capacity) => capacity as i64,
+            None => ERR_INVALID_INPUT as i64,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_into_in_place() {
+        let key = [0x42u8; 32];
+        let plaintext = b"sealed where it lies";
+        let n = plaintext.len();
+
+        let mut buf = vec![0u8; n + SEAL_OVERHEAD];
+        buf[..n].copy_from_slice(plaintext);
+
+        unsafe {
+            let rc = vault_seal_into(key.as_ptr(), 32, buf.as_ptr(), n as u32, buf.as_mut_ptr(), buf.len() as u32);
+            assert_eq!(rc, 0);
+            assert_eq!(buf[0], FORMAT_XCHACHA);
+
+            // Interchangeable with vault_seal blobs
+            let opened = vault_unseal(key.as_ptr(), buf.as_ptr(), buf.len() as u32);
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+
+            let rc = vault_unseal_into(key.as_ptr(), 32, buf.as_ptr(), buf.len() as u32, buf.as_mut_ptr(), n as u32);
+            assert_eq!(rc, 0);
+            assert_eq!(&buf[..n], plaintext);
+            assert!(buf[n..].iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn test_seal_into_separate_buffers() {
+        let key = [0x42u8; 32];
+        let plaintext = b"copied across";
+        let n = plaintext.len();
+
+        unsafe {
+            let mut sealed = vec![0u8; n + SEAL_OVERHEAD];
+            let rc = vault_seal_into(key.as_ptr(), 32, plaintext.as_ptr(), n as u32, sealed.as_mut_ptr(), sealed.len() as u32);
+            assert_eq!(rc, 0);
+
+            let mut out = vec![0xAAu8; n];
+            let rc = vault_unseal_into(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, out.as_mut_ptr(), n as u32);
+            assert_eq!(rc, 0);
+            assert_eq!(out, plaintext);
+
+            // A failed unseal leaves nothing behind in either mode
+            sealed[CIPHERTEXT_OFFSET] ^= 1;
+            let rc = vault_unseal_into(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, out.as_mut_ptr(), n as u32);
+            assert_eq!(rc, ERR_DECRYPT_FAILED);
+            assert!(out.iter().all(|&b| b == 0));
+
+            let before = sealed.clone();
+            let rc = vault_unseal_into(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, sealed.as_mut_ptr(), n as u32);
+            assert_eq!(rc, ERR_DECRYPT_FAILED);
+            assert_eq!(sealed, before);
+
+            let rc = vault_seal_into(key.as_ptr(), 32, plaintext.as_ptr(), n as u32, sealed.as_mut_ptr(), n as u32);
+            assert_eq!(rc, ERR_INVALID_INPUT);
+
+            // A null pointer with zero length seals an empty plaintext
+            let mut empty = [0u8; SEAL_OVERHEAD];
+            let rc = vault_seal_into(key.as_ptr(), 32, ptr::null(), 0, empty.as_mut_ptr(), SEAL_OVERHEAD as u32);
+            assert_eq!(rc, 0);
+            let opened = vault_unseal(key.as_ptr(), empty.as_ptr(), SEAL_OVERHEAD as u32);
+            assert_eq!((opened.error, opened.len), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_partial_overlap_rejected() {
+        let key = [0x42u8; 32];
+        let n = 32;
+        let mut buf = vec![7u8; n + SEAL_OVERHEAD + 8];
+
+        unsafe {
+            let untouched = buf.clone();
+            let base = buf.as_mut_ptr();
+
+            // Plaintext starting inside the output, and output starting inside the plaintext
+            let rc = vault_seal_into(key.as_ptr(), 32, base.add(4), n as u32, base, (n + SEAL_OVERHEAD) as u32);
+            assert_eq!(rc, ERR_INVALID_INPUT);
+            let rc = vault_seal_into(key.as_ptr(), 32, base, n as u32, base.add(8), (n + SEAL_OVERHEAD) as u32);
+            assert_eq!(rc, ERR_INVALID_INPUT);
+
+            let sealed_len = (n + SEAL_OVERHEAD) as u32;
+            let rc = vault_unseal_into(key.as_ptr(), 32, base, sealed_len, base.add(1), n as u32);
+            assert_eq!(rc, ERR_INVALID_INPUT);
+            assert_eq!(buf, untouched);
+        }
+
+        assert_eq!(aliasing(8 as *const u8, 8, 16 as *const u8, 8), Aliasing::Disjoint);
+        assert_eq!(aliasing(8 as *const u8, 9, 16 as *const u8, 8), Aliasing::Partial);
+    }
+
+    #[test]
+    fn test_seal_overhead_matches_output() {
+        let plaintext = b"sized by the library";
+        let n = plaintext.len() as u32;
+        let key = [0x42u8; 64];
+
+        unsafe {
+            let sealed = [
+                (FORMAT_XCHACHA, vault_seal(key.as_ptr(), plaintext.as_ptr(), n)),
+                (FORMAT_SIV, vault_seal_siv(key.as_ptr(), 64, plaintext.as_ptr(), n, ptr::null(), 0)),
+                (FORMAT_IETF, vault_seal_ietf(key.as_ptr(), 32, plaintext.as_ptr(), n)),
+            ];
+            for (format, blob) in sealed {
+                assert_eq!(blob.error, 0);
+                let overhead = vault_seal_overhead(format as u32);
+                assert_eq!(blob.len - n, overhead);
+                assert_eq!(vault_max_plaintext(blob.len, format as u32), n as i64);
+                assert_eq!(vault_max_plaintext(overhead - 1, format as u32), ERR_INVALID_INPUT as i64);
+                vault_free(blob.data, blob.len);
+            }
+        }
+
+        assert_eq!(vault_seal_overhead(FORMAT_STREAM as u32), 0);
+        assert_eq!(vault_seal_overhead(0x101), 0);
+        assert_eq!(vault_max_plaintext(4096, 0x7F), ERR_UNSUPPORTED_VERSION as i64);
+    }
+}
@@ -0,0 +1,1179 @@
+//! Key Derivation Extensions
+//!
+//! Variations on `vault_derive_key` for integration patterns the core
+//! function leaves to the caller, and the process-wide default Argon2
+//! parameters it uses.
+//!
+//! The defaults start at the compiled-in costs (64 MiB, 3 iterations, 4
+//! lanes) and can be changed at startup with
+//! `vault_set_default_argon2_params`. They are not stored anywhere: a vault
+//! created under one set of defaults cannot be re-derived after they change
+//! unless its parameters were stored with it (see `VaultKdfParams` and
+//! `vault_record_pack`, and re-derive with `vault_derive_key_ex`).
+//!
+//! For side-channel-exposed deployments, the `DERIVE_FORCE_ARGON2I` flag
+//! makes a `vault_derive_key_ex` call use the data-independent Argon2i.
+//!
+//! `vault_derive_key_2fa` additionally binds the key to a device secret, so
+//! that neither the passphrase nor the device alone can re-derive it.
+
+use std::slice;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::subkey::hkdf_sha256;
+
+use super::*;
+
+/// Size of a salted key: salt (16) || key (32)
+const SALTED_KEY_SIZE: usize = SALT_SIZE + KEY_SIZE;
+
+/// Passphrase for timing runs; the derived key is thrown away
+const TIMING_PASSPHRASE: &[u8] = b"vault_core timing run";
+
+/// Least memory `vault_calibrate_kdf` recommends (16 MiB), however slow
+const CALIBRATION_MIN_M_COST: u32 = 16 * 1024;
+
+/// Most iterations `vault_calibrate_kdf` recommends
+const CALIBRATION_MAX_T_COST: u32 = 16;
+
+/// Default Argon2 costs, packed as `m_cost << 32 | t_cost << 16 | p_cost` so
+/// all three are always read and replaced together
+static DEFAULT_ARGON2_PARAMS: AtomicU64 = AtomicU64::new(pack_params(ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST));
+
+/// `vault_derive_key_ex` flag: free the Argon2 working memory without
+/// wiping it (see the function documentation before using it)
+const DERIVE_SKIP_MEMORY_WIPE: u32 = 1;
+
+/// `vault_derive_key_ex` flag: run Argon2i whatever variant is passed
+const DERIVE_FORCE_ARGON2I: u32 = 2;
+
+/// Every `vault_derive_key_ex` flag
+const DERIVE_FLAGS: u32 = DERIVE_SKIP_MEMORY_WIPE | DERIVE_FORCE_ARGON2I;
+
+/// Smallest hardware key accepted by `vault_derive_key_2fa`
+const MIN_HARDWARE_KEY_SIZE: usize = 16;
+
+/// HKDF info for two-factor keys
+const TWO_FACTOR_INFO: &[u8] = b"vault_core 2025-01 two-factor key";
+
+const fn pack_params(m_cost: u32, t_cost: u32, p_cost: u32) -> u64 {
+    (m_cost as u64) << 32 | (t_cost as u64) << 16 | p_cost as u64
+}
+
+/// Position of the first Argon2 cost outside the spec limits (1 = `m_cost`,
+/// 2 = `t_cost`, 3 = `p_cost`): `t_cost >= 1`, `1 <= p_cost < 2^24`, and
+/// `m_cost >= 8 * p_cost`.
+pub(crate) fn argon2_param_error(m_cost: u32, t_cost: u32, p_cost: u32) -> Option<u32> {
+    if t_cost < Params::MIN_T_COST {
+        return Some(2);
+    }
+    if !(Params::MIN_P_COST..=Params::MAX_P_COST).contains(&p_cost) {
+        return Some(3);
+    }
+    if m_cost < Params::MIN_M_COST.max(8 * p_cost) {
+        return Some(1);
+    }
+    None
+}
+
+/// Check Argon2 costs, recording which one is out of range.
+pub(crate) fn argon2_params_arg(m_cost: u32, t_cost: u32, p_cost: u32) -> VaultResult<()> {
+    let detail = match argon2_param_error(m_cost, t_cost, p_cost) {
+        None => return Ok(()),
+        Some(1) => format!("m_cost {m_cost} KiB is below the minimum {} for p_cost {p_cost}", Params::MIN_M_COST.max(8 * p_cost)),
+        Some(2) => format!("t_cost {t_cost} is below the minimum {}", Params::MIN_T_COST),
+        Some(_) => format!("p_cost {p_cost} is outside {}..={}", Params::MIN_P_COST, Params::MAX_P_COST),
+    };
+    Err(error_detail(ERR_INVALID_INPUT, format_args!("{detail}")))
+}
+
+/// The current default `(m_cost, t_cost, p_cost)`.
+pub(crate) fn default_argon2_params() -> (u32, u32, u32) {
+    let packed = DEFAULT_ARGON2_PARAMS.load(Ordering::Acquire);
+    ((packed >> 32) as u32, (packed >> 16) as u16 as u32, packed as u16 as u32)
+}
+
+/// Argon2id under the current default costs.
+pub(crate) fn argon2id_default(passphrase: &[u8], salt: &[u8]) -> VaultResult<Secret<[u8; KEY_SIZE]>> {
+    let (m_cost, t_cost, p_cost) = default_argon2_params();
+    argon2id(passphrase, salt, m_cost, t_cost, p_cost)
+}
+
+/// Serializes tests that change or depend on the default costs.
+#[cfg(test)]
+pub(crate) fn lock_default_params() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Set the Argon2id costs used by `vault_derive_key` and the other
+/// functions that take no explicit parameters.
+///
+/// Safe to call from any thread; a derivation already running keeps the
+/// costs it started with. Changing the defaults does not change how
+/// existing vaults were derived, so store the parameters with each vault
+/// (see the module documentation) before relying on this.
+///
+/// # Returns
+///
+/// 0 on success, or `ERR_INVALID_INPUT` if Argon2 rejects the costs or
+/// `t_cost`/`p_cost` exceed 65535; the previous defaults are then kept
+#[no_mangle]
+pub extern "C" fn vault_set_default_argon2_params(m_cost: u32, t_cost: u32, p_cost: u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if t_cost > u16::MAX as u32 || p_cost > u16::MAX as u32 {
+            return ERR_INVALID_INPUT;
+        }
+        if let Err(code) = argon2_params_arg(m_cost, t_cost, p_cost) {
+            return code;
+        }
+
+        DEFAULT_ARGON2_PARAMS.store(pack_params(m_cost, t_cost, p_cost), Ordering::Release);
+        0
+    })
+}
+
+/// Read the current default Argon2id costs.
+///
+/// # Safety
+///
+/// - `out_m`, `out_t` and `out_p` must each be writable for one `u32`
+///
+/// # Returns
+///
+/// 0 on success, -1 if any output pointer is null
+#[no_mangle]
+pub unsafe extern "C" fn vault_get_default_argon2_params(out_m: *mut u32, out_t: *mut u32, out_p: *mut u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_m.is_null() || out_t.is_null() || out_p.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+
+        let (m_cost, t_cost, p_cost) = default_argon2_params();
+        *out_m = m_cost;
+        *out_t = t_cost;
+        *out_p = p_cost;
+        0
+    })
+}
+
+/// The variant `vault_derive_key_ex` runs for a requested one and its flags.
+fn effective_variant(variant: u32, flags: u32) -> u32 {
+    if flags & DERIVE_FORCE_ARGON2I != 0 {
+        ARGON2_VARIANT_I
+    } else {
+        variant
+    }
+}
+
+/// Report the variant `vault_derive_key_ex` runs for `variant` and `flags`.
+///
+/// Store this, not the requested variant, with each vault so re-derivation
+/// matches (see `VaultKdfParams`). It depends only on the arguments, so
+/// the same call always runs the same variant.
+///
+/// # Returns
+///
+/// The variant (0 = Argon2id, 1 = Argon2i, 2 = Argon2d), or
+/// `ERR_INVALID_INPUT` for an unknown variant or flag
+#[no_mangle]
+pub extern "C" fn vault_argon2_effective_variant(variant: u32, flags: u32) -> i32 {
+    ffi_boundary(|| {
+        if argon2_algorithm(variant).is_err() || flags & !DERIVE_FLAGS != 0 {
+            return ERR_INVALID_INPUT;
+        }
+        effective_variant(variant, flags) as i32
+    })
+}
+
+/// Derive a key under a freshly generated salt, returning both together.
+///
+/// A caller-chosen salt that is lost means the data is gone for good.
+/// Returning `salt || key` lets the caller persist the salt atomically
+/// with whatever it encrypts. Split it with `vault_salted_key_split`.
+///
+/// # Format
+///
+/// Output: `salt (16 bytes) || key (32 bytes)`
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_key_gen_salt(
+    passphrase: *const u8,
+    passphrase_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+
+        let mut salt = [0u8; SALT_SIZE];
+        if let Err(code) = random_bytes(&mut salt) {
+            return VaultBuffer::error(code);
+        }
+
+        let key = match argon2id_default(passphrase_slice, &salt) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(SALTED_KEY_SIZE) {
+            Ok(o) => o,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&salt);
+        output.extend_from_slice(key.as_ref());
+        VaultBuffer::success(output)
+    })
+}
+
+/// Derive a key with explicit Argon2 parameters and variant.
+///
+/// For vaults created elsewhere with other costs or with Argon2i/Argon2d.
+/// Whatever is passed here must be stored with the salt (see
+/// `VaultKdfParams` and `vault_record_pack`), or the key cannot be
+/// re-derived.
+///
+/// `variant`: 0 = Argon2id, 1 = Argon2i, 2 = Argon2d.
+///
+/// `pepper` is an optional application-wide secret (from the binary or a
+/// keystore) used as Argon2's secret input, so a stolen salt and ciphertext
+/// cannot be attacked offline without it. It is read in place and never
+/// copied. With no pepper (`pepper_len` 0) the result is the same as before
+/// peppers existed.
+///
+/// `flags` may combine `DERIVE_FORCE_ARGON2I` (2) and
+/// `DERIVE_SKIP_MEMORY_WIPE` (1).
+///
+/// `DERIVE_FORCE_ARGON2I` runs Argon2i whatever `variant` is passed, for
+/// high-threat deployments. Argon2id (the default) mixes a data-independent
+/// first half pass with data-dependent passes, which is what makes GPU and
+/// tradeoff attacks expensive. Its data-dependent memory accesses depend on
+/// the passphrase, so an attacker who can observe cache timing on the same
+/// device (shared tablets, kiosks) learns something about it. Argon2i's
+/// accesses depend only on the salt and costs, so it leaks nothing through
+/// that channel, at the price of weaker resistance to GPU cracking: raise
+/// `t_cost` to compensate (at least 3). Record the variant from
+/// `vault_argon2_effective_variant` with the vault so re-derivation matches.
+///
+/// `DERIVE_SKIP_MEMORY_WIPE` skips zeroizing the Argon2 working memory
+/// (`m_cost` KiB) before it is freed. The key is the same either way; the
+/// flag only trades wipe time against what is left in freed memory:
+///
+/// - Wiped (the default): costs one extra write pass over the working
+///   memory, small next to the `t_cost` passes Argon2 itself makes over it
+///   (see the `derive_memory_wipe` benchmark; measure on the target device).
+/// - Not wiped: the freed pages keep the final Argon2 blocks until the
+///   allocator reuses them. These are not just passphrase-derived noise:
+///   the key is a hash of the last block of each lane, and those blocks can
+///   be recomputed from the rest of the memory without the passphrase, so
+///   anyone who can read the freed memory (a heap dump, a memory disclosure
+///   bug, a crash report, swap) can recover the key. Use it only where the
+///   key itself is about to sit in the same process's memory anyway (for
+///   example re-deriving many vault keys in one upgrade loop) and such reads
+///   are already out of scope.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `pepper` must be valid for `pepper_len` bytes (may be null when `pepper_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte key, `ERR_INVALID_INPUT` for an
+/// unknown variant or flag, a null pepper with a length, or a cost outside the
+/// Argon2 limits (`vault_argon2_params_status` says which), or
+/// `ERR_OUT_OF_MEMORY` if the working memory cannot be allocated
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn vault_derive_key_ex(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    pepper: *const u8,
+    pepper_len: u32,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    variant: u32,
+    flags: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || passphrase_len == 0 || (pepper.is_null() && pepper_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if flags & !DERIVE_FLAGS != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if argon2_algorithm(variant).is_err() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let algorithm = match argon2_algorithm(effective_variant(variant, flags)) {
+            Ok(a) => a,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if let Err(code) = argon2_params_arg(m_cost, t_cost, p_cost) {
+            return VaultBuffer::error(code);
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+        let pepper_slice: &[u8] = if pepper_len == 0 { &[] } else { slice::from_raw_parts(pepper, pepper_len as usize) };
+
+        let wipe_memory = flags & DERIVE_SKIP_MEMORY_WIPE == 0;
+        match argon2_key_with(passphrase_slice, salt_slice, pepper_slice, m_cost, t_cost, p_cost, algorithm, wipe_memory)
+            .and_then(|key| output_copy(key.as_ref()))
+        {
+            Ok(key) => VaultBuffer::success(key),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Derive a key with Argon2id at explicit costs.
+///
+/// `vault_derive_key_ex` with Argon2id, no pepper and no flags, for callers
+/// that only need to pick costs per device class. As with any non-default
+/// costs, store them with the salt so the key can be re-derived.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte key, or the errors of
+/// `vault_derive_key_ex`
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_key_with_params(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        vault_derive_key_ex(passphrase, passphrase_len, salt, ptr::null(), 0, m_cost, t_cost, p_cost, ARGON2_VARIANT_ID, 0)
+    })
+}
+
+/// Derive a key that needs both a passphrase and a hardware-held secret.
+///
+/// Runs Argon2id over the passphrase at the default costs, as
+/// `vault_derive_key` does, then mixes in `hardware_key` with HKDF-SHA256:
+///
+/// `key = HKDF(ikm = Argon2id(passphrase, salt), salt = hardware_key,
+/// info = "vault_core 2025-01 two-factor key")`
+///
+/// Without the hardware key the passphrase only yields the intermediate
+/// Argon2id output, which is not the vault key; without the passphrase the
+/// hardware key is just an HKDF salt. `hardware_key` should be a uniformly
+/// random secret of at least 16 bytes that stays on the device, such as a
+/// value the secure element releases or an HMAC it computes over a fixed
+/// challenge. Whatever produces it must give the same bytes every time.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `hardware_key` must be valid for `hardware_key_len` bytes (at least 16)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte key, `ERR_INVALID_INPUT` for a null
+/// pointer or an empty passphrase, or `ERR_BAD_KEY_SIZE` for a hardware key
+/// under 16 bytes
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_key_2fa(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    hardware_key: *const u8,
+    hardware_key_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || hardware_key.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (hardware_key_len as usize) < MIN_HARDWARE_KEY_SIZE {
+            let detail = format_args!("hardware key is {hardware_key_len} bytes, expected at least {MIN_HARDWARE_KEY_SIZE}");
+            return VaultBuffer::error(error_detail(ERR_BAD_KEY_SIZE, detail));
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+        let hardware_key_slice = slice::from_raw_parts(hardware_key, hardware_key_len as usize);
+
+        let passphrase_key = match argon2id_default(passphrase_slice, salt_slice) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        match hkdf_sha256(passphrase_key.as_ref(), hardware_key_slice, TWO_FACTOR_INFO, KEY_SIZE) {
+            Ok(key) => VaultBuffer::success(key),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Derive a key with the default parameters from a salt of any length.
+///
+/// `vault_derive_key` always reads exactly 16 salt bytes; use this for
+/// salts of other lengths (commonly 32), so the whole salt is used.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must be valid for `salt_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte key, or `ERR_BAD_SALT_SIZE` for a salt
+/// shorter than Argon2's 8-byte minimum
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_key_salted(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    salt_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (salt_len as usize) < argon2::MIN_SALT_LEN {
+            let detail = format_args!("salt is {salt_len} bytes, Argon2 needs at least {}", argon2::MIN_SALT_LEN);
+            return VaultBuffer::error(error_detail(ERR_BAD_SALT_SIZE, detail));
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, salt_len as usize);
+
+        match argon2id_default(passphrase_slice, salt_slice).and_then(|key| output_copy(key.as_ref())) {
+            Ok(key) => VaultBuffer::success(key),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Re-seal a vault under a key derived with upgraded Argon2id costs.
+///
+/// For raising the KDF cost on the next successful unlock: derives the key
+/// under the old costs, unseals, derives again under the new costs with
+/// the same salt and reseals. A wrong passphrase is caught by the unseal,
+/// before the second (more expensive) derivation runs. The plaintext only
+/// ever exists in wiped native memory.
+///
+/// Store `new_m`/`new_t`/`new_p` with the returned blob, replacing the old
+/// costs; only they re-derive its key.
+///
+/// # Format
+///
+/// Input and output: `vault_seal` blobs under the respective keys
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the resealed blob, `ERR_INVALID_INPUT` if either
+/// set of costs is outside the Argon2 limits, or `ERR_DECRYPT_FAILED` if the
+/// passphrase does not open `sealed` under the old costs
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn vault_upgrade_kdf(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    old_m: u32,
+    old_t: u32,
+    old_p: u32,
+    new_m: u32,
+    new_t: u32,
+    new_p: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || sealed.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if let Err(code) = argon2_params_arg(old_m, old_t, old_p).and_then(|()| argon2_params_arg(new_m, new_t, new_p)) {
+            return VaultBuffer::error(code);
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let old_key = match argon2id(passphrase_slice, salt_slice, old_m, old_t, old_p) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext = match open_blob(old_key.as_ref(), sealed_slice) {
+            Ok(p) => Zeroizing::new(p),
+            Err(code) => return VaultBuffer::error(code),
+        };
+        drop(old_key);
+
+        let new_key = match argon2id(passphrase_slice, salt_slice, new_m, new_t, new_p) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        match seal_blob(new_key.as_ref(), &plaintext) {
+            Ok(resealed) => VaultBuffer::success(resealed),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Wall-clock time of one Argon2id derivation under a dummy passphrase.
+fn time_derivation(m_cost: u32, t_cost: u32, p_cost: u32) -> VaultResult<Duration> {
+    let start = Instant::now();
+    // The key is a `Secret` and is wiped as it drops here
+    argon2id(TIMING_PASSPHRASE, &[0u8; SALT_SIZE], m_cost, t_cost, p_cost)?;
+    Ok(start.elapsed())
+}
+
+/// Time one Argon2id derivation with the given costs, discarding the key.
+///
+/// For calibration screens ("about 240 ms on this device"). A fixed dummy
+/// passphrase and salt are used, and the throwaway key is wiped before
+/// returning.
+///
+/// # Returns
+///
+/// Elapsed wall-clock time in microseconds, or a negative error code
+/// (`ERR_KDF_FAILED` for parameters Argon2 rejects, `ERR_OUT_OF_MEMORY`)
+#[no_mangle]
+pub extern "C" fn vault_derive_timing(m_cost: u32, t_cost: u32, p_cost: u32) -> i64 {
+    ffi_boundary(|| {
+        match time_derivation(m_cost, t_cost, p_cost) {
+            Ok(elapsed) => i64::try_from(elapsed.as_micros()).unwrap_or(i64::MAX),
+            Err(code) => code as i64,
+        }
+    })
+}
+
+/// Recommend Argon2id costs that take about `target_millis` on this device.
+///
+/// Starts from the default memory (64 MiB) and lanes and times a single
+/// pass. While one pass alone overshoots the target the memory is halved,
+/// down to 16 MiB; then as many passes are used as fit in the target
+/// (at least 1, at most 16). Memory is preferred over passes because it is
+/// what makes guessing expensive on GPUs.
+///
+/// The result is measured, so it varies with load; run it once at setup,
+/// not on every unlock, and store the costs with the salt (see
+/// `VaultKdfParams`). On a device too slow to reach the target even at
+/// 16 MiB and one pass, those costs are returned anyway.
+///
+/// # Safety
+///
+/// - `out_params` must be writable
+///
+/// # Returns
+///
+/// 0 with Argon2id costs written to `out_params`, `ERR_INVALID_INPUT` for a
+/// null output or a zero target, or `ERR_OUT_OF_MEMORY`
+#[no_mangle]
+pub unsafe extern "C" fn vault_calibrate_kdf(target_millis: u32, out_params: *mut VaultKdfParams) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_params.is_null() || target_millis == 0 {
+            return ERR_INVALID_INPUT;
+        }
+        let target = Duration::from_millis(target_millis as u64);
+
+        let mut m_cost = ARGON2_M_COST;
+        let mut pass = match time_derivation(m_cost, 1, ARGON2_P_COST) {
+            Ok(elapsed) => elapsed,
+            Err(code) => return code,
+        };
+        while pass > target && m_cost > CALIBRATION_MIN_M_COST {
+            m_cost /= 2;
+            pass = match time_derivation(m_cost, 1, ARGON2_P_COST) {
+                Ok(elapsed) => elapsed,
+                Err(code) => return code,
+            };
+        }
+
+        let passes = target.as_micros() / pass.as_micros().max(1);
+        let t_cost = passes.clamp(1, CALIBRATION_MAX_T_COST as u128) as u32;
+        *out_params = VaultKdfParams { m_cost, t_cost, p_cost: ARGON2_P_COST, variant: ARGON2_VARIANT_ID };
+        0
+    })
+}
+
+/// Report the memory and time an Argon2id derivation with these costs takes.
+///
+/// For security settings screens ("64 MiB of memory, about 240 ms"). The
+/// memory is computed exactly: Argon2 fills `m_cost` KiB blocks. The time is
+/// one measured run, as in `vault_derive_timing`, so it varies with load
+/// and is an estimate for this device only.
+///
+/// # Safety
+///
+/// - `out_mem_bytes` and `out_est_millis` must be writable
+///
+/// # Returns
+///
+/// 0 on success, `ERR_INVALID_INPUT` for a null output or a cost outside
+/// the Argon2 limits (checked before anything runs), or `ERR_OUT_OF_MEMORY`
+#[no_mangle]
+pub unsafe extern "C" fn vault_argon2_cost_summary(
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    out_mem_bytes: *mut u64,
+    out_est_millis: *mut u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_mem_bytes.is_null() || out_est_millis.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        if let Err(code) = argon2_params_arg(m_cost, t_cost, p_cost) {
+            return code;
+        }
+
+        let millis = match time_derivation(m_cost, t_cost, p_cost) {
+            Ok(elapsed) => u32::try_from(elapsed.as_millis()).unwrap_or(u32::MAX),
+            Err(code) => return code,
+        };
+
+        *out_mem_bytes = m_cost as u64 * 1024;
+        *out_est_millis = millis;
+        0
+    })
+}
+
+/// Split a `vault_derive_key_gen_salt` result into its salt and key.
+///
+/// # Safety
+///
+/// - `salted` must be valid for `salted_len` bytes (`salted_len` must be 48)
+/// - `out_salt` must be writable for 16 bytes, or null to skip
+/// - `out_key` must be writable for 32 bytes, or null to skip
+///
+/// # Returns
+///
+/// 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn vault_salted_key_split(
+    salted: *const u8,
+    salted_len: u32,
+    out_salt: *mut u8,
+    out_key: *mut u8,
+) -> i32 {
+    ffi_boundary(|| {
+        if salted.is_null() || salted_len as usize != SALTED_KEY_SIZE {
+            return ERR_INVALID_INPUT;
+        }
+        let salted_slice = slice::from_raw_parts(salted, SALTED_KEY_SIZE);
+        let (salt, key) = salted_slice.split_at(SALT_SIZE);
+
+        if !out_salt.is_null() {
+            ptr::copy_nonoverlapping(salt.as_ptr(), out_salt, SALT_SIZE);
+        }
+        if !out_key.is_null() {
+            ptr::copy_nonoverlapping(key.as_ptr(), out_key, KEY_SIZE);
+        }
+        0
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn derive_ex(variant: u32) -> VaultBuffer {
+        let passphrase = b"interop passphrase";
+        let salt = [5u8; SALT_SIZE];
+        vault_derive_key_ex(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr(), ptr::null(), 0, 256, 1, 1, variant, 0)
+    }
+
+    #[test]
+    fn test_derive_key_ex_variants_differ() {
+        unsafe {
+            let id = derive_ex(ARGON2_VARIANT_ID);
+            let i = derive_ex(ARGON2_VARIANT_I);
+            let d = derive_ex(ARGON2_VARIANT_D);
+            assert_eq!(id.error, 0);
+            assert_eq!(i.error, 0);
+            assert_eq!(d.error, 0);
+
+            let id_key = slice::from_raw_parts(id.data, KEY_SIZE);
+            let i_key = slice::from_raw_parts(i.data, KEY_SIZE);
+            let d_key = slice::from_raw_parts(d.data, KEY_SIZE);
+            assert_ne!(id_key, i_key);
+            assert_ne!(id_key, d_key);
+            assert_ne!(i_key, d_key);
+
+            vault_free(id.data, id.len);
+            vault_free(i.data, i.len);
+            vault_free(d.data, d.len);
+
+            assert_eq!(derive_ex(3).error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_derive_key_ex_pepper() {
+        let passphrase = b"interop passphrase";
+        let salt = [5u8; SALT_SIZE];
+        let derive = |pepper: &[u8]| unsafe {
+            let pepper_ptr = if pepper.is_empty() { ptr::null() } else { pepper.as_ptr() };
+            let result = vault_derive_key_ex(
+                passphrase.as_ptr(),
+                passphrase.len() as u32,
+                salt.as_ptr(),
+                pepper_ptr,
+                pepper.len() as u32,
+                256,
+                1,
+                1,
+                ARGON2_VARIANT_ID,
+                0,
+            );
+            assert_eq!(result.error, 0);
+            let key = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+            vault_free(result.data, result.len);
+            key
+        };
+
+        let a = derive(b"pepper A");
+        let b = derive(b"pepper B");
+        assert_ne!(a, b);
+        assert_eq!(a, derive(b"pepper A"));
+
+        // No pepper is the unkeyed derivation
+        let legacy = argon2_key(passphrase, &salt, &[], 256, 1, 1, Algorithm::Argon2id).unwrap();
+        assert_eq!(derive(&[]), legacy.to_vec());
+        assert_ne!(a, legacy.to_vec());
+
+        unsafe {
+            let result = vault_derive_key_ex(passphrase.as_ptr(), 18, salt.as_ptr(), ptr::null(), 8, 256, 1, 1, 0, 0);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_force_argon2i() {
+        let derive = |variant, flags| unsafe {
+            let passphrase = b"interop passphrase";
+            let salt = [5u8; SALT_SIZE];
+            let result = vault_derive_key_ex(passphrase.as_ptr(), 18, salt.as_ptr(), ptr::null(), 0, 256, 1, 1, variant, flags);
+            assert_eq!(result.error, 0);
+            let key = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+            vault_free(result.data, result.len);
+            key
+        };
+
+        let id = derive(ARGON2_VARIANT_ID, 0);
+        let i = derive(ARGON2_VARIANT_I, 0);
+        assert_ne!(id, i);
+        assert_eq!(vault_argon2_effective_variant(ARGON2_VARIANT_ID, 0), 0);
+
+        // The flag applies to its own call only, and is reproducible
+        let forced = derive(ARGON2_VARIANT_ID, DERIVE_FORCE_ARGON2I);
+        assert_eq!(forced, i);
+        assert_eq!(derive(ARGON2_VARIANT_ID, DERIVE_FORCE_ARGON2I), forced);
+        assert_eq!(derive(ARGON2_VARIANT_ID, 0), id);
+        assert_eq!(vault_argon2_effective_variant(ARGON2_VARIANT_ID, DERIVE_FORCE_ARGON2I), 1);
+        assert_eq!(derive(ARGON2_VARIANT_I, DERIVE_FORCE_ARGON2I | DERIVE_SKIP_MEMORY_WIPE), i);
+
+        assert_eq!(vault_argon2_effective_variant(3, 0), ERR_INVALID_INPUT);
+        assert_eq!(vault_argon2_effective_variant(ARGON2_VARIANT_ID, 4), ERR_INVALID_INPUT);
+        assert_eq!(unsafe { derive_ex(3) }.error, ERR_INVALID_INPUT);
+    }
+
+    #[test]
+    fn test_derive_key_ex_param_bounds() {
+        let derive = |m_cost, t_cost, p_cost| unsafe {
+            let passphrase = b"bounds";
+            let salt = [5u8; SALT_SIZE];
+            let result = vault_derive_key_ex(passphrase.as_ptr(), 6, salt.as_ptr(), ptr::null(), 0, m_cost, t_cost, p_cost, 0, 0);
+            vault_free(result.data, result.len);
+            result.error
+        };
+
+        assert_eq!(derive(64, 0, 1), ERR_INVALID_INPUT);
+        assert_eq!(derive(64, 1, 0), ERR_INVALID_INPUT);
+        assert_eq!(derive(31, 1, 4), ERR_INVALID_INPUT);
+        assert_eq!(derive(64, 1, 1 << 24), ERR_INVALID_INPUT);
+        assert_eq!(derive(32, 1, 4), 0);
+
+        assert_eq!(argon2_param_error(64, 0, 1), Some(2));
+        assert_eq!(argon2_param_error(64, 1, 0), Some(3));
+        assert_eq!(argon2_param_error(31, 1, 4), Some(1));
+        assert_eq!(argon2_param_error(7, 1, 1), Some(1));
+        assert_eq!(argon2_param_error(8, 1, 1), None);
+    }
+
+    #[test]
+    fn test_upgrade_kdf() {
+        let passphrase = b"upgrade me";
+        let salt = [3u8; SALT_SIZE];
+        let plaintext = b"vault contents";
+        let old_key = argon2id(passphrase, &salt, 64, 1, 1).unwrap();
+        let new_key = argon2id(passphrase, &salt, 128, 2, 1).unwrap();
+        let sealed = seal_blob(old_key.as_ref(), plaintext).unwrap();
+
+        let upgrade = |passphrase: &[u8]| unsafe {
+            let result = vault_upgrade_kdf(
+                passphrase.as_ptr(),
+                passphrase.len() as u32,
+                salt.as_ptr(),
+                64,
+                1,
+                1,
+                128,
+                2,
+                1,
+                sealed.as_ptr(),
+                sealed.len() as u32,
+            );
+            if result.error != 0 {
+                return Err(result.error);
+            }
+            let blob = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+            vault_free(result.data, result.len);
+            Ok(blob)
+        };
+
+        let upgraded = upgrade(passphrase).unwrap();
+        assert_eq!(open_blob(new_key.as_ref(), &upgraded).unwrap(), plaintext);
+        assert_eq!(open_blob(old_key.as_ref(), &upgraded), Err(ERR_DECRYPT_FAILED));
+
+        assert_eq!(upgrade(b"wrong passphrase"), Err(ERR_DECRYPT_FAILED));
+
+        unsafe {
+            let result = vault_upgrade_kdf(passphrase.as_ptr(), 10, salt.as_ptr(), 64, 1, 1, 128, 0, 1, sealed.as_ptr(), sealed.len() as u32);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_derive_key_salted_uses_whole_salt() {
+        let _defaults = lock_default_params();
+        let passphrase = b"long salt";
+        let mut salt = [9u8; 32];
+
+        unsafe {
+            let derive = |salt: &[u8]| {
+                let result = vault_derive_key_salted(passphrase.as_ptr(), 9, salt.as_ptr(), salt.len() as u32);
+                assert_eq!(result.error, 0);
+                let key = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+                vault_free(result.data, result.len);
+                key
+            };
+
+            let full = derive(&salt);
+            salt[16] ^= 1;
+            assert_ne!(derive(&salt), full);
+
+            // A 16-byte salt matches the legacy function
+            let legacy = vault_derive_key(passphrase.as_ptr(), 9, salt.as_ptr());
+            assert_eq!(derive(&salt[..SALT_SIZE]), slice::from_raw_parts(legacy.data, KEY_SIZE));
+            vault_free(legacy.data, legacy.len);
+
+            let short = vault_derive_key_salted(passphrase.as_ptr(), 9, salt.as_ptr(), 7);
+            assert_eq!(short.error, ERR_BAD_SALT_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_derive_key_with_params() {
+        let _defaults = lock_default_params();
+        let passphrase = b"per device";
+        let salt = [0x07u8; SALT_SIZE];
+
+        unsafe {
+            let derive = |m_cost, t_cost, p_cost| {
+                let result = vault_derive_key_with_params(passphrase.as_ptr(), 10, salt.as_ptr(), m_cost, t_cost, p_cost);
+                assert_eq!(result.error, 0);
+                let key = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+                vault_free(result.data, result.len);
+                key
+            };
+
+            // The compiled-in costs reproduce vault_derive_key
+            let standard = vault_derive_key(passphrase.as_ptr(), 10, salt.as_ptr());
+            assert_eq!(derive(ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST), slice::from_raw_parts(standard.data, KEY_SIZE));
+            vault_free(standard.data, standard.len);
+
+            let light = derive(8192, 1, 1);
+            assert_eq!(light, derive(8192, 1, 1));
+            assert_ne!(light, derive(8192, 2, 1));
+
+            let bad = vault_derive_key_with_params(passphrase.as_ptr(), 10, salt.as_ptr(), 8192, 0, 1);
+            assert_eq!(bad.error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_derive_key_2fa() {
+        let _defaults = lock_default_params();
+        let passphrase = b"two factors";
+        let salt = [0x07u8; SALT_SIZE];
+        let hardware_key = [0x5Au8; 32];
+
+        unsafe {
+            let derive = |passphrase: &[u8], hardware_key: &[u8]| {
+                let result = vault_derive_key_2fa(
+                    passphrase.as_ptr(),
+                    passphrase.len() as u32,
+                    salt.as_ptr(),
+                    hardware_key.as_ptr(),
+                    hardware_key.len() as u32,
+                );
+                assert_eq!(result.error, 0);
+                let key = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+                vault_free(result.data, result.len);
+                key
+            };
+
+            // Both factors together are reproducible
+            let key = derive(passphrase, &hardware_key);
+            assert_eq!(key.len(), KEY_SIZE);
+            assert_eq!(derive(passphrase, &hardware_key), key);
+
+            // Each factor matters
+            let mut other_device = hardware_key;
+            other_device[31] ^= 1;
+            assert_ne!(derive(passphrase, &other_device), key);
+            assert_ne!(derive(b"two factorz", &hardware_key), key);
+
+            // The passphrase alone gives only the intermediate key
+            let alone = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            assert_eq!(alone.error, 0);
+            assert_ne!(slice::from_raw_parts(alone.data, alone.len as usize), &key[..]);
+            vault_free(alone.data, alone.len);
+
+            let short = vault_derive_key_2fa(passphrase.as_ptr(), 11, salt.as_ptr(), hardware_key.as_ptr(), 15);
+            assert_eq!(short.error, ERR_BAD_KEY_SIZE);
+            let shortest = vault_derive_key_2fa(passphrase.as_ptr(), 11, salt.as_ptr(), hardware_key.as_ptr(), 16);
+            assert_eq!(shortest.error, 0);
+            vault_free(shortest.data, shortest.len);
+            let missing = vault_derive_key_2fa(passphrase.as_ptr(), 11, salt.as_ptr(), ptr::null(), 32);
+            assert_eq!(missing.error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_derive_timing_grows_with_t_cost() {
+        let fast = vault_derive_timing(8192, 1, 1);
+        let slow = vault_derive_timing(8192, 8, 1);
+        assert!(fast > 0);
+        assert!(slow > fast, "t_cost 8 took {slow} us, t_cost 1 took {fast} us");
+
+        assert_eq!(vault_derive_timing(1, 1, 1), ERR_KDF_FAILED as i64);
+    }
+
+    #[test]
+    fn test_calibrate_kdf() {
+        let calibrate = |target| {
+            let mut params = VaultKdfParams::default();
+            assert_eq!(unsafe { vault_calibrate_kdf(target, &mut params) }, 0);
+            assert_eq!(argon2_param_error(params.m_cost, params.t_cost, params.p_cost), None);
+            params
+        };
+
+        // An unreachable target bottoms out at the floor
+        let fast = calibrate(1);
+        assert_eq!((fast.m_cost, fast.t_cost), (CALIBRATION_MIN_M_COST, 1));
+        assert_eq!(fast.variant, ARGON2_VARIANT_ID);
+
+        // A generous one keeps the full memory and adds passes
+        let slow = calibrate(10_000);
+        assert_eq!(slow.m_cost, ARGON2_M_COST);
+        assert!(slow.t_cost > 1, "{slow:?}");
+
+        unsafe {
+            assert_eq!(vault_calibrate_kdf(0, &mut VaultKdfParams::default()), ERR_INVALID_INPUT);
+            assert_eq!(vault_calibrate_kdf(250, ptr::null_mut()), ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_argon2_cost_summary() {
+        let summary = |m_cost, t_cost| {
+            let mut mem = 0u64;
+            let mut millis = 0u32;
+            let rc = unsafe { vault_argon2_cost_summary(m_cost, t_cost, 1, &mut mem, &mut millis) };
+            assert_eq!(rc, 0);
+            (mem, millis)
+        };
+
+        let (fast_mem, fast) = summary(16384, 1);
+        let (slow_mem, slow) = summary(16384, 8);
+        assert_eq!(fast_mem, 16384 * 1024);
+        assert_eq!(slow_mem, fast_mem);
+        assert!(slow > fast, "t_cost 8 took {slow} ms, t_cost 1 took {fast} ms");
+
+        let mut mem = 0u64;
+        let mut millis = 0u32;
+        unsafe {
+            assert_eq!(vault_argon2_cost_summary(1, 1, 1, &mut mem, &mut millis), ERR_INVALID_INPUT);
+            assert_eq!(vault_argon2_cost_summary(16384, 0, 1, &mut mem, &mut millis), ERR_INVALID_INPUT);
+            assert_eq!(vault_argon2_cost_summary(16384, 1, 1, ptr::null_mut(), &mut millis), ERR_INVALID_INPUT);
+        }
+        assert_eq!((mem, millis), (0, 0));
+    }
+
+    #[test]
+    fn test_derive_key_ex_out_of_memory() {
+        use crate::test_alloc::with_failing_alloc;
+
+        let passphrase = b"low memory device";
+        let salt = [5u8; SALT_SIZE];
+
+        unsafe {
+            // Working memory that cannot be allocated fails cleanly instead of aborting
+            let result = with_failing_alloc(1 << 20, || {
+                vault_derive_key_ex(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr(), ptr::null(), 0, 8192, 1, 1, 0, 0)
+            });
+            assert_eq!(result.error, ERR_OUT_OF_MEMORY);
+            assert!(result.data.is_null());
+        }
+    }
+
+    #[test]
+    fn test_derive_key_ex_memory_wipe() {
+        use crate::test_alloc::{leaked_copies, NEEDLE_SIZE};
+
+        let _defaults = lock_default_params();
+        let passphrase = b"wipe mode passphrase";
+        let salt = [5u8; SALT_SIZE];
+
+        // Rerun the derivation in our own memory to learn what its last block holds
+        let params = Params::new(256, 1, 1, Some(KEY_SIZE)).unwrap();
+        let mut blocks = vec![Block::default(); params.block_count()];
+        let mut expected = [0u8; KEY_SIZE];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into_with_memory(passphrase, &salt, &mut expected, &mut blocks)
+            .unwrap();
+        let words = &blocks[blocks.len() - 1].as_ref()[..2];
+        let mut needle = [0u8; NEEDLE_SIZE];
+        needle[..8].copy_from_slice(&words[0].to_ne_bytes());
+        needle[8..].copy_from_slice(&words[1].to_ne_bytes());
+
+        let derive = |flags| unsafe {
+            let result = vault_derive_key_ex(
+                passphrase.as_ptr(),
+                passphrase.len() as u32,
+                salt.as_ptr(),
+                ptr::null(),
+                0,
+                256,
+                1,
+                1,
+                ARGON2_VARIANT_ID,
+                flags,
+            );
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), &expected);
+            vault_free(result.data, result.len);
+        };
+
+        assert_eq!(leaked_copies(needle, || derive(0)), 0);
+        assert!(leaked_copies(needle, || derive(DERIVE_SKIP_MEMORY_WIPE)) > 0);
+
+        unsafe {
+            let result = vault_derive_key_ex(passphrase.as_ptr(), 20, salt.as_ptr(), ptr::null(), 0, 256, 1, 1, 0, 4);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_derive_key_gen_salt() {
+        let _defaults = lock_default_params();
+        let passphrase = b"test passphrase";
+
+        unsafe {
+            let first = vault_derive_key_gen_salt(passphrase.as_ptr(), passphrase.len() as u32);
+            let second = vault_derive_key_gen_salt(passphrase.as_ptr(), passphrase.len() as u32);
+            assert_eq!(first.error, 0);
+            assert_eq!(first.len as usize, SALTED_KEY_SIZE);
+
+            let mut salt1 = [0u8; SALT_SIZE];
+            let mut key1 = [0u8; KEY_SIZE];
+            let mut salt2 = [0u8; SALT_SIZE];
+            assert_eq!(vault_salted_key_split(first.data, first.len, salt1.as_mut_ptr(), key1.as_mut_ptr()), 0);
+            assert_eq!(vault_salted_key_split(second.data, second.len, salt2.as_mut_ptr(), ptr::null_mut()), 0);
+            assert_ne!(salt1, salt2);
+
+            // The returned salt reproduces the returned key
+            let derived = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt1.as_ptr());
+            assert_eq!(derived.error, 0);
+            assert_eq!(slice::from_raw_parts(derived.data, KEY_SIZE), &key1);
+
+            vault_free(first.data, first.len);
+            vault_free(second.data, second.len);
+            vault_free(derived.data, derived.len);
+        }
+    }
+
+    #[test]
+    fn test_default_argon2_params() {
+        let _defaults = lock_default_params();
+        let get = || unsafe {
+            let (mut m, mut t, mut p) = (0, 0, 0);
+            assert_eq!(vault_get_default_argon2_params(&mut m, &mut t, &mut p), 0);
+            (m, t, p)
+        };
+        assert_eq!(get(), (ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST));
+
+        assert_eq!(vault_set_default_argon2_params(256, 2, 1), 0);
+        assert_eq!(get(), (256, 2, 1));
+
+        // Rejected values leave the previous defaults in place
+        assert_eq!(vault_set_default_argon2_params(256, 0, 1), ERR_INVALID_INPUT);
+        assert_eq!(vault_set_default_argon2_params(1, 1, 1), ERR_INVALID_INPUT);
+        assert_eq!(vault_set_default_argon2_params(1 << 20, 1, 1 << 16), ERR_INVALID_INPUT);
+        assert_eq!(get(), (256, 2, 1));
+
+        // vault_derive_key picks them up
+        let passphrase = b"configured";
+        let salt = [6u8; SALT_SIZE];
+        let expected = argon2id(passphrase, &salt, 256, 2, 1).unwrap();
+        unsafe {
+            let result = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), expected.as_ref());
+            vault_free(result.data, result.len);
+
+            assert_eq!(vault_get_default_argon2_params(ptr::null_mut(), &mut 0, &mut 0), ERR_INVALID_INPUT);
+        }
+
+        assert_eq!(vault_set_default_argon2_params(ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST), 0);
+    }
+}
@@ -0,0 +1,496 @@
+//! Opaque Key Handles
+//!
+//! Keys held in native memory and named by a `u64` handle, so a derived key
+//! never has to be copied into the caller's heap (where a garbage-collected
+//! runtime cannot be relied on to wipe it). Derive or import a key once,
+//! seal and unseal by handle, and destroy the handle to wipe the key.
+//!
+//! Handles are never reused: a handle that was destroyed, or never issued,
+//! is rejected with `ERR_INVALID_INPUT` rather than reaching another key.
+//! `0` is never a valid handle. The table is shared by all threads behind a
+//! lock; each operation copies the key out into a wiped temporary, so seals
+//! on different handles do not wait on each other's encryption.
+//!
+//! Each key lives in its own heap allocation for its whole life and is
+//! zeroized before that is freed. Its pages are not pinned against swap.
+//!
+//! Each key is tagged with a purpose when its handle is created, and every
+//! function checks it: a sealing key cannot sign, and a signing or
+//! key-agreement key cannot seal or be wrapped. Reusing one key across
+//! algorithms is rejected with `ERR_INVALID_INPUT`. `vault_key_import`,
+//! `vault_key_generate` and `vault_key_derive_to_handle` make sealing keys;
+//! import signing keys with `vault_key_import_for`.
+//!
+//! A handle from the `vault_hd_*` functions also carries the key's BIP-32
+//! chain state, and is a signing key for its tree's curve.
+
+use std::collections::BTreeMap;
+use std::slice;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use zeroize::Zeroize;
+
+use crate::hd::HdChain;
+use crate::kdf::argon2id_default;
+
+use super::*;
+
+/// What the key behind a handle may be used for, fixed at creation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum KeyPurpose {
+    /// Sealing, unsealing and key wrapping
+    Seal,
+    /// secp256k1 ECDSA signing
+    Secp256k1,
+    /// Ed25519 signing
+    Ed25519,
+    /// X25519 key agreement
+    X25519,
+}
+
+impl KeyPurpose {
+    /// The purpose for a `vault_key_import_for` code.
+    fn from_code(code: u32) -> Option<Self> {
+        match code {
+            0 => Some(KeyPurpose::Seal),
+            1 => Some(KeyPurpose::Secp256k1),
+            2 => Some(KeyPurpose::Ed25519),
+            3 => Some(KeyPurpose::X25519),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            KeyPurpose::Seal => "sealing",
+            KeyPurpose::Secp256k1 => "secp256k1",
+            KeyPurpose::Ed25519 => "Ed25519",
+            KeyPurpose::X25519 => "X25519",
+        }
+    }
+}
+
+/// A key and its purpose, with its BIP-32 chain state when it is an HD node
+#[derive(Clone, Zeroize)]
+pub(crate) struct HeldKey {
+    pub(crate) key: [u8; KEY_SIZE],
+    #[zeroize(skip)]
+    pub(crate) purpose: KeyPurpose,
+    pub(crate) chain: Option<HdChain>,
+}
+
+/// A held key boxed so that the table can move it without leaving copies behind
+type BoxedKey = Box<Secret<HeldKey>>;
+
+/// Live keys by handle
+static KEYS: Mutex<BTreeMap<u64, BoxedKey>> = Mutex::new(BTreeMap::new());
+
+/// The next handle to issue
+static NEXT_HANDLE: AtomicU64 = AtomicU64::new(1);
+
+fn keys() -> MutexGuard<'static, BTreeMap<u64, BoxedKey>> {
+    // A panic while holding the lock cannot leave an entry half-written
+    KEYS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Store `key` for `purpose`, with its chain state if it is an HD node, and
+/// return its new handle.
+pub(crate) fn insert_key(key: &[u8], purpose: KeyPurpose, chain: Option<&HdChain>) -> u64 {
+    let mut held: BoxedKey = Box::new(Secret::new(HeldKey { key: [0u8; KEY_SIZE], purpose, chain: None }));
+    held.key.copy_from_slice(key);
+    held.chain = chain.cloned();
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    keys().insert(handle, held);
+    handle
+}
+
+/// A wiped-on-drop copy of the entry behind `handle`.
+pub(crate) fn held_for(handle: u64) -> VaultResult<Secret<HeldKey>> {
+    let keys = keys();
+    let held = keys.get(&handle).ok_or(ERR_INVALID_INPUT)?;
+    Ok(Secret::new((***held).clone()))
+}
+
+/// A wiped-on-drop copy of the key behind `handle`, which must be for `purpose`.
+pub(crate) fn key_for(handle: u64, purpose: KeyPurpose) -> VaultResult<Secret<[u8; KEY_SIZE]>> {
+    let held = held_for(handle)?;
+    if held.purpose != purpose {
+        return Err(error_detail(
+            ERR_INVALID_INPUT,
+            format_args!("handle {handle} is a {} key, not a {} key", held.purpose.name(), purpose.name()),
+        ));
+    }
+    Ok(Secret::new(held.key))
+}
+
+/// Copy a raw 32-byte key into native memory and return a handle to it.
+///
+/// The caller should wipe its own copy once this returns.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_BAD_KEY_SIZE`, or `ERR_INVALID_INPUT` for a null pointer
+#[no_mangle]
+pub unsafe extern "C" fn vault_key_import(key: *const u8, key_len: u32, out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_handle.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+
+        *out_handle = insert_key(key_slice, KeyPurpose::Seal, None);
+        0
+    })
+}
+
+/// Copy a raw 32-byte key for `purpose` into native memory and return a
+/// handle to it.
+///
+/// `purpose`: 0 = sealing (as `vault_key_import`), 1 = secp256k1 signing,
+/// 2 = Ed25519 signing (the seed), 3 = X25519 key agreement. The handle is
+/// refused by every function of another purpose.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_BAD_KEY_SIZE`, or `ERR_INVALID_INPUT` for a null
+/// pointer or an unknown purpose
+#[no_mangle]
+pub unsafe extern "C" fn vault_key_import_for(key: *const u8, key_len: u32, purpose: u32, out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_handle.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let Some(purpose) = KeyPurpose::from_code(purpose) else {
+            return error_detail(ERR_INVALID_INPUT, format_args!("unknown key purpose {purpose}"));
+        };
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+
+        *out_handle = insert_key(key_slice, purpose, None);
+        0
+    })
+}
+
+/// Generate a random 32-byte key in native memory and return a handle to it.
+///
+/// For data-encryption keys that are only ever stored wrapped
+/// (`vault_wrap_key`).
+///
+/// # Safety
+///
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_RNG_FAILED`, or `ERR_INVALID_INPUT` for a null pointer
+#[no_mangle]
+pub unsafe extern "C" fn vault_key_generate(out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_handle.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let mut key = Secret::new([0u8; KEY_SIZE]);
+        if let Err(code) = random_bytes(key.as_mut()) {
+            return code;
+        }
+
+        *out_handle = insert_key(key.as_ref(), KeyPurpose::Seal, None);
+        0
+    })
+}
+
+/// Derive a key as `vault_derive_key` does and keep it behind a handle.
+///
+/// The key itself is never returned.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_INVALID_INPUT`, or the derivation's error
+/// (`ERR_OUT_OF_MEMORY`, `ERR_KDF_FAILED`)
+#[no_mangle]
+pub unsafe extern "C" fn vault_key_derive_to_handle(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    out_handle: *mut u64,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || out_handle.is_null() || passphrase_len == 0 {
+            return ERR_INVALID_INPUT;
+        }
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+
+        match argon2id_default(passphrase_slice, salt_slice) {
+            Ok(key) => {
+                *out_handle = insert_key(key.as_ref(), KeyPurpose::Seal, None);
+                0
+            }
+            Err(code) => code,
+        }
+    })
+}
+
+/// Encrypt with the key behind `handle`, in the `vault_seal` format.
+///
+/// # Safety
+///
+/// - `plaintext` must be valid for `plaintext_len` bytes (may be null when
+///   `plaintext_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the sealed blob, or `ERR_INVALID_INPUT` for an
+/// unknown handle or one that is not a sealing key, or the errors of
+/// `vault_seal`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_with_handle(handle: u64, plaintext: *const u8, plaintext_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if (plaintext.is_null() && plaintext_len != 0) || plaintext_len > MAX_SEAL_PLAINTEXT {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key = match key_for(handle, KeyPurpose::Seal) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        match seal_blob(key.as_ref(), plaintext_slice) {
+            Ok(sealed) => VaultBuffer::success(sealed),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt a `vault_seal` blob with the key behind `handle`.
+///
+/// # Safety
+///
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, or `ERR_INVALID_INPUT` for an
+/// unknown handle or one that is not a sealing key, or the errors of
+/// `vault_unseal`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_with_handle(handle: u64, sealed: *const u8, sealed_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key = match key_for(handle, KeyPurpose::Seal) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        match unseal_safe(key.as_ref(), sealed_slice) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(err) => VaultBuffer::error(err.code()),
+        }
+    })
+}
+
+/// Wipe the key behind `handle` and retire the handle.
+///
+/// # Returns
+///
+/// 0 on success, or `ERR_INVALID_INPUT` for a handle that is not live
+#[no_mangle]
+pub extern "C" fn vault_key_destroy(handle: u64) -> i32 {
+    ffi_boundary(|| {
+        match keys().remove(&handle) {
+            // The boxed key is wiped as it drops here
+            Some(_) => 0,
+            None => ERR_INVALID_INPUT,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let bytes = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        bytes
+    }
+
+    #[test]
+    fn test_key_handle_lifecycle() {
+        let key = [0x42u8; 32];
+        let plaintext = b"sealed by handle";
+
+        unsafe {
+            let mut handle = 0u64;
+            assert_eq!(vault_key_import(key.as_ptr(), 32, &mut handle), 0);
+            assert_ne!(handle, 0);
+
+            // Interchangeable with raw-key sealing
+            let sealed = take(vault_seal_with_handle(handle, plaintext.as_ptr(), 16));
+            assert_eq!(take(vault_unseal(key.as_ptr(), sealed.as_ptr(), sealed.len() as u32)), plaintext);
+            let raw = take(vault_seal(key.as_ptr(), plaintext.as_ptr(), 16));
+            assert_eq!(take(vault_unseal_with_handle(handle, raw.as_ptr(), raw.len() as u32)), plaintext);
+
+            // Another handle is another key
+            let mut other = 0u64;
+            assert_eq!(vault_key_import([0x43u8; 32].as_ptr(), 32, &mut other), 0);
+            assert_ne!(other, handle);
+            assert_eq!(vault_unseal_with_handle(other, sealed.as_ptr(), sealed.len() as u32).error, ERR_DECRYPT_FAILED);
+
+            // Destroyed handles stay dead
+            assert_eq!(vault_key_destroy(handle), 0);
+            assert_eq!(vault_key_destroy(handle), ERR_INVALID_INPUT);
+            assert_eq!(vault_seal_with_handle(handle, plaintext.as_ptr(), 16).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_unseal_with_handle(handle, sealed.as_ptr(), sealed.len() as u32).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_key_destroy(other), 0);
+
+            assert_eq!(vault_key_destroy(0), ERR_INVALID_INPUT);
+            assert_eq!(vault_key_import(key.as_ptr(), 16, &mut handle), ERR_BAD_KEY_SIZE);
+            assert_eq!(vault_key_import(key.as_ptr(), 32, ptr::null_mut()), ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_key_purpose_enforced() {
+        let key = [0x42u8; 32];
+        let hash = [0x17u8; 32];
+        let plaintext = b"purpose";
+
+        unsafe {
+            let (mut seal, mut secp, mut ed, mut x) = (0u64, 0u64, 0u64, 0u64);
+            assert_eq!(vault_key_import(key.as_ptr(), 32, &mut seal), 0);
+            assert_eq!(vault_key_import_for(key.as_ptr(), 32, 1, &mut secp), 0);
+            assert_eq!(vault_key_import_for(key.as_ptr(), 32, 2, &mut ed), 0);
+            assert_eq!(vault_key_import_for(key.as_ptr(), 32, 3, &mut x), 0);
+            let mut hd = 0u64;
+            assert_eq!(vault_hd_master_from_seed([7u8; 64].as_ptr(), 64, &mut hd), 0);
+
+            // Each handle works for its own purpose only
+            for handle in [secp, ed, x, hd] {
+                assert_eq!(vault_seal_with_handle(handle, plaintext.as_ptr(), 7).error, ERR_INVALID_INPUT);
+                assert_eq!(vault_unseal_with_handle(handle, plaintext.as_ptr(), 7).error, ERR_INVALID_INPUT);
+                assert_eq!(vault_wrap_key(seal, handle).error, ERR_INVALID_INPUT);
+                assert_eq!(vault_wrap_key(handle, seal).error, ERR_INVALID_INPUT);
+            }
+            for handle in [seal, ed, x] {
+                assert_eq!(vault_secp256k1_sign(handle, hash.as_ptr(), 32).error, ERR_INVALID_INPUT);
+            }
+            for handle in [seal, secp, x, hd] {
+                assert_eq!(vault_ed25519_sign_with_handle(handle, plaintext.as_ptr(), 7).error, ERR_INVALID_INPUT);
+            }
+            for handle in [seal, secp, ed, hd] {
+                assert_eq!(vault_x25519_public_key(handle).error, ERR_INVALID_INPUT);
+            }
+            let sealed = take(vault_seal_with_handle(seal, plaintext.as_ptr(), 7));
+            take(vault_unseal_with_handle(seal, sealed.as_ptr(), sealed.len() as u32));
+            take(vault_wrap_key(seal, seal));
+            take(vault_secp256k1_sign(secp, hash.as_ptr(), 32));
+            take(vault_secp256k1_sign(hd, hash.as_ptr(), 32));
+            take(vault_ed25519_sign_with_handle(ed, plaintext.as_ptr(), 7));
+            take(vault_x25519_public_key(x));
+
+            let mut other = 0u64;
+            assert_eq!(vault_key_import_for(key.as_ptr(), 32, 4, &mut other), ERR_INVALID_INPUT);
+            assert_eq!(vault_key_import_for(key.as_ptr(), 16, 1, &mut other), ERR_BAD_KEY_SIZE);
+            assert_eq!(vault_key_import_for(key.as_ptr(), 32, 1, ptr::null_mut()), ERR_INVALID_INPUT);
+
+            for handle in [seal, secp, ed, x, hd] {
+                assert_eq!(vault_key_destroy(handle), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_generate() {
+        unsafe {
+            let (mut first, mut second) = (0u64, 0u64);
+            assert_eq!(vault_key_generate(&mut first), 0);
+            assert_eq!(vault_key_generate(&mut second), 0);
+            assert_ne!(*key_for(first, KeyPurpose::Seal).unwrap(), *key_for(second, KeyPurpose::Seal).unwrap());
+            assert_eq!(vault_key_destroy(first), 0);
+            assert_eq!(vault_key_destroy(second), 0);
+            assert_eq!(vault_key_generate(ptr::null_mut()), ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_key_derive_to_handle() {
+        let _defaults = crate::kdf::lock_default_params();
+        let passphrase = b"kept native";
+        let salt = [0x07u8; SALT_SIZE];
+
+        unsafe {
+            let mut handle = 0u64;
+            assert_eq!(vault_key_derive_to_handle(passphrase.as_ptr(), 11, salt.as_ptr(), &mut handle), 0);
+
+            // Same key as vault_derive_key
+            let key = take(vault_derive_key(passphrase.as_ptr(), 11, salt.as_ptr()));
+            let sealed = take(vault_seal_with_handle(handle, b"x".as_ptr(), 1));
+            assert_eq!(take(vault_unseal(key.as_ptr(), sealed.as_ptr(), sealed.len() as u32)), b"x");
+            assert_eq!(vault_key_destroy(handle), 0);
+
+            assert_eq!(vault_key_derive_to_handle(passphrase.as_ptr(), 0, salt.as_ptr(), &mut handle), ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_key_handle_wiped_on_destroy() {
+        use crate::test_alloc::{leaked_copies, NEEDLE_SIZE};
+
+        let key = *b"handle key bytes: never leak me!";
+        let mut needle = [0u8; NEEDLE_SIZE];
+        needle.copy_from_slice(&key[..NEEDLE_SIZE]);
+
+        let hits = leaked_copies(needle, || unsafe {
+            let mut handle = 0u64;
+            assert_eq!(vault_key_import(key.as_ptr(), 32, &mut handle), 0);
+            let sealed = vault_seal_with_handle(handle, b"payload".as_ptr(), 7);
+            assert_eq!(sealed.error, 0);
+            vault_free(sealed.data, sealed.len);
+            assert_eq!(vault_key_destroy(handle), 0);
+        });
+        assert_eq!(hits, 0);
+    }
+}
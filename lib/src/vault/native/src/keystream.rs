@@ -0,0 +1,121 @@
+//! Raw XChaCha20 Keystream
+//!
+//! The bare keystream for a key and nonce, for checking against reference
+//! implementations and for interop with formats that XOR it in themselves.
+//!
+//! **This provides no authentication.** Data XORed with the keystream can be
+//! altered bit by bit without detection, so anything confidential needs a
+//! separate MAC over it, or should use `vault_seal` instead.
+//!
+//! **Never ask for the keystream of a key used with the sealing functions.**
+//! Block 0 of the keystream for a nonce is the Poly1305 key that
+//! authenticates the sealed blob under that nonce: anyone who sees it can
+//! forge messages, and the rest of it decrypts the blob.
+
+use std::slice;
+
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::XChaCha20;
+
+use super::*;
+
+/// Fill a buffer with XChaCha20 keystream, starting at block 0.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `nonce` must point to exactly 24 bytes (`nonce_len` must be 24)
+/// - `out` must be writable for `out_len` bytes
+///
+/// # Returns
+///
+/// 0 on success, `ERR_BAD_KEY_SIZE`, `ERR_BAD_NONCE_SIZE`, or
+/// `ERR_INVALID_INPUT` for a null pointer
+#[no_mangle]
+pub unsafe extern "C" fn vault_keystream(
+    key: *const u8,
+    key_len: u32,
+    nonce: *const u8,
+    nonce_len: u32,
+    out: *mut u8,
+    out_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if nonce.is_null() || out.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+        if nonce_len as usize != NONCE_SIZE {
+            return error_detail(ERR_BAD_NONCE_SIZE, format_args!("nonce is {nonce_len} bytes, expected {NONCE_SIZE}"));
+        }
+
+        let nonce_slice = slice::from_raw_parts(nonce, NONCE_SIZE);
+        let mut cipher = match XChaCha20::new_from_slices(key_slice, nonce_slice) {
+            Ok(c) => c,
+            Err(_) => return ERR_INVALID_INPUT,
+        };
+
+        // The keystream is XORed into zeros
+        let out_slice = slice::from_raw_parts_mut(out, out_len as usize);
+        out_slice.fill(0);
+        cipher.apply_keystream(out_slice);
+        0
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_keystream_xchacha_vector() {
+        // draft-arciszewski-xchacha-03, appendix A.3.2 (from block 1)
+        let key = hex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f");
+        let nonce = hex("404142434445464748494a4b4c4d4e4f5051525354555658");
+        let expected = hex(concat!(
+            "29624b4b1b140ace53740e405b2168540fd7d630c1f536fecd722fc3cddba7f4",
+            "cca98cf9e47e5e64d115450f9b125b54449ff76141ca620a1f9cfcab2a1a8a25",
+            "5e766a5266b878846120ea64ad99aa479471e63befcbd37cd1c22a221fe46221",
+            "5cf32c74895bf505863ccddd48f62916dc6521f1ec50a5ae08903aa259d9bf60",
+            "7cd8026fba548604f1b6072d91bc91243a5b845f7fd171b02edc5a0a84cf28dd",
+            "241146bc376e3f48df5e7fee1d11048c190a3d3deb0feb64b42d9c6fdeee290f",
+            "a0e6ae2c26c0249ea8c181f7e2ffd100cbe5fd3c4f8271d62b15330cb8fdcf00",
+            "b3df507ca8c924f7017b7e712d15a2eb5c50484451e54e1b4b995bd8fdd94597",
+            "bb94d7af0b2c04df10ba0890899ed9293a0f55b8bafa999264035f1d4fbe7fe0",
+            "aafa109a62372027e50e10cdfecca127",
+        ));
+
+        let mut out = vec![0xFFu8; 64 + expected.len()];
+        unsafe {
+            let rc = vault_keystream(key.as_ptr(), 32, nonce.as_ptr(), 24, out.as_mut_ptr(), out.len() as u32);
+            assert_eq!(rc, 0);
+        }
+        assert_eq!(&out[64..], &expected[..]);
+    }
+
+    #[test]
+    fn test_keystream_errors() {
+        let key = [0x42u8; 32];
+        let nonce = [0x07u8; NONCE_SIZE];
+        let mut out = [0u8; 16];
+
+        unsafe {
+            assert_eq!(vault_keystream(key.as_ptr(), 16, nonce.as_ptr(), 24, out.as_mut_ptr(), 16), ERR_BAD_KEY_SIZE);
+            assert_eq!(vault_keystream(key.as_ptr(), 32, nonce.as_ptr(), 12, out.as_mut_ptr(), 16), ERR_BAD_NONCE_SIZE);
+            assert_eq!(vault_keystream(key.as_ptr(), 32, nonce.as_ptr(), 24, ptr::null_mut(), 16), ERR_INVALID_INPUT);
+            assert_eq!(vault_keystream(key.as_ptr(), 32, nonce.as_ptr(), 24, out.as_mut_ptr(), 0), 0);
+        }
+    }
+}
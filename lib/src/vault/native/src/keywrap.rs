@@ -0,0 +1,347 @@
+//! AES Key Wrap with Padding
+//!
+//! RFC 5649 wrapping of content keys under a 256-bit key-encryption key, so
+//! keys can be stored in or exchanged with a cloud KMS that speaks the
+//! standard format. Use the sealing functions for everything else: key wrap
+//! is deterministic and has no associated data.
+//!
+//! ## Format
+//!
+//! Plain RFC 5649 output with no format byte: the key padded with zeros to a
+//! multiple of 8 bytes, plus an 8-byte integrity check value carrying its
+//! exact length.
+//!
+//! ## By Handle
+//!
+//! `vault_wrap_key` and `vault_unwrap_key` do the same between key handles,
+//! so a data-encryption key (`vault_key_generate`) can be kept wrapped under
+//! a passphrase-derived key (`vault_key_derive_to_handle`) without either
+//! reaching the caller. A 32-byte key wraps to 40 bytes, and changing the
+//! passphrase re-wraps only that blob.
+
+use std::slice;
+
+use aes_kw::cipher::consts::U16;
+use aes_kw::cipher::{BlockCipherDecrypt, BlockCipherEncrypt};
+use aes_kw::{AesKwp, Error, KeyInit, KwpAes256};
+
+use crate::keyhandle::{insert_key, key_for, KeyPurpose};
+
+use super::*;
+
+/// Size of the RFC 5649 integrity check value (one semiblock)
+const KWP_ICV_SIZE: usize = aes_kw::IV_LEN;
+
+/// Wrap `key` under an initialized RFC 5649 context.
+fn kwp_wrap<C: BlockCipherEncrypt<BlockSize = U16>>(kw: &AesKwp<C>, key: &[u8]) -> VaultResult<Vec<u8>> {
+    if key.is_empty() {
+        return Err(ERR_INVALID_INPUT);
+    }
+    let wrapped_len = key.len().div_ceil(KWP_ICV_SIZE) * KWP_ICV_SIZE + KWP_ICV_SIZE;
+    let mut output = output_buffer(wrapped_len)?;
+    output.resize(wrapped_len, 0);
+    kw.wrap_key(key, &mut output).map_err(|_| ERR_INVALID_INPUT)?;
+    Ok(output)
+}
+
+/// Unwrap and verify `wrapped` under an initialized RFC 5649 context.
+fn kwp_unwrap<C: BlockCipherDecrypt<BlockSize = U16>>(kw: &AesKwp<C>, wrapped: &[u8]) -> VaultResult<Vec<u8>> {
+    if wrapped.len() < 2 * KWP_ICV_SIZE || !wrapped.len().is_multiple_of(KWP_ICV_SIZE) {
+        return Err(ERR_INVALID_INPUT);
+    }
+    // Wiped on drop: it holds unauthenticated key bytes until the check passes
+    let mut output = Secret::with_capacity(wrapped.len() - KWP_ICV_SIZE)?;
+    output.resize(wrapped.len() - KWP_ICV_SIZE, 0);
+    let key_len = match kw.unwrap_key(wrapped, &mut output) {
+        Ok(key) => key.len(),
+        Err(Error::IntegrityCheckFailed) => return Err(ERR_DECRYPT_FAILED),
+        Err(_) => return Err(ERR_INVALID_INPUT),
+    };
+    // The padding is zero (checked above), so nothing secret is left behind
+    output.truncate(key_len);
+    Ok(output.into_inner())
+}
+
+/// Wrap a key with AES-256 Key Wrap with Padding (RFC 5649).
+///
+/// # Format
+///
+/// Output: `ICV and padded key, encrypted` (key length rounded up to a multiple of 8, plus 8)
+///
+/// # Safety
+///
+/// - `kek` must point to exactly 32 bytes (`kek_len` must be 32)
+/// - `key` must be valid for `key_len` bytes (at least 1)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_keywrap(kek: *const u8, kek_len: u32, key: *const u8, key_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let kek_slice = match key_arg(kek, kek_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if key.is_null() || key_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let key_slice = slice::from_raw_parts(key, key_len as usize);
+        let kw = match KwpAes256::new_from_slice(kek_slice) {
+            Ok(kw) => kw,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+
+        match kwp_wrap(&kw, key_slice) {
+            Ok(wrapped) => VaultBuffer::success(wrapped),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Unwrap a key wrapped with `vault_keywrap` (or any RFC 5649 AES-256 wrapper).
+///
+/// # Safety
+///
+/// - `kek` must point to exactly 32 bytes (`kek_len` must be 32)
+/// - `wrapped` must be valid for `wrapped_len` bytes (a multiple of 8, at least 16)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// The key, or `ERR_DECRYPT_FAILED` if the integrity check value does not match
+#[no_mangle]
+pub unsafe extern "C" fn vault_keyunwrap(
+    kek: *const u8,
+    kek_len: u32,
+    wrapped: *const u8,
+    wrapped_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let kek_slice = match key_arg(kek, kek_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if wrapped.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let wrapped_slice = slice::from_raw_parts(wrapped, wrapped_len as usize);
+        let kw = match KwpAes256::new_from_slice(kek_slice) {
+            Ok(kw) => kw,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+
+        match kwp_unwrap(&kw, wrapped_slice) {
+            Ok(key) => VaultBuffer::success(key),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Wrap the key behind `key_handle` under the key behind `kek_handle`.
+///
+/// # Format
+///
+/// The 40-byte RFC 5649 wrap of the 32-byte key, as `vault_keywrap` makes.
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the wrapped key, or `ERR_INVALID_INPUT` for an
+/// unknown handle or one that is not a sealing key (signing, key-agreement
+/// and HD keys cannot be wrapped)
+#[no_mangle]
+pub unsafe extern "C" fn vault_wrap_key(kek_handle: u64, key_handle: u64) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let kek = match key_for(kek_handle, KeyPurpose::Seal) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let key = match key_for(key_handle, KeyPurpose::Seal) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let kw = match KwpAes256::new_from_slice(kek.as_ref()) {
+            Ok(kw) => kw,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+        match kwp_wrap(&kw, key.as_ref()) {
+            Ok(wrapped) => VaultBuffer::success(wrapped),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Unwrap a `vault_wrap_key` blob under the key behind `kek_handle` into a new handle.
+///
+/// # Safety
+///
+/// - `wrapped` must be valid for `wrapped_len` bytes
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_DECRYPT_FAILED` if the integrity check value does not
+/// match, `ERR_BAD_KEY_SIZE` if the wrapped key is not 32 bytes, or
+/// `ERR_INVALID_INPUT` for a null pointer, an unknown handle or one that is
+/// not a sealing key, or a blob of the wrong shape
+#[no_mangle]
+pub unsafe extern "C" fn vault_unwrap_key(kek_handle: u64, wrapped: *const u8, wrapped_len: u32, out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if wrapped.is_null() || out_handle.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let kek = match key_for(kek_handle, KeyPurpose::Seal) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+        let wrapped_slice = slice::from_raw_parts(wrapped, wrapped_len as usize);
+
+        let kw = match KwpAes256::new_from_slice(kek.as_ref()) {
+            Ok(kw) => kw,
+            Err(_) => return ERR_INVALID_INPUT,
+        };
+        let key = match kwp_unwrap(&kw, wrapped_slice) {
+            Ok(key) => Zeroizing::new(key),
+            Err(code) => return code,
+        };
+        if key.len() != KEY_SIZE {
+            return error_detail(ERR_BAD_KEY_SIZE, format_args!("wrapped key is {} bytes, expected {KEY_SIZE}", key.len()));
+        }
+
+        *out_handle = insert_key(&key, KeyPurpose::Seal, None);
+        0
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use aes_kw::KwpAes192;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_keywrap_rfc5649_vectors() {
+        // RFC 5649 section 6 (the RFC only publishes 192-bit KEK vectors)
+        let kw = KwpAes192::new_from_slice(&hex("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8")).unwrap();
+        let vectors = [
+            (
+                "c37b7e6492584340bed12207808941155068f738",
+                "138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a",
+            ),
+            ("466f7250617369", "afbeb0f07dfbf5419200f2ccb50bb24f"),
+        ];
+
+        for (key, wrapped) in vectors {
+            let (key, wrapped) = (hex(key), hex(wrapped));
+            assert_eq!(kwp_wrap(&kw, &key).unwrap(), wrapped);
+            assert_eq!(kwp_unwrap(&kw, &wrapped).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_keywrap_roundtrip_and_tamper() {
+        let kek = [0x42u8; 32];
+        let content_key = [0x17u8; 20];
+
+        unsafe {
+            let wrapped = vault_keywrap(kek.as_ptr(), 32, content_key.as_ptr(), 20);
+            assert_eq!(wrapped.error, 0);
+            assert_eq!(wrapped.len, 32);
+            let mut blob = slice::from_raw_parts(wrapped.data, wrapped.len as usize).to_vec();
+            vault_free(wrapped.data, wrapped.len);
+
+            let key = vault_keyunwrap(kek.as_ptr(), 32, blob.as_ptr(), blob.len() as u32);
+            assert_eq!(key.error, 0);
+            assert_eq!(slice::from_raw_parts(key.data, key.len as usize), &content_key);
+            vault_free(key.data, key.len);
+
+            let wrong_kek = [0x43u8; 32];
+            let result = vault_keyunwrap(wrong_kek.as_ptr(), 32, blob.as_ptr(), blob.len() as u32);
+            assert_eq!(result.error, ERR_DECRYPT_FAILED);
+
+            for i in 0..blob.len() {
+                blob[i] ^= 0x01;
+                let result = vault_keyunwrap(kek.as_ptr(), 32, blob.as_ptr(), blob.len() as u32);
+                assert_eq!(result.error, ERR_DECRYPT_FAILED);
+                blob[i] ^= 0x01;
+            }
+
+            let result = vault_keyunwrap(kek.as_ptr(), 32, blob.as_ptr(), 12);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+            let result = vault_keywrap(kek.as_ptr(), 16, content_key.as_ptr(), 20);
+            assert_eq!(result.error, ERR_BAD_KEY_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_wrap_key_by_handle() {
+        let kek = [0x42u8; 32];
+        let dek = [0x17u8; 32];
+        let take = |buffer: VaultBuffer| unsafe {
+            assert_eq!(buffer.error, 0);
+            let bytes = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+            vault_free(buffer.data, buffer.len);
+            bytes
+        };
+
+        unsafe {
+            let (mut kek_handle, mut dek_handle, mut other_kek) = (0u64, 0u64, 0u64);
+            assert_eq!(vault_key_import(kek.as_ptr(), 32, &mut kek_handle), 0);
+            assert_eq!(vault_key_import(dek.as_ptr(), 32, &mut dek_handle), 0);
+            assert_eq!(vault_key_import([0x43u8; 32].as_ptr(), 32, &mut other_kek), 0);
+
+            // The same blob vault_keywrap makes from the raw keys
+            let wrapped = take(vault_wrap_key(kek_handle, dek_handle));
+            assert_eq!(wrapped.len(), 40);
+            assert_eq!(wrapped, take(vault_keywrap(kek.as_ptr(), 32, dek.as_ptr(), 32)));
+
+            // The unwrapped handle opens what the original sealed
+            let mut unwrapped = 0u64;
+            assert_eq!(vault_unwrap_key(kek_handle, wrapped.as_ptr(), 40, &mut unwrapped), 0);
+            let sealed = take(vault_seal_with_handle(dek_handle, b"vault item".as_ptr(), 10));
+            assert_eq!(take(vault_unseal_with_handle(unwrapped, sealed.as_ptr(), sealed.len() as u32)), b"vault item");
+
+            // A passphrase change re-wraps under the new key
+            let rewrapped = take(vault_wrap_key(other_kek, unwrapped));
+            let mut rejected = 0u64;
+            assert_eq!(vault_unwrap_key(kek_handle, rewrapped.as_ptr(), 40, &mut rejected), ERR_DECRYPT_FAILED);
+            let mut reunwrapped = 0u64;
+            assert_eq!(vault_unwrap_key(other_kek, rewrapped.as_ptr(), 40, &mut reunwrapped), 0);
+            assert_eq!(take(vault_unseal_with_handle(reunwrapped, sealed.as_ptr(), sealed.len() as u32)), b"vault item");
+
+            // Only 32-byte keys unwrap to a handle
+            let short = take(vault_keywrap(kek.as_ptr(), 32, dek.as_ptr(), 20));
+            assert_eq!(vault_unwrap_key(kek_handle, short.as_ptr(), short.len() as u32, &mut rejected), ERR_BAD_KEY_SIZE);
+            assert_eq!(vault_unwrap_key(kek_handle, wrapped.as_ptr(), 36, &mut rejected), ERR_INVALID_INPUT);
+            assert_eq!(vault_unwrap_key(kek_handle, wrapped.as_ptr(), 40, ptr::null_mut()), ERR_INVALID_INPUT);
+            assert_eq!(rejected, 0);
+
+            // HD handles carry chain state that a wrap would drop
+            let mut master = 0u64;
+            assert_eq!(vault_hd_master_from_seed([0x01u8; 32].as_ptr(), 32, &mut master), 0);
+            assert_eq!(vault_wrap_key(kek_handle, master).error, ERR_INVALID_INPUT);
+
+            for handle in [kek_handle, dek_handle, other_kek, unwrapped, reunwrapped, master] {
+                assert_eq!(vault_key_destroy(handle), 0);
+            }
+            assert_eq!(vault_wrap_key(kek_handle, dek_handle).error, ERR_INVALID_INPUT);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Legacy Sealed Format
+//!
+//! Blobs sealed before the format byte was introduced are
+//! `nonce (24) || ciphertext || tag (16)` with no associated data. Their
+//! first byte is a random nonce byte, so it can collide with a format id
+//! and a blob cannot be classified by inspection alone.
+//!
+//! `vault_unseal` stays strict. Callers migrating old vaults opt in here:
+//! `vault_unseal_legacy` reads only the old layout, and `vault_unseal_auto`
+//! tries both. The ambiguity is resolved by authentication rather than by
+//! guessing: each candidate interpretation is decrypted, and only one can
+//! pass the tag under the right key. Re-seal with `vault_seal` once read.
+//!
+//! Some older stores kept the nonce, ciphertext and tag in separate columns;
+//! `vault_unseal_split` takes the three parts as they were stored.
+
+use std::slice;
+
+use super::*;
+
+/// Decrypt a legacy `nonce || ciphertext || tag` blob.
+fn open_legacy(key: &[u8], sealed: &[u8]) -> VaultResult<Vec<u8>> {
+    xchacha_open(key, sealed, &[])
+}
+
+/// Join separately stored `nonce`, `ciphertext` and `tag` into one
+/// `nonce || ciphertext || tag` buffer.
+fn join_split(nonce: &[u8], ciphertext: &[u8], tag: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut joined = output_buffer(NONCE_SIZE + ciphertext.len() + TAG_SIZE)?;
+    joined.extend_from_slice(nonce);
+    joined.extend_from_slice(ciphertext);
+    joined.extend_from_slice(tag);
+    Ok(joined)
+}
+
+/// Decrypt a blob sealed in the pre-versioning layout.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must contain: nonce (24) || ciphertext || tag (16)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_legacy(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < NONCE_SIZE + TAG_SIZE {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        match open_legacy(key_slice, sealed_slice) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt a `vault_seal` blob in either the versioned or the legacy layout.
+///
+/// A blob whose first byte is the `vault_seal` format id is opened as
+/// versioned first; if that fails (or the byte is anything else) it is
+/// opened as legacy. A wrong key therefore costs two decryption attempts.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, or `ERR_DECRYPT_FAILED` if
+/// neither interpretation authenticates
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_auto(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < NONCE_SIZE + TAG_SIZE {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        if sealed_slice[0] == FORMAT_XCHACHA {
+            if let Ok(plaintext) = open_blob(key_slice, sealed_slice) {
+                return VaultBuffer::success(plaintext);
+            }
+        }
+        match open_legacy(key_slice, sealed_slice) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Decrypt a sealed entry whose nonce, ciphertext and tag are stored apart.
+///
+/// The parts are the nonce, ciphertext and tag of either a `vault_seal`
+/// blob (after its format byte) or a legacy blob. They are opened as
+/// `vault_seal` parts first and as legacy parts if that fails, so a wrong
+/// key costs two decryption attempts, as in `vault_unseal_auto`.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `nonce` must point to exactly 24 bytes (`nonce_len` must be 24)
+/// - `ciphertext` must be valid for `ciphertext_len` bytes (may be null
+///   when `ciphertext_len` is 0)
+/// - `tag` must point to exactly 16 bytes (`tag_len` must be 16)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_BAD_NONCE_SIZE`,
+/// `ERR_INVALID_INPUT` for a null pointer or a tag that is not 16 bytes, or
+/// `ERR_DECRYPT_FAILED` if neither interpretation authenticates
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn vault_unseal_split(
+    key: *const u8,
+    key_len: u32,
+    nonce: *const u8,
+    nonce_len: u32,
+    ciphertext: *const u8,
+    ciphertext_len: u32,
+    tag: *const u8,
+    tag_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if nonce.is_null() || tag.is_null() || (ciphertext.is_null() && ciphertext_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if nonce_len as usize != NONCE_SIZE {
+            return VaultBuffer::error(error_detail(ERR_BAD_NONCE_SIZE, format_args!("nonce is {nonce_len} bytes, expected {NONCE_SIZE}")));
+        }
+        if tag_len as usize != TAG_SIZE {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let nonce_slice = slice::from_raw_parts(nonce, NONCE_SIZE);
+        let ciphertext_slice: &[u8] = if ciphertext_len == 0 { &[] } else { slice::from_raw_parts(ciphertext, ciphertext_len as usize) };
+        let tag_slice = slice::from_raw_parts(tag, TAG_SIZE);
+
+        let joined = match join_split(nonce_slice, ciphertext_slice, tag_slice) {
+            Ok(j) => j,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if let Ok(plaintext) = xchacha_open(key_slice, &joined, &[FORMAT_XCHACHA]) {
+            return VaultBuffer::success(plaintext);
+        }
+        match open_legacy(key_slice, &joined) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `b"legacy vault entry"` under key `[0x42; 32]`, nonce `[0x24; 24]`
+    const LEGACY_BLOB: &str = "242424242424242424242424242424242424242424242424\
+                               c93bb0040d5d6cd0c35a534e2eed63be40889a0e747a\
+                               d55cd5b539a46e08dee8798d";
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    unsafe fn auto(key: &[u8], sealed: &[u8]) -> VaultBuffer {
+        vault_unseal_auto(key.as_ptr(), key.len() as u32, sealed.as_ptr(), sealed.len() as u32)
+    }
+
+    #[test]
+    fn test_unseal_auto_legacy_and_versioned() {
+        let key = [0x42u8; 32];
+        let legacy = hex(LEGACY_BLOB);
+
+        unsafe {
+            let result = auto(&key, &legacy);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), b"legacy vault entry");
+            vault_free(result.data, result.len);
+
+            // The strict path refuses it
+            let strict = vault_unseal(key.as_ptr(), legacy.as_ptr(), legacy.len() as u32);
+            assert_eq!(strict.error, ERR_UNSUPPORTED_VERSION);
+
+            let short = &legacy[..NONCE_SIZE + TAG_SIZE - 1];
+            assert_eq!(auto(&key, short).error, ERR_CORRUPT_DATA);
+            assert_eq!(vault_unseal_legacy(key.as_ptr(), 32, short.as_ptr(), short.len() as u32).error, ERR_CORRUPT_DATA);
+
+            let plaintext = b"versioned entry";
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            let result = vault_unseal_auto(key.as_ptr(), 32, sealed.data, sealed.len);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), plaintext);
+            vault_free(result.data, result.len);
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+
+    #[test]
+    fn test_unseal_auto_legacy_nonce_collides_with_format() {
+        let key = [0x42u8; 32];
+        let plaintext = b"unlucky nonce";
+
+        // A legacy blob whose first nonce byte happens to be the format id
+        let legacy = loop {
+            let candidate = xchacha_seal(&key, plaintext, &[]).unwrap();
+            if candidate[0] == FORMAT_XCHACHA {
+                break candidate;
+            }
+        };
+
+        unsafe {
+            let result = auto(&key, &legacy);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), plaintext);
+            vault_free(result.data, result.len);
+
+            assert_eq!(auto(&[0x43u8; 32], &legacy).error, ERR_DECRYPT_FAILED);
+        }
+    }
+
+    unsafe fn split(key: &[u8], nonce: &[u8], ciphertext: &[u8], tag: &[u8]) -> VaultBuffer {
+        vault_unseal_split(
+            key.as_ptr(),
+            key.len() as u32,
+            nonce.as_ptr(),
+            nonce.len() as u32,
+            ciphertext.as_ptr(),
+            ciphertext.len() as u32,
+            tag.as_ptr(),
+            tag.len() as u32,
+        )
+    }
+
+    #[test]
+    fn test_unseal_split_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintext = b"stored in three columns";
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            let blob = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+            vault_free(sealed.data, sealed.len);
+
+            let body = &blob[FORMAT_HEADER_SIZE..];
+            let (nonce, rest) = body.split_at(NONCE_SIZE);
+            let (ciphertext, tag) = rest.split_at(rest.len() - TAG_SIZE);
+            let result = split(&key, nonce, ciphertext, tag);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), plaintext);
+            vault_free(result.data, result.len);
+
+            // Legacy parts
+            let legacy = hex(LEGACY_BLOB);
+            let (nonce, rest) = legacy.split_at(NONCE_SIZE);
+            let (ciphertext, legacy_tag) = rest.split_at(rest.len() - TAG_SIZE);
+            let result = split(&key, nonce, ciphertext, legacy_tag);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), b"legacy vault entry");
+            vault_free(result.data, result.len);
+
+            // Empty plaintext, with a null ciphertext pointer
+            let empty = xchacha_seal(&key, b"", &[FORMAT_XCHACHA]).unwrap();
+            let result = vault_unseal_split(key.as_ptr(), 32, empty.as_ptr(), 24, ptr::null(), 0, empty[NONCE_SIZE..].as_ptr(), 16);
+            assert_eq!(result.error, 0);
+            assert_eq!(result.len, 0);
+            vault_free(result.data, result.len);
+        }
+    }
+
+    #[test]
+    fn test_unseal_split_rejects_bad_parts() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), b"secret".as_ptr(), 6);
+            assert_eq!(sealed.error, 0);
+            let blob = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+            vault_free(sealed.data, sealed.len);
+
+            let body = &blob[FORMAT_HEADER_SIZE..];
+            let (nonce, rest) = body.split_at(NONCE_SIZE);
+            let (ciphertext, tag) = rest.split_at(rest.len() - TAG_SIZE);
+
+            let mut wrong_tag = tag.to_vec();
+            wrong_tag[0] ^= 0x01;
+            assert_eq!(split(&key, nonce, ciphertext, &wrong_tag).error, ERR_DECRYPT_FAILED);
+            assert_eq!(split(&[0x43u8; 32], nonce, ciphertext, tag).error, ERR_DECRYPT_FAILED);
+
+            assert_eq!(split(&key, &nonce[..12], ciphertext, tag).error, ERR_BAD_NONCE_SIZE);
+            assert_eq!(split(&key, nonce, ciphertext, &tag[..12]).error, ERR_INVALID_INPUT);
+            assert_eq!(split(&key[..16], nonce, ciphertext, tag).error, ERR_BAD_KEY_SIZE);
+        }
+    }
+}
@@ -5,7 +5,7 @@
 //!
 //! ## Design Principles
 //!
-//! 1. **Minimal surface**: Only 6 C-ABI functions exposed
+//! 1. **Minimal surface**: 6 core C-ABI functions; extensions live in submodules
 //! 2. **Memory safety**: All secrets zeroized on drop
 //! 3. **No allocations leak**: Caller frees all returned memory
 //! 4. **Constant-time**: Crypto operations don't leak timing
@@ -15,24 +15,220 @@
 //! | Function | Purpose |
 //! |----------|---------|
 //! | `vault_derive_key` | Argon2id KDF (passphrase → 32-byte key) |
-//! | `vault_seal` | ChaCha20-Poly1305 encrypt |
-//! | `vault_unseal` | ChaCha20-Poly1305 decrypt |
+//! | `vault_seal` | XChaCha20-Poly1305 encrypt |
+//! | `vault_unseal` | XChaCha20-Poly1305 decrypt |
 //! | `vault_free` | Secure free (zeroize + deallocate) |
 //! | `vault_zeroize` | Zeroize buffer in place |
 //! | `vault_random` | CSPRNG bytes |
 //!
+//! ## Modules
+//!
+//! | Module | Purpose |
+//! |--------|---------|
+//! | `hash` | One-shot and incremental SHA-256 / BLAKE3 digests |
+//! | `headed` | Records with a cleartext, authenticated header |
+//! | `hint` | Seals carrying an encrypted recovery hint |
+//! | `ietf` | 12-byte-nonce ChaCha20-Poly1305 for interop |
+//! | `inplace` | Sealing into caller buffers, optionally in place |
+//! | `kdf` | Key derivation extensions |
+//! | `keyhandle` | Keys kept in native memory behind opaque handles |
+//! | `keystream` | Raw XChaCha20 keystream for interop and testing |
+//! | `keywrap` | AES Key Wrap with Padding (RFC 5649) for KMS interop |
+//! | `legacy` | Opt-in reading of the pre-versioning sealed layout |
+//! | `log` | Hash-chained, tamper-evident audit log entries |
+//! | `mnemonic` | BIP-39 phrases and seed derivation |
+//! | `passphrase` | Derive-and-seal in a single call |
+//! | `pin` | PIN quick-unlock with a failed-attempt lockout |
+//! | `account` | Checksummed account identifiers from public keys |
+//! | `archive` | Many sealed entries framed as one indexed blob |
+//! | `base32` | Crockford base32 for transcribed recovery keys |
+//! | `batch` | Many-item operations in a single FFI call |
+//! | `bound` | Seals bound to a device identifier |
+//! | `boundary` | Panics caught at the FFI boundary |
+//! | `capabilities` | Supported algorithms reported at runtime |
+//! | `checked` | Sealing that refuses degenerate keys |
+//! | `cipher` | Reusable keyed cipher contexts |
+//! | `compress` | DEFLATE-compressed sealing |
+//! | `cose` | COSE_Encrypt0 (CBOR) export for standard tooling |
+//! | `envelope` | Passphrase changes over a wrapped data key |
+//! | `digested` | Seals that also record a plaintext digest |
+//! | `domain` | Seals bound to a purpose so they cannot be replayed in another |
+//! | `ed25519` | Ed25519 keypairs, signing and verification |
+//! | `ephemeral` | Clipboard-style secrets wiped after a timeout |
+//! | `error` | Descriptions of error codes |
+//! | `expiry` | Seals with an authenticated expiry time |
+//! | `file` | Sealing files by path in bounded memory |
+//! | `fingerprint` | Short, non-reversible key fingerprints |
+//! | `hd` | BIP-32 and SLIP-0010 Ed25519 key derivation by handle |
+//! | `profile` | Build profile reported at runtime |
+//! | `recovery` | Recovery keys with a check character |
+//! | `record` | Canonical on-disk vault record |
+//! | `refresh` | Re-sealing under a fresh nonce |
+//! | `rng` | Buffered ChaCha20 generator for bulk random fills |
+//! | `safe` | Safe Rust wrappers for Rust callers |
+//! | `secp256k1` | secp256k1 ECDSA signing by key handle |
+//! | `secret` | Wipe-on-drop holder for intermediate secrets |
+//! | `siv` | Deterministic AES-SIV sealing |
+//! | `status` | Status results with argument and OS error detail |
+//! | `stream` | Chunked STREAM format and incremental sealing of large payloads |
+//! | `subkey` | HKDF-SHA256 and labeled subkeys |
+//! | `synthetic` | Sealing under counter-derived nonces |
+//! | `timelock` | Sequential-work gate for exported blobs |
+//! | `totp` | Authenticator-app codes as an unlock factor |
+//! | `unlock` | Derive-and-unseal with a minimum duration |
+//! | `validate` | Keyless structural checks on sealed blobs |
+//! | `verifier` | Passphrase verifiers independent of the key |
+//! | `x25519` | X25519 key agreement into a sealing key handle |
+//! | `wasm` | JavaScript bindings (`wasm` feature) |
+//! | `guard` | Double-free detection (`debug-guard` feature) |
+//! | `selfcheck` | Timing smoke test of secret comparisons (`timing-selfcheck` feature) |
+//!
 //! Copyright (c) 2024-2025 OBIVERSE LLC
 //! Licensed under MIT OR Apache-2.0
 
+use std::fmt;
+use std::mem;
 use std::slice;
 use std::ptr;
+use std::sync::atomic;
 
-use argon2::{Argon2, Algorithm, Version, Params};
+use argon2::{Argon2, Algorithm, Block, Version, Params};
 use chacha20poly1305::{
-    aead::{Aead, KeyInit},
-    XChaCha20Poly1305, XNonce,
+    aead::{AeadInPlace, KeyInit},
+    Tag, XChaCha20Poly1305, XNonce,
 };
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
+
+mod account;
+mod archive;
+mod base32;
+mod batch;
+mod bound;
+mod boundary;
+mod capabilities;
+mod checked;
+mod cipher;
+mod compress;
+mod cose;
+mod digested;
+mod domain;
+mod ed25519;
+mod envelope;
+#[cfg(not(target_arch = "wasm32"))]
+mod ephemeral;
+mod error;
+mod expiry;
+#[cfg(not(target_arch = "wasm32"))]
+mod file;
+mod fingerprint;
+mod hash;
+mod hd;
+mod headed;
+mod hint;
+mod ietf;
+mod inplace;
+mod kdf;
+mod keyhandle;
+mod keystream;
+mod keywrap;
+mod legacy;
+mod log;
+mod mnemonic;
+mod passphrase;
+mod pin;
+mod profile;
+mod record;
+mod recovery;
+mod refresh;
+mod rng;
+pub mod safe;
+mod secp256k1;
+mod secret;
+mod siv;
+mod status;
+#[cfg(not(target_arch = "wasm32"))]
+mod stream;
+mod subkey;
+mod synthetic;
+mod timelock;
+mod totp;
+mod unlock;
+mod validate;
+mod verifier;
+mod wordlist;
+mod x25519;
+
+pub use account::*;
+pub use archive::*;
+pub use base32::*;
+pub use batch::*;
+pub use bound::*;
+pub use capabilities::*;
+pub use checked::*;
+pub use cipher::*;
+pub use compress::*;
+pub use cose::*;
+pub use digested::*;
+pub use domain::*;
+pub use ed25519::*;
+pub use envelope::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use ephemeral::*;
+pub use error::*;
+pub use expiry::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::*;
+pub use fingerprint::*;
+pub use hash::*;
+pub use hd::*;
+pub use headed::*;
+pub use hint::*;
+pub use ietf::*;
+pub use inplace::*;
+pub use kdf::*;
+pub use keyhandle::*;
+pub use keystream::*;
+pub use keywrap::*;
+pub use legacy::*;
+pub use log::*;
+pub use mnemonic::*;
+pub use passphrase::*;
+pub use pin::*;
+pub use profile::*;
+pub use record::*;
+pub use recovery::*;
+pub use refresh::*;
+pub use rng::*;
+pub use secp256k1::*;
+pub use siv::*;
+pub use status::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use stream::*;
+pub use subkey::*;
+pub use synthetic::*;
+pub use timelock::*;
+pub use totp::*;
+pub use unlock::*;
+pub use validate::*;
+pub use verifier::*;
+pub use x25519::*;
+
+use boundary::*;
+use secret::*;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "debug-guard")]
+mod guard;
+
+#[cfg(feature = "timing-selfcheck")]
+mod selfcheck;
+#[cfg(feature = "timing-selfcheck")]
+pub use selfcheck::*;
+
+#[cfg(test)]
+mod test_alloc;
 
 // =============================================================================
 // Constants
@@ -50,17 +246,58 @@ const TAG_SIZE: usize = 16;
 /// Salt size for Argon2 (128 bits recommended)
 const SALT_SIZE: usize = 16;
 
+// Sealed blob format identifiers. Every sealed blob starts with one of these
+// bytes, and the byte is bound into the AEAD as associated data.
+const FORMAT_XCHACHA: u8 = 0x01;  // format || nonce (24) || ciphertext || tag (16)
+const FORMAT_SIV: u8 = 0x02;      // format || siv tag (16) || ciphertext
+const FORMAT_TIMELOCK: u8 = 0x03; // format || difficulty (8) || salt (16) || nonce (24) || ciphertext || tag (16)
+const FORMAT_EXPIRING: u8 = 0x04; // format || not_after (8) || nonce (24) || ciphertext || tag (16)
+const FORMAT_STREAM: u8 = 0x05;   // format || nonce prefix (19) || chunk size (4) || chunks (ciphertext || tag (16))*
+const FORMAT_HINTED: u8 = 0x06;   // format || hint_len (2) || sealed hint || nonce (24) || ciphertext || tag (16)
+const FORMAT_SYNTHETIC: u8 = 0x07; // format || counter (8) || ciphertext || tag (16)
+const FORMAT_LOG: u8 = 0x08;      // format || ciphertext_len (4) || nonce (24) || ciphertext || tag (16) || chain hash (32)
+const FORMAT_IETF: u8 = 0x09;     // format || nonce (12) || ciphertext || tag (16)
+const FORMAT_BOUND: u8 = 0x0A;    // format || nonce (24) || ciphertext || tag (16), device id in AAD
+const FORMAT_HEADED: u8 = 0x0B;   // format || header_len (2) || header || nonce (24) || ciphertext || tag (16)
+const FORMAT_DOMAIN: u8 = 0x0C;   // format || nonce (24) || ciphertext || tag (16), domain tag in AAD
+const FORMAT_COUNTER: u8 = 0x0D;  // format || counter (8) || random (16) || ciphertext || tag (16)
+
+/// Format-byte flag: the sealed payload is `original length (4) || deflate stream`
+const FORMAT_COMPRESSED: u8 = 0x80;
+
+/// Size of the format header on sealed blobs
+const FORMAT_HEADER_SIZE: usize = 1;
+
+/// Largest plaintext `vault_seal` accepts: its blob must still fit the
+/// 32-bit `VaultBuffer` length
+const MAX_SEAL_PLAINTEXT: u32 = u32::MAX - (FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE) as u32;
+
+/// STREAM nonce prefix: the XChaCha nonce less the 5-byte counter and flag
+const STREAM_NONCE_PREFIX_SIZE: usize = NONCE_SIZE - 5;
+
+/// STREAM plaintext bytes per chunk, and the largest a header may declare
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
 // Argon2id parameters (OWASP recommended for 2024)
 // Target: ~200ms on modern hardware
 const ARGON2_M_COST: u32 = 65536;  // 64 MiB memory
 const ARGON2_T_COST: u32 = 3;      // 3 iterations
 const ARGON2_P_COST: u32 = 4;      // 4 parallel lanes
 
+// Argon2 variant selectors accepted at the FFI boundary
+const ARGON2_VARIANT_ID: u32 = 0;
+const ARGON2_VARIANT_I: u32 = 1;
+const ARGON2_VARIANT_D: u32 = 2;
+
 // =============================================================================
 // Result Structure
 // =============================================================================
 
 /// Result buffer returned by seal/unseal operations
+///
+/// An empty successful result (e.g. unsealing an empty plaintext) is
+/// `data` null, `len` 0, `error` 0. Passing it to `vault_free` is a no-op,
+/// so callers can free every result the same way.
 #[repr(C)]
 pub struct VaultBuffer {
     /// Pointer to data (owned by this struct)
@@ -72,22 +309,397 @@ pub struct VaultBuffer {
 }
 
 impl VaultBuffer {
-    fn success(data: Vec<u8>) -> Self {
-        let len = data.len() as u32;
-        let boxed = data.into_boxed_slice();
+    fn success(mut data: Vec<u8>) -> Self {
+        error::clear_error_detail();
+        if data.is_empty() {
+            return Self { data: ptr::null_mut(), len: 0, error: 0 };
+        }
+        // The C ABI length is 32-bit; never hand out a truncated length
+        let len = match u32::try_from(data.len()) {
+            Ok(len) => len,
+            Err(_) => {
+                data.zeroize();
+                return Self::error(ERR_INVALID_INPUT);
+            }
+        };
+        // `into_boxed_slice` reallocates when there is spare capacity and
+        // frees the old allocation unwiped; copy out and wipe it instead.
+        let boxed: Box<[u8]> = if data.capacity() == data.len() {
+            data.into_boxed_slice()
+        } else {
+            let mut exact = match output_buffer(data.len()) {
+                Ok(b) => b,
+                Err(code) => {
+                    data.zeroize();
+                    return Self::error(code);
+                }
+            };
+            exact.extend_from_slice(&data);
+            data.zeroize();
+            exact.into_boxed_slice()
+        };
         let ptr = Box::into_raw(boxed) as *mut u8;
+        #[cfg(feature = "debug-guard")]
+        guard::track(ptr, len as usize);
         Self { data: ptr, len, error: 0 }
     }
 
     fn error(code: i32) -> Self {
+        error::record_error(code);
         Self { data: ptr::null_mut(), len: 0, error: code }
     }
 }
 
+/// Shows the length and error code; the contents may be plaintext or key
+/// material and are never printed.
+impl fmt::Debug for VaultBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VaultBuffer")
+            .field("data", &format_args!("***redacted***"))
+            .field("len", &self.len)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+/// Borrowed input slice for functions taking many items at once
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VaultSlice {
+    /// Pointer to the bytes (borrowed from the caller)
+    pub ptr: *const u8,
+    /// Length of the bytes
+    pub len: u32,
+}
+
+/// Argon2 cost parameters, as stored alongside a derived key's salt
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VaultKdfParams {
+    /// Memory cost in KiB
+    pub m_cost: u32,
+    /// Number of iterations
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+    /// Argon2 variant: 0 = Argon2id, 1 = Argon2i, 2 = Argon2d
+    pub variant: u32,
+}
+
+impl Default for VaultKdfParams {
+    fn default() -> Self {
+        Self {
+            m_cost: ARGON2_M_COST,
+            t_cost: ARGON2_T_COST,
+            p_cost: ARGON2_P_COST,
+            variant: ARGON2_VARIANT_ID,
+        }
+    }
+}
+
 // Error codes
 const ERR_INVALID_INPUT: i32 = -1;
 const ERR_DECRYPT_FAILED: i32 = -2;
 const ERR_KDF_FAILED: i32 = -3;
+const ERR_LOCKED_OUT: i32 = -4;
+const ERR_UNSUPPORTED_VERSION: i32 = -5;
+const ERR_CORRUPT_DATA: i32 = -6;
+const ERR_EXPIRED: i32 = -7;
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))] // file I/O is native-only
+const ERR_IO: i32 = -8;
+const ERR_BAD_KEY_SIZE: i32 = -9;
+const ERR_BAD_NONCE_SIZE: i32 = -10;
+const ERR_BAD_SALT_SIZE: i32 = -11;
+const ERR_OUT_OF_MEMORY: i32 = -12;
+const ERR_WIPE_FAILED: i32 = -13;
+const ERR_RNG_FAILED: i32 = -14;
+const ERR_BUSY: i32 = -15;
+const ERR_WEAK_KEY: i32 = -16;
+const ERR_TIMING_LEAK: i32 = -17;
+const ERR_INTERNAL_PANIC: i32 = -18;
+
+/// Result of an internal operation; the error is one of the `ERR_*` codes.
+type VaultResult<T> = Result<T, i32>;
+
+// =============================================================================
+// Internal Primitives
+// =============================================================================
+
+/// Borrow a caller-supplied key, requiring exactly `KEY_SIZE` bytes.
+unsafe fn key_arg<'a>(key: *const u8, key_len: u32) -> VaultResult<&'a [u8]> {
+    if key.is_null() {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("key is null")));
+    }
+    if key_len as usize != KEY_SIZE {
+        return Err(error_detail(ERR_BAD_KEY_SIZE, format_args!("key is {key_len} bytes, expected {KEY_SIZE}")));
+    }
+    Ok(slice::from_raw_parts(key, KEY_SIZE))
+}
+
+/// Borrow optional associated data (null is allowed only when empty).
+unsafe fn aad_arg<'a>(aad: *const u8, aad_len: u32) -> VaultResult<&'a [u8]> {
+    if aad_len == 0 {
+        return Ok(&[]);
+    }
+    if aad.is_null() {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(slice::from_raw_parts(aad, aad_len as usize))
+}
+
+/// Compare two secret-derived values in constant time.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    use subtle::ConstantTimeEq;
+    a.ct_eq(b).into()
+}
+
+/// Fill `buf` from the OS CSPRNG.
+///
+/// Failure is `ERR_RNG_FAILED`, never a bad-argument code, and callers must
+/// not use `buf` after it: it may be partly filled or still zero.
+fn random_bytes(buf: &mut [u8]) -> VaultResult<()> {
+    #[cfg(test)]
+    if RNG_FAILS.get() {
+        return Err(ERR_RNG_FAILED);
+    }
+    getrandom::getrandom(buf).map_err(|e| error_detail(ERR_RNG_FAILED, format_args!("{e}")))
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Makes `random_bytes` fail on this thread (see `with_failing_rng`)
+    static RNG_FAILS: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Run `f` with the entropy source failing on this thread.
+#[cfg(test)]
+fn with_failing_rng<R>(f: impl FnOnce() -> R) -> R {
+    RNG_FAILS.set(true);
+    let result = f();
+    RNG_FAILS.set(false);
+    result
+}
+
+/// Map an FFI variant selector to the Argon2 algorithm.
+fn argon2_algorithm(variant: u32) -> VaultResult<Algorithm> {
+    match variant {
+        ARGON2_VARIANT_ID => Ok(Algorithm::Argon2id),
+        ARGON2_VARIANT_I => Ok(Algorithm::Argon2i),
+        ARGON2_VARIANT_D => Ok(Algorithm::Argon2d),
+        _ => Err(ERR_INVALID_INPUT),
+    }
+}
+
+/// Argon2id with explicit cost parameters, producing a 32-byte key.
+fn argon2id(
+    passphrase: &[u8],
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> VaultResult<Secret<[u8; KEY_SIZE]>> {
+    argon2_key(passphrase, salt, &[], m_cost, t_cost, p_cost, Algorithm::Argon2id)
+}
+
+/// Argon2 of any variant with explicit cost parameters, producing a 32-byte key.
+///
+/// A non-empty `pepper` is Argon2's secret (keyed mode) input; it is
+/// borrowed, never copied. An empty pepper is plain unkeyed Argon2.
+fn argon2_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    pepper: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    algorithm: Algorithm,
+) -> VaultResult<Secret<[u8; KEY_SIZE]>> {
+    argon2_key_with(passphrase, salt, pepper, m_cost, t_cost, p_cost, algorithm, true)
+}
+
+/// [`argon2_key`], optionally returning the working memory to the allocator
+/// without wiping it.
+///
+/// Only `vault_derive_key_ex` with `DERIVE_SKIP_MEMORY_WIPE` passes
+/// `wipe_memory = false`; see there for what that exposes.
+#[allow(clippy::too_many_arguments)]
+fn argon2_key_with(
+    passphrase: &[u8],
+    salt: &[u8],
+    pepper: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    algorithm: Algorithm,
+    wipe_memory: bool,
+) -> VaultResult<Secret<[u8; KEY_SIZE]>> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_SIZE))
+        .map_err(|e| error_detail(ERR_KDF_FAILED, format_args!("Argon2 rejected m_cost {m_cost}, t_cost {t_cost}, p_cost {p_cost}: {e}")))?;
+    let mut blocks = argon2_blocks(params.block_count())?;
+    let argon2 = if pepper.is_empty() {
+        Argon2::new(algorithm, Version::V0x13, params)
+    } else {
+        Argon2::new_with_secret(pepper, algorithm, Version::V0x13, params).map_err(|_| ERR_KDF_FAILED)?
+    };
+
+    let mut key = Secret::new([0u8; KEY_SIZE]);
+    if wipe_memory {
+        let mut blocks = Secret::new(blocks);
+        argon2_hash(&argon2, passphrase, salt, key.as_mut(), &mut blocks)?;
+    } else {
+        argon2
+            .hash_password_into_with_memory(passphrase, salt, key.as_mut(), &mut blocks)
+            .map_err(|_| ERR_KDF_FAILED)?;
+    }
+    Ok(key)
+}
+
+/// Allocate Argon2 working memory, reporting failure instead of aborting.
+///
+/// At the default 64 MiB this can fail on low-memory devices; the caller
+/// can retry with a smaller `m_cost` on `ERR_OUT_OF_MEMORY`.
+fn argon2_blocks(count: usize) -> VaultResult<Vec<Block>> {
+    let mut blocks = Vec::new();
+    blocks
+        .try_reserve_exact(count)
+        .map_err(|_| error_detail(ERR_OUT_OF_MEMORY, format_args!("could not allocate {count} KiB of Argon2 working memory")))?;
+    blocks.resize(count, Block::default());
+    Ok(blocks)
+}
+
+/// Run Argon2 in caller-owned working memory, wiping it on every path.
+///
+/// `hash_password_into` frees its internal block memory without zeroizing
+/// it, and those blocks hold passphrase-derived state. Owning the memory
+/// lets us guarantee the wipe before it returns to the allocator.
+fn argon2_hash(
+    argon2: &Argon2,
+    passphrase: &[u8],
+    salt: &[u8],
+    out: &mut [u8],
+    blocks: &mut [Block],
+) -> VaultResult<()> {
+    let result = argon2.hash_password_into_with_memory(passphrase, salt, out, &mut *blocks);
+    for block in blocks.iter_mut() {
+        block.zeroize();
+    }
+    result.map_err(|_| ERR_KDF_FAILED)
+}
+
+/// An empty buffer with room for `capacity` bytes, or `ERR_OUT_OF_MEMORY`.
+///
+/// Output sizes follow caller input, or a length read from a blob, so
+/// allocating them must fail with an error code: the infallible `Vec`
+/// constructors abort the whole host app when memory runs out.
+fn output_buffer(capacity: usize) -> VaultResult<Vec<u8>> {
+    let mut buffer = Vec::new();
+    buffer
+        .try_reserve_exact(capacity)
+        .map_err(|_| error_detail(ERR_OUT_OF_MEMORY, format_args!("could not allocate {capacity} bytes of output")))?;
+    Ok(buffer)
+}
+
+/// `bytes` copied into a fresh output buffer, or `ERR_OUT_OF_MEMORY`.
+fn output_copy(bytes: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut buffer = output_buffer(bytes.len())?;
+    buffer.extend_from_slice(bytes);
+    Ok(buffer)
+}
+
+/// Encrypt with XChaCha20-Poly1305 under a fresh random nonce.
+///
+/// Output: `nonce (24 bytes) || ciphertext || tag (16 bytes)`
+///
+/// Encrypts in place in the output buffer, so plaintext is never copied
+/// into a buffer that could be dropped unwiped on an error path.
+fn xchacha_seal(key: &[u8], plaintext: &[u8], aad: &[u8]) -> VaultResult<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?;
+    xchacha_seal_with(&cipher, plaintext, aad)
+}
+
+/// [`xchacha_seal`] with an already keyed cipher.
+///
+/// The nonce is not secret, but its stack copy is wiped on every return
+/// like any other intermediate.
+fn xchacha_seal_with(cipher: &XChaCha20Poly1305, plaintext: &[u8], aad: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut nonce_bytes = Secret::new([0u8; NONCE_SIZE]);
+    random_bytes(nonce_bytes.as_mut())?;
+    let nonce = XNonce::from_slice(nonce_bytes.as_ref());
+
+    let mut output = Secret::with_capacity(NONCE_SIZE + plaintext.len() + TAG_SIZE)?;
+    output.extend_from_slice(nonce_bytes.as_ref());
+    output.extend_from_slice(plaintext);
+    let tag = cipher
+        .encrypt_in_place_detached(nonce, aad, &mut output[NONCE_SIZE..])
+        .map_err(|_| ERR_INVALID_INPUT)?;
+    output.extend_from_slice(&tag);
+
+    Ok(output.into_inner())
+}
+
+/// Decrypt `nonce || ciphertext || tag` produced by [`xchacha_seal`].
+///
+/// The returned plaintext is exactly sized, and the working buffer is
+/// wiped if authentication fails.
+fn xchacha_open(key: &[u8], sealed: &[u8], aad: &[u8]) -> VaultResult<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?;
+    xchacha_open_with(&cipher, sealed, aad)
+}
+
+/// [`xchacha_open`] with an already keyed cipher.
+fn xchacha_open_with(cipher: &XChaCha20Poly1305, sealed: &[u8], aad: &[u8]) -> VaultResult<Vec<u8>> {
+    if sealed.len() < NONCE_SIZE + TAG_SIZE {
+        return Err(ERR_INVALID_INPUT);
+    }
+
+    let (nonce_bytes, rest) = sealed.split_at(NONCE_SIZE);
+    let (ciphertext, tag) = rest.split_at(rest.len() - TAG_SIZE);
+    let nonce = XNonce::from_slice(nonce_bytes);
+
+    let mut plaintext = Secret::copy_of(ciphertext)?;
+    cipher
+        .decrypt_in_place_detached(nonce, aad, &mut plaintext, Tag::from_slice(tag))
+        .map_err(|_| ERR_DECRYPT_FAILED)?;
+
+    Ok(plaintext.into_inner())
+}
+
+/// Associated data of a `vault_seal` blob: its format byte, then the
+/// caller's context (empty for plain `vault_seal`).
+fn blob_aad(aad: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut bound = output_buffer(FORMAT_HEADER_SIZE + aad.len())?;
+    bound.push(FORMAT_XCHACHA);
+    bound.extend_from_slice(aad);
+    Ok(bound)
+}
+
+/// Seal in the `vault_seal` format: `format || nonce || ciphertext || tag`.
+fn seal_blob(key: &[u8], plaintext: &[u8]) -> VaultResult<Vec<u8>> {
+    seal_blob_aad(key, plaintext, &[])
+}
+
+/// [`seal_blob`] binding caller-supplied associated data.
+fn seal_blob_aad(key: &[u8], plaintext: &[u8], aad: &[u8]) -> VaultResult<Vec<u8>> {
+    let header = [FORMAT_XCHACHA];
+    let sealed = xchacha_seal(key, plaintext, &blob_aad(aad)?)?;
+
+    let mut output = output_buffer(FORMAT_HEADER_SIZE + sealed.len())?;
+    output.extend_from_slice(&header);
+    output.extend_from_slice(&sealed);
+    Ok(output)
+}
+
+/// Open a blob in the `vault_seal` format.
+fn open_blob(key: &[u8], sealed: &[u8]) -> VaultResult<Vec<u8>> {
+    if sealed.len() < FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let (header, body) = sealed.split_at(FORMAT_HEADER_SIZE);
+    if header[0] != FORMAT_XCHACHA {
+        return Err(ERR_UNSUPPORTED_VERSION);
+    }
+    xchacha_open(key, body, header)
+}
 
 // =============================================================================
 // Key Derivation (Argon2id)
@@ -95,6 +707,9 @@ const ERR_KDF_FAILED: i32 = -3;
 
 /// Derive a 32-byte encryption key from a passphrase using Argon2id.
 ///
+/// Uses the default costs, which `vault_set_default_argon2_params` can
+/// change; store the costs with each vault if they are ever changed.
+///
 /// # Safety
 ///
 /// - `passphrase` must be a valid UTF-8 string pointer
@@ -102,6 +717,13 @@ const ERR_KDF_FAILED: i32 = -3;
 /// - `salt` must point to exactly 16 bytes
 /// - Returned buffer must be freed with `vault_free`
 ///
+/// # Memory Hygiene
+///
+/// The Argon2 working memory (64 MiB by default) and every internal copy are zeroized
+/// before this returns. The passphrase is read in place and never copied,
+/// so the caller remains responsible for wiping its own passphrase buffer
+/// (e.g. with `vault_zeroize`) and the returned key once it is done.
+///
 /// # Returns
 ///
 /// VaultBuffer containing 32-byte key, or error code
@@ -111,31 +733,23 @@ pub unsafe extern "C" fn vault_derive_key(
     passphrase_len: u32,
     salt: *const u8,
 ) -> VaultBuffer {
-    // Validate inputs
-    if passphrase.is_null() || salt.is_null() || passphrase_len == 0 {
-        return VaultBuffer::error(ERR_INVALID_INPUT);
-    }
-
-    let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
-    let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
-
-    // Configure Argon2id
-    let params = match Params::new(ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST, Some(KEY_SIZE)) {
-        Ok(p) => p,
-        Err(_) => return VaultBuffer::error(ERR_KDF_FAILED),
-    };
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
 
-    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
 
-    // Derive key
-    let mut key = vec![0u8; KEY_SIZE];
-    match argon2.hash_password_into(passphrase_slice, salt_slice, &mut key) {
-        Ok(_) => VaultBuffer::success(key),
-        Err(_) => {
-            key.zeroize();
-            VaultBuffer::error(ERR_KDF_FAILED)
+        match kdf::argon2id_default(passphrase_slice, salt_slice) {
+            Ok(key) => match Secret::copy_of(key.as_ref()) {
+                Ok(copy) => VaultBuffer::success(copy.into_inner()),
+                Err(code) => VaultBuffer::error(code),
+            },
+            Err(code) => VaultBuffer::error(code),
         }
-    }
+    })
 }
 
 // =============================================================================
@@ -146,52 +760,145 @@ pub unsafe extern "C" fn vault_derive_key(
 ///
 /// # Format
 ///
-/// Output: `nonce (24 bytes) || ciphertext || tag (16 bytes)`
+/// Output: `format (1 byte) || nonce (24 bytes) || ciphertext || tag (16 bytes)`
+///
+/// The format byte is `0x01` and is authenticated as associated data. To
+/// bind the blob to its context as well, use `vault_seal_aad`.
 ///
 /// # Safety
 ///
 /// - `key` must point to exactly 32 bytes
-/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - `plaintext` must be valid for `plaintext_len` bytes (may be null when
+///   `plaintext_len` is 0; an empty plaintext seals to a 41-byte blob)
 /// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the sealed blob, or `ERR_INVALID_INPUT` for a
+/// null pointer or a plaintext over `u32::MAX - 41` bytes, whose blob
+/// length would not fit in 32 bits
 #[no_mangle]
 pub unsafe extern "C" fn vault_seal(
     key: *const u8,
     plaintext: *const u8,
     plaintext_len: u32,
 ) -> VaultBuffer {
-    // Validate inputs
-    if key.is_null() || plaintext.is_null() {
-        return VaultBuffer::error(ERR_INVALID_INPUT);
-    }
+    ffi_boundary(|| {
+        // Validate inputs
+        if key.is_null() || (plaintext.is_null() && plaintext_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        // Refused before anything near 4 GiB is allocated
+        if plaintext_len > MAX_SEAL_PLAINTEXT {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
 
-    let key_slice = slice::from_raw_parts(key, KEY_SIZE);
-    let plaintext_slice = slice::from_raw_parts(plaintext, plaintext_len as usize);
+        let key_slice = slice::from_raw_parts(key, KEY_SIZE);
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
 
-    // Generate random nonce
-    let mut nonce_bytes = [0u8; NONCE_SIZE];
-    if getrandom::getrandom(&mut nonce_bytes).is_err() {
-        return VaultBuffer::error(ERR_INVALID_INPUT);
-    }
-    let nonce = XNonce::from_slice(&nonce_bytes);
+        match seal_blob(key_slice, plaintext_slice) {
+            Ok(output) => VaultBuffer::success(output),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
 
-    // Create cipher
-    let cipher = match XChaCha20Poly1305::new_from_slice(key_slice) {
-        Ok(c) => c,
-        Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
-    };
+/// Encrypt like `vault_seal`, binding associated data to the blob.
+///
+/// The associated data (a wallet id, a record type and version, ...) is
+/// authenticated but neither encrypted nor stored: `vault_unseal_aad` must
+/// be given the same bytes, and fails with `ERR_DECRYPT_FAILED` otherwise.
+/// Binding each record's identity this way stops a blob from being moved
+/// into another record unnoticed.
+///
+/// # Format
+///
+/// Output: the `vault_seal` format, with `format || aad` as the AEAD's
+/// associated data. With no AAD the blob is an ordinary `vault_seal` blob.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes (may be null when
+///   `plaintext_len` is 0)
+/// - `aad` must be valid for `aad_len` bytes (may be null when `aad_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the sealed blob, `ERR_BAD_KEY_SIZE`, or
+/// `ERR_INVALID_INPUT` as for `vault_seal`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_aad(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    aad: *const u8,
+    aad_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if (plaintext.is_null() && plaintext_len != 0) || plaintext_len > MAX_SEAL_PLAINTEXT {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let aad_slice = match aad_arg(aad, aad_len) {
+            Ok(a) => a,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
 
-    // Encrypt
-    let ciphertext = match cipher.encrypt(nonce, plaintext_slice) {
-        Ok(ct) => ct,
-        Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
-    };
+        match seal_blob_aad(key_slice, plaintext_slice, aad_slice) {
+            Ok(output) => VaultBuffer::success(output),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
 
-    // Output: nonce || ciphertext (includes tag)
-    let mut output = Vec::with_capacity(NONCE_SIZE + ciphertext.len());
-    output.extend_from_slice(&nonce_bytes);
-    output.extend_from_slice(&ciphertext);
+/// Decrypt a `vault_seal_aad` blob given the associated data it was sealed
+/// with.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - `aad` must be valid for `aad_len` bytes (may be null when `aad_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, or the errors of `vault_unseal`;
+/// different associated data is `ERR_DECRYPT_FAILED`, like a wrong key
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_aad(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+    aad: *const u8,
+    aad_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if key.is_null() || sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let aad_slice = match aad_arg(aad, aad_len) {
+            Ok(a) => a,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let key_slice = slice::from_raw_parts(key, key_len as usize);
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
 
-    VaultBuffer::success(output)
+        match unseal_checked(key_slice, sealed_slice, aad_slice) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(err) => VaultBuffer::error(err.code()),
+        }
+    })
 }
 
 /// Decrypt data encrypted with `vault_seal`.
@@ -199,37 +906,83 @@ pub unsafe extern "C" fn vault_seal(
 /// # Safety
 ///
 /// - `key` must point to exactly 32 bytes
-/// - `sealed` must contain: nonce (24) || ciphertext || tag (16)
+/// - `sealed` must contain: format (1) || nonce (24) || ciphertext || tag (16);
+///   its length is checked against the layout its format byte declares
+///   before it is split
 /// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_UNSUPPORTED_VERSION` for a
+/// blob in another format, `ERR_CORRUPT_DATA` for a blob too short for its
+/// declared format (truncated storage), or `ERR_DECRYPT_FAILED` if the
+/// framing is sound but the tag does not verify (most likely a wrong key)
 #[no_mangle]
 pub unsafe extern "C" fn vault_unseal(
     key: *const u8,
     sealed: *const u8,
     sealed_len: u32,
 ) -> VaultBuffer {
-    // Validate inputs
-    let min_len = NONCE_SIZE + TAG_SIZE;
-    if key.is_null() || sealed.is_null() || (sealed_len as usize) < min_len {
-        return VaultBuffer::error(ERR_INVALID_INPUT);
-    }
+    ffi_boundary(|| {
+        // Validate inputs
+        if key.is_null() || sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
 
-    let key_slice = slice::from_raw_parts(key, KEY_SIZE);
-    let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+        let key_slice = slice::from_raw_parts(key, KEY_SIZE);
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
 
-    // Extract nonce and ciphertext
-    let (nonce_bytes, ciphertext) = sealed_slice.split_at(NONCE_SIZE);
-    let nonce = XNonce::from_slice(nonce_bytes);
+        match unseal_safe(key_slice, sealed_slice) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(err) => VaultBuffer::error(err.code()),
+        }
+    })
+}
 
-    // Create cipher
-    let cipher = match XChaCha20Poly1305::new_from_slice(key_slice) {
-        Ok(c) => c,
-        Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+/// Safe core of `vault_unseal`, taking arbitrary slices.
+///
+/// Checks the key length, then the blob length against the layout its
+/// format byte declares, before any slicing, so every input either decrypts
+/// or returns an error. This is the entry point for fuzzing
+/// (`fuzz/fuzz_targets/unseal.rs`); FFI and wasm wrappers go through it.
+pub fn unseal_safe(key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, VaultError> {
+    unseal_checked(key, sealed, &[])
+}
+
+/// [`unseal_safe`] with the caller's associated data.
+fn unseal_checked(key: &[u8], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, VaultError> {
+    if key.len() != KEY_SIZE {
+        return Err(VaultError::BadKeySize);
+    }
+    let format = match sealed.first() {
+        Some(&format) => format,
+        None => return Err(error_detail(ERR_CORRUPT_DATA, format_args!("sealed data is empty")).into()),
     };
+    let nonce_size = match unseal_nonce_size(format) {
+        Some(size) => size,
+        None => return Err(error_detail(ERR_UNSUPPORTED_VERSION, format_args!("format byte {format:#04x} is not recognized")).into()),
+    };
+    // Truncation is reported as such; only a tag failure is ERR_DECRYPT_FAILED
+    let minimum = FORMAT_HEADER_SIZE + nonce_size + TAG_SIZE;
+    if sealed.len() < minimum {
+        let detail = format_args!("sealed data is {} bytes, the format needs at least {minimum}", sealed.len());
+        return Err(error_detail(ERR_CORRUPT_DATA, detail).into());
+    }
 
-    // Decrypt
-    match cipher.decrypt(nonce, ciphertext) {
-        Ok(plaintext) => VaultBuffer::success(plaintext),
-        Err(_) => VaultBuffer::error(ERR_DECRYPT_FAILED),
+    let body = &sealed[FORMAT_HEADER_SIZE..];
+    match format {
+        FORMAT_XCHACHA => blob_aad(aad).and_then(|bound| xchacha_open(key, body, &bound)).map_err(VaultError::from),
+        // Well-formed, but opened by `vault_unseal_ietf`
+        _ => Err(VaultError::UnsupportedVersion),
+    }
+}
+
+/// Nonce size of a single-nonce format, or `None` for other layouts.
+fn unseal_nonce_size(format: u8) -> Option<usize> {
+    match format {
+        FORMAT_XCHACHA => Some(NONCE_SIZE),
+        FORMAT_IETF => Some(ietf::IETF_NONCE_SIZE),
+        _ => None,
     }
 }
 
@@ -244,18 +997,28 @@ pub unsafe extern "C" fn vault_unseal(
 /// - `ptr` must have been returned by a vault function
 /// - `len` must match the original length
 /// - Must not be called twice on the same pointer
+///
+/// With the `debug-guard` feature a double free, a foreign pointer or a
+/// wrong `len` is reported on stderr and the call does nothing.
 #[no_mangle]
 pub unsafe extern "C" fn vault_free(ptr: *mut u8, len: u32) {
-    if ptr.is_null() || len == 0 {
-        return;
-    }
+    ffi_boundary(|| {
+        if ptr.is_null() || len == 0 {
+            return;
+        }
+
+        #[cfg(feature = "debug-guard")]
+        if !guard::release(ptr, len as usize) {
+            return;
+        }
 
-    // Zeroize before freeing
-    let slice = slice::from_raw_parts_mut(ptr, len as usize);
-    slice.zeroize();
+        // Zeroize before freeing
+        let slice = slice::from_raw_parts_mut(ptr, len as usize);
+        slice.zeroize();
 
-    // Reconstruct and drop the Box to free
-    let _ = Box::from_raw(slice::from_raw_parts_mut(ptr, len as usize));
+        // Reconstruct and drop the Box to free
+        let _ = Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len as usize));
+    })
 }
 
 /// Zeroize a buffer in place (for Dart-allocated memory).
@@ -266,12 +1029,51 @@ pub unsafe extern "C" fn vault_free(ptr: *mut u8, len: u32) {
 /// - Memory must be writable
 #[no_mangle]
 pub unsafe extern "C" fn vault_zeroize(ptr: *mut u8, len: u32) {
-    if ptr.is_null() || len == 0 {
-        return;
-    }
+    ffi_boundary(|| {
+        if ptr.is_null() || len == 0 {
+            return;
+        }
 
-    let slice = slice::from_raw_parts_mut(ptr, len as usize);
-    slice.zeroize();
+        let slice = slice::from_raw_parts_mut(ptr, len as usize);
+        slice.zeroize();
+    })
+}
+
+/// Zeroize a buffer in place, then read it back to confirm the wipe.
+///
+/// The readback uses volatile reads, so it is not optimized away and
+/// observes what is actually in memory.
+///
+/// # Safety
+///
+/// - `ptr` must be valid for `len` bytes
+/// - Memory must be writable
+///
+/// # Returns
+///
+/// 0 if every byte reads back as zero, `ERR_WIPE_FAILED` if any does not,
+/// or `ERR_INVALID_INPUT` for a null pointer or zero length
+#[no_mangle]
+pub unsafe extern "C" fn vault_zeroize_verified(ptr: *mut u8, len: u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if ptr.is_null() || len == 0 {
+            return ERR_INVALID_INPUT;
+        }
+
+        vault_zeroize(ptr, len);
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+
+        let mut residue = 0u8;
+        for i in 0..len as usize {
+            residue |= ptr::read_volatile(ptr.add(i));
+        }
+        if residue == 0 {
+            0
+        } else {
+            ERR_WIPE_FAILED
+        }
+    })
 }
 
 /// Fill a buffer with cryptographically secure random bytes.
@@ -283,18 +1085,58 @@ pub unsafe extern "C" fn vault_zeroize(ptr: *mut u8, len: u32) {
 ///
 /// # Returns
 ///
-/// 0 on success, -1 on error
+/// 0 on success, -1 on invalid arguments, or `ERR_RNG_FAILED` if the OS
+/// entropy source fails (the buffer must then not be used)
 #[no_mangle]
 pub unsafe extern "C" fn vault_random(out: *mut u8, len: u32) -> i32 {
-    if out.is_null() || len == 0 {
-        return ERR_INVALID_INPUT;
-    }
+    ffi_boundary(|| {
+        if out.is_null() || len == 0 {
+            return ERR_INVALID_INPUT;
+        }
 
-    let slice = slice::from_raw_parts_mut(out, len as usize);
-    match getrandom::getrandom(slice) {
-        Ok(_) => 0,
-        Err(_) => ERR_INVALID_INPUT,
-    }
+        let slice = slice::from_raw_parts_mut(out, len as usize);
+        match random_bytes(slice) {
+            Ok(_) => 0,
+            Err(code) => code,
+        }
+    })
+}
+
+/// Generate the random material for a new vault in one call.
+///
+/// Fills a 16-byte salt, a 32-byte content key and a 24-byte nonce from a
+/// single CSPRNG draw, which is split three ways and wiped.
+///
+/// # Safety
+///
+/// - `out_salt` must be writable for 16 bytes
+/// - `out_key` must be writable for 32 bytes
+/// - `out_nonce` must be writable for 24 bytes
+///
+/// # Returns
+///
+/// 0 on success, `ERR_INVALID_INPUT` if any pointer is null, or
+/// `ERR_RNG_FAILED` if the CSPRNG fails
+#[no_mangle]
+pub unsafe extern "C" fn vault_new_vault_material(out_salt: *mut u8, out_key: *mut u8, out_nonce: *mut u8) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_salt.is_null() || out_key.is_null() || out_nonce.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+
+        let mut material = Secret::new([0u8; SALT_SIZE + KEY_SIZE + NONCE_SIZE]);
+        if let Err(code) = random_bytes(material.as_mut()) {
+            return code;
+        }
+        let (salt, rest) = material.split_at(SALT_SIZE);
+        let (key, nonce) = rest.split_at(KEY_SIZE);
+
+        ptr::copy_nonoverlapping(salt.as_ptr(), out_salt, SALT_SIZE);
+        ptr::copy_nonoverlapping(key.as_ptr(), out_key, KEY_SIZE);
+        ptr::copy_nonoverlapping(nonce.as_ptr(), out_nonce, NONCE_SIZE);
+        0
+    })
 }
 
 // =============================================================================
@@ -307,6 +1149,7 @@ mod tests {
 
     #[test]
     fn test_derive_key() {
+        let _defaults = kdf::lock_default_params();
         let passphrase = b"test passphrase";
         let salt = [0u8; 16];
 
@@ -337,6 +1180,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_argon2_memory_wiped() {
+        let params = Params::new(64, 1, 1, Some(KEY_SIZE)).unwrap();
+        let mut blocks = vec![Block::default(); params.block_count()];
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let mut key = [0u8; KEY_SIZE];
+
+        argon2_hash(&argon2, b"passphrase", &[1u8; SALT_SIZE], &mut key, &mut blocks).unwrap();
+
+        assert_ne!(key, [0u8; KEY_SIZE]);
+        assert!(blocks.iter().all(|b| b.as_ref().iter().all(|&w| w == 0)));
+
+        // Failure paths wipe too (salt below the Argon2 minimum)
+        blocks.iter_mut().for_each(|b| b.as_mut().fill(0xAA));
+        assert_eq!(argon2_hash(&argon2, b"passphrase", &[1u8; 4], &mut key, &mut blocks), Err(ERR_KDF_FAILED));
+        assert!(blocks.iter().all(|b| b.as_ref().iter().all(|&w| w == 0)));
+    }
+
     #[test]
     fn test_seal_unseal_roundtrip() {
         let key = [0x42u8; 32];
@@ -347,6 +1208,7 @@ mod tests {
             let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
             assert_eq!(sealed.error, 0);
             assert!(sealed.len > plaintext.len() as u32); // nonce + tag overhead
+            assert_eq!(*sealed.data, FORMAT_XCHACHA);
 
             // Unseal
             let unsealed = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
@@ -361,6 +1223,110 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_no_secret_survives_free() {
+        use crate::test_alloc::{leaked_copies, NEEDLE_SIZE};
+
+        let key = [0x42u8; 32];
+        let wrong_key = [0x43u8; 32];
+        let secret = *b"needle: top secret plaintext";
+        let mut needle = [0u8; NEEDLE_SIZE];
+        needle.copy_from_slice(&secret[8..8 + NEEDLE_SIZE]);
+
+        unsafe {
+            let hits = leaked_copies(needle, || {
+                let sealed = vault_seal(key.as_ptr(), secret.as_ptr(), secret.len() as u32);
+                assert_eq!(sealed.error, 0);
+
+                // Success path, including the caller's free
+                let unsealed = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(unsealed.error, 0);
+                vault_free(unsealed.data, unsealed.len);
+
+                // Failure paths
+                let failed = vault_unseal(wrong_key.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(failed.error, ERR_DECRYPT_FAILED);
+                let short = vault_unseal(key.as_ptr(), sealed.data, 8);
+                assert_eq!(short.error, ERR_CORRUPT_DATA);
+
+                vault_free(sealed.data, sealed.len);
+            });
+            assert_eq!(hits, 0);
+
+            // Spare capacity on a successful result must not leak either
+            let mut padded = Vec::with_capacity(secret.len() * 4);
+            padded.extend_from_slice(&secret);
+            let hits = leaked_copies(needle, || {
+                let buffer = VaultBuffer::success(padded);
+                vault_free(buffer.data, buffer.len);
+            });
+            assert_eq!(hits, 0);
+
+            // Derived key: learn it once, then watch a second derivation
+            let salt = [3u8; SALT_SIZE];
+            let passphrase = b"derive twice";
+            let first = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            assert_eq!(first.error, 0);
+            needle.copy_from_slice(slice::from_raw_parts(first.data, NEEDLE_SIZE));
+            vault_free(first.data, first.len);
+
+            let hits = leaked_copies(needle, || {
+                let second = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+                assert_eq!(second.error, 0);
+                vault_free(second.data, second.len);
+            });
+            assert_eq!(hits, 0);
+        }
+    }
+
+    #[test]
+    fn test_seal_aad_binds_context() {
+        let key = [0x42u8; 32];
+        let plaintext = b"record body";
+        let record_a = b"wallet-1/record-7/v2";
+        let record_b = b"wallet-1/record-8/v2";
+
+        unsafe {
+            let seal = |aad: &[u8]| {
+                let result = vault_seal_aad(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32, aad.as_ptr(), aad.len() as u32);
+                assert_eq!(result.error, 0);
+                let blob = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+                vault_free(result.data, result.len);
+                blob
+            };
+            let unseal = |sealed: &[u8], aad: &[u8]| {
+                vault_unseal_aad(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, aad.as_ptr(), aad.len() as u32)
+            };
+
+            let sealed = seal(record_a);
+            let opened = unseal(&sealed, record_a);
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+
+            // Swapped into another record, or read without its context
+            assert_eq!(unseal(&sealed, record_b).error, ERR_DECRYPT_FAILED);
+            assert_eq!(unseal(&sealed, b"").error, ERR_DECRYPT_FAILED);
+            assert_eq!(vault_unseal(key.as_ptr(), sealed.as_ptr(), sealed.len() as u32).error, ERR_DECRYPT_FAILED);
+
+            // No AAD is plain vault_seal
+            let plain = seal(b"");
+            let opened = vault_unseal(key.as_ptr(), plain.as_ptr(), plain.len() as u32);
+            assert_eq!(opened.error, 0);
+            vault_free(opened.data, opened.len);
+            let classic = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            let opened = vault_unseal_aad(key.as_ptr(), 32, classic.data, classic.len, ptr::null(), 0);
+            assert_eq!(opened.error, 0);
+            vault_free(opened.data, opened.len);
+            vault_free(classic.data, classic.len);
+
+            let no_aad = vault_seal_aad(key.as_ptr(), 32, plaintext.as_ptr(), 11, ptr::null(), 4);
+            assert_eq!(no_aad.error, ERR_INVALID_INPUT);
+            let short_key = vault_unseal_aad(key.as_ptr(), 16, sealed.as_ptr(), sealed.len() as u32, record_a.as_ptr(), 20);
+            assert_eq!(short_key.error, ERR_BAD_KEY_SIZE);
+        }
+    }
+
     #[test]
     fn test_wrong_key_fails() {
         let key1 = [0x42u8; 32];
@@ -379,6 +1345,104 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unseal_safe_fuzz_regressions() {
+        // Shapes from fuzz/corpus/unseal
+        let key = [0x42u8; 32];
+        assert_eq!(unseal_safe(&key, &[FORMAT_XCHACHA; 21]), Err(VaultError::CorruptData));
+        assert_eq!(unseal_safe(&[], &[0u8; 49]), Err(VaultError::BadKeySize));
+        assert_eq!(unseal_safe(&key[..10], &[]), Err(VaultError::BadKeySize));
+        assert_eq!(unseal_safe(&key, &[]), Err(VaultError::CorruptData));
+
+        let mut forged = vec![FORMAT_XCHACHA];
+        forged.extend_from_slice(&[0u8; NONCE_SIZE]);
+        forged.extend_from_slice(b"payload");
+        forged.extend_from_slice(&[0u8; TAG_SIZE]);
+        assert_eq!(unseal_safe(&key, &forged), Err(VaultError::DecryptFailed));
+    }
+
+    #[test]
+    fn test_unseal_length_checked_against_format() {
+        let key = [0x42u8; 32];
+
+        // Long enough for the IETF layout but not for XChaCha's 24-byte nonce
+        let mut blob = vec![FORMAT_XCHACHA; FORMAT_HEADER_SIZE + 12 + TAG_SIZE + 4];
+        assert_eq!(unseal_safe(&key, &blob), Err(VaultError::CorruptData));
+        blob[0] = FORMAT_IETF;
+        assert_eq!(unseal_safe(&key, &blob), Err(VaultError::UnsupportedVersion));
+        blob.truncate(FORMAT_HEADER_SIZE + 12 + TAG_SIZE - 1);
+        assert_eq!(unseal_safe(&key, &blob), Err(VaultError::CorruptData));
+        blob[0] = 0x7F;
+        assert_eq!(unseal_safe(&key, &blob), Err(VaultError::UnsupportedVersion));
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), b"x".as_ptr(), 1);
+            assert_eq!(sealed.error, 0);
+            let short = vault_unseal(key.as_ptr(), sealed.data, (FORMAT_HEADER_SIZE + NONCE_SIZE) as u32);
+            assert_eq!(short.error, ERR_CORRUPT_DATA);
+
+            // Full blob, wrong key: the tag check fails
+            let wrong = [0x43u8; 32];
+            let opened = vault_unseal(wrong.as_ptr(), sealed.data, sealed.len);
+            assert_eq!(opened.error, ERR_DECRYPT_FAILED);
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+
+    #[cfg(all(feature = "large-alloc-tests", target_pointer_width = "64"))]
+    #[test]
+    fn test_success_rejects_oversized() {
+        // Zeroed allocations are lazily mapped; only the wipe touches pages
+        let oversized = vec![0u8; u32::MAX as usize + 1];
+        let result = VaultBuffer::success(oversized);
+        assert_eq!(result.error, ERR_INVALID_INPUT);
+        assert!(result.data.is_null());
+        assert_eq!(result.len, 0);
+    }
+
+    #[cfg(all(feature = "large-alloc-tests", target_pointer_width = "64"))]
+    #[test]
+    fn test_seal_rejects_plaintext_past_u32_blob() {
+        let key = [0x42u8; 32];
+        // Lazily mapped and never written; the check comes before any copy
+        let plaintext = vec![0u8; u32::MAX as usize];
+
+        for len in [MAX_SEAL_PLAINTEXT + 1, u32::MAX - 1, u32::MAX] {
+            let result = unsafe { vault_seal(key.as_ptr(), plaintext.as_ptr(), len) };
+            assert_eq!(result.error, ERR_INVALID_INPUT, "len {len}");
+            assert!(result.data.is_null());
+            assert_eq!(result.len, 0);
+        }
+    }
+
+    #[test]
+    fn test_allocation_failure_reported() {
+        use crate::test_alloc::with_failing_alloc;
+
+        let key = [0x42u8; 32];
+        let plaintext = [0x17u8; 4096];
+
+        unsafe {
+            // Output buffers are refused; the error comes back instead of an abort
+            let sealed = with_failing_alloc(4096, || vault_seal(key.as_ptr(), plaintext.as_ptr(), 4096));
+            assert_eq!(sealed.error, ERR_OUT_OF_MEMORY);
+            assert!(sealed.data.is_null());
+
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), 4096);
+            assert_eq!(sealed.error, 0);
+            let unsealed = with_failing_alloc(4096, || vault_unseal(key.as_ptr(), sealed.data, sealed.len));
+            assert_eq!(unsealed.error, ERR_OUT_OF_MEMORY);
+            vault_free(sealed.data, sealed.len);
+
+            // The exact-size copy `success` makes of a buffer with spare capacity
+            let mut spare = Vec::with_capacity(8192);
+            spare.extend_from_slice(&plaintext);
+            let result = with_failing_alloc(4096, || VaultBuffer::success(spare));
+            assert_eq!(result.error, ERR_OUT_OF_MEMORY);
+            assert!(result.data.is_null());
+        }
+    }
+
     #[test]
     fn test_random() {
         let mut buf1 = [0u8; 32];
@@ -392,4 +1456,126 @@ mod tests {
             assert_ne!(buf1, buf2);
         }
     }
+
+    #[test]
+    fn test_rng_failure_reported() {
+        let key = [0x42u8; 32];
+        let plaintext = b"never sealed under a zero nonce";
+        let mut out = [0u8; 24];
+
+        unsafe {
+            with_failing_rng(|| {
+                let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+                assert_eq!(sealed.error, ERR_RNG_FAILED);
+                assert!(sealed.data.is_null());
+                assert_eq!(sealed.len, 0);
+
+                assert_eq!(vault_random(out.as_mut_ptr(), 24), ERR_RNG_FAILED);
+                assert_eq!(vault_new_vault_material(out.as_mut_ptr(), [0u8; 32].as_mut_ptr(), out.as_mut_ptr()), ERR_RNG_FAILED);
+            });
+
+            // Argument errors stay distinct
+            assert_eq!(vault_random(ptr::null_mut(), 24), ERR_INVALID_INPUT);
+            assert_eq!(vault_random(out.as_mut_ptr(), 24), 0);
+        }
+    }
+
+    #[test]
+    fn test_empty_plaintext_roundtrip() {
+        let key = [0x42u8; 32];
+        let empty = [0u8; 0];
+
+        unsafe {
+            for plaintext in [empty.as_ptr(), ptr::null()] {
+                let sealed = vault_seal(key.as_ptr(), plaintext, 0);
+                assert_eq!(sealed.error, 0);
+                assert_eq!(sealed.len as usize, FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE);
+
+                // Empty success is null data with zero length
+                let unsealed = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(unsealed.error, 0);
+                assert!(unsealed.data.is_null());
+                assert_eq!(unsealed.len, 0);
+                vault_free(unsealed.data, unsealed.len);
+
+                vault_free(sealed.data, sealed.len);
+            }
+
+            let no_plaintext = vault_seal(key.as_ptr(), ptr::null(), 4);
+            assert_eq!(no_plaintext.error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_empty_plaintext_every_format() {
+        let key = [0x42u8; 64];
+        let device = b"device";
+        let (k, none) = (key.as_ptr(), ptr::null());
+        type Unseal<'a> = &'a dyn Fn(*const u8, u32) -> VaultBuffer;
+
+        unsafe {
+            // A null pointer with zero length is an empty plaintext in every format
+            let formats: [(VaultBuffer, Unseal); 9] = [
+                (vault_seal_bound(k, 32, none, 0, device.as_ptr(), 6), &|s, n| vault_unseal_bound(k, 32, s, n, device.as_ptr(), 6)),
+                (vault_seal_compressed(k, 32, none, 0, 6), &|s, n| vault_unseal_compressed(k, 32, s, n)),
+                (vault_seal_cose(k, 32, none, 0), &|s, n| vault_unseal_cose(k, 32, s, n)),
+                (vault_seal_expiring(k, 32, none, 0, i64::MAX), &|s, n| vault_unseal_expiring(k, 32, s, n, 0)),
+                (vault_seal_with_hint(k, 32, none, 0, none, 0), &|s, n| vault_unseal_with_hint(k, 32, s, n)),
+                (vault_seal_ietf(k, 32, none, 0), &|s, n| vault_unseal_ietf(k, 32, s, n)),
+                (vault_seal_siv(k, 64, none, 0, none, 0), &|s, n| vault_unseal_siv(k, 64, s, n, none, 0)),
+                (vault_seal_synthetic(k, 32, 1, none, 0), &|s, n| vault_unseal_synthetic(k, 32, s, n)),
+                (vault_timelock_seal(k, 32, none, 0, 1), &|s, n| vault_timelock_unseal(k, 32, s, n)),
+            ];
+            for (sealed, unseal) in formats {
+                assert_eq!(sealed.error, 0);
+                let unsealed = unseal(sealed.data, sealed.len);
+                assert_eq!((unsealed.error, unsealed.len), (0, 0));
+                // Truncation is corrupt data, not an invalid argument
+                assert_eq!(unseal(sealed.data, 1).error, ERR_CORRUPT_DATA);
+                vault_free(sealed.data, sealed.len);
+            }
+
+            assert_eq!(vault_seal_ietf(k, 32, none, 4).error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_zeroize_verified() {
+        let mut buf: Vec<u8> = (1..=64).collect();
+
+        unsafe {
+            assert_eq!(vault_zeroize_verified(buf.as_mut_ptr(), buf.len() as u32), 0);
+            assert!(buf.iter().all(|&b| b == 0));
+            assert_eq!(vault_zeroize_verified(ptr::null_mut(), 8), ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_new_vault_material() {
+        let draw = || {
+            let mut salt = [0u8; SALT_SIZE];
+            let mut key = [0u8; KEY_SIZE];
+            let mut nonce = [0u8; NONCE_SIZE];
+            let rc = unsafe { vault_new_vault_material(salt.as_mut_ptr(), key.as_mut_ptr(), nonce.as_mut_ptr()) };
+            assert_eq!(rc, 0);
+            (salt, key, nonce)
+        };
+
+        let (salt1, key1, nonce1) = draw();
+        let (salt2, key2, nonce2) = draw();
+        for region in [&salt1[..], &key1, &nonce1, &salt2, &key2, &nonce2] {
+            assert!(region.iter().any(|&b| b != 0));
+        }
+        // Regions are not copies of each other, within or across calls
+        assert_ne!(salt1, salt2);
+        assert_ne!(key1, key2);
+        assert_ne!(nonce1, nonce2);
+        assert_ne!(&key1[..SALT_SIZE], &salt1);
+        assert_ne!(&key1[..NONCE_SIZE], &nonce1);
+
+        let mut key = [0u8; KEY_SIZE];
+        let mut nonce = [0u8; NONCE_SIZE];
+        let rc = unsafe { vault_new_vault_material(ptr::null_mut(), key.as_mut_ptr(), nonce.as_mut_ptr()) };
+        assert_eq!(rc, ERR_INVALID_INPUT);
+    }
 }
@@ -0,0 +1,251 @@
+//! Hash-Chained Audit Log
+//!
+//! Each log entry is sealed with the previous entry's chain hash as
+//! associated data, and carries its own chain hash
+//! `BLAKE3(prev_hash || sealed entry)`. Deleting, reordering or splicing
+//! entries breaks the chain at the first affected entry.
+//!
+//! Truncating the tail leaves a valid shorter chain; callers that need to
+//! detect that should keep the latest chain hash somewhere the log's
+//! storage cannot rewrite, and compare it with the last entry's.
+//!
+//! ## Format
+//!
+//! One entry: `format (1, 0x08) || ciphertext_len (4, LE) || nonce (24) || ciphertext || tag (16) || chain hash (32)`
+//!
+//! A log is entries concatenated in order. The header and the previous
+//! chain hash are authenticated as associated data.
+
+use std::slice;
+
+use super::*;
+
+/// Size of a chain hash
+const LOG_HASH_SIZE: usize = 32;
+
+/// Size of an entry header: format || ciphertext_len
+const LOG_HEADER_SIZE: usize = FORMAT_HEADER_SIZE + 4;
+
+/// Associated data for an entry: its header, then the previous chain hash.
+fn log_aad(header: &[u8], prev_hash: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(header.len() + prev_hash.len());
+    aad.extend_from_slice(header);
+    aad.extend_from_slice(prev_hash);
+    aad
+}
+
+/// Chain hash following `prev_hash` for a sealed entry.
+fn chain_hash(prev_hash: &[u8], sealed_entry: &[u8]) -> [u8; LOG_HASH_SIZE] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(prev_hash);
+    hasher.update(sealed_entry);
+    *hasher.finalize().as_bytes()
+}
+
+/// Check one entry at the start of `log` and return (its length, its chain hash).
+fn verify_entry(key: &[u8], prev_hash: &[u8], log: &[u8]) -> VaultResult<(usize, [u8; LOG_HASH_SIZE])> {
+    if log.len() < LOG_HEADER_SIZE || log[0] != FORMAT_LOG {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let ciphertext_len = u32::from_le_bytes([log[1], log[2], log[3], log[4]]) as usize;
+    let sealed_len = LOG_HEADER_SIZE + NONCE_SIZE + ciphertext_len + TAG_SIZE;
+    if log.len() < sealed_len + LOG_HASH_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    let (sealed_entry, rest) = log.split_at(sealed_len);
+    let (header, body) = sealed_entry.split_at(LOG_HEADER_SIZE);
+    xchacha_open(key, body, &log_aad(header, prev_hash))?.zeroize();
+
+    let hash = chain_hash(prev_hash, sealed_entry);
+    if !ct_eq(&hash, &rest[..LOG_HASH_SIZE]) {
+        return Err(ERR_DECRYPT_FAILED);
+    }
+    Ok((sealed_len + LOG_HASH_SIZE, hash))
+}
+
+/// Seal a log entry chained to the previous one.
+///
+/// For the first entry, pass any fixed 32-byte value the verifier will also
+/// use (e.g. all zeros, or a log identifier hashed to 32 bytes).
+///
+/// # Format
+///
+/// Output: `format (1) || ciphertext_len (4) || nonce (24) || ciphertext || tag (16) || chain hash (32)`
+///
+/// The last 32 bytes are the `prev_hash` for the next entry.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `prev_hash` must point to exactly 32 bytes (`prev_hash_len` must be 32)
+/// - `entry` must be valid for `entry_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_log_seal(
+    key: *const u8,
+    key_len: u32,
+    prev_hash: *const u8,
+    prev_hash_len: u32,
+    entry: *const u8,
+    entry_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if prev_hash.is_null() || prev_hash_len as usize != LOG_HASH_SIZE || entry.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let prev_hash_slice = slice::from_raw_parts(prev_hash, LOG_HASH_SIZE);
+        let entry_slice = slice::from_raw_parts(entry, entry_len as usize);
+
+        let mut output = match output_buffer(LOG_HEADER_SIZE + NONCE_SIZE + entry_slice.len() + TAG_SIZE + LOG_HASH_SIZE) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.push(FORMAT_LOG);
+        output.extend_from_slice(&entry_len.to_le_bytes());
+
+        let sealed = match xchacha_seal(key_slice, entry_slice, &log_aad(&output, prev_hash_slice)) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&sealed);
+
+        let hash = chain_hash(prev_hash_slice, &output);
+        output.extend_from_slice(&hash);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Verify a whole log produced by `vault_log_seal`.
+///
+/// Every entry is authenticated against its predecessor's chain hash, and
+/// every stored chain hash is recomputed. Entries are not returned; use this
+/// to audit a log before trusting it.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `first_prev_hash` must point to the 32-byte value the first entry was sealed with
+/// - `entries` must be valid for `entries_len` bytes
+///
+/// # Returns
+///
+/// 0 if every entry verifies, otherwise `-(i + 1)` where `i` is the index of
+/// the first broken entry. Invalid arguments are reported as -1, the same as
+/// a broken first entry.
+#[no_mangle]
+pub unsafe extern "C" fn vault_log_verify(
+    key: *const u8,
+    key_len: u32,
+    first_prev_hash: *const u8,
+    first_prev_hash_len: u32,
+    entries: *const u8,
+    entries_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if first_prev_hash.is_null() || first_prev_hash_len as usize != LOG_HASH_SIZE || entries.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(_) => return ERR_INVALID_INPUT,
+        };
+        let mut log = slice::from_raw_parts(entries, entries_len as usize);
+
+        let mut prev_hash = [0u8; LOG_HASH_SIZE];
+        prev_hash.copy_from_slice(slice::from_raw_parts(first_prev_hash, LOG_HASH_SIZE));
+
+        let mut index: i32 = 0;
+        while !log.is_empty() {
+            match verify_entry(key_slice, &prev_hash, log) {
+                Ok((len, hash)) => {
+                    log = &log[len..];
+                    prev_hash = hash;
+                }
+                Err(_) => return -(index + 1),
+            }
+            index += 1;
+        }
+
+        0
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: [u8; LOG_HASH_SIZE] = [0u8; LOG_HASH_SIZE];
+
+    /// Seal `entries` as a chain, returning each sealed entry separately.
+    unsafe fn seal_chain(key: &[u8], entries: &[&[u8]]) -> Vec<Vec<u8>> {
+        let mut prev = GENESIS.to_vec();
+        entries
+            .iter()
+            .map(|entry| {
+                let result = vault_log_seal(key.as_ptr(), 32, prev.as_ptr(), 32, entry.as_ptr(), entry.len() as u32);
+                assert_eq!(result.error, 0);
+                let sealed = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+                vault_free(result.data, result.len);
+                prev = sealed[sealed.len() - LOG_HASH_SIZE..].to_vec();
+                sealed
+            })
+            .collect()
+    }
+
+    unsafe fn verify(key: &[u8], sealed: &[&Vec<u8>]) -> i32 {
+        let log: Vec<u8> = sealed.iter().flat_map(|s| s.iter().copied()).collect();
+        vault_log_verify(key.as_ptr(), 32, GENESIS.as_ptr(), 32, log.as_ptr(), log.len() as u32)
+    }
+
+    #[test]
+    fn test_log_valid_chain() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let chain = seal_chain(&key, &[b"login", b"export keys", b"logout"]);
+            assert_eq!(verify(&key, &[&chain[0], &chain[1], &chain[2]]), 0);
+            assert_eq!(verify(&key, &[]), 0);
+
+            let wrong = [0x43u8; 32];
+            assert_eq!(verify(&wrong, &[&chain[0], &chain[1], &chain[2]]), -1);
+        }
+    }
+
+    #[test]
+    fn test_log_reordered_fails() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let chain = seal_chain(&key, &[b"login", b"export keys", b"logout"]);
+            assert_eq!(verify(&key, &[&chain[0], &chain[2], &chain[1]]), -2);
+            assert_eq!(verify(&key, &[&chain[1], &chain[0], &chain[2]]), -1);
+        }
+    }
+
+    #[test]
+    fn test_log_deleted_entry_fails() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let chain = seal_chain(&key, &[b"login", b"export keys", b"logout"]);
+            assert_eq!(verify(&key, &[&chain[0], &chain[2]]), -2);
+
+            // A rewritten chain hash is caught too
+            let mut forged = chain[1].clone();
+            let last = forged.len() - 1;
+            forged[last] ^= 1;
+            assert_eq!(verify(&key, &[&chain[0], &forged, &chain[2]]), -2);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! BIP-39 Mnemonics
+//!
+//! Generating and checking English BIP-39 phrases natively, so the entropy
+//! and the words are only ever held in buffers that are wiped on free.
+//!
+//! A phrase of 12, 15, 18, 21 or 24 words encodes 128 to 256 bits of
+//! entropy followed by the first `entropy_bits / 32` bits of its SHA-256,
+//! 11 bits per word. Words are looked up by comparing against the whole
+//! list, so the time taken does not depend on which words were typed.
+//!
+//! ## Seed
+//!
+//! `vault_mnemonic_to_seed` is the BIP-39 seed step: PBKDF2-HMAC-SHA512,
+//! 2048 iterations, over the phrase with salt `"mnemonic" || passphrase`,
+//! both NFKD-normalized. The phrase is checked first and re-rendered with
+//! single spaces, so stray whitespace still gives the wallet's seed.
+
+use std::slice;
+
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256, Sha512};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::wordlist::ENGLISH;
+
+use super::*;
+
+/// Bits encoded by each word
+const MNEMONIC_WORD_BITS: usize = 11;
+
+/// Most words in a phrase (256 bits of entropy)
+const MNEMONIC_MAX_WORDS: usize = 24;
+
+/// Entropy and checksum of the longest phrase, in bytes
+const MNEMONIC_MAX_BITS_SIZE: usize = MNEMONIC_MAX_WORDS * MNEMONIC_WORD_BITS / 8;
+
+/// Longest word in the English list
+const MNEMONIC_MAX_WORD_LEN: usize = 8;
+
+/// BIP-39 seed size (one SHA-512 block of PBKDF2 output)
+const MNEMONIC_SEED_SIZE: usize = 64;
+
+/// PBKDF2 iterations fixed by BIP-39
+const MNEMONIC_PBKDF2_ROUNDS: u32 = 2048;
+
+/// Prefix of the PBKDF2 salt
+const MNEMONIC_SALT_PREFIX: &[u8] = b"mnemonic";
+
+/// Whether `strength_bits` is an entropy size BIP-39 defines.
+fn valid_strength(strength_bits: usize) -> bool {
+    (128..=256).contains(&strength_bits) && strength_bits.is_multiple_of(32)
+}
+
+/// Read the 11-bit group `index` from a big-endian bit string.
+fn word_index(bits: &[u8; MNEMONIC_MAX_BITS_SIZE + 1], index: usize) -> usize {
+    let start = index * MNEMONIC_WORD_BITS;
+    let wide = (bits[start / 8] as u32) << 16 | (bits[start / 8 + 1] as u32) << 8 | bits[start / 8 + 2] as u32;
+    ((wide >> (24 - MNEMONIC_WORD_BITS - start % 8)) & 0x7FF) as usize
+}
+
+/// The checksum bits of `entropy`, left-aligned in a byte.
+fn checksum(entropy: &[u8]) -> u8 {
+    let bits = entropy.len() / 4;
+    Sha256::digest(entropy)[0] & (0xFF00u16 >> bits) as u8
+}
+
+/// Render entropy (16 to 32 bytes, a multiple of 4) as a space-separated phrase.
+pub(crate) fn entropy_to_mnemonic(entropy: &[u8]) -> VaultResult<Vec<u8>> {
+    if !valid_strength(entropy.len() * 8) {
+        return Err(ERR_INVALID_INPUT);
+    }
+    // One spare byte keeps `word_index` in bounds for the last word
+    let mut bits = Secret::new([0u8; MNEMONIC_MAX_BITS_SIZE + 1]);
+    bits[..entropy.len()].copy_from_slice(entropy);
+    bits[entropy.len()] = checksum(entropy);
+
+    let word_count = entropy.len() * 8 * 33 / 32 / MNEMONIC_WORD_BITS;
+    let mut phrase = output_buffer(word_count * (MNEMONIC_MAX_WORD_LEN + 1))?;
+    for i in 0..word_count {
+        if i > 0 {
+            phrase.push(b' ');
+        }
+        phrase.extend_from_slice(ENGLISH[word_index(&bits, i)].as_bytes());
+    }
+    Ok(phrase)
+}
+
+/// Find a word in the list, comparing against every entry.
+fn lookup_word(word: &[u8]) -> Option<u16> {
+    let mut found = 0u16;
+    let mut index = 0u16;
+    for (i, candidate) in ENGLISH.iter().enumerate() {
+        let matched = ct_eq(candidate.as_bytes(), word) as u16;
+        found |= matched;
+        index |= 0u16.wrapping_sub(matched) & i as u16;
+    }
+    (found == 1).then_some(index)
+}
+
+/// Check a phrase and return its entropy.
+///
+/// Words may be separated by any run of ASCII whitespace.
+pub(crate) fn mnemonic_to_entropy(phrase: &[u8]) -> VaultResult<Zeroizing<Vec<u8>>> {
+    let mut bits = Secret::new([0u8; MNEMONIC_MAX_BITS_SIZE + 1]);
+    let mut word_count = 0usize;
+    for word in phrase.split(|c| c.is_ascii_whitespace()).filter(|w| !w.is_empty()) {
+        if word_count == MNEMONIC_MAX_WORDS {
+            return Err(error_detail(ERR_INVALID_INPUT, format_args!("more than {MNEMONIC_MAX_WORDS} words")));
+        }
+        // Positions are reported, never the words themselves
+        let index = lookup_word(word).ok_or_else(|| {
+            error_detail(ERR_INVALID_INPUT, format_args!("word {} is not in the BIP-39 English list", word_count + 1))
+        })?;
+        let start = word_count * MNEMONIC_WORD_BITS;
+        let shifted = (index as u32) << (24 - MNEMONIC_WORD_BITS - start % 8);
+        bits[start / 8] |= (shifted >> 16) as u8;
+        bits[start / 8 + 1] |= (shifted >> 8) as u8;
+        bits[start / 8 + 2] |= shifted as u8;
+        word_count += 1;
+    }
+
+    let entropy_bits = word_count * MNEMONIC_WORD_BITS * 32 / 33;
+    if !word_count.is_multiple_of(3) || !valid_strength(entropy_bits) {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("{word_count} words; expected 12, 15, 18, 21 or 24")));
+    }
+    let entropy = Zeroizing::new(bits[..entropy_bits / 8].to_vec());
+    if !ct_eq(&[checksum(&entropy)], &[bits[entropy_bits / 8]]) {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("checksum does not match")));
+    }
+    Ok(entropy)
+}
+
+/// NFKD form of a UTF-8 passphrase, allocated once at its final size.
+fn nfkd(passphrase: &[u8]) -> VaultResult<Zeroizing<Vec<u8>>> {
+    let text = std::str::from_utf8(passphrase).map_err(|_| ERR_INVALID_INPUT)?;
+    let len = text.nfkd().map(char::len_utf8).sum();
+    let mut normalized = Zeroizing::new(output_buffer(len)?);
+    let mut utf8 = [0u8; 4];
+    for c in text.nfkd() {
+        normalized.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+    }
+    Ok(normalized)
+}
+
+/// PBKDF2-HMAC-SHA512 for a single 64-byte output block.
+fn pbkdf2_sha512(password: &[u8], salt: &[&[u8]], rounds: u32) -> VaultResult<Secret<[u8; MNEMONIC_SEED_SIZE]>> {
+    let prf = Hmac::<Sha512>::new_from_slice(password).map_err(|_| ERR_INVALID_INPUT)?;
+
+    let mut mac = prf.clone();
+    for part in salt {
+        mac.update(part);
+    }
+    mac.update(&1u32.to_be_bytes());
+    let mut block = Secret::new(<[u8; MNEMONIC_SEED_SIZE]>::from(mac.finalize().into_bytes()));
+    let mut output = Secret::new(*block);
+    for _ in 1..rounds {
+        let mut mac = prf.clone();
+        mac.update(block.as_ref());
+        block = Secret::new(<[u8; MNEMONIC_SEED_SIZE]>::from(mac.finalize().into_bytes()));
+        for (out, byte) in output.iter_mut().zip(block.iter()) {
+            *out ^= byte;
+        }
+    }
+    Ok(output)
+}
+
+/// The BIP-39 seed of a checked phrase and a UTF-8 passphrase.
+fn mnemonic_seed(phrase: &[u8], passphrase: &[u8]) -> VaultResult<Secret<[u8; MNEMONIC_SEED_SIZE]>> {
+    let entropy = mnemonic_to_entropy(phrase)?;
+    // English words are already NFKD; rendering again normalizes spacing
+    let canonical = Zeroizing::new(entropy_to_mnemonic(&entropy)?);
+    let passphrase = nfkd(passphrase)?;
+    pbkdf2_sha512(&canonical, &[MNEMONIC_SALT_PREFIX, &passphrase], MNEMONIC_PBKDF2_ROUNDS)
+}
+
+/// Generate a new English BIP-39 phrase.
+///
+/// # Format
+///
+/// Output: lowercase words separated by single spaces, ASCII, no trailing
+/// NUL. 128 bits give 12 words, 256 bits give 24.
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the phrase, `ERR_INVALID_INPUT` if
+/// `strength_bits` is not 128, 160, 192, 224 or 256, or `ERR_RNG_FAILED`
+#[no_mangle]
+pub unsafe extern "C" fn vault_mnemonic_generate(strength_bits: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if !valid_strength(strength_bits as usize) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let mut entropy = Secret::new([0u8; MNEMONIC_MAX_BITS_SIZE]);
+        let entropy = &mut entropy[..strength_bits as usize / 8];
+        if let Err(code) = random_bytes(entropy) {
+            return VaultBuffer::error(code);
+        }
+        match entropy_to_mnemonic(entropy) {
+            Ok(phrase) => VaultBuffer::success(phrase),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Check an English BIP-39 phrase: its length, its words and its checksum.
+///
+/// Words must be lowercase and may be separated by any ASCII whitespace.
+/// `vault_last_error_message` says which check failed, giving the position
+/// of an unknown word but never the word.
+///
+/// # Safety
+///
+/// - `words` must be valid for `words_len` bytes
+///
+/// # Returns
+///
+/// 0 if the phrase is valid, or `ERR_INVALID_INPUT`
+#[no_mangle]
+pub unsafe extern "C" fn vault_mnemonic_validate(words: *const u8, words_len: u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if words.is_null() || words_len == 0 {
+            return ERR_INVALID_INPUT;
+        }
+        let words_slice = slice::from_raw_parts(words, words_len as usize);
+
+        match mnemonic_to_entropy(words_slice) {
+            Ok(_) => 0,
+            Err(code) => code,
+        }
+    })
+}
+
+/// Derive the 64-byte BIP-39 seed from a phrase and optional passphrase.
+///
+/// The phrase must pass `vault_mnemonic_validate`; a mistyped phrase is
+/// rejected rather than turned into the seed of an empty wallet. An empty
+/// passphrase is the BIP-39 default. See the module documentation for the
+/// derivation.
+///
+/// # Safety
+///
+/// - `mnemonic` must be valid for `mnemonic_len` bytes
+/// - `passphrase` must be valid UTF-8 for `passphrase_len` bytes (may be
+///   null when `passphrase_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 64-byte seed, or `ERR_INVALID_INPUT` for an
+/// invalid phrase, a null pointer, or a passphrase that is not UTF-8
+#[no_mangle]
+pub unsafe extern "C" fn vault_mnemonic_to_seed(
+    mnemonic: *const u8,
+    mnemonic_len: u32,
+    passphrase: *const u8,
+    passphrase_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if mnemonic.is_null() || mnemonic_len == 0 || (passphrase.is_null() && passphrase_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let mnemonic_slice = slice::from_raw_parts(mnemonic, mnemonic_len as usize);
+        let passphrase_slice: &[u8] = if passphrase_len == 0 { &[] } else { slice::from_raw_parts(passphrase, passphrase_len as usize) };
+
+        match mnemonic_seed(mnemonic_slice, passphrase_slice) {
+            Ok(seed) => VaultBuffer::success(seed.to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    /// Trezor reference vectors (entropy, phrase)
+    const VECTORS: &[(&str, &str)] = &[
+        ("00000000000000000000000000000000", "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"),
+        ("7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f", "legal winner thank year wave sausage worth useful legal winner thank yellow"),
+        (
+            "000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon agent",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote",
+        ),
+        (
+            "9e885d952ad362caeb4efe34a8e91bd2",
+            "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+        ),
+    ];
+
+    #[test]
+    fn test_mnemonic_vectors() {
+        for (entropy, phrase) in VECTORS {
+            let entropy = hex(entropy);
+            assert_eq!(entropy_to_mnemonic(&entropy).unwrap(), phrase.as_bytes());
+            assert_eq!(*mnemonic_to_entropy(phrase.as_bytes()).unwrap(), entropy);
+        }
+    }
+
+    #[test]
+    fn test_mnemonic_generate() {
+        for (strength, words) in [(128u32, 12usize), (160, 15), (192, 18), (224, 21), (256, 24)] {
+            unsafe {
+                let result = vault_mnemonic_generate(strength);
+                assert_eq!(result.error, 0);
+                let phrase = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+                vault_free(result.data, result.len);
+
+                assert_eq!(phrase.split(|&c| c == b' ').count(), words);
+                assert_eq!(vault_mnemonic_validate(phrase.as_ptr(), phrase.len() as u32), 0);
+            }
+        }
+        for strength in [0u32, 64, 127, 129, 288] {
+            assert_eq!(unsafe { vault_mnemonic_generate(strength) }.error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_mnemonic_to_seed() {
+        let seed = |phrase: &str, passphrase: &str| unsafe {
+            let result = vault_mnemonic_to_seed(phrase.as_ptr(), phrase.len() as u32, passphrase.as_ptr(), passphrase.len() as u32);
+            if result.error != 0 {
+                return Err(result.error);
+            }
+            let seed = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+            vault_free(result.data, result.len);
+            Ok(seed)
+        };
+
+        // Trezor reference vectors
+        let about = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let expected = hex("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+        assert_eq!(seed(about, "TREZOR").unwrap(), expected);
+        let yellow = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let expected = hex("2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607");
+        assert_eq!(seed(yellow, "TREZOR").unwrap(), expected);
+
+        // No passphrase, and spacing that is not canonical
+        let expected = hex("5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc19a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4");
+        assert_eq!(seed(about, "").unwrap(), expected);
+        assert_eq!(seed(&format!("  {}\n", about.replace(' ', "  ")), "").unwrap(), expected);
+        let null_passphrase = unsafe { vault_mnemonic_to_seed(about.as_ptr(), about.len() as u32, ptr::null(), 0) };
+        assert_eq!(null_passphrase.error, 0);
+        unsafe { vault_free(null_passphrase.data, null_passphrase.len) };
+
+        // The passphrase is NFKD-normalized: precomposed and combining forms agree
+        assert_eq!(seed(about, "caf\u{e9}").unwrap(), seed(about, "cafe\u{301}").unwrap());
+
+        assert_eq!(seed(&about.replace("about", "above"), ""), Err(ERR_INVALID_INPUT));
+        let not_utf8 = [0xFFu8, 0xFE];
+        let result = unsafe { vault_mnemonic_to_seed(about.as_ptr(), about.len() as u32, not_utf8.as_ptr(), 2) };
+        assert_eq!(result.error, ERR_INVALID_INPUT);
+    }
+
+    #[test]
+    fn test_mnemonic_validate_rejects() {
+        let validate = |phrase: &str| unsafe { vault_mnemonic_validate(phrase.as_ptr(), phrase.len() as u32) };
+
+        assert_eq!(validate(" abandon  abandon abandon abandon abandon abandon\tabandon abandon abandon abandon abandon about\n"), 0);
+        // Checksum
+        assert_eq!(validate("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon"), ERR_INVALID_INPUT);
+        // Unknown word, and a word that is only a prefix of one
+        assert_eq!(validate("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon aboutt"), ERR_INVALID_INPUT);
+        assert_eq!(validate("aban abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"), ERR_INVALID_INPUT);
+        assert_eq!(validate("Abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"), ERR_INVALID_INPUT);
+        // Word counts
+        assert_eq!(validate("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"), ERR_INVALID_INPUT);
+        assert_eq!(validate(&["zoo"; 25].join(" ")), ERR_INVALID_INPUT);
+        assert_eq!(unsafe { vault_mnemonic_validate(ptr::null(), 12) }, ERR_INVALID_INPUT);
+    }
+}
@@ -0,0 +1,384 @@
+//! Passphrase Sealing in One Call
+//!
+//! Creating a vault entry with `vault_derive_key` and `vault_seal` takes two
+//! FFI crossings and hands the key to the caller's heap in between, where it
+//! cannot be reliably wiped. `vault_derive_and_seal` and
+//! `vault_derive_and_unseal` derive the key with the default Argon2id costs,
+//! use it and wipe it before returning, so it never leaves native memory.
+//!
+//! ## Format
+//!
+//! With a caller-provided salt the output is a plain `vault_seal` blob. With
+//! a null salt a fresh one is generated and prepended:
+//!
+//! `salt (16) || format (1) || nonce (24) || ciphertext || tag (16)`
+//!
+//! `vault_seal_v2` and `vault_unseal_v2` instead write and read a vault
+//! record (see the `record` module): magic, version, salt, Argon2 costs and
+//! variant, then the sealed blob, whose format byte names the cipher. Old
+//! entries stay readable after the defaults change, and callers never parse
+//! a header themselves.
+
+use std::slice;
+
+use crate::kdf::{argon2id_default, default_argon2_params};
+use crate::record::{pack_record, unpack_record};
+
+use super::*;
+
+/// Derive a key from a passphrase and seal a plaintext under it.
+///
+/// # Format
+///
+/// Output: the `vault_seal` blob, prefixed with the generated salt (16
+/// bytes) when `salt` is null
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes, or be null to generate one
+/// - `plaintext` must be valid for `plaintext_len` bytes (may be null when
+///   `plaintext_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_and_seal(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || passphrase_len == 0 || (plaintext.is_null() && plaintext_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let mut output = Vec::new();
+        let mut generated = [0u8; SALT_SIZE];
+        let salt_slice = if salt.is_null() {
+            if let Err(code) = random_bytes(&mut generated) {
+                return VaultBuffer::error(code);
+            }
+            output.extend_from_slice(&generated);
+            &generated[..]
+        } else {
+            slice::from_raw_parts(salt, SALT_SIZE)
+        };
+
+        // The key is a `Secret` and is wiped as it drops
+        let sealed = argon2id_default(passphrase_slice, salt_slice).and_then(|key| seal_blob(key.as_ref(), plaintext_slice));
+        match sealed {
+            Ok(sealed) => {
+                output.extend_from_slice(&sealed);
+                VaultBuffer::success(output)
+            }
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Derive a key from a passphrase and unseal a blob with it.
+///
+/// The mirror of `vault_derive_and_seal`: pass the same salt, or null if
+/// the salt was generated and is at the start of `sealed`.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes, or be null if `sealed` starts
+///   with it
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, or `ERR_DECRYPT_FAILED` for a
+/// wrong passphrase
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_and_unseal(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || sealed.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+        let (salt_slice, blob) = if salt.is_null() {
+            if sealed_slice.len() < SALT_SIZE {
+                return VaultBuffer::error(ERR_INVALID_INPUT);
+            }
+            sealed_slice.split_at(SALT_SIZE)
+        } else {
+            (slice::from_raw_parts(salt, SALT_SIZE), sealed_slice)
+        };
+
+        match argon2id_default(passphrase_slice, salt_slice).and_then(|key| open_blob(key.as_ref(), blob)) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Derive a key under fresh salt and seal a plaintext as a vault record.
+///
+/// # Format
+///
+/// Output: a version 2 vault record (see `vault_record_pack`) holding the
+/// generated salt, the costs used and a `vault_seal` blob
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `params` must point to a valid `VaultKdfParams`, or be null for the
+///   current default Argon2id costs
+/// - `plaintext` must be valid for `plaintext_len` bytes (may be null when
+///   `plaintext_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the record, or `ERR_INVALID_INPUT` for a bad
+/// argument, variant or cost
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_v2(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    params: *const VaultKdfParams,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || passphrase_len == 0 || (plaintext.is_null() && plaintext_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let params = if params.is_null() {
+            let (m_cost, t_cost, p_cost) = default_argon2_params();
+            VaultKdfParams { m_cost, t_cost, p_cost, variant: ARGON2_VARIANT_ID }
+        } else {
+            *params
+        };
+        let algorithm = match argon2_algorithm(params.variant) {
+            Ok(a) => a,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if argon2_param_error(params.m_cost, params.t_cost, params.p_cost).is_some() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let mut salt = [0u8; SALT_SIZE];
+        if let Err(code) = random_bytes(&mut salt) {
+            return VaultBuffer::error(code);
+        }
+        let sealed = argon2_key(passphrase_slice, &salt, &[], params.m_cost, params.t_cost, params.p_cost, algorithm)
+            .and_then(|key| seal_blob(key.as_ref(), plaintext_slice))
+            .and_then(|sealed| pack_record(&salt, &params, &sealed));
+        match sealed {
+            Ok(record) => VaultBuffer::success(record),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Open a vault record from `vault_seal_v2` with its passphrase.
+///
+/// The salt and costs are read from the record, so records sealed under
+/// any defaults open the same way.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `record` must be valid for `record_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_DECRYPT_FAILED` for a wrong
+/// passphrase, `ERR_UNSUPPORTED_VERSION` for an unknown record version or
+/// cipher, or `ERR_CORRUPT_DATA` for a malformed record or costs Argon2
+/// rejects
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_v2(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    record: *const u8,
+    record_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || record.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let record_slice = slice::from_raw_parts(record, record_len as usize);
+
+        let (salt, params, sealed) = match unpack_record(record_slice) {
+            Ok(parts) => parts,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if argon2_param_error(params.m_cost, params.t_cost, params.p_cost).is_some() {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let algorithm = match argon2_algorithm(params.variant) {
+            Ok(a) => a,
+            Err(_) => return VaultBuffer::error(ERR_CORRUPT_DATA),
+        };
+
+        let opened = argon2_key(passphrase_slice, salt, &[], params.m_cost, params.t_cost, params.p_cost, algorithm)
+            .and_then(|key| open_blob(key.as_ref(), sealed));
+        match opened {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &[u8] = b"correct horse";
+
+    #[test]
+    fn test_derive_and_seal_roundtrip() {
+        let _defaults = crate::kdf::lock_default_params();
+        let plaintext = b"new vault entry";
+        let salt = [8u8; SALT_SIZE];
+
+        unsafe {
+            // Caller-provided salt: a plain vault_seal blob
+            let sealed = vault_derive_and_seal(PASSPHRASE.as_ptr(), 13, salt.as_ptr(), plaintext.as_ptr(), 15);
+            assert_eq!(sealed.error, 0);
+            assert_eq!(*sealed.data, FORMAT_XCHACHA);
+            let opened = vault_derive_and_unseal(PASSPHRASE.as_ptr(), 13, salt.as_ptr(), sealed.data, sealed.len);
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+            vault_free(sealed.data, sealed.len);
+
+            // Generated salt travels in front of the blob
+            let sealed = vault_derive_and_seal(PASSPHRASE.as_ptr(), 13, ptr::null(), plaintext.as_ptr(), 15);
+            assert_eq!(sealed.error, 0);
+            assert_eq!(sealed.len as usize, SALT_SIZE + FORMAT_HEADER_SIZE + NONCE_SIZE + 15 + TAG_SIZE);
+            let opened = vault_derive_and_unseal(PASSPHRASE.as_ptr(), 13, ptr::null(), sealed.data, sealed.len);
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+
+    #[test]
+    fn test_seal_v2_record_roundtrip() {
+        let _defaults = crate::kdf::lock_default_params();
+        let plaintext = b"self-describing";
+        let light = VaultKdfParams { m_cost: 8192, t_cost: 1, p_cost: 1, variant: ARGON2_VARIANT_ID };
+
+        unsafe {
+            let seal = |params: *const VaultKdfParams| {
+                let result = vault_seal_v2(PASSPHRASE.as_ptr(), 13, params, plaintext.as_ptr(), 15);
+                assert_eq!(result.error, 0);
+                let record = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+                vault_free(result.data, result.len);
+                record
+            };
+            let unseal = |passphrase: &[u8], record: &[u8]| {
+                vault_unseal_v2(passphrase.as_ptr(), passphrase.len() as u32, record.as_ptr(), record.len() as u32)
+            };
+
+            // The record carries its own salt and costs
+            let record = seal(&light);
+            let (salt, params, sealed) = unpack_record(&record).unwrap();
+            assert_eq!(params, light);
+            assert_eq!(sealed[0], FORMAT_XCHACHA);
+            let key = argon2id(PASSPHRASE, salt, 8192, 1, 1).unwrap();
+            assert_eq!(open_blob(key.as_ref(), sealed).unwrap(), plaintext);
+
+            // Opens under other defaults
+            assert_eq!(crate::kdf::vault_set_default_argon2_params(16384, 2, 1), 0);
+            let opened = unseal(PASSPHRASE, &record);
+            assert_eq!(opened.error, 0);
+            assert_eq!(slice::from_raw_parts(opened.data, opened.len as usize), plaintext);
+            vault_free(opened.data, opened.len);
+
+            // Null params take the defaults of the moment
+            let record = seal(ptr::null());
+            assert_eq!(unpack_record(&record).unwrap().1, VaultKdfParams { m_cost: 16384, t_cost: 2, p_cost: 1, variant: ARGON2_VARIANT_ID });
+            assert_eq!(crate::kdf::vault_set_default_argon2_params(ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST), 0);
+            let opened = unseal(PASSPHRASE, &record);
+            assert_eq!(opened.error, 0);
+            vault_free(opened.data, opened.len);
+
+            assert_eq!(unseal(b"incorrect horse", &record).error, ERR_DECRYPT_FAILED);
+        }
+    }
+
+    #[test]
+    fn test_seal_v2_rejects_bad_records() {
+        let _defaults = crate::kdf::lock_default_params();
+        let light = VaultKdfParams { m_cost: 8192, t_cost: 1, p_cost: 1, variant: ARGON2_VARIANT_ID };
+
+        unsafe {
+            let bad = VaultKdfParams { variant: 9, ..light };
+            assert_eq!(vault_seal_v2(PASSPHRASE.as_ptr(), 13, &bad, b"x".as_ptr(), 1).error, ERR_INVALID_INPUT);
+            let bad = VaultKdfParams { t_cost: 0, ..light };
+            assert_eq!(vault_seal_v2(PASSPHRASE.as_ptr(), 13, &bad, b"x".as_ptr(), 1).error, ERR_INVALID_INPUT);
+
+            let result = vault_seal_v2(PASSPHRASE.as_ptr(), 13, &light, b"x".as_ptr(), 1);
+            assert_eq!(result.error, 0);
+            let record = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+            vault_free(result.data, result.len);
+            let unseal = |record: &[u8]| vault_unseal_v2(PASSPHRASE.as_ptr(), 13, record.as_ptr(), record.len() as u32).error;
+
+            let mut bad_version = record.clone();
+            bad_version[4] = 9;
+            assert_eq!(unseal(&bad_version), ERR_UNSUPPORTED_VERSION);
+
+            // t_cost of 0, and an unknown variant
+            let t_at = 4 + 1 + SALT_SIZE + 4;
+            let mut bad_cost = record.clone();
+            bad_cost[t_at..t_at + 4].copy_from_slice(&0u32.to_le_bytes());
+            assert_eq!(unseal(&bad_cost), ERR_CORRUPT_DATA);
+            let mut bad_variant = record.clone();
+            bad_variant[t_at + 8..t_at + 12].copy_from_slice(&9u32.to_le_bytes());
+            assert_eq!(unseal(&bad_variant), ERR_CORRUPT_DATA);
+
+            assert_eq!(unseal(&record[..record.len() - 1]), ERR_CORRUPT_DATA);
+        }
+    }
+
+    #[test]
+    fn test_derive_and_unseal_wrong_passphrase() {
+        let _defaults = crate::kdf::lock_default_params();
+        let wrong = b"incorrect horse";
+
+        unsafe {
+            let sealed = vault_derive_and_seal(PASSPHRASE.as_ptr(), 13, ptr::null(), b"x".as_ptr(), 1);
+            assert_eq!(sealed.error, 0);
+            let opened = vault_derive_and_unseal(wrong.as_ptr(), wrong.len() as u32, ptr::null(), sealed.data, sealed.len);
+            assert_eq!(opened.error, ERR_DECRYPT_FAILED);
+            vault_free(sealed.data, sealed.len);
+
+            let result = vault_derive_and_unseal(PASSPHRASE.as_ptr(), 13, ptr::null(), b"short".as_ptr(), 5);
+            assert_eq!(result.error, ERR_INVALID_INPUT);
+        }
+    }
+}
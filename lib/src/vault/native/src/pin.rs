@@ -0,0 +1,391 @@
+//! PIN Quick-Unlock with Failed-Attempt Lockout
+//!
+//! A PIN has too little entropy to rely on Argon2 alone, so the wrapped key
+//! carries an attempt counter that native code enforces. The UI cannot skip
+//! the check: once the counter reaches `PIN_MAX_ATTEMPTS` no derivation runs.
+//!
+//! ## Format
+//!
+//! `version (1) || salt (16) || nonce (24) || ciphertext || tag (16) || counter (36)`
+//!
+//! where `counter = failures (4, LE) || mac (32)`.
+//!
+//! ## Counter Integrity
+//!
+//! The counter is MACed (keyed BLAKE3, over the rest of the blob and
+//! `failures`) under a 32-byte `counter_key` that the caller keeps in the
+//! platform keystore, never next to the blob. Every attempt that reaches the
+//! PIN check ratchets the key one way (`next = KDF(counter_key)`) and MACs
+//! the new counter under the new key, so:
+//!
+//! - editing `failures` is detected before any derivation runs
+//! - writing back an older counter trailer, even one seen verbatim, fails:
+//!   it was MACed under a key the keystore no longer holds
+//! - without the keystore key no valid trailer can be made at all
+//!
+//! The caller must persist both the new trailer and the new key before acting
+//! on the result. Losing either one leaves the blob locked (fall back to the
+//! passphrase), never unlocked.
+//!
+//! What this cannot stop is restoring an older copy of the blob *together
+//! with* the keystore entry, or an offline attack on the blob itself. The
+//! Argon2 cost governs the latter.
+
+use std::slice;
+
+use super::*;
+
+/// Wrapped PIN blob format version
+const PIN_VERSION: u8 = 1;
+
+/// Failed attempts allowed before the blob locks
+const PIN_MAX_ATTEMPTS: u32 = 5;
+
+/// Size of the counter trailer: failures (4) || mac (32)
+const PIN_COUNTER_SIZE: usize = 4 + 32;
+
+/// Size of the authenticated header: version || salt
+const PIN_HEADER_SIZE: usize = 1 + SALT_SIZE;
+
+const PIN_COUNTER_MAC_CONTEXT: &str = "vault_core 2025-01 pin counter mac";
+const PIN_COUNTER_RATCHET_CONTEXT: &str = "vault_core 2025-01 pin counter ratchet";
+
+/// MAC over the blob body and failure count under `counter_key`.
+fn counter_mac(counter_key: &[u8; KEY_SIZE], body: &[u8], failures: u32) -> [u8; 32] {
+    let mac_key = Zeroizing::new(blake3::derive_key(PIN_COUNTER_MAC_CONTEXT, counter_key));
+    let mut hasher = blake3::Hasher::new_keyed(&mac_key);
+    hasher.update(body);
+    hasher.update(&failures.to_le_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+/// The counter key that replaces `counter_key` after an attempt.
+fn ratchet(counter_key: &[u8; KEY_SIZE]) -> Secret<[u8; KEY_SIZE]> {
+    Secret::new(blake3::derive_key(PIN_COUNTER_RATCHET_CONTEXT, counter_key))
+}
+
+/// Encode the counter trailer.
+fn encode_counter(counter_key: &[u8; KEY_SIZE], body: &[u8], failures: u32) -> [u8; PIN_COUNTER_SIZE] {
+    let mut out = [0u8; PIN_COUNTER_SIZE];
+    out[..4].copy_from_slice(&failures.to_le_bytes());
+    out[4..].copy_from_slice(&counter_mac(counter_key, body, failures));
+    out
+}
+
+/// Wrap `key` under a PIN.
+///
+/// # Format
+///
+/// Output: see the module docs. Store the whole blob; `vault_pin_unwrap`
+/// hands back an updated counter trailer that replaces its last 36 bytes.
+///
+/// # Safety
+///
+/// - `pin` must be valid for `pin_len` bytes
+/// - `key` must be valid for `key_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `counter_key` must point to exactly 32 bytes (`counter_key_len` must be
+///   32): a random key the caller keeps in the platform keystore
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_pin_wrap(
+    pin: *const u8,
+    pin_len: u32,
+    key: *const u8,
+    key_len: u32,
+    salt: *const u8,
+    counter_key: *const u8,
+    counter_key_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if pin.is_null() || key.is_null() || salt.is_null() || pin_len == 0 || key_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let mut counter_key_copy = Secret::new([0u8; KEY_SIZE]);
+        match key_arg(counter_key, counter_key_len) {
+            Ok(k) => counter_key_copy.copy_from_slice(k),
+            Err(code) => return VaultBuffer::error(code),
+        }
+
+        let pin_slice = slice::from_raw_parts(pin, pin_len as usize);
+        let key_slice = slice::from_raw_parts(key, key_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+
+        let kek = match argon2id(pin_slice, salt_slice, ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut header = Vec::with_capacity(PIN_HEADER_SIZE);
+        header.push(PIN_VERSION);
+        header.extend_from_slice(salt_slice);
+
+        let sealed = match xchacha_seal(kek.as_ref(), key_slice, &header) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(header.len() + sealed.len() + PIN_COUNTER_SIZE) {
+            Ok(o) => o,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&sealed);
+        let counter = encode_counter(&counter_key_copy, &output, 0);
+        output.extend_from_slice(&counter);
+
+        VaultBuffer::success(output)
+    })
+}
+
+/// Unwrap a key wrapped with `vault_pin_wrap`, enforcing the attempt limit.
+///
+/// Every call that reaches the PIN check writes the updated counter trailer
+/// (`PIN_COUNTER_SIZE` = 36 bytes) to `counter_state` and replaces the 32
+/// bytes at `counter_key` with the next key. The caller must persist both,
+/// the trailer over the blob's last 36 bytes and the key back to the
+/// keystore, before acting on the result; otherwise failed attempts are not
+/// counted. A refused call (`ERR_LOCKED_OUT`) changes neither.
+///
+/// # Safety
+///
+/// - `pin` must be valid for `pin_len` bytes
+/// - `wrapped` must be valid for `wrapped_len` bytes
+/// - `counter_key` must be readable and writable for exactly 32 bytes
+///   (`counter_key_len` must be 32)
+/// - `counter_state` must be writable for `counter_len` bytes (at least 36)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the key, `ERR_DECRYPT_FAILED` for a wrong PIN, or
+/// `ERR_LOCKED_OUT` once the limit is reached or the counter fails to verify
+/// under `counter_key`
+#[no_mangle]
+pub unsafe extern "C" fn vault_pin_unwrap(
+    pin: *const u8,
+    pin_len: u32,
+    wrapped: *const u8,
+    wrapped_len: u32,
+    counter_key: *mut u8,
+    counter_key_len: u32,
+    counter_state: *mut u8,
+    counter_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = PIN_HEADER_SIZE + NONCE_SIZE + TAG_SIZE + PIN_COUNTER_SIZE;
+        if pin.is_null()
+            || wrapped.is_null()
+            || counter_state.is_null()
+            || pin_len == 0
+            || (wrapped_len as usize) < min_len
+            || (counter_len as usize) < PIN_COUNTER_SIZE
+        {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let mut current_key = Secret::new([0u8; KEY_SIZE]);
+        match key_arg(counter_key, counter_key_len) {
+            Ok(k) => current_key.copy_from_slice(k),
+            Err(code) => return VaultBuffer::error(code),
+        }
+
+        let pin_slice = slice::from_raw_parts(pin, pin_len as usize);
+        let wrapped_slice = slice::from_raw_parts(wrapped, wrapped_len as usize);
+        let counter_out = slice::from_raw_parts_mut(counter_state, PIN_COUNTER_SIZE);
+
+        let (body, counter) = wrapped_slice.split_at(wrapped_slice.len() - PIN_COUNTER_SIZE);
+        let (header, sealed) = body.split_at(PIN_HEADER_SIZE);
+        if header[0] != PIN_VERSION {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let salt_slice = &header[1..];
+
+        let mut failures_bytes = [0u8; 4];
+        failures_bytes.copy_from_slice(&counter[..4]);
+        let failures = u32::from_le_bytes(failures_bytes);
+
+        // Echo the current state so a refused attempt leaves the caller consistent
+        counter_out.copy_from_slice(counter);
+
+        // Verify the counter before doing any work with the PIN
+        if !ct_eq(&counter_mac(&current_key, body, failures), &counter[4..]) || failures >= PIN_MAX_ATTEMPTS {
+            return VaultBuffer::error(ERR_LOCKED_OUT);
+        }
+
+        let kek = match argon2id(pin_slice, salt_slice, ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let result = xchacha_open(kek.as_ref(), sealed, header);
+        let next_failures = match result {
+            Ok(_) => 0,
+            Err(ERR_DECRYPT_FAILED) => failures + 1,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let next_key = ratchet(&current_key);
+        counter_out.copy_from_slice(&encode_counter(&next_key, body, next_failures));
+        slice::from_raw_parts_mut(counter_key, KEY_SIZE).copy_from_slice(next_key.as_ref());
+
+        match result {
+            Ok(key) => VaultBuffer::success(key),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIN: &[u8] = b"4821";
+    const WRONG_PIN: &[u8] = b"0000";
+    const COUNTER_KEY: [u8; KEY_SIZE] = [0x3Cu8; KEY_SIZE];
+
+    unsafe fn wrap(key: &[u8]) -> Vec<u8> {
+        let salt = [7u8; SALT_SIZE];
+        let result = vault_pin_wrap(
+            PIN.as_ptr(),
+            PIN.len() as u32,
+            key.as_ptr(),
+            key.len() as u32,
+            salt.as_ptr(),
+            COUNTER_KEY.as_ptr(),
+            KEY_SIZE as u32,
+        );
+        assert_eq!(result.error, 0);
+        let blob = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        blob
+    }
+
+    /// Attempt an unwrap and persist the counter back into `blob` and the key into `counter_key`.
+    unsafe fn attempt(pin: &[u8], blob: &mut [u8], counter_key: &mut [u8; KEY_SIZE]) -> VaultBuffer {
+        let mut counter = [0u8; PIN_COUNTER_SIZE];
+        let result = vault_pin_unwrap(
+            pin.as_ptr(),
+            pin.len() as u32,
+            blob.as_ptr(),
+            blob.len() as u32,
+            counter_key.as_mut_ptr(),
+            KEY_SIZE as u32,
+            counter.as_mut_ptr(),
+            counter.len() as u32,
+        );
+        let tail = blob.len() - PIN_COUNTER_SIZE;
+        blob[tail..].copy_from_slice(&counter);
+        result
+    }
+
+    fn failures(blob: &[u8]) -> u32 {
+        let tail = blob.len() - PIN_COUNTER_SIZE;
+        u32::from_le_bytes(blob[tail..tail + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_pin_success_resets_counter() {
+        let key = [0x5Au8; 32];
+        let mut counter_key = COUNTER_KEY;
+
+        unsafe {
+            let mut blob = wrap(&key);
+
+            assert_eq!(attempt(WRONG_PIN, &mut blob, &mut counter_key).error, ERR_DECRYPT_FAILED);
+            assert_eq!(attempt(WRONG_PIN, &mut blob, &mut counter_key).error, ERR_DECRYPT_FAILED);
+            assert_eq!(failures(&blob), 2);
+            assert_ne!(counter_key, COUNTER_KEY);
+
+            let result = attempt(PIN, &mut blob, &mut counter_key);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), &key);
+            vault_free(result.data, result.len);
+            assert_eq!(failures(&blob), 0);
+
+            // The reset counter verifies again
+            let result = attempt(PIN, &mut blob, &mut counter_key);
+            assert_eq!(result.error, 0);
+            vault_free(result.data, result.len);
+        }
+    }
+
+    #[test]
+    fn test_pin_lockout() {
+        let key = [0x11u8; 32];
+        let mut counter_key = COUNTER_KEY;
+
+        unsafe {
+            let mut blob = wrap(&key);
+
+            for _ in 0..PIN_MAX_ATTEMPTS {
+                assert_eq!(attempt(WRONG_PIN, &mut blob, &mut counter_key).error, ERR_DECRYPT_FAILED);
+            }
+
+            // Even the correct PIN is refused now, and the refusal changes nothing
+            let locked_key = counter_key;
+            assert_eq!(attempt(PIN, &mut blob, &mut counter_key).error, ERR_LOCKED_OUT);
+            assert_eq!(failures(&blob), PIN_MAX_ATTEMPTS);
+            assert_eq!(counter_key, locked_key);
+        }
+    }
+
+    #[test]
+    fn test_pin_tampered_counter_rejected() {
+        let key = [0x22u8; 32];
+        let mut counter_key = COUNTER_KEY;
+
+        unsafe {
+            let mut blob = wrap(&key);
+            assert_eq!(attempt(WRONG_PIN, &mut blob, &mut counter_key).error, ERR_DECRYPT_FAILED);
+
+            // Reset the failure count without the matching MAC
+            let tail = blob.len() - PIN_COUNTER_SIZE;
+            blob[tail..tail + 4].copy_from_slice(&0u32.to_le_bytes());
+
+            assert_eq!(attempt(PIN, &mut blob, &mut counter_key).error, ERR_LOCKED_OUT);
+        }
+    }
+
+    #[test]
+    fn test_pin_replayed_counter_rejected() {
+        let key = [0x33u8; 32];
+        let mut counter_key = COUNTER_KEY;
+
+        unsafe {
+            let mut blob = wrap(&key);
+            let tail = blob.len() - PIN_COUNTER_SIZE;
+            let fresh: Vec<u8> = blob[tail..].to_vec();
+
+            for _ in 0..PIN_MAX_ATTEMPTS {
+                assert_eq!(attempt(WRONG_PIN, &mut blob, &mut counter_key).error, ERR_DECRYPT_FAILED);
+            }
+
+            // Writing back the failures=0 trailer seen earlier does not unlock it
+            blob[tail..].copy_from_slice(&fresh);
+            assert_eq!(attempt(PIN, &mut blob, &mut counter_key).error, ERR_LOCKED_OUT);
+
+            // Nor does a trailer from before the last attempt
+            let mut counter_key = COUNTER_KEY;
+            let mut blob = wrap(&key);
+            assert_eq!(attempt(WRONG_PIN, &mut blob, &mut counter_key).error, ERR_DECRYPT_FAILED);
+            let one_failure: Vec<u8> = blob[tail..].to_vec();
+            assert_eq!(attempt(WRONG_PIN, &mut blob, &mut counter_key).error, ERR_DECRYPT_FAILED);
+            blob[tail..].copy_from_slice(&one_failure);
+            assert_eq!(attempt(PIN, &mut blob, &mut counter_key).error, ERR_LOCKED_OUT);
+
+            // A wrong keystore key cannot verify any trailer
+            let mut other_key = [0x3Du8; KEY_SIZE];
+            let mut blob = wrap(&key);
+            assert_eq!(attempt(PIN, &mut blob, &mut other_key).error, ERR_LOCKED_OUT);
+            assert_eq!(
+                vault_pin_wrap(PIN.as_ptr(), 4, key.as_ptr(), 32, [7u8; SALT_SIZE].as_ptr(), COUNTER_KEY.as_ptr(), 16).error,
+                ERR_BAD_KEY_SIZE
+            );
+        }
+    }
+}
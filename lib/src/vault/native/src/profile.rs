@@ -0,0 +1,66 @@
+//! Build Profile
+//!
+//! Lets the wallet tell at runtime what kind of native library it loaded. A
+//! debug build is slow enough to throw off Argon2 timing targets and keeps
+//! assertions that can abort, so it should never ship; the app can check
+//! `vault_build_profile` at startup and warn or refuse.
+
+use super::*;
+
+/// Profile bit: built with debug assertions enabled
+const PROFILE_DEBUG_ASSERTIONS: u32 = 1 << 0;
+
+/// Profile bit: built with optimizations (opt-level other than 0)
+const PROFILE_OPTIMIZED: u32 = 1 << 1;
+
+/// Shift of the 16-bit target triple hash in the profile word
+const PROFILE_TARGET_SHIFT: u32 = 16;
+
+/// Describe how this library was built.
+///
+/// # Format
+///
+/// | Bits | Meaning |
+/// |------|---------|
+/// | 0 | Debug assertions enabled |
+/// | 1 | Optimized (opt-level > 0) |
+/// | 2-15 | Reserved (zero) |
+/// | 16-31 | First two bytes of BLAKE3(target triple), big-endian |
+///
+/// The target hash only distinguishes builds; compare it against the value
+/// from a known release library rather than decoding it.
+#[no_mangle]
+pub extern "C" fn vault_build_profile() -> u32 {
+    ffi_boundary(|| {
+        let mut profile = 0;
+        if cfg!(debug_assertions) {
+            profile |= PROFILE_DEBUG_ASSERTIONS;
+        }
+        if env!("VAULT_BUILD_OPT_LEVEL") != "0" {
+            profile |= PROFILE_OPTIMIZED;
+        }
+
+        let target = blake3::hash(env!("VAULT_BUILD_TARGET").as_bytes());
+        let target_bits = u16::from_be_bytes([target.as_bytes()[0], target.as_bytes()[1]]) as u32;
+        profile | (target_bits << PROFILE_TARGET_SHIFT)
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_profile() {
+        let profile = vault_build_profile();
+
+        assert_eq!(profile & PROFILE_DEBUG_ASSERTIONS != 0, cfg!(debug_assertions));
+        assert_eq!(profile & PROFILE_OPTIMIZED != 0, env!("VAULT_BUILD_OPT_LEVEL") != "0");
+        assert_eq!(profile & 0xFFFC, 0);
+        assert_eq!(profile, vault_build_profile());
+    }
+}
@@ -0,0 +1,287 @@
+//! Vault Records
+//!
+//! One canonical, self-describing container for everything needed to open a
+//! vault: the Argon2 salt and cost parameters, and the sealed blob. Defining
+//! it here means every platform reads and writes exactly the same bytes.
+//!
+//! ## Format (version 2)
+//!
+//! `magic (4, "VLTR") || version (1) || salt (16) || m_cost (4) || t_cost (4) || p_cost (4) || variant (4) || sealed_len (4) || sealed`
+//!
+//! All integers are little-endian. The record must end exactly where
+//! `sealed_len` says it does.
+//!
+//! Version 1 records have no `variant` field and are read as Argon2id.
+//!
+//! The sealed blob's own format byte identifies the cipher. To build and
+//! open records from a passphrase in one call, see `vault_seal_v2`.
+
+use std::slice;
+
+use super::*;
+
+/// Record magic
+const RECORD_MAGIC: [u8; 4] = *b"VLTR";
+
+/// Current record version
+const RECORD_VERSION: u8 = 2;
+
+/// Version 1: no variant field, always Argon2id
+const RECORD_VERSION_1: u8 = 1;
+
+/// Size of everything before the sealed blob
+const RECORD_HEADER_SIZE: usize = 4 + 1 + SALT_SIZE + 4 * 4 + 4;
+
+/// Header size of a version 1 record
+const RECORD_V1_HEADER_SIZE: usize = RECORD_HEADER_SIZE - 4;
+
+/// Read a little-endian `u32` at `offset`.
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0u8; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
+
+/// Encode a record.
+pub(crate) fn pack_record(salt: &[u8], params: &VaultKdfParams, sealed: &[u8]) -> VaultResult<Vec<u8>> {
+    let mut output = output_buffer(RECORD_HEADER_SIZE + sealed.len())?;
+    output.extend_from_slice(&RECORD_MAGIC);
+    output.push(RECORD_VERSION);
+    output.extend_from_slice(salt);
+    output.extend_from_slice(&params.m_cost.to_le_bytes());
+    output.extend_from_slice(&params.t_cost.to_le_bytes());
+    output.extend_from_slice(&params.p_cost.to_le_bytes());
+    output.extend_from_slice(&params.variant.to_le_bytes());
+    output.extend_from_slice(&(sealed.len() as u32).to_le_bytes());
+    output.extend_from_slice(sealed);
+    Ok(output)
+}
+
+/// Decode a record into `(salt, params, sealed)`, borrowing from `record`.
+pub(crate) fn unpack_record(record: &[u8]) -> VaultResult<(&[u8], VaultKdfParams, &[u8])> {
+    if record.len() < RECORD_MAGIC.len() + 1 || record[..4] != RECORD_MAGIC {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let header_size = match record[4] {
+        RECORD_VERSION => RECORD_HEADER_SIZE,
+        RECORD_VERSION_1 => RECORD_V1_HEADER_SIZE,
+        _ => return Err(ERR_UNSUPPORTED_VERSION),
+    };
+    if record.len() < header_size {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    let salt = &record[5..5 + SALT_SIZE];
+    let mut offset = 5 + SALT_SIZE;
+    let mut params = VaultKdfParams {
+        m_cost: read_u32(record, offset),
+        t_cost: read_u32(record, offset + 4),
+        p_cost: read_u32(record, offset + 8),
+        variant: ARGON2_VARIANT_ID,
+    };
+    offset += 12;
+    if record[4] == RECORD_VERSION {
+        params.variant = read_u32(record, offset);
+        offset += 4;
+    }
+    let sealed_len = read_u32(record, offset) as usize;
+
+    let sealed = &record[header_size..];
+    if sealed.len() != sealed_len {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    Ok((salt, params, sealed))
+}
+
+/// Pack a salt, KDF parameters and sealed blob into a vault record.
+///
+/// # Format
+///
+/// Output: see the module docs.
+///
+/// # Safety
+///
+/// - `salt` must point to exactly 16 bytes
+/// - `params` must point to a valid `VaultKdfParams`
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_record_pack(
+    salt: *const u8,
+    params: *const VaultKdfParams,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if salt.is_null() || params.is_null() || sealed.is_null() || sealed_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let params = &*params;
+        if Params::new(params.m_cost, params.t_cost, params.p_cost, Some(KEY_SIZE)).is_err()
+            || argon2_algorithm(params.variant).is_err()
+        {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        match pack_record(salt_slice, params, sealed_slice) {
+            Ok(record) => VaultBuffer::success(record),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Parse a vault record produced by `vault_record_pack`.
+///
+/// The sealed blob is not copied: `out_sealed_ptr` is set to point inside
+/// `record` and is only valid while `record` is.
+///
+/// # Safety
+///
+/// - `record` must be valid for `record_len` bytes
+/// - `out_salt` must be writable for 16 bytes
+/// - `out_params` must be writable for one `VaultKdfParams`
+/// - `out_sealed_ptr` and `out_sealed_len` must be writable
+///
+/// # Returns
+///
+/// 0 on success, `ERR_UNSUPPORTED_VERSION` for an unknown record version, or
+/// `ERR_CORRUPT_DATA` for a bad magic or inconsistent lengths
+#[no_mangle]
+pub unsafe extern "C" fn vault_record_unpack(
+    record: *const u8,
+    record_len: u32,
+    out_salt: *mut u8,
+    out_params: *mut VaultKdfParams,
+    out_sealed_ptr: *mut *const u8,
+    out_sealed_len: *mut u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if record.is_null()
+            || out_salt.is_null()
+            || out_params.is_null()
+            || out_sealed_ptr.is_null()
+            || out_sealed_len.is_null()
+        {
+            return ERR_INVALID_INPUT;
+        }
+        let record_slice = slice::from_raw_parts(record, record_len as usize);
+
+        match unpack_record(record_slice) {
+            Ok((salt, params, sealed)) => {
+                ptr::copy_nonoverlapping(salt.as_ptr(), out_salt, SALT_SIZE);
+                *out_params = params;
+                *out_sealed_ptr = sealed.as_ptr();
+                *out_sealed_len = sealed.len() as u32;
+                0
+            }
+            Err(code) => code,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn pack(salt: &[u8; SALT_SIZE], params: &VaultKdfParams, sealed: &[u8]) -> Vec<u8> {
+        let result = vault_record_pack(salt.as_ptr(), params, sealed.as_ptr(), sealed.len() as u32);
+        assert_eq!(result.error, 0);
+        let record = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        record
+    }
+
+    unsafe fn unpack(record: &[u8]) -> (i32, [u8; SALT_SIZE], VaultKdfParams, Vec<u8>) {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut params = VaultKdfParams { m_cost: 0, t_cost: 0, p_cost: 0, variant: 0 };
+        let mut sealed_ptr = ptr::null();
+        let mut sealed_len = 0u32;
+        let rc = vault_record_unpack(
+            record.as_ptr(),
+            record.len() as u32,
+            salt.as_mut_ptr(),
+            &mut params,
+            &mut sealed_ptr,
+            &mut sealed_len,
+        );
+        let sealed = if rc == 0 {
+            slice::from_raw_parts(sealed_ptr, sealed_len as usize).to_vec()
+        } else {
+            Vec::new()
+        };
+        (rc, salt, params, sealed)
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let salt = [9u8; SALT_SIZE];
+        let params = VaultKdfParams { m_cost: 19456, t_cost: 2, p_cost: 1, variant: ARGON2_VARIANT_I };
+        let sealed = b"\x01 pretend this is a sealed blob";
+
+        unsafe {
+            let record = pack(&salt, &params, sealed);
+            assert_eq!(&record[..4], b"VLTR");
+            assert_eq!(record.len(), RECORD_HEADER_SIZE + sealed.len());
+
+            let (rc, out_salt, out_params, out_sealed) = unpack(&record);
+            assert_eq!(rc, 0);
+            assert_eq!(out_salt, salt);
+            assert_eq!(out_params, params);
+            assert_eq!(out_sealed, sealed);
+        }
+    }
+
+    #[test]
+    fn test_record_v1_reads_as_argon2id() {
+        let salt = [9u8; SALT_SIZE];
+        let sealed = b"\x01 legacy";
+
+        let mut record = Vec::new();
+        record.extend_from_slice(&RECORD_MAGIC);
+        record.push(RECORD_VERSION_1);
+        record.extend_from_slice(&salt);
+        for word in [ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST, sealed.len() as u32] {
+            record.extend_from_slice(&word.to_le_bytes());
+        }
+        record.extend_from_slice(sealed);
+
+        unsafe {
+            let (rc, out_salt, out_params, out_sealed) = unpack(&record);
+            assert_eq!(rc, 0);
+            assert_eq!(out_salt, salt);
+            assert_eq!(out_params, VaultKdfParams::default());
+            assert_eq!(out_sealed, sealed);
+        }
+    }
+
+    #[test]
+    fn test_record_corrupted_header_rejected() {
+        let salt = [9u8; SALT_SIZE];
+        let params = VaultKdfParams::default();
+        let sealed = b"\x01 sealed";
+
+        unsafe {
+            let record = pack(&salt, &params, sealed);
+
+            let mut bad_magic = record.clone();
+            bad_magic[0] ^= 0xFF;
+            assert_eq!(unpack(&bad_magic).0, ERR_CORRUPT_DATA);
+
+            let mut bad_version = record.clone();
+            bad_version[4] = RECORD_VERSION + 1;
+            assert_eq!(unpack(&bad_version).0, ERR_UNSUPPORTED_VERSION);
+
+            assert_eq!(unpack(&record[..record.len() - 1]).0, ERR_CORRUPT_DATA);
+            assert_eq!(unpack(&record[..RECORD_HEADER_SIZE - 1]).0, ERR_CORRUPT_DATA);
+        }
+    }
+}
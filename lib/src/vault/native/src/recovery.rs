@@ -0,0 +1,252 @@
+//! Recovery Keys
+//!
+//! A shorter alternative to a BIP39 phrase: 160 bits of entropy written as
+//! 32 Crockford base32 characters (see the `base32` module) plus one check
+//! character, 33 in all. The check is verified before the key is used, so a
+//! mistyped character is reported instead of silently deriving the wrong
+//! seed.
+//!
+//! ## Check Character
+//!
+//! Crockford's check symbol: the entropy read as a big-endian integer,
+//! modulo 37, written with the base32 alphabet extended by `* ~ $ = U` for
+//! the values 32 to 36. Since 37 is prime, any single wrong character
+//! changes the value and is caught.
+//!
+//! ## Seed
+//!
+//! `seed = BLAKE3-derive_key("vault_core 2025-01 recovery key seed", entropy)`
+
+use std::slice;
+
+use crate::base32::{base32_decode, base32_encode, decode_symbol, encode_symbol, range_mask};
+
+use super::*;
+
+/// Entropy in a recovery key, in bytes
+const RECOVERY_ENTROPY_SIZE: usize = 20;
+
+/// Modulus of the check character
+const RECOVERY_CHECK_MODULUS: u16 = 37;
+
+/// Check characters for the values 32 to 36
+const RECOVERY_CHECK_SYMBOLS: [u8; 5] = *b"*~$=U";
+
+const RECOVERY_SEED_CONTEXT: &str = "vault_core 2025-01 recovery key seed";
+
+/// The entropy as a big-endian integer, modulo 37.
+fn check_value(entropy: &[u8]) -> u8 {
+    let mut rem = 0u16;
+    for &byte in entropy {
+        rem = (rem << 8 | byte as u16) % RECOVERY_CHECK_MODULUS;
+    }
+    rem as u8
+}
+
+/// Map a check value (0 to 36) to its character, without branching on it.
+fn encode_check(v: u8) -> u8 {
+    let mut c = encode_symbol(v & 0x1F) & range_mask(v, 0, 31);
+    for (i, &symbol) in RECOVERY_CHECK_SYMBOLS.iter().enumerate() {
+        let value = 32 + i as u8;
+        c |= symbol & range_mask(v, value, value);
+    }
+    c
+}
+
+/// Map a check character to its value, or `None` if it is not one.
+fn decode_check(c: u8) -> Option<u8> {
+    let upper = c - (0x20 & range_mask(c, b'a', b'z'));
+    let mut value = 0u8;
+    let mut valid = 0u8;
+    for (i, &symbol) in RECOVERY_CHECK_SYMBOLS.iter().enumerate() {
+        let mask = range_mask(upper, symbol, symbol);
+        value |= mask & (32 + i as u8);
+        valid |= mask;
+    }
+    decode_symbol(c).or((valid != 0).then_some(value))
+}
+
+/// Verify a typed recovery key and return its entropy.
+fn parse_recovery_key(text: &[u8]) -> VaultResult<Zeroizing<Vec<u8>>> {
+    // The check character is the last one that is not a separator
+    let check_at = text.iter().rposition(|&c| c != b' ' && c != b'-').ok_or(ERR_INVALID_INPUT)?;
+    let check = decode_check(text[check_at]).ok_or(ERR_INVALID_INPUT)?;
+
+    let entropy = Zeroizing::new(base32_decode(&text[..check_at])?);
+    if entropy.len() != RECOVERY_ENTROPY_SIZE || !ct_eq(&[check_value(&entropy)], &[check]) {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(entropy)
+}
+
+/// Generate a new recovery key.
+///
+/// # Format
+///
+/// Output: 33 uppercase ASCII characters, 32 of Crockford base32 entropy and
+/// one check character (see the module documentation)
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the recovery key, or `ERR_RNG_FAILED`
+#[no_mangle]
+pub unsafe extern "C" fn vault_recovery_key_generate() -> VaultBuffer {
+    ffi_boundary(|| {
+        let mut entropy = Secret::new([0u8; RECOVERY_ENTROPY_SIZE]);
+        if let Err(code) = random_bytes(entropy.as_mut()) {
+            return VaultBuffer::error(code);
+        }
+
+        let mut text = match base32_encode(entropy.as_ref()) {
+            Ok(t) => t,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        text.push(encode_check(check_value(entropy.as_ref())));
+        VaultBuffer::success(text)
+    })
+}
+
+/// Check a typed recovery key for transcription errors.
+///
+/// Accepts what the base32 decoder accepts (lowercase, spaces, hyphens,
+/// `O`/`I`/`L` for `0`/`1`/`1`).
+///
+/// # Safety
+///
+/// - `text` must be valid for `len` bytes
+///
+/// # Returns
+///
+/// 0 if the key is well-formed and its check character matches, or
+/// `ERR_INVALID_INPUT`
+#[no_mangle]
+pub unsafe extern "C" fn vault_recovery_key_validate(text: *const u8, len: u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if text.is_null() || len == 0 {
+            return ERR_INVALID_INPUT;
+        }
+        let text_slice = slice::from_raw_parts(text, len as usize);
+
+        match parse_recovery_key(text_slice) {
+            Ok(_) => 0,
+            Err(code) => code,
+        }
+    })
+}
+
+/// Derive a 32-byte seed from a recovery key, after validating it.
+///
+/// # Safety
+///
+/// - `text` must be valid for `len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the seed, or `ERR_INVALID_INPUT` if the key does
+/// not validate
+#[no_mangle]
+pub unsafe extern "C" fn vault_recovery_key_to_seed(text: *const u8, len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if text.is_null() || len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let text_slice = slice::from_raw_parts(text, len as usize);
+
+        let entropy = match parse_recovery_key(text_slice) {
+            Ok(e) => e,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let seed = Secret::new(blake3::derive_key(RECOVERY_SEED_CONTEXT, &entropy));
+        VaultBuffer::success(seed.to_vec())
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn generate() -> Vec<u8> {
+        let key = vault_recovery_key_generate();
+        assert_eq!(key.error, 0);
+        let text = slice::from_raw_parts(key.data, key.len as usize).to_vec();
+        vault_free(key.data, key.len);
+        text
+    }
+
+    unsafe fn seed(text: &[u8]) -> Result<Vec<u8>, i32> {
+        let result = vault_recovery_key_to_seed(text.as_ptr(), text.len() as u32);
+        if result.error != 0 {
+            return Err(result.error);
+        }
+        let seed = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        Ok(seed)
+    }
+
+    #[test]
+    fn test_recovery_key_validates() {
+        unsafe {
+            let text = generate();
+            assert_eq!(text.len(), 33);
+            assert_eq!(vault_recovery_key_validate(text.as_ptr(), 33), 0);
+
+            // Typed back lowercase in hyphenated groups
+            let typed: Vec<u8> = text.chunks(4).collect::<Vec<_>>().join(&b'-').to_ascii_lowercase();
+            assert_eq!(vault_recovery_key_validate(typed.as_ptr(), typed.len() as u32), 0);
+
+            // Missing the check character
+            assert_eq!(vault_recovery_key_validate(text.as_ptr(), 32), ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_recovery_key_typo_fails() {
+        let alphabet = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+        unsafe {
+            let text = generate();
+            for i in 0..text.len() {
+                let mut typo = text.clone();
+                let at = alphabet.iter().position(|&c| c == typo[i]).unwrap_or(0);
+                typo[i] = alphabet[(at + 1) % alphabet.len()];
+                assert_eq!(vault_recovery_key_validate(typo.as_ptr(), 33), ERR_INVALID_INPUT, "position {i}");
+                assert_eq!(seed(&typo), Err(ERR_INVALID_INPUT));
+            }
+        }
+    }
+
+    #[test]
+    fn test_recovery_key_seed_deterministic() {
+        // Entropy 0..20 has check value 24, written 'R'
+        let entropy: Vec<u8> = (0..20).collect();
+        assert_eq!(check_value(&entropy), 24);
+        let mut text = base32_encode(&entropy).unwrap();
+        text.push(encode_check(24));
+        assert_eq!(text.last(), Some(&b'R'));
+
+        unsafe {
+            let a = seed(&text).unwrap();
+            assert_eq!(a.len(), KEY_SIZE);
+            assert_eq!(seed(&text).unwrap(), a);
+            assert_eq!(a, blake3::derive_key(RECOVERY_SEED_CONTEXT, &entropy));
+            assert_ne!(seed(&generate()).unwrap(), a);
+        }
+
+        for v in 0..37 {
+            assert_eq!(decode_check(encode_check(v)), Some(v));
+        }
+        assert_eq!(encode_check(34), b'$');
+        assert_eq!(decode_check(b'u'), Some(36));
+    }
+}
@@ -0,0 +1,101 @@
+//! Nonce Refresh
+//!
+//! Re-seals a stored blob under the same key with a fresh random nonce,
+//! for rotating ciphertexts without a key change. The plaintext only ever
+//! exists in wiped native memory.
+
+use std::slice;
+
+use super::*;
+
+/// Re-seal a `vault_seal` blob under the same key with a new nonce.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the new blob, or `ERR_DECRYPT_FAILED` if `key`
+/// does not open `sealed`
+#[no_mangle]
+pub unsafe extern "C" fn vault_refresh_nonce(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let plaintext = match open_blob(key_slice, sealed_slice) {
+            Ok(p) => Zeroizing::new(p),
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        match seal_blob(key_slice, &plaintext) {
+            Ok(refreshed) => VaultBuffer::success(refreshed),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refresh_nonce() {
+        let key = [0x42u8; 32];
+        let plaintext = b"long-lived entry";
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            let original = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+
+            let refreshed = vault_refresh_nonce(key.as_ptr(), 32, sealed.data, sealed.len);
+            assert_eq!(refreshed.error, 0);
+            let renewed = slice::from_raw_parts(refreshed.data, refreshed.len as usize).to_vec();
+
+            let nonce = FORMAT_HEADER_SIZE..FORMAT_HEADER_SIZE + NONCE_SIZE;
+            assert_ne!(original[nonce.clone()], renewed[nonce]);
+
+            let unsealed = vault_unseal(key.as_ptr(), refreshed.data, refreshed.len);
+            assert_eq!(unsealed.error, 0);
+            assert_eq!(slice::from_raw_parts(unsealed.data, unsealed.len as usize), plaintext);
+
+            vault_free(unsealed.data, unsealed.len);
+            vault_free(refreshed.data, refreshed.len);
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+
+    #[test]
+    fn test_refresh_nonce_wrong_key() {
+        let key = [0x42u8; 32];
+        let wrong_key = [0x43u8; 32];
+        let plaintext = b"long-lived entry";
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            let result = vault_refresh_nonce(wrong_key.as_ptr(), 32, sealed.data, sealed.len);
+            assert_eq!(result.error, ERR_DECRYPT_FAILED);
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+}
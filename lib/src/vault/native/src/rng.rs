@@ -0,0 +1,207 @@
+//! Buffered Random Generator
+//!
+//! `vault_random` asks the OS for every byte, which is slow for bulk fills
+//! (initializing a large keystore, overwriting a file). A `VaultRng` is
+//! seeded once from the OS and then generates a ChaCha20 keystream in
+//! process.
+//!
+//! Each fill first draws a fresh 32-byte key from the keystream and then
+//! forgets the old one ("fast key erasure"), so a context captured later
+//! cannot reproduce output it has already handed out.
+//!
+//! A context is not internally locked: use it from one thread at a time
+//! (moving it between threads is fine). Its state is wiped when it is freed
+//! with `vault_rng_free`.
+//!
+//! With the `test-rng` feature, `vault_rng_new_seeded` builds a context
+//! from a caller-chosen seed for reproducible test streams. Never enable it
+//! in a shipping build.
+
+use std::fmt;
+use std::slice;
+
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::ChaCha20;
+
+use super::*;
+
+/// Size of a generator key (and of a test seed)
+const RNG_KEY_SIZE: usize = 32;
+
+/// ChaCha20 generator context (opaque to callers)
+pub struct VaultRng {
+    key: Secret<[u8; RNG_KEY_SIZE]>,
+}
+
+/// Never prints the state.
+impl fmt::Debug for VaultRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VaultRng(***redacted***)")
+    }
+}
+
+impl VaultRng {
+    fn from_seed(seed: &[u8]) -> Self {
+        let mut key = Secret::new([0u8; RNG_KEY_SIZE]);
+        key.copy_from_slice(seed);
+        Self { key }
+    }
+
+    /// Replace the key from the keystream, then fill `out` from the rest of it.
+    fn fill(&mut self, out: &mut [u8]) {
+        let mut cipher = ChaCha20::new(self.key.as_ref().into(), &[0u8; 12].into());
+        self.key.zeroize();
+        cipher.apply_keystream(self.key.as_mut());
+
+        out.fill(0);
+        cipher.apply_keystream(out);
+    }
+}
+
+/// Create a generator seeded from the OS CSPRNG.
+///
+/// # Safety
+///
+/// - The returned pointer must be released with `vault_rng_free`
+///
+/// # Returns
+///
+/// A new context, or null if the OS entropy source fails
+#[no_mangle]
+pub unsafe extern "C" fn vault_rng_new() -> *mut VaultRng {
+    ffi_boundary(|| {
+        let mut seed = Secret::new([0u8; RNG_KEY_SIZE]);
+        if random_bytes(seed.as_mut()).is_err() {
+            return ptr::null_mut();
+        }
+        Box::into_raw(Box::new(VaultRng::from_seed(seed.as_ref())))
+    })
+}
+
+/// Create a generator from a fixed seed (`test-rng` feature only).
+///
+/// The same seed always produces the same stream. For tests only.
+///
+/// # Safety
+///
+/// - `seed` must point to exactly 32 bytes (`seed_len` must be 32)
+/// - The returned pointer must be released with `vault_rng_free`
+///
+/// # Returns
+///
+/// A new context, or null for a null or wrong-length seed
+#[cfg(feature = "test-rng")]
+#[no_mangle]
+pub unsafe extern "C" fn vault_rng_new_seeded(seed: *const u8, seed_len: u32) -> *mut VaultRng {
+    ffi_boundary(|| {
+        if seed.is_null() || seed_len as usize != RNG_KEY_SIZE {
+            return ptr::null_mut();
+        }
+        let seed_slice = slice::from_raw_parts(seed, RNG_KEY_SIZE);
+        Box::into_raw(Box::new(VaultRng::from_seed(seed_slice)))
+    })
+}
+
+/// Fill `out` with `len` random bytes from a generator.
+///
+/// # Safety
+///
+/// - `rng` must come from `vault_rng_new`, not yet be freed, and not be in
+///   use on another thread
+/// - `out` must be writable for `len` bytes
+///
+/// # Returns
+///
+/// 0 on success, -1 on error
+#[no_mangle]
+pub unsafe extern "C" fn vault_rng_fill(rng: *mut VaultRng, out: *mut u8, len: u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if rng.is_null() || out.is_null() || len == 0 {
+            return ERR_INVALID_INPUT;
+        }
+
+        (*rng).fill(slice::from_raw_parts_mut(out, len as usize));
+        0
+    })
+}
+
+/// Release a generator, wiping its state.
+///
+/// # Safety
+///
+/// - `rng` must come from `vault_rng_new` and not yet be freed (null is ignored)
+#[no_mangle]
+pub unsafe extern "C" fn vault_rng_free(rng: *mut VaultRng) {
+    ffi_boundary(|| {
+        if !rng.is_null() {
+            drop(Box::from_raw(rng));
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    unsafe fn draw(rng: *mut VaultRng, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        assert_eq!(vault_rng_fill(rng, out.as_mut_ptr(), len as u32), 0);
+        out
+    }
+
+    #[test]
+    fn test_rng_bulk_fill_non_repeating() {
+        unsafe {
+            let rng = vault_rng_new();
+            assert!(!rng.is_null());
+
+            let bulk = draw(rng, 1 << 20);
+            let blocks: HashSet<&[u8]> = bulk.chunks(64).collect();
+            assert_eq!(blocks.len(), (1 << 20) / 64);
+
+            // Successive fills continue with fresh output
+            let next = draw(rng, 64);
+            assert!(!blocks.contains(next.as_slice()));
+
+            assert_eq!(vault_rng_fill(rng, ptr::null_mut(), 1), ERR_INVALID_INPUT);
+            assert_eq!(vault_rng_fill(ptr::null_mut(), next.as_ptr() as *mut u8, 1), ERR_INVALID_INPUT);
+            vault_rng_free(rng);
+            vault_rng_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_rng_streams_differ() {
+        unsafe {
+            let a = vault_rng_new();
+            let b = vault_rng_new();
+            assert_ne!(draw(a, 64), draw(b, 64));
+            vault_rng_free(a);
+            vault_rng_free(b);
+        }
+    }
+
+    #[cfg(feature = "test-rng")]
+    #[test]
+    fn test_rng_seeded_reproducible() {
+        let seed = [7u8; RNG_KEY_SIZE];
+
+        unsafe {
+            let a = vault_rng_new_seeded(seed.as_ptr(), 32);
+            let b = vault_rng_new_seeded(seed.as_ptr(), 32);
+            assert_eq!(draw(a, 100), draw(b, 100));
+            assert_eq!(draw(a, 4096), draw(b, 4096));
+            vault_rng_free(a);
+            vault_rng_free(b);
+
+            assert!(vault_rng_new_seeded(seed.as_ptr(), 16).is_null());
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Safe Rust API
+//!
+//! Wrappers over the core C-ABI functions for Rust callers (integration
+//! tests, a native CLI), so they need no `unsafe` blocks and no manual
+//! `vault_free`. Each wrapper calls the exported function itself, so Rust
+//! and C callers run exactly the same code, and the returned buffer is
+//! freed (and wiped) when its owner drops.
+//!
+//! Keys and plaintexts come back in `Zeroizing` containers; sealed blobs
+//! are not secret and come back as plain `Vec`s.
+
+use zeroize::Zeroizing;
+
+use super::*;
+
+/// A `VaultBuffer` released with `vault_free` on drop.
+struct OwnedBuffer(VaultBuffer);
+
+impl OwnedBuffer {
+    /// Take ownership of an FFI result, turning an error code into `Err`.
+    fn new(buffer: VaultBuffer) -> Result<Self, VaultError> {
+        match buffer.error {
+            0 => Ok(Self(buffer)),
+            code => Err(VaultError::from(code)),
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        if self.0.data.is_null() {
+            return &[];
+        }
+        // SAFETY: a successful VaultBuffer owns `len` initialized bytes at
+        // `data` until it is passed to `vault_free`, which only drop does
+        unsafe { slice::from_raw_parts(self.0.data, self.0.len as usize) }
+    }
+}
+
+impl Drop for OwnedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: the buffer came from a vault function and is freed once
+        unsafe { vault_free(self.0.data, self.0.len) }
+    }
+}
+
+/// The length of an input as the C ABI takes it.
+fn ffi_len(bytes: &[u8]) -> Result<u32, VaultError> {
+    u32::try_from(bytes.len()).map_err(|_| VaultError::InvalidInput)
+}
+
+/// Derive a 32-byte key from a passphrase with Argon2id at the default costs.
+///
+/// Same as `vault_derive_key`.
+pub fn derive_key(passphrase: &[u8], salt: &[u8; SALT_SIZE]) -> Result<Zeroizing<[u8; KEY_SIZE]>, VaultError> {
+    let passphrase_len = ffi_len(passphrase)?;
+    // SAFETY: both pointers come from live slices of the stated lengths
+    let buffer = OwnedBuffer::new(unsafe { vault_derive_key(passphrase.as_ptr(), passphrase_len, salt.as_ptr()) })?;
+
+    let mut key = Zeroizing::new([0u8; KEY_SIZE]);
+    key.copy_from_slice(buffer.as_slice());
+    Ok(key)
+}
+
+/// Encrypt with XChaCha20-Poly1305 in the `vault_seal` format.
+pub fn seal(key: &[u8; KEY_SIZE], plaintext: &[u8]) -> Result<Vec<u8>, VaultError> {
+    let plaintext_len = ffi_len(plaintext)?;
+    // SAFETY: both pointers come from live slices of the stated lengths
+    let buffer = OwnedBuffer::new(unsafe { vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext_len) })?;
+    Ok(buffer.as_slice().to_vec())
+}
+
+/// Decrypt a blob from `seal` or `vault_seal`.
+pub fn unseal(key: &[u8; KEY_SIZE], sealed: &[u8]) -> Result<Zeroizing<Vec<u8>>, VaultError> {
+    let sealed_len = ffi_len(sealed)?;
+    // SAFETY: both pointers come from live slices of the stated lengths
+    let buffer = OwnedBuffer::new(unsafe { vault_unseal(key.as_ptr(), sealed.as_ptr(), sealed_len) })?;
+    Ok(Zeroizing::new(buffer.as_slice().to_vec()))
+}
@@ -0,0 +1,323 @@
+//! secp256k1 ECDSA
+//!
+//! Bitcoin- and Ethereum-style transaction signing with the private key
+//! held behind a key handle (see `keyhandle`), so it never reaches the app.
+//! Import the 32-byte private key with `vault_key_import_for` (purpose 1),
+//! or derive it with the BIP-32 functions of `hd`; any secp256k1 handle
+//! whose key is a valid scalar can sign.
+//!
+//! Signatures are deterministic (RFC 6979 nonces from HMAC-SHA256) and
+//! always low-S (`s <= n/2`), as Bitcoin relay policy and Ethereum require.
+//! They are the 64-byte compact form, `r || s` big-endian; DER encoding and
+//! Ethereum's recovery id are left to the caller.
+//!
+//! The message is a 32-byte hash the caller computed (double SHA-256 for
+//! Bitcoin, Keccak-256 for Ethereum); it is not hashed again.
+//!
+//! ## Implementation
+//!
+//! The curve arithmetic, RFC 6979 nonces and low-S normalization come from
+//! the RustCrypto `k256` crate, whose scalar multiplication and signing are
+//! constant-time. Private keys are wiped after use.
+
+use std::slice;
+
+use k256::ecdsa::signature::hazmat::{PrehashSigner, PrehashVerifier};
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use k256::elliptic_curve::ff::PrimeField;
+use k256::{FieldBytes, Scalar};
+
+use crate::keyhandle::{key_for, KeyPurpose};
+
+use super::*;
+
+/// Message hash size
+const SECP256K1_HASH_SIZE: usize = 32;
+
+/// Compressed public key size (`02`/`03` prefix and x)
+pub(crate) const SECP256K1_PUBLIC_KEY_SIZE: usize = 33;
+
+/// Uncompressed public key size (`04` prefix, x and y)
+const SECP256K1_UNCOMPRESSED_SIZE: usize = 65;
+
+/// Compact signature size (`r || s`)
+pub(crate) const SECP256K1_SIGNATURE_SIZE: usize = 64;
+
+/// A private key as a signing key, rejecting 0 and values of at least `n`.
+fn signing_key(private_key: &[u8; 32]) -> VaultResult<SigningKey> {
+    SigningKey::from_bytes(&FieldBytes::from(*private_key))
+        .map_err(|_| error_detail(ERR_INVALID_INPUT, format_args!("not a valid secp256k1 private key")))
+}
+
+/// Sign a 32-byte hash, returning the low-S compact signature.
+fn sign_hash(private_key: &[u8; 32], hash: &[u8; 32]) -> VaultResult<[u8; SECP256K1_SIGNATURE_SIZE]> {
+    let signature: Signature = signing_key(private_key)?.sign_prehash(hash).map_err(|_| ERR_INVALID_INPUT)?;
+    Ok(signature.to_bytes().into())
+}
+
+/// Check a low-S compact signature over a 32-byte hash.
+fn verify_hash(public_key: &VerifyingKey, hash: &[u8; 32], signature: &[u8; SECP256K1_SIGNATURE_SIZE]) -> bool {
+    // Rejects r or s of 0 or at least n; k256 also refuses high-S
+    match Signature::from_slice(signature) {
+        Ok(signature) => public_key.verify_prehash(hash, &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// `private_key + tweak mod n`, as BIP-32 child derivation needs.
+///
+/// A tweak of at least `n`, or a sum of 0, is `ERR_INVALID_INPUT`.
+pub(crate) fn tweak_add(private_key: &[u8; 32], tweak: &[u8; 32]) -> VaultResult<Secret<[u8; 32]>> {
+    let d = signing_key(private_key)?;
+    let t: Option<Scalar> = Scalar::from_repr(FieldBytes::from(*tweak)).into();
+    let t = Secret::new(t.ok_or(ERR_INVALID_INPUT)?);
+    let sum = Secret::new(*d.as_nonzero_scalar().as_ref() + *t);
+    if bool::from(sum.is_zero()) {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(Secret::new(sum.to_repr().into()))
+}
+
+/// The compressed public key for a private key.
+pub(crate) fn public_key_for(private_key: &[u8; 32]) -> VaultResult<[u8; SECP256K1_PUBLIC_KEY_SIZE]> {
+    let point = signing_key(private_key)?.verifying_key().to_sec1_point(true);
+    let mut public_key = [0u8; SECP256K1_PUBLIC_KEY_SIZE];
+    public_key.copy_from_slice(point.as_bytes());
+    Ok(public_key)
+}
+
+/// Read a 32-byte hash argument.
+unsafe fn hash_arg(hash: *const u8, hash_len: u32) -> VaultResult<[u8; SECP256K1_HASH_SIZE]> {
+    if hash.is_null() || hash_len as usize != SECP256K1_HASH_SIZE {
+        return Err(ERR_INVALID_INPUT);
+    }
+    let mut out = [0u8; SECP256K1_HASH_SIZE];
+    out.copy_from_slice(slice::from_raw_parts(hash, SECP256K1_HASH_SIZE));
+    Ok(out)
+}
+
+/// Sign a 32-byte message hash with the secp256k1 key behind `key_handle`.
+///
+/// # Format
+///
+/// `r (32 bytes) || s (32 bytes)`, big-endian, with `s <= n/2`.
+///
+/// # Safety
+///
+/// - `msg_hash` must point to exactly 32 bytes (`msg_hash_len` must be 32)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 64-byte signature, or `ERR_INVALID_INPUT` for
+/// an unknown handle or one that is not a secp256k1 key, a hash that is not
+/// 32 bytes, or a key that is not a valid secp256k1 private key (zero, or
+/// not below the group order)
+#[no_mangle]
+pub unsafe extern "C" fn vault_secp256k1_sign(key_handle: u64, msg_hash: *const u8, msg_hash_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let hash = match hash_arg(msg_hash, msg_hash_len) {
+            Ok(h) => h,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let key = match key_for(key_handle, KeyPurpose::Secp256k1) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        match sign_hash(&key, &hash) {
+            Ok(signature) => VaultBuffer::success(signature.to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// The compressed public key of the secp256k1 key behind `key_handle`.
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 33-byte compressed public key, or
+/// `ERR_INVALID_INPUT` for an unknown handle or one that is not a secp256k1
+/// key, or an invalid private key
+#[no_mangle]
+pub unsafe extern "C" fn vault_secp256k1_public_key(key_handle: u64) -> VaultBuffer {
+    ffi_boundary(|| {
+        let key = match key_for(key_handle, KeyPurpose::Secp256k1) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        match public_key_for(&key) {
+            Ok(public_key) => VaultBuffer::success(public_key.to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Verify a compact secp256k1 signature over a 32-byte message hash.
+///
+/// High-S signatures are rejected, so each message has one valid signature
+/// per key, matching what `vault_secp256k1_sign` produces.
+///
+/// # Safety
+///
+/// - `public_key` must be valid for `public_key_len` bytes: 33 (compressed)
+///   or 65 (uncompressed)
+/// - `msg_hash` must point to exactly 32 bytes (`msg_hash_len` must be 32)
+/// - `signature` must point to exactly 64 bytes (`signature_len` must be 64)
+///
+/// # Returns
+///
+/// 0 if the signature is valid, `ERR_DECRYPT_FAILED` if it is not (including
+/// a high-S signature or a public key not on the curve), `ERR_BAD_KEY_SIZE`,
+/// or `ERR_INVALID_INPUT` for a null pointer or a wrong hash or signature
+/// length
+#[no_mangle]
+pub unsafe extern "C" fn vault_secp256k1_verify(
+    public_key: *const u8,
+    public_key_len: u32,
+    msg_hash: *const u8,
+    msg_hash_len: u32,
+    signature: *const u8,
+    signature_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if public_key.is_null() || signature.is_null() || signature_len as usize != SECP256K1_SIGNATURE_SIZE {
+            return ERR_INVALID_INPUT;
+        }
+        if !matches!(public_key_len as usize, SECP256K1_PUBLIC_KEY_SIZE | SECP256K1_UNCOMPRESSED_SIZE) {
+            let detail = format_args!(
+                "public key is {public_key_len} bytes, expected {SECP256K1_PUBLIC_KEY_SIZE} or {SECP256K1_UNCOMPRESSED_SIZE}"
+            );
+            return error_detail(ERR_BAD_KEY_SIZE, detail);
+        }
+        let hash = match hash_arg(msg_hash, msg_hash_len) {
+            Ok(h) => h,
+            Err(code) => return code,
+        };
+        let mut signature_bytes = [0u8; SECP256K1_SIGNATURE_SIZE];
+        signature_bytes.copy_from_slice(slice::from_raw_parts(signature, SECP256K1_SIGNATURE_SIZE));
+
+        let point = match VerifyingKey::from_sec1_bytes(slice::from_raw_parts(public_key, public_key_len as usize)) {
+            Ok(p) => p,
+            Err(_) => return ERR_DECRYPT_FAILED,
+        };
+        if verify_hash(&point, &hash, &signature_bytes) {
+            0
+        } else {
+            ERR_DECRYPT_FAILED
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn hex32(s: &str) -> [u8; 32] {
+        hex(s).try_into().unwrap()
+    }
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let bytes = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        bytes
+    }
+
+    #[test]
+    fn test_secp256k1_vectors() {
+        // (private key, SHA-256 of the message, compressed public key, r || s)
+        let vectors = [
+            // Key 1, "Satoshi Nakamoto": the widely published RFC 6979 vector
+            (
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "a0dc65ffca799873cbea0ac274015b9526505daaaed385155425f7337704883e",
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d82442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+            ),
+            // Key n - 1, "Satoshi Nakamoto"
+            (
+                "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+                "a0dc65ffca799873cbea0ac274015b9526505daaaed385155425f7337704883e",
+                "0379be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "fd567d121db66e382991534ada77a6bd3106f0a1098c231e47993447cd6af2d06b39cd0eb1bc8603e159ef5c20a5c8ad685a45b06ce9bebed3f153d10d93bed5",
+            ),
+            // RFC 6979 A.2.5's key on this curve, "sample"
+            (
+                "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+                "af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf",
+                "032c8c31fc9f990c6b55e3865a184a4ce50e09481f2eaeb3e60ec1cea13a6ae645",
+                "432310e32cb80eb6503a26ce83cc165c783b870845fb8aad6d970889fcd7a6c8530128b6b81c548874a6305d93ed071ca6e05074d85863d4056ce89b02bfab69",
+            ),
+        ];
+
+        for (private_key, hash, public_key, signature) in vectors {
+            let (private_key, hash) = (hex32(private_key), hex32(hash));
+            assert_eq!(public_key_for(&private_key).unwrap().to_vec(), hex(public_key));
+            let signed = sign_hash(&private_key, &hash).unwrap();
+            assert_eq!(signed.to_vec(), hex(signature));
+            let point = VerifyingKey::from_sec1_bytes(&hex(public_key)).unwrap();
+            assert!(verify_hash(&point, &hash, &signed));
+        }
+    }
+
+    #[test]
+    fn test_secp256k1_by_handle() {
+        let private_key = [0x42u8; 32];
+        let hash = [0x17u8; 32];
+
+        unsafe {
+            let mut handle = 0u64;
+            assert_eq!(vault_key_import_for(private_key.as_ptr(), 32, 1, &mut handle), 0);
+            let public_key = take(vault_secp256k1_public_key(handle));
+            let mut signature = take(vault_secp256k1_sign(handle, hash.as_ptr(), 32));
+            assert_eq!(take(vault_secp256k1_sign(handle, hash.as_ptr(), 32)), signature);
+
+            let verify = |public_key: &[u8], hash: &[u8], signature: &[u8]| {
+                vault_secp256k1_verify(public_key.as_ptr(), public_key.len() as u32, hash.as_ptr(), 32, signature.as_ptr(), 64)
+            };
+            assert_eq!(verify(&public_key, &hash, &signature), 0);
+            assert_eq!(verify(&public_key, &[0x18u8; 32], &signature), ERR_DECRYPT_FAILED);
+
+            // The same key uncompressed
+            let uncompressed = signing_key(&private_key).unwrap().verifying_key().to_sec1_point(false);
+            assert_eq!(verify(uncompressed.as_bytes(), &hash, &signature), 0);
+
+            // The high-S twin of a valid signature is refused
+            let parsed = Signature::from_slice(&signature).unwrap();
+            let high = Signature::from_scalars(parsed.r().to_repr(), (-*parsed.s().as_ref()).to_repr()).unwrap();
+            assert_eq!(verify(&public_key, &hash, &high.to_bytes()), ERR_DECRYPT_FAILED);
+
+            signature[10] ^= 0x01;
+            assert_eq!(verify(&public_key, &hash, &signature), ERR_DECRYPT_FAILED);
+            let mut off_curve = public_key.clone();
+            off_curve[0] = 0x05;
+            assert_eq!(verify(&off_curve, &hash, &signature), ERR_DECRYPT_FAILED);
+            assert_eq!(verify(&public_key[..32], &hash, &signature), ERR_BAD_KEY_SIZE);
+
+            assert_eq!(vault_secp256k1_sign(handle, hash.as_ptr(), 31).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_key_destroy(handle), 0);
+            assert_eq!(vault_secp256k1_sign(handle, hash.as_ptr(), 32).error, ERR_INVALID_INPUT);
+
+            // Zero is not a private key
+            assert_eq!(vault_key_import_for([0u8; 32].as_ptr(), 32, 1, &mut handle), 0);
+            assert_eq!(vault_secp256k1_sign(handle, hash.as_ptr(), 32).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_key_destroy(handle), 0);
+        }
+    }
+}
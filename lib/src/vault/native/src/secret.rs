@@ -0,0 +1,180 @@
+//! Secret Buffers
+//!
+//! `Secret<T>` holds an intermediate that touches key or plaintext material
+//! (derived keys, Argon2 working memory, AEAD buffers). It wipes its value
+//! on drop, so the wipe happens on early returns and during unwinding as
+//! well as on the normal path, instead of relying on scattered `zeroize`
+//! calls.
+//!
+//! A heap buffer leaves a `Secret` only through `into_inner`, which hands
+//! the caller the responsibility for wiping it (typically
+//! `VaultBuffer::success` followed by the caller's `vault_free`).
+//!
+//! `Debug` and `Display` print a redaction marker, never the contents, so a
+//! stray `{:?}` in a log line cannot leak key material.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use super::*;
+
+/// A value that is zeroized when dropped.
+pub(crate) struct Secret<T: Zeroize>(Zeroizing<T>);
+
+impl<T: Zeroize> Secret<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(Zeroizing::new(value))
+    }
+}
+
+impl Secret<Vec<u8>> {
+    /// An empty buffer that will not reallocate below `capacity` bytes, or
+    /// `ERR_OUT_OF_MEMORY`.
+    ///
+    /// Growing past `capacity` frees the old allocation unwiped, so size it
+    /// for the final contents.
+    pub(crate) fn with_capacity(capacity: usize) -> VaultResult<Self> {
+        let buffer = output_buffer(capacity)?;
+        #[cfg(test)]
+        if capacity > 0 {
+            test_alloc::watch(buffer.as_ptr());
+        }
+        Ok(Self::new(buffer))
+    }
+
+    /// A wiped-on-drop copy of `bytes`, or `ERR_OUT_OF_MEMORY`.
+    pub(crate) fn copy_of(bytes: &[u8]) -> VaultResult<Self> {
+        let mut buffer = Self::with_capacity(bytes.len())?;
+        buffer.extend_from_slice(bytes);
+        Ok(buffer)
+    }
+
+    /// Release the buffer; the caller becomes responsible for wiping it.
+    pub(crate) fn into_inner(mut self) -> Vec<u8> {
+        let buffer = mem::take(&mut *self.0);
+        #[cfg(test)]
+        test_alloc::unwatch(buffer.as_ptr());
+        buffer
+    }
+}
+
+impl<T: Zeroize> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(***redacted***)")
+    }
+}
+
+impl<T: Zeroize> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_alloc::dirty_secret_frees;
+
+    #[test]
+    fn test_secret_wiped_on_drop() {
+        let dirty = dirty_secret_frees(|| {
+            let mut buffer = Secret::with_capacity(64).unwrap();
+            buffer.extend_from_slice(&[0xAA; 48]);
+        });
+        assert_eq!(dirty, 0);
+
+        // Early return and panic paths drop the same way
+        let dirty = dirty_secret_frees(|| {
+            let result = std::panic::catch_unwind(|| {
+                let _buffer = Secret::copy_of(&[0xBB; 32]).unwrap();
+                panic!("unwinding with a live secret");
+            });
+            assert!(result.is_err());
+        });
+        assert_eq!(dirty, 0);
+    }
+
+    #[test]
+    fn test_secret_array_wiped_on_drop() {
+        // Stack arrays such as the seal nonce: drop in place, then look at the bytes left behind
+        let mut nonce = mem::ManuallyDrop::new(Secret::new([0xCCu8; NONCE_SIZE]));
+        let bytes: *const [u8; NONCE_SIZE] = &**nonce;
+        unsafe {
+            ptr::drop_in_place(&mut *nonce);
+            assert_eq!(ptr::read_volatile(bytes), [0u8; NONCE_SIZE]);
+        }
+
+        // vault_seal returns early on an RNG failure; the nonce drops (and is wiped) the same way
+        let key = [0x42u8; 32];
+        let sealed = with_failing_rng(|| xchacha_seal(&key, b"early return", &[]));
+        assert_eq!(sealed, Err(ERR_RNG_FAILED));
+    }
+
+    #[test]
+    fn test_secret_formatting_redacted() {
+        let key: [u8; KEY_SIZE] = std::array::from_fn(|i| 0xF0 | i as u8 & 0x0F);
+        let secret = Secret::new(key);
+
+        for rendered in [format!("{secret:?}"), format!("{secret:#?}"), secret.to_string()] {
+            assert!(rendered.contains("redacted"));
+            assert!(key.iter().all(|b| !rendered.contains(&format!("{b:02x}"))));
+            assert!(key.iter().all(|b| !rendered.contains(&format!("{b:02X}"))));
+            assert!(key.iter().all(|b| !rendered.contains(&b.to_string())));
+        }
+
+        unsafe {
+            let buffer = VaultBuffer::success(key.to_vec());
+            let rendered = format!("{buffer:?}");
+            assert!(rendered.contains("redacted") && rendered.contains("len: 32"));
+            assert!(key.iter().all(|b| !rendered.contains(&b.to_string())));
+            vault_free(buffer.data, buffer.len);
+        }
+    }
+
+    #[test]
+    fn test_secret_paths_free_only_zeros() {
+        let key = [0x42u8; 32];
+        let wrong_key = [0x43u8; 32];
+        let plaintext = b"every temporary is wiped";
+        let salt = [3u8; SALT_SIZE];
+
+        unsafe {
+            let dirty = dirty_secret_frees(|| {
+                let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+                assert_eq!(sealed.error, 0);
+
+                let unsealed = vault_unseal(key.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(unsealed.error, 0);
+                vault_free(unsealed.data, unsealed.len);
+
+                let failed = vault_unseal(wrong_key.as_ptr(), sealed.data, sealed.len);
+                assert_eq!(failed.error, ERR_DECRYPT_FAILED);
+                vault_free(sealed.data, sealed.len);
+
+                let derived = vault_derive_key(b"pass".as_ptr(), 4, salt.as_ptr());
+                assert_eq!(derived.error, 0);
+                vault_free(derived.data, derived.len);
+            });
+            assert_eq!(dirty, 0);
+        }
+    }
+}
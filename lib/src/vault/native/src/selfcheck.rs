@@ -0,0 +1,178 @@
+//! Timing Self-Check (`timing-selfcheck` feature)
+//!
+//! A runtime smoke test that the secret comparisons do not take visibly
+//! longer when inputs agree for longer. Each check times the same operation
+//! on inputs differing in their first byte and in their last byte,
+//! interleaved, and flags a leak only when the two timing distributions are
+//! both statistically (Welch's t-test) and materially (median gap) apart.
+//!
+//! This is a coarse check for gross regressions, such as a `==` slipping
+//! into a tag comparison, on the device it runs on. Passing it is not a
+//! proof of constant-time behavior: small or data-dependent leaks below
+//! timer resolution, cache effects and compiler changes on other targets
+//! all go unseen. It takes a few seconds, so it is built only with the
+//! feature and meant for diagnostics and compliance runs.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use super::*;
+
+/// Timing samples per input class
+const TIMING_SAMPLES: usize = 2000;
+
+/// Operations timed together per sample, to stay well above timer resolution
+const TIMING_BATCH: usize = 16;
+
+/// Size of the compared inputs; long enough that an early exit is obvious
+const TIMING_INPUT_SIZE: usize = 4096;
+
+/// Share of the slowest samples dropped from each class as interrupt noise
+const TIMING_TRIM: f64 = 0.1;
+
+/// Welch's t above which the classes are taken to differ
+const TIMING_T_THRESHOLD: f64 = 10.0;
+
+/// Relative median gap above which a difference is taken to matter
+const TIMING_GAP_THRESHOLD: f64 = 0.2;
+
+/// Mean and variance of a sample.
+fn mean_variance(samples: &[f64]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let variance = samples.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// Sort and drop the slowest `TIMING_TRIM` of a class.
+fn trimmed(mut samples: Vec<f64>) -> Vec<f64> {
+    samples.sort_by(f64::total_cmp);
+    samples.truncate(samples.len() - (samples.len() as f64 * TIMING_TRIM) as usize);
+    samples
+}
+
+/// Time `op(late)` for early- and late-differing inputs and report whether
+/// their timings differ.
+fn timing_differs(mut op: impl FnMut(bool)) -> bool {
+    let mut early = Vec::with_capacity(TIMING_SAMPLES);
+    let mut late = Vec::with_capacity(TIMING_SAMPLES);
+
+    for i in 0..2 * TIMING_SAMPLES {
+        let is_late = i % 2 == 1;
+        let start = Instant::now();
+        for _ in 0..TIMING_BATCH {
+            op(is_late);
+        }
+        let elapsed = start.elapsed().as_nanos() as f64;
+        if is_late {
+            late.push(elapsed);
+        } else {
+            early.push(elapsed);
+        }
+    }
+
+    let (early, late) = (trimmed(early), trimmed(late));
+    let (early_mean, early_var) = mean_variance(&early);
+    let (late_mean, late_var) = mean_variance(&late);
+    let standard_error = (early_var / early.len() as f64 + late_var / late.len() as f64).sqrt();
+    let t = if standard_error > 0.0 { (early_mean - late_mean).abs() / standard_error } else { 0.0 };
+
+    let (early_median, late_median) = (early[early.len() / 2], late[late.len() / 2]);
+    let gap = (early_median - late_median).abs() / early_median.min(late_median).max(1.0);
+
+    t > TIMING_T_THRESHOLD && gap > TIMING_GAP_THRESHOLD
+}
+
+/// Inputs equal to `base` except in their first or their last byte.
+fn differing_pair(base: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut early = base.to_vec();
+    early[0] ^= 0x01;
+    let mut late = base.to_vec();
+    late[base.len() - 1] ^= 0x01;
+    (early, late)
+}
+
+/// Whether `compare` takes longer when its inputs agree for longer.
+fn comparison_leaks(compare: fn(&[u8], &[u8]) -> bool) -> bool {
+    let base = vec![0x5Au8; TIMING_INPUT_SIZE];
+    let (early, late) = differing_pair(&base);
+    timing_differs(|is_late| {
+        let other = if is_late { &late } else { &early };
+        black_box(compare(black_box(&base), black_box(other)));
+    })
+}
+
+/// Whether unsealing rejects a tag faster when it is wrong in its first byte.
+fn tag_check_leaks() -> VaultResult<bool> {
+    let key = [0x42u8; KEY_SIZE];
+    let sealed = seal_blob(&key, &[0u8; TIMING_INPUT_SIZE])?;
+    let tag_at = sealed.len() - TAG_SIZE;
+
+    let mut early = sealed.clone();
+    early[tag_at] ^= 0x01;
+    let mut late = sealed;
+    late[tag_at + TAG_SIZE - 1] ^= 0x01;
+    Ok(timing_differs(|is_late| {
+        let blob = if is_late { &late } else { &early };
+        let _ = black_box(open_blob(black_box(&key), black_box(blob)));
+    }))
+}
+
+/// Check for gross input-dependent timing in the secret comparisons.
+///
+/// Times the constant-time comparison used for secret-derived values and
+/// the tag check in `vault_unseal`, each with inputs differing early and
+/// late. See the module documentation for what this can and cannot show.
+///
+/// # Safety
+///
+/// Always safe to call. Runs for a few seconds in an optimized build.
+///
+/// # Returns
+///
+/// 0 if no difference was found, `ERR_TIMING_LEAK` if one was, or
+/// `ERR_RNG_FAILED` if the test blob could not be sealed
+#[no_mangle]
+pub unsafe extern "C" fn vault_timing_selfcheck() -> i32 {
+    ffi_boundary(|| {
+        if comparison_leaks(ct_eq) {
+            return ERR_TIMING_LEAK;
+        }
+        match tag_check_leaks() {
+            Ok(false) => 0,
+            Ok(true) => ERR_TIMING_LEAK,
+            Err(code) => code,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stops at the first difference, as a careless comparison would
+    fn early_exit_eq(a: &[u8], b: &[u8]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        for (x, y) in a.iter().zip(b) {
+            if x != y {
+                return false;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn test_timing_selfcheck() {
+        assert!(!comparison_leaks(ct_eq));
+        assert!(comparison_leaks(early_exit_eq));
+        unsafe {
+            assert_eq!(vault_timing_selfcheck(), 0);
+        }
+    }
+}
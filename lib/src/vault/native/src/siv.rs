@@ -0,0 +1,220 @@
+//! Deterministic AES-SIV Sealing
+//!
+//! AES-SIV (RFC 5297) derives its IV from the key, associated data, and
+//! plaintext. Identical inputs produce identical output, and unlike a random
+//! nonce path there is no nonce to reuse: the worst case is revealing that two
+//! messages are equal, never the XOR of two plaintexts.
+//!
+//! Use this where determinism is wanted (deduplicated storage, lookup keys)
+//! or where nonce management cannot be trusted.
+//!
+//! ## Format
+//!
+//! `format (1 byte, 0x02) || siv tag (16 bytes) || ciphertext`
+//!
+//! The format byte is authenticated as the first associated-data component.
+
+use std::slice;
+
+use aes_siv::{siv::Aes256Siv, KeyInit};
+
+use super::*;
+
+/// AES-256-SIV key size (two 256-bit AES keys)
+pub(crate) const SIV_KEY_SIZE: usize = 64;
+
+/// Encrypt deterministically with AES-256-SIV.
+///
+/// # Format
+///
+/// Output: `format (1 byte) || siv tag (16 bytes) || ciphertext`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 64 bytes (`key_len` must be 64)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - `aad` must be valid for `aad_len` bytes (may be null when `aad_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_siv(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    aad: *const u8,
+    aad_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if key.is_null() || (plaintext.is_null() && plaintext_len != 0) {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if key_len as usize != SIV_KEY_SIZE {
+            return VaultBuffer::error(ERR_BAD_KEY_SIZE);
+        }
+        let aad_slice = match aad_arg(aad, aad_len) {
+            Ok(a) => a,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let key_slice = slice::from_raw_parts(key, SIV_KEY_SIZE);
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let mut cipher = match Aes256Siv::new_from_slice(key_slice) {
+            Ok(c) => c,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+
+        let header = [FORMAT_SIV];
+        let ciphertext = match cipher.encrypt([&header[..], aad_slice], plaintext_slice) {
+            Ok(ct) => ct,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+
+        let mut output = match output_buffer(FORMAT_HEADER_SIZE + ciphertext.len()) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&ciphertext);
+
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt data encrypted with `vault_seal_siv`.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 64 bytes (`key_len` must be 64)
+/// - `sealed` must contain: format (1) || siv tag (16) || ciphertext
+/// - `aad` must match the value given to `vault_seal_siv`
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_siv(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+    aad: *const u8,
+    aad_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = FORMAT_HEADER_SIZE + TAG_SIZE;
+        if key.is_null() || sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        if key_len as usize != SIV_KEY_SIZE {
+            return VaultBuffer::error(ERR_BAD_KEY_SIZE);
+        }
+        let aad_slice = match aad_arg(aad, aad_len) {
+            Ok(a) => a,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let key_slice = slice::from_raw_parts(key, SIV_KEY_SIZE);
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, ciphertext) = sealed_slice.split_at(FORMAT_HEADER_SIZE);
+        if header[0] != FORMAT_SIV {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+
+        let mut cipher = match Aes256Siv::new_from_slice(key_slice) {
+            Ok(c) => c,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+
+        match cipher.decrypt([header, aad_slice], ciphertext) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(_) => VaultBuffer::error(ERR_DECRYPT_FAILED),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn seal(key: &[u8], plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+        let sealed = vault_seal_siv(
+            key.as_ptr(),
+            key.len() as u32,
+            plaintext.as_ptr(),
+            plaintext.len() as u32,
+            aad.as_ptr(),
+            aad.len() as u32,
+        );
+        assert_eq!(sealed.error, 0);
+        let out = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+        vault_free(sealed.data, sealed.len);
+        out
+    }
+
+    #[test]
+    fn test_siv_roundtrip() {
+        let key = [0x24u8; 64];
+        let plaintext = b"deterministic secret payload";
+        let aad = b"record:7";
+
+        unsafe {
+            let sealed = seal(&key, plaintext, aad);
+            assert_eq!(sealed[0], FORMAT_SIV);
+            assert_eq!(sealed.len(), FORMAT_HEADER_SIZE + TAG_SIZE + plaintext.len());
+
+            let unsealed = vault_unseal_siv(
+                key.as_ptr(),
+                64,
+                sealed.as_ptr(),
+                sealed.len() as u32,
+                aad.as_ptr(),
+                aad.len() as u32,
+            );
+            assert_eq!(unsealed.error, 0);
+            assert_eq!(slice::from_raw_parts(unsealed.data, unsealed.len as usize), plaintext);
+            vault_free(unsealed.data, unsealed.len);
+
+            // Not accepted by the XChaCha path
+            let key32 = [0x24u8; 32];
+            let wrong_path = vault_unseal(key32.as_ptr(), sealed.as_ptr(), sealed.len() as u32);
+            assert_eq!(wrong_path.error, ERR_UNSUPPORTED_VERSION);
+        }
+    }
+
+    #[test]
+    fn test_siv_wrong_key_fails() {
+        let key1 = [0x24u8; 64];
+        let key2 = [0x25u8; 64];
+        let plaintext = b"secret";
+
+        unsafe {
+            let sealed = seal(&key1, plaintext, &[]);
+            let unsealed = vault_unseal_siv(key2.as_ptr(), 64, sealed.as_ptr(), sealed.len() as u32, ptr::null(), 0);
+            assert_eq!(unsealed.error, ERR_DECRYPT_FAILED);
+
+            // 32-byte keys are rejected outright
+            let short = vault_unseal_siv(key1.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32, ptr::null(), 0);
+            assert_eq!(short.error, ERR_BAD_KEY_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_siv_deterministic() {
+        let key = [0x24u8; 64];
+        let plaintext = b"same input";
+
+        unsafe {
+            assert_eq!(seal(&key, plaintext, b"ctx"), seal(&key, plaintext, b"ctx"));
+            assert_ne!(seal(&key, plaintext, b"ctx"), seal(&key, plaintext, b"other"));
+            assert_ne!(seal(&key, plaintext, b"ctx"), seal(&key, b"same inpuT", b"ctx"));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Status Results
+//!
+//! The simple `i32`-returning functions can only say that something failed.
+//! The `_status` variants return a `VaultStatus` whose `detail` says more:
+//! which argument was rejected, or the OS error behind a CSPRNG failure, so
+//! the app can show an actionable message. The simple functions remain for
+//! compatibility.
+
+use std::slice;
+
+use crate::kdf::argon2_param_error;
+
+use super::*;
+
+/// Detail kind: `detail` is the 1-based position of the rejected argument
+const STATUS_DETAIL_ARGUMENT: u32 = 0;
+
+/// Detail kind: `detail` is the OS error code from the entropy source
+const STATUS_DETAIL_OS_ERROR: u32 = 1 << 31;
+
+/// Result code with a machine-readable detail
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VaultStatus {
+    /// Error code (0 = success)
+    pub code: i32,
+    /// 0 when there is no detail. Otherwise, with the top bit clear, the
+    /// 1-based position of the offending argument; with it set, the OS error
+    /// code in the low 31 bits.
+    pub detail: u32,
+}
+
+impl VaultStatus {
+    fn ok() -> Self {
+        Self { code: 0, detail: 0 }
+    }
+
+    /// Reject argument number `position` (1-based).
+    fn bad_argument(position: u32) -> Self {
+        Self { code: ERR_INVALID_INPUT, detail: STATUS_DETAIL_ARGUMENT | position }
+    }
+}
+
+/// `vault_random` with a detailed status.
+///
+/// # Safety
+///
+/// - `out` must be valid for `len` bytes
+/// - Memory must be writable
+///
+/// # Returns
+///
+/// `code` 0 on success; otherwise `ERR_INVALID_INPUT` with `detail` 1 (null
+/// `out`) or 2 (zero `len`), or `ERR_RNG_FAILED` with the OS error flag set
+/// if the CSPRNG failed
+#[no_mangle]
+pub unsafe extern "C" fn vault_random_status(out: *mut u8, len: u32) -> VaultStatus {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out.is_null() {
+            return VaultStatus::bad_argument(1);
+        }
+        if len == 0 {
+            return VaultStatus::bad_argument(2);
+        }
+
+        let slice = slice::from_raw_parts_mut(out, len as usize);
+        match getrandom::getrandom(slice) {
+            Ok(_) => VaultStatus::ok(),
+            Err(err) => VaultStatus {
+                code: ERR_RNG_FAILED,
+                detail: STATUS_DETAIL_OS_ERROR | (err.code().get() & !STATUS_DETAIL_OS_ERROR),
+            },
+        }
+    })
+}
+
+/// Check Argon2 costs before deriving, reporting which one is out of range.
+///
+/// The limits are those of the Argon2 spec: `t_cost >= 1`,
+/// `1 <= p_cost <= 2^24 - 1`, and `m_cost >= 8 * p_cost` (KiB). Costs that
+/// pass can still fail with `ERR_OUT_OF_MEMORY` when derived.
+///
+/// # Returns
+///
+/// `code` 0 if all three are valid; otherwise `ERR_INVALID_INPUT` with
+/// `detail` 1 (`m_cost`), 2 (`t_cost`) or 3 (`p_cost`)
+#[no_mangle]
+pub extern "C" fn vault_argon2_params_status(m_cost: u32, t_cost: u32, p_cost: u32) -> VaultStatus {
+    ffi_boundary(|| {
+        match argon2_param_error(m_cost, t_cost, p_cost) {
+            Some(position) => VaultStatus::bad_argument(position),
+            None => VaultStatus::ok(),
+        }
+    })
+}
+
+/// `vault_zeroize` with a detailed status.
+///
+/// # Safety
+///
+/// - `ptr` must be valid for `len` bytes
+/// - Memory must be writable
+///
+/// # Returns
+///
+/// `code` 0 on success; otherwise `ERR_INVALID_INPUT` with `detail` 1 (null
+/// `ptr`) or 2 (zero `len`)
+#[no_mangle]
+pub unsafe extern "C" fn vault_zeroize_status(ptr: *mut u8, len: u32) -> VaultStatus {
+    ffi_boundary(|| {
+        // Validate inputs
+        if ptr.is_null() {
+            return VaultStatus::bad_argument(1);
+        }
+        if len == 0 {
+            return VaultStatus::bad_argument(2);
+        }
+
+        vault_zeroize(ptr, len);
+        VaultStatus::ok()
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_reports_argument() {
+        let mut buf = [0u8; 16];
+
+        unsafe {
+            assert_eq!(vault_random_status(ptr::null_mut(), 16), VaultStatus { code: ERR_INVALID_INPUT, detail: 1 });
+            assert_eq!(vault_random_status(buf.as_mut_ptr(), 0), VaultStatus { code: ERR_INVALID_INPUT, detail: 2 });
+            assert_eq!(vault_zeroize_status(ptr::null_mut(), 16), VaultStatus { code: ERR_INVALID_INPUT, detail: 1 });
+        }
+    }
+
+    #[test]
+    fn test_status_argon2_params() {
+        let bad = |detail| VaultStatus { code: ERR_INVALID_INPUT, detail };
+
+        assert_eq!(vault_argon2_params_status(65536, 0, 4), bad(2));
+        assert_eq!(vault_argon2_params_status(65536, 3, 0), bad(3));
+        assert_eq!(vault_argon2_params_status(65536, 3, 1 << 24), bad(3));
+        assert_eq!(vault_argon2_params_status(31, 3, 4), bad(1));
+        assert_eq!(vault_argon2_params_status(32, 1, 4), VaultStatus::ok());
+        assert_eq!(vault_argon2_params_status(u32::MAX, u32::MAX, (1 << 24) - 1), VaultStatus::ok());
+    }
+
+    #[test]
+    fn test_status_success() {
+        let mut buf = [0u8; 32];
+
+        unsafe {
+            assert_eq!(vault_random_status(buf.as_mut_ptr(), 32), VaultStatus { code: 0, detail: 0 });
+            assert!(buf.iter().any(|&b| b != 0));
+
+            assert_eq!(vault_zeroize_status(buf.as_mut_ptr(), 32), VaultStatus { code: 0, detail: 0 });
+            assert_eq!(buf, [0u8; 32]);
+        }
+    }
+}
@@ -0,0 +1,600 @@
+//! Chunked STREAM Format
+//!
+//! Large inputs are sealed as a sequence of independently authenticated
+//! chunks using the STREAM construction (big-endian 32-bit counter plus a
+//! last-chunk flag in the XChaCha20-Poly1305 nonce). Chunks cannot be
+//! reordered, dropped or truncated without failing authentication, and only
+//! one chunk is ever held in memory.
+//!
+//! The same format is produced by `vault_seal_file` and by the incremental
+//! `vault_seal_stream_*` functions, which take the plaintext in pieces of
+//! any size, so a caller never needs the whole payload in memory. The
+//! `vault_unseal_stream_*` functions read either.
+//!
+//! ## Format
+//!
+//! `format (1, 0x05) || nonce prefix (19) || chunk size (4, LE) || chunk*`
+//!
+//! Every chunk is `ciphertext || tag (16)`; all but the last carry exactly
+//! `chunk size` bytes of ciphertext, and the last (possibly empty) is sealed
+//! with the last-chunk flag. The header is each chunk's associated data.
+//! The header is not authenticated until the first chunk opens, so a chunk
+//! size above `STREAM_CHUNK_SIZE` is rejected before anything is allocated.
+
+use std::io::Write;
+use std::slice;
+
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+
+use super::*;
+
+/// Size of the stream header: format || nonce prefix || chunk size
+pub(crate) const STREAM_HEADER_SIZE: usize = FORMAT_HEADER_SIZE + STREAM_NONCE_PREFIX_SIZE + 4;
+
+/// Map a write failure to the FFI error code.
+fn io_err(_: std::io::Error) -> i32 {
+    ERR_IO
+}
+
+/// Incremental stream sealing state (opaque to callers)
+///
+/// The last full chunk is held back until more input arrives, since only
+/// `finish` knows which chunk carries the last-chunk flag.
+pub struct VaultSealStream {
+    header: [u8; STREAM_HEADER_SIZE],
+    header_written: bool,
+    encryptor: EncryptorBE32<XChaCha20Poly1305>,
+    pending: Zeroizing<Vec<u8>>,
+}
+
+impl VaultSealStream {
+    fn new(key: &[u8]) -> VaultResult<Self> {
+        let mut prefix = [0u8; STREAM_NONCE_PREFIX_SIZE];
+        random_bytes(&mut prefix)?;
+
+        let mut header = [0u8; STREAM_HEADER_SIZE];
+        header[0] = FORMAT_STREAM;
+        header[1..1 + STREAM_NONCE_PREFIX_SIZE].copy_from_slice(&prefix);
+        header[1 + STREAM_NONCE_PREFIX_SIZE..].copy_from_slice(&(STREAM_CHUNK_SIZE as u32).to_le_bytes());
+
+        let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?;
+        Ok(Self {
+            header,
+            header_written: false,
+            encryptor: EncryptorBE32::from_aead(cipher, prefix.as_ref().into()),
+            pending: Zeroizing::new(output_buffer(STREAM_CHUNK_SIZE + TAG_SIZE)?),
+        })
+    }
+
+    /// Upper bound on what `push(input)` and then `finish` can write.
+    fn output_bound(&self, input_len: usize) -> Option<usize> {
+        let total = self.pending.len().checked_add(input_len)?;
+        let chunks = total / STREAM_CHUNK_SIZE + 1;
+        total.checked_add(chunks.checked_mul(TAG_SIZE)?)?.checked_add(STREAM_HEADER_SIZE)
+    }
+
+    fn write_header(&mut self, out: &mut impl Write) -> VaultResult<()> {
+        if !self.header_written {
+            out.write_all(&self.header).map_err(io_err)?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
+    /// Seal every full chunk of `pending || input` that is not the last.
+    fn push(&mut self, mut input: &[u8], out: &mut impl Write) -> VaultResult<()> {
+        self.write_header(out)?;
+        loop {
+            if self.pending.len() == STREAM_CHUNK_SIZE && !input.is_empty() {
+                self.encryptor
+                    .encrypt_next_in_place(&self.header, &mut *self.pending)
+                    .map_err(|_| ERR_INVALID_INPUT)?;
+                out.write_all(&self.pending).map_err(io_err)?;
+                self.pending.clear();
+            }
+            if input.is_empty() {
+                return Ok(());
+            }
+            let take = input.len().min(STREAM_CHUNK_SIZE - self.pending.len());
+            self.pending.extend_from_slice(&input[..take]);
+            input = &input[take..];
+        }
+    }
+
+    /// Seal what is held back as the last chunk.
+    fn finish(mut self, out: &mut impl Write) -> VaultResult<()> {
+        self.write_header(out)?;
+        let Self { header, encryptor, mut pending, .. } = self;
+        encryptor
+            .encrypt_last_in_place(&header, &mut *pending)
+            .map_err(|_| ERR_INVALID_INPUT)?;
+        out.write_all(&pending).map_err(io_err)
+    }
+}
+
+/// Incremental stream unsealing state (opaque to callers)
+///
+/// Like sealing, the last complete chunk is held back until `finish`, which
+/// opens it with the last-chunk flag.
+pub struct VaultUnsealStream {
+    cipher: Option<XChaCha20Poly1305>,
+    header: [u8; STREAM_HEADER_SIZE],
+    chunk_size: usize,
+    decryptor: Option<DecryptorBE32<XChaCha20Poly1305>>,
+    pending: Zeroizing<Vec<u8>>,
+    failed: Option<i32>,
+}
+
+impl VaultUnsealStream {
+    fn new(key: &[u8]) -> VaultResult<Self> {
+        Ok(Self {
+            cipher: Some(XChaCha20Poly1305::new_from_slice(key).map_err(|_| ERR_INVALID_INPUT)?),
+            header: [0u8; STREAM_HEADER_SIZE],
+            chunk_size: 0,
+            decryptor: None,
+            pending: Zeroizing::new(output_buffer(STREAM_HEADER_SIZE)?),
+            failed: None,
+        })
+    }
+
+    /// Parse the header held in `pending` and set up the decryptor.
+    fn start(&mut self) -> VaultResult<()> {
+        if self.pending[0] != FORMAT_STREAM {
+            return Err(ERR_UNSUPPORTED_VERSION);
+        }
+        self.header.copy_from_slice(&self.pending);
+        let mut size_bytes = [0u8; 4];
+        size_bytes.copy_from_slice(&self.header[1 + STREAM_NONCE_PREFIX_SIZE..]);
+        let chunk_size = u32::from_le_bytes(size_bytes) as usize;
+        if chunk_size == 0 || chunk_size > STREAM_CHUNK_SIZE {
+            return Err(ERR_CORRUPT_DATA);
+        }
+
+        let cipher = self.cipher.take().ok_or(ERR_INVALID_INPUT)?;
+        let prefix = &self.header[1..1 + STREAM_NONCE_PREFIX_SIZE];
+        self.decryptor = Some(DecryptorBE32::from_aead(cipher, prefix.into()));
+        self.chunk_size = chunk_size;
+        self.pending.clear();
+        let wanted = chunk_size.checked_add(TAG_SIZE).ok_or(ERR_CORRUPT_DATA)?;
+        self.pending.try_reserve_exact(wanted).map_err(|_| ERR_OUT_OF_MEMORY)
+    }
+
+    /// Bytes `pending` must reach before it is processed.
+    fn wanted(&self) -> usize {
+        match self.decryptor {
+            Some(_) => self.chunk_size + TAG_SIZE,
+            None => STREAM_HEADER_SIZE,
+        }
+    }
+
+    /// Open every complete chunk of `pending || input` that is not the last.
+    fn push(&mut self, mut input: &[u8], out: &mut impl Write) -> VaultResult<()> {
+        loop {
+            if self.decryptor.is_none() && self.pending.len() == STREAM_HEADER_SIZE {
+                self.start()?;
+            }
+            if let Some(decryptor) = self.decryptor.as_mut() {
+                if self.pending.len() == self.chunk_size + TAG_SIZE && !input.is_empty() {
+                    decryptor
+                        .decrypt_next_in_place(&self.header, &mut *self.pending)
+                        .map_err(|_| ERR_DECRYPT_FAILED)?;
+                    out.write_all(&self.pending).map_err(io_err)?;
+                    self.pending.clear();
+                }
+            }
+            if input.is_empty() {
+                return Ok(());
+            }
+            let take = input.len().min(self.wanted() - self.pending.len());
+            self.pending.extend_from_slice(&input[..take]);
+            input = &input[take..];
+        }
+    }
+
+    /// Open what is held back as the last chunk.
+    fn finish(mut self, out: &mut impl Write) -> VaultResult<()> {
+        if self.decryptor.is_none() && self.pending.len() == STREAM_HEADER_SIZE {
+            self.start()?;
+        }
+        let decryptor = self.decryptor.take().ok_or(ERR_CORRUPT_DATA)?;
+        if self.pending.len() < TAG_SIZE {
+            return Err(ERR_CORRUPT_DATA);
+        }
+        decryptor
+            .decrypt_last_in_place(&self.header, &mut *self.pending)
+            .map_err(|_| ERR_DECRYPT_FAILED)?;
+        out.write_all(&self.pending).map_err(io_err)
+    }
+}
+
+/// Seal `plaintext` in the stream format, writing output to `out` as it goes.
+pub(crate) fn stream_seal_to(key: &[u8], plaintext: &[u8], out: &mut impl Write) -> VaultResult<()> {
+    let mut stream = VaultSealStream::new(key)?;
+    stream.push(plaintext, out)?;
+    stream.finish(out)
+}
+
+/// Open a stream-format blob, writing plaintext to `out` chunk by chunk.
+///
+/// Plaintext from chunks that authenticated may already have been written
+/// when a later chunk fails; the caller must discard `out` on error.
+pub(crate) fn stream_open_to(key: &[u8], sealed: &[u8], out: &mut impl Write) -> VaultResult<()> {
+    if sealed.len() < STREAM_HEADER_SIZE + TAG_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let mut stream = VaultUnsealStream::new(key)?;
+    stream.push(sealed, out)?;
+    stream.finish(out)
+}
+
+/// Borrow a chunk of stream input (null is allowed only when empty).
+unsafe fn input_arg<'a>(data: *const u8, data_len: u32) -> VaultResult<&'a [u8]> {
+    if data_len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(slice::from_raw_parts(data, data_len as usize))
+}
+
+/// Start sealing a payload in pieces, in the stream format.
+///
+/// Feed the plaintext to `vault_seal_stream_push` in pieces of any size and
+/// end with `vault_seal_stream_finish`. Concatenating every returned buffer
+/// in order gives the sealed blob. Memory use stays at about one 64 KiB
+/// chunk plus the piece being pushed, whatever the payload size.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - The returned pointer must be released by `vault_seal_stream_finish`
+///   or `vault_seal_stream_free`
+///
+/// # Returns
+///
+/// A new stream, or null for a null or wrong-length key or an RNG failure
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_stream_init(key: *const u8, key_len: u32) -> *mut VaultSealStream {
+    ffi_boundary(|| {
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(_) => return ptr::null_mut(),
+        };
+        match VaultSealStream::new(key_slice) {
+            Ok(stream) => Box::into_raw(Box::new(stream)),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Seal the next piece of plaintext.
+///
+/// The output is the stream header on the first call, then whichever
+/// chunks the input completed; it is often empty (`data` null, `len` 0).
+///
+/// # Safety
+///
+/// - `stream` must come from `vault_seal_stream_init` and still be live
+/// - `data` must be valid for `data_len` bytes (may be null when `data_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_stream_push(stream: *mut VaultSealStream, data: *const u8, data_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if stream.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let data_slice = match input_arg(data, data_len) {
+            Ok(d) => d,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let stream = &mut *stream;
+
+        let mut output = match stream.output_bound(data_slice.len()).ok_or(ERR_INVALID_INPUT).and_then(output_buffer) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        match stream.push(data_slice, &mut output) {
+            Ok(()) => VaultBuffer::success(output),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Seal the last chunk and release the stream.
+///
+/// The stream is released whether or not this succeeds.
+///
+/// # Safety
+///
+/// - `stream` must come from `vault_seal_stream_init` and still be live;
+///   it must not be used again
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_stream_finish(stream: *mut VaultSealStream) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if stream.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let stream = Box::from_raw(stream);
+
+        let mut output = match stream.output_bound(0).ok_or(ERR_INVALID_INPUT).and_then(output_buffer) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        match stream.finish(&mut output) {
+            Ok(()) => VaultBuffer::success(output),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Abandon a sealing stream, wiping its key and buffered plaintext.
+///
+/// # Safety
+///
+/// - `stream` must come from `vault_seal_stream_init` and not yet be
+///   finished or freed (null is ignored)
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_stream_free(stream: *mut VaultSealStream) {
+    ffi_boundary(|| {
+        if !stream.is_null() {
+            drop(Box::from_raw(stream));
+        }
+    })
+}
+
+/// Start unsealing a stream-format blob in pieces.
+///
+/// Feed the blob to `vault_unseal_stream_push` in pieces of any size and end
+/// with `vault_unseal_stream_finish`. Each returned buffer is plaintext from
+/// chunks that authenticated, but the payload is only known to be complete
+/// once `finish` succeeds: a truncated or failing stream may already have
+/// returned earlier chunks, so discard everything on any error.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - The returned pointer must be released by `vault_unseal_stream_finish`
+///   or `vault_unseal_stream_free`
+///
+/// # Returns
+///
+/// A new stream, or null for a null or wrong-length key
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_stream_init(key: *const u8, key_len: u32) -> *mut VaultUnsealStream {
+    ffi_boundary(|| {
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(_) => return ptr::null_mut(),
+        };
+        match VaultUnsealStream::new(key_slice) {
+            Ok(stream) => Box::into_raw(Box::new(stream)),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Unseal the next piece of a stream blob.
+///
+/// After an error the stream only returns that error; finish or free it.
+///
+/// # Safety
+///
+/// - `stream` must come from `vault_unseal_stream_init` and still be live
+/// - `data` must be valid for `data_len` bytes (may be null when `data_len` is 0)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer with the plaintext of the chunks this piece completed
+/// (often empty), `ERR_UNSUPPORTED_VERSION` for a blob in another format,
+/// `ERR_CORRUPT_DATA` for a bad header, or `ERR_DECRYPT_FAILED`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_stream_push(stream: *mut VaultUnsealStream, data: *const u8, data_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if stream.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let data_slice = match input_arg(data, data_len) {
+            Ok(d) => d,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let stream = &mut *stream;
+        if let Some(code) = stream.failed {
+            return VaultBuffer::error(code);
+        }
+
+        let bound = stream.pending.len().checked_add(data_slice.len()).ok_or(ERR_INVALID_INPUT);
+        let mut output = match bound.and_then(output_buffer) {
+            Ok(b) => Zeroizing::new(b),
+            Err(code) => return VaultBuffer::error(code),
+        };
+        match stream.push(data_slice, &mut *output) {
+            Ok(()) => VaultBuffer::success(mem::take(&mut *output)),
+            Err(code) => {
+                stream.failed = Some(code);
+                VaultBuffer::error(code)
+            }
+        }
+    })
+}
+
+/// Unseal the last chunk, confirm the stream is complete and release it.
+///
+/// The stream is released whether or not this succeeds.
+///
+/// # Safety
+///
+/// - `stream` must come from `vault_unseal_stream_init` and still be live;
+///   it must not be used again
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer with the last chunk's plaintext, `ERR_CORRUPT_DATA` for a
+/// stream that ended early, `ERR_DECRYPT_FAILED` if the last chunk does not
+/// authenticate as last (including truncation at a chunk boundary), or the
+/// error an earlier push returned
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_stream_finish(stream: *mut VaultUnsealStream) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if stream.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let stream = Box::from_raw(stream);
+        if let Some(code) = stream.failed {
+            return VaultBuffer::error(code);
+        }
+
+        let mut output = match output_buffer(stream.pending.len()) {
+            Ok(b) => Zeroizing::new(b),
+            Err(code) => return VaultBuffer::error(code),
+        };
+        match stream.finish(&mut *output) {
+            Ok(()) => VaultBuffer::success(mem::take(&mut *output)),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Abandon an unsealing stream, wiping its key and buffered plaintext.
+///
+/// # Safety
+///
+/// - `stream` must come from `vault_unseal_stream_init` and not yet be
+///   finished or freed (null is ignored)
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_stream_free(stream: *mut VaultUnsealStream) {
+    ffi_boundary(|| {
+        if !stream.is_null() {
+            drop(Box::from_raw(stream));
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let bytes = if buffer.data.is_null() { Vec::new() } else { slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec() };
+        vault_free(buffer.data, buffer.len);
+        bytes
+    }
+
+    /// Seal `plaintext` through the incremental API, `piece` bytes per push.
+    unsafe fn seal_in_pieces(key: &[u8], plaintext: &[u8], piece: usize) -> Vec<u8> {
+        let stream = vault_seal_stream_init(key.as_ptr(), 32);
+        assert!(!stream.is_null());
+        let mut sealed = Vec::new();
+        for part in plaintext.chunks(piece) {
+            sealed.extend(take(vault_seal_stream_push(stream, part.as_ptr(), part.len() as u32)));
+        }
+        sealed.extend(take(vault_seal_stream_finish(stream)));
+        sealed
+    }
+
+    /// Unseal through the incremental API, `piece` bytes per push.
+    unsafe fn unseal_in_pieces(key: &[u8], sealed: &[u8], piece: usize) -> Result<Vec<u8>, i32> {
+        let stream = vault_unseal_stream_init(key.as_ptr(), 32);
+        assert!(!stream.is_null());
+        let mut plaintext = Vec::new();
+        for part in sealed.chunks(piece) {
+            let result = vault_unseal_stream_push(stream, part.as_ptr(), part.len() as u32);
+            if result.error != 0 {
+                vault_unseal_stream_free(stream);
+                return Err(result.error);
+            }
+            plaintext.extend(take(result));
+        }
+        let result = vault_unseal_stream_finish(stream);
+        if result.error != 0 {
+            return Err(result.error);
+        }
+        plaintext.extend(take(result));
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn test_stream_pieces_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintext: Vec<u8> = (0..2 * STREAM_CHUNK_SIZE + 1000).map(|i| (i % 251) as u8).collect();
+
+        unsafe {
+            // Piece sizes that split chunks, match them, and span several
+            for piece in [1000, STREAM_CHUNK_SIZE, 3 * STREAM_CHUNK_SIZE] {
+                let sealed = seal_in_pieces(&key, &plaintext, piece);
+                assert_eq!(sealed.len(), STREAM_HEADER_SIZE + plaintext.len() + 3 * TAG_SIZE);
+                assert_eq!(unseal_in_pieces(&key, &sealed, 777).unwrap(), plaintext, "piece {piece}");
+
+                // Interoperates with the one-shot stream format
+                let mut opened = Vec::new();
+                stream_open_to(&key, &sealed, &mut opened).unwrap();
+                assert_eq!(opened, plaintext);
+            }
+
+            // Exactly one chunk, and nothing at all
+            let one = &plaintext[..STREAM_CHUNK_SIZE];
+            let sealed = seal_in_pieces(&key, one, 4096);
+            assert_eq!(unseal_in_pieces(&key, &sealed, STREAM_CHUNK_SIZE + TAG_SIZE).unwrap(), one);
+            let stream = vault_seal_stream_init(key.as_ptr(), 32);
+            let empty = take(vault_seal_stream_finish(stream));
+            assert_eq!(empty.len(), STREAM_HEADER_SIZE + TAG_SIZE);
+            assert!(unseal_in_pieces(&key, &empty, 5).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_stream_pieces_errors() {
+        let key = [0x42u8; 32];
+        let plaintext = vec![7u8; STREAM_CHUNK_SIZE + 500];
+
+        unsafe {
+            let sealed = seal_in_pieces(&key, &plaintext, 10_000);
+
+            assert_eq!(unseal_in_pieces(&[0x43u8; 32], &sealed, 10_000), Err(ERR_DECRYPT_FAILED));
+
+            // Truncated at the chunk boundary, mid-chunk, and inside the header
+            let boundary = STREAM_HEADER_SIZE + STREAM_CHUNK_SIZE + TAG_SIZE;
+            assert_eq!(unseal_in_pieces(&key, &sealed[..boundary], 10_000), Err(ERR_DECRYPT_FAILED));
+            assert_eq!(unseal_in_pieces(&key, &sealed[..boundary + 8], 10_000), Err(ERR_CORRUPT_DATA));
+            assert_eq!(unseal_in_pieces(&key, &sealed[..10], 10_000), Err(ERR_CORRUPT_DATA));
+
+            // An oversized chunk size is refused before it is allocated
+            let mut huge_chunk = sealed.clone();
+            huge_chunk[1 + STREAM_NONCE_PREFIX_SIZE..STREAM_HEADER_SIZE].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert_eq!(unseal_in_pieces(&key, &huge_chunk, 10_000), Err(ERR_CORRUPT_DATA));
+            let mut one_over = sealed.clone();
+            one_over[1 + STREAM_NONCE_PREFIX_SIZE..STREAM_HEADER_SIZE].copy_from_slice(&(STREAM_CHUNK_SIZE as u32 + 1).to_le_bytes());
+            assert_eq!(unseal_in_pieces(&key, &one_over, 10_000), Err(ERR_CORRUPT_DATA));
+
+            let mut other_format = sealed.clone();
+            other_format[0] = FORMAT_XCHACHA;
+            assert_eq!(unseal_in_pieces(&key, &other_format, 10_000), Err(ERR_UNSUPPORTED_VERSION));
+
+            // A failed stream keeps failing
+            let mut tampered = sealed.clone();
+            tampered[STREAM_HEADER_SIZE] ^= 1;
+            let stream = vault_unseal_stream_init(key.as_ptr(), 32);
+            assert_eq!(vault_unseal_stream_push(stream, tampered.as_ptr(), tampered.len() as u32).error, ERR_DECRYPT_FAILED);
+            assert_eq!(vault_unseal_stream_push(stream, ptr::null(), 0).error, ERR_DECRYPT_FAILED);
+            assert_eq!(vault_unseal_stream_finish(stream).error, ERR_DECRYPT_FAILED);
+
+            assert!(vault_seal_stream_init(key.as_ptr(), 16).is_null());
+            assert_eq!(vault_seal_stream_push(ptr::null_mut(), plaintext.as_ptr(), 1).error, ERR_INVALID_INPUT);
+            let stream = vault_seal_stream_init(key.as_ptr(), 32);
+            assert_eq!(vault_seal_stream_push(stream, ptr::null(), 1).error, ERR_INVALID_INPUT);
+            vault_seal_stream_free(stream);
+        }
+    }
+}
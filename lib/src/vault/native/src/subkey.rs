@@ -0,0 +1,421 @@
+//! HKDF Subkeys
+//!
+//! HKDF-SHA256 (RFC 5869) for deriving purpose-specific keys from one
+//! master key, in a form that other implementations can reproduce. Use
+//! `vault_hkdf` for a single output and `vault_derive_subkeys` for a fixed
+//! set of labeled 32-byte keys ("enc", "mac", "sync", ...) in one call.
+//! `vault_hkdf_extract` and `vault_hkdf_expand` are the two RFC 5869 steps
+//! on their own, for protocols that specify them separately or expand one
+//! pseudorandom key many times. `vault_combine_entropy` merges two devices'
+//! contributions into one key.
+
+use std::slice;
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::batch::release;
+
+use super::*;
+
+/// Largest HKDF-SHA256 output (255 hash blocks)
+const HKDF_MAX_OUTPUT: usize = 255 * 32;
+
+/// HKDF-SHA256 pseudorandom key size (one hash)
+const HKDF_PRK_SIZE: usize = 32;
+
+/// Smallest entropy contribution accepted from each party
+const MIN_CONTRIBUTION_SIZE: usize = 16;
+
+/// HKDF info for combined entropy
+const COMBINE_ENTROPY_INFO: &[u8] = b"vault_core 2025-01 combine entropy";
+
+/// Borrow an optional HKDF input (null is allowed only when empty).
+unsafe fn optional_arg<'a>(ptr: *const u8, len: u32) -> VaultResult<&'a [u8]> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(slice::from_raw_parts(ptr, len as usize))
+}
+
+/// Extract-and-expand `out_len` bytes of HKDF-SHA256.
+pub(crate) fn hkdf_sha256(ikm: &[u8], salt: &[u8], info: &[u8], out_len: usize) -> VaultResult<Vec<u8>> {
+    let salt = if salt.is_empty() { None } else { Some(salt) };
+    let hkdf = Hkdf::<Sha256>::new(salt, ikm);
+
+    let mut okm = Secret::with_capacity(out_len)?;
+    okm.resize(out_len, 0);
+    hkdf.expand(info, &mut okm).map_err(|_| ERR_INVALID_INPUT)?;
+    Ok(okm.into_inner())
+}
+
+/// Derive `out_len` bytes with HKDF-SHA256.
+///
+/// An empty salt is treated as absent (RFC 5869 then uses a zero salt).
+///
+/// # Safety
+///
+/// - `ikm` must be valid for `ikm_len` bytes (non-empty)
+/// - `salt` must be valid for `salt_len` bytes (may be null when `salt_len` is 0)
+/// - `info` must be valid for `info_len` bytes (may be null when `info_len` is 0)
+/// - `out_len` must be between 1 and 8160
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_hkdf(
+    ikm: *const u8,
+    ikm_len: u32,
+    salt: *const u8,
+    salt_len: u32,
+    info: *const u8,
+    info_len: u32,
+    out_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if ikm.is_null() || ikm_len == 0 || out_len == 0 || out_len as usize > HKDF_MAX_OUTPUT {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let (salt_slice, info_slice) = match (optional_arg(salt, salt_len), optional_arg(info, info_len)) {
+            (Ok(s), Ok(i)) => (s, i),
+            _ => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+        let ikm_slice = slice::from_raw_parts(ikm, ikm_len as usize);
+
+        match hkdf_sha256(ikm_slice, salt_slice, info_slice, out_len as usize) {
+            Ok(okm) => VaultBuffer::success(okm),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// HKDF-Extract: condense input keying material into a pseudorandom key.
+///
+/// An empty salt is treated as absent, as in `vault_hkdf`.
+///
+/// # Safety
+///
+/// - `salt` must be valid for `salt_len` bytes (may be null when `salt_len` is 0)
+/// - `ikm` must be valid for `ikm_len` bytes (non-empty)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte pseudorandom key, or
+/// `ERR_INVALID_INPUT` for a null pointer or empty `ikm`
+#[no_mangle]
+pub unsafe extern "C" fn vault_hkdf_extract(salt: *const u8, salt_len: u32, ikm: *const u8, ikm_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if ikm.is_null() || ikm_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let salt_slice = match optional_arg(salt, salt_len) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let ikm_slice = slice::from_raw_parts(ikm, ikm_len as usize);
+
+        let salt = if salt_slice.is_empty() { None } else { Some(salt_slice) };
+        let (prk, _) = Hkdf::<Sha256>::extract(salt, ikm_slice);
+        let prk = Secret::new(<[u8; HKDF_PRK_SIZE]>::from(prk));
+        VaultBuffer::success(prk.to_vec())
+    })
+}
+
+/// HKDF-Expand: derive `out_len` bytes from a pseudorandom key.
+///
+/// `vault_hkdf` is `vault_hkdf_extract` followed by this.
+///
+/// # Safety
+///
+/// - `prk` must be valid for `prk_len` bytes (at least 32)
+/// - `info` must be valid for `info_len` bytes (may be null when `info_len` is 0)
+/// - `out_len` must be between 1 and 8160
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the output, or `ERR_INVALID_INPUT` for a null
+/// pointer, a short `prk` or an out-of-range `out_len`
+#[no_mangle]
+pub unsafe extern "C" fn vault_hkdf_expand(
+    prk: *const u8,
+    prk_len: u32,
+    info: *const u8,
+    info_len: u32,
+    out_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if prk.is_null() || out_len == 0 || out_len as usize > HKDF_MAX_OUTPUT {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (prk_len as usize) < HKDF_PRK_SIZE {
+            return VaultBuffer::error(error_detail(
+                ERR_INVALID_INPUT,
+                format_args!("pseudorandom key is {prk_len} bytes, expected at least {HKDF_PRK_SIZE}"),
+            ));
+        }
+        let info_slice = match optional_arg(info, info_len) {
+            Ok(i) => i,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let prk_slice = slice::from_raw_parts(prk, prk_len as usize);
+
+        let hkdf = match Hkdf::<Sha256>::from_prk(prk_slice) {
+            Ok(h) => h,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+        let mut okm = match Secret::with_capacity(out_len as usize) {
+            Ok(o) => o,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        okm.resize(out_len as usize, 0);
+        match hkdf.expand(info_slice, &mut okm) {
+            Ok(()) => VaultBuffer::success(okm.into_inner()),
+            Err(_) => VaultBuffer::error(ERR_INVALID_INPUT),
+        }
+    })
+}
+
+/// Derive one 32-byte subkey per label from a master key.
+///
+/// Each subkey is `vault_hkdf(master, no salt, info = label, 32)`, so equal
+/// labels give equal subkeys. On any failure every subkey produced is freed,
+/// every slot holds an error buffer, and the error code is returned.
+///
+/// # Safety
+///
+/// - `master` must be valid for `master_len` bytes (non-empty)
+/// - `labels` must point to `label_count` valid `VaultSlice`s
+/// - `out_buffers` must be writable for `label_count` `VaultBuffer`s
+/// - Each returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// 0 on success, negative error code on failure
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_subkeys(
+    master: *const u8,
+    master_len: u32,
+    labels: *const VaultSlice,
+    label_count: u32,
+    out_buffers: *mut VaultBuffer,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if master.is_null() || master_len == 0 || labels.is_null() || out_buffers.is_null() || label_count == 0 {
+            return ERR_INVALID_INPUT;
+        }
+
+        let master_slice = slice::from_raw_parts(master, master_len as usize);
+        let labels = slice::from_raw_parts(labels, label_count as usize);
+        let out = slice::from_raw_parts_mut(out_buffers, label_count as usize);
+        for buffer in out.iter_mut() {
+            *buffer = VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let hkdf = Hkdf::<Sha256>::new(None, master_slice);
+        for (i, label) in labels.iter().enumerate() {
+            let info = match optional_arg(label.ptr, label.len) {
+                Ok(info) => info,
+                Err(code) => {
+                    release(&mut out[..i], code);
+                    return code;
+                }
+            };
+
+            let mut subkey = Secret::new(vec![0u8; KEY_SIZE]);
+            if hkdf.expand(info, &mut subkey).is_err() {
+                release(&mut out[..i], ERR_INVALID_INPUT);
+                return ERR_INVALID_INPUT;
+            }
+            out[i] = VaultBuffer::success(subkey.into_inner());
+        }
+
+        0
+    })
+}
+
+/// Combine two parties' entropy into one 32-byte key.
+///
+/// For key-generation ceremonies where neither device trusts the other's
+/// RNG: the key is HKDF-SHA256 over both contributions, so it is
+/// unpredictable as long as either one is.
+///
+/// The result does not depend on argument order. The two contributions are
+/// put in canonical order (the lexicographically smaller first) and each is
+/// length-prefixed before hashing:
+///
+/// `ikm = len(lo) (4, LE) || lo || len(hi) (4, LE) || hi`, `info` = a fixed
+/// context string, no salt.
+///
+/// # Safety
+///
+/// - `a` must be valid for `a_len` bytes and `b` for `b_len` bytes, each at
+///   least 16
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte key, or `ERR_INVALID_INPUT` for a null
+/// or too-short contribution
+#[no_mangle]
+pub unsafe extern "C" fn vault_combine_entropy(a: *const u8, a_len: u32, b: *const u8, b_len: u32) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if a.is_null() || b.is_null() || (a_len as usize) < MIN_CONTRIBUTION_SIZE || (b_len as usize) < MIN_CONTRIBUTION_SIZE {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let a_slice = slice::from_raw_parts(a, a_len as usize);
+        let b_slice = slice::from_raw_parts(b, b_len as usize);
+        let (lo, hi) = if a_slice <= b_slice { (a_slice, b_slice) } else { (b_slice, a_slice) };
+
+        let mut ikm = match Secret::with_capacity(8 + lo.len() + hi.len()) {
+            Ok(b) => b,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        for part in [lo, hi] {
+            ikm.extend_from_slice(&(part.len() as u32).to_le_bytes());
+            ikm.extend_from_slice(part);
+        }
+
+        match hkdf_sha256(&ikm, &[], COMBINE_ENTROPY_INFO, KEY_SIZE) {
+            Ok(key) => VaultBuffer::success(key),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(buffer: &VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let out = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        out
+    }
+
+    #[test]
+    fn test_hkdf_rfc5869_case_1() {
+        let ikm = [0x0bu8; 22];
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+
+        unsafe {
+            let okm = vault_hkdf(ikm.as_ptr(), 22, salt.as_ptr(), 13, info.as_ptr(), 10, 42);
+            let expected = "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865";
+            let hex: String = take(&okm).iter().map(|b| format!("{b:02x}")).collect();
+            assert_eq!(hex, expected);
+        }
+    }
+
+    #[test]
+    fn test_hkdf_extract_expand() {
+        // RFC 5869 test case 1, one step at a time
+        let ikm = [0x0bu8; 22];
+        let salt: Vec<u8> = (0x00..=0x0c).collect();
+        let info: Vec<u8> = (0xf0..=0xf9).collect();
+        let hex = |bytes: Vec<u8>| -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() };
+
+        unsafe {
+            let prk = take(&vault_hkdf_extract(salt.as_ptr(), 13, ikm.as_ptr(), 22));
+            assert_eq!(hex(prk.clone()), "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5");
+            let okm = take(&vault_hkdf_expand(prk.as_ptr(), 32, info.as_ptr(), 10, 42));
+            assert_eq!(hex(okm), "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865");
+
+            // No salt, as vault_hkdf treats it
+            let prk = take(&vault_hkdf_extract(ptr::null(), 0, ikm.as_ptr(), 22));
+            let okm = take(&vault_hkdf_expand(prk.as_ptr(), 32, b"sync".as_ptr(), 4, 32));
+            assert_eq!(okm, take(&vault_hkdf(ikm.as_ptr(), 22, ptr::null(), 0, b"sync".as_ptr(), 4, 32)));
+
+            assert_eq!(vault_hkdf_extract(salt.as_ptr(), 13, ikm.as_ptr(), 0).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_hkdf_extract(ptr::null(), 13, ikm.as_ptr(), 22).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_hkdf_expand(prk.as_ptr(), 31, ptr::null(), 0, 32).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_hkdf_expand(prk.as_ptr(), 32, ptr::null(), 0, 0).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_hkdf_expand(prk.as_ptr(), 32, ptr::null(), 0, 255 * 32 + 1).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_hkdf_expand(prk.as_ptr(), 32, ptr::null(), 4, 32).error, ERR_INVALID_INPUT);
+        }
+    }
+
+    #[test]
+    fn test_derive_subkeys_match_hkdf() {
+        let master = [0x42u8; 32];
+        let names: [&[u8]; 4] = [b"enc", b"mac", b"sync", b"index"];
+        let labels: Vec<VaultSlice> = names.iter().map(|n| VaultSlice { ptr: n.as_ptr(), len: n.len() as u32 }).collect();
+
+        unsafe {
+            let mut out: Vec<VaultBuffer> = (0..4).map(|_| VaultBuffer::error(0)).collect();
+            let rc = vault_derive_subkeys(master.as_ptr(), 32, labels.as_ptr(), 4, out.as_mut_ptr());
+            assert_eq!(rc, 0);
+            let subkeys: Vec<Vec<u8>> = out.iter().map(|b| take(b)).collect();
+
+            for (i, name) in names.iter().enumerate() {
+                assert_eq!(subkeys[i].len(), KEY_SIZE);
+                assert!(subkeys[i + 1..].iter().all(|other| other != &subkeys[i]));
+
+                let single = vault_hkdf(master.as_ptr(), 32, ptr::null(), 0, name.as_ptr(), name.len() as u32, 32);
+                assert_eq!(take(&single), subkeys[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_derive_subkeys_duplicates_and_errors() {
+        let master = [0x42u8; 32];
+        let label = b"enc";
+        let labels = [VaultSlice { ptr: label.as_ptr(), len: 3 }; 2];
+
+        unsafe {
+            let mut out: Vec<VaultBuffer> = (0..2).map(|_| VaultBuffer::error(0)).collect();
+            assert_eq!(vault_derive_subkeys(master.as_ptr(), 32, labels.as_ptr(), 2, out.as_mut_ptr()), 0);
+            assert_eq!(take(&out[0]), take(&out[1]));
+
+            assert_eq!(vault_derive_subkeys(master.as_ptr(), 32, labels.as_ptr(), 0, out.as_mut_ptr()), ERR_INVALID_INPUT);
+
+            // A bad label frees the subkeys already written
+            let bad = [labels[0], VaultSlice { ptr: ptr::null(), len: 4 }];
+            assert_eq!(vault_derive_subkeys(master.as_ptr(), 32, bad.as_ptr(), 2, out.as_mut_ptr()), ERR_INVALID_INPUT);
+            assert!(out.iter().all(|b| b.data.is_null() && b.error == ERR_INVALID_INPUT));
+        }
+    }
+
+    #[test]
+    fn test_combine_entropy() {
+        let a = [0x11u8; 32];
+        let b = [0x22u8; 16];
+        let combine = |x: &[u8], y: &[u8]| unsafe { take(&vault_combine_entropy(x.as_ptr(), x.len() as u32, y.as_ptr(), y.len() as u32)) };
+
+        let key = combine(&a, &b);
+        assert_eq!(key.len(), KEY_SIZE);
+
+        // Canonical order: swapping the parties gives the same key
+        assert_eq!(combine(&b, &a), key);
+
+        // Each contribution matters
+        let mut a2 = a;
+        a2[31] ^= 1;
+        assert_ne!(combine(&a2, &b), key);
+        let mut b2 = b;
+        b2[0] ^= 1;
+        assert_ne!(combine(&a, &b2), key);
+
+        // Length prefixes keep the split point unambiguous
+        assert_ne!(combine(&[0x11u8; 24], &[0x11u8; 24]), combine(&[0x11u8; 16], &[0x11u8; 32]));
+
+        unsafe {
+            assert_eq!(vault_combine_entropy(a.as_ptr(), 32, b.as_ptr(), 15).error, ERR_INVALID_INPUT);
+            assert_eq!(vault_combine_entropy(ptr::null(), 32, b.as_ptr(), 16).error, ERR_INVALID_INPUT);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Counter-Derived (Synthetic) Nonces
+//!
+//! For protocols that keep a monotonic message counter anyway, the nonce can
+//! be derived from it instead of drawn at random and stored: the caller
+//! persists an 8-byte counter rather than a 24-byte nonce, and sealing needs
+//! no randomness.
+//!
+//! The nonce is keyed BLAKE3 of the counter under a nonce key derived from
+//! the sealing key, truncated to 24 bytes. It depends only on the counter,
+//! so a blob can be opened without any stored nonce.
+//!
+//! **The caller must never reuse a counter value under the same key.** Two
+//! messages sealed at one counter share a nonce, which reveals the XOR of
+//! their plaintexts and allows forgeries. If a counter cannot be guaranteed
+//! unique (restored backups, multiple devices), use `vault_seal` instead.
+//!
+//! ## Format
+//!
+//! `format (1, 0x07) || counter (8, LE) || ciphertext || tag (16)`
+//!
+//! The header (format, counter) is authenticated as associated data.
+
+use std::slice;
+
+use super::*;
+
+/// Domain separation for the nonce key
+const SYNTHETIC_NONCE_CONTEXT: &str = "vault_core 2025-01 synthetic nonce key";
+
+/// Size of the authenticated header: format || counter
+const SYNTHETIC_HEADER_SIZE: usize = FORMAT_HEADER_SIZE + 8;
+
+/// Derive the nonce for `counter` under `key`.
+fn synthetic_nonce(key: &[u8], counter: u64) -> [u8; NONCE_SIZE] {
+    let nonce_key = Zeroizing::new(blake3::derive_key(SYNTHETIC_NONCE_CONTEXT, key));
+    let hash = blake3::keyed_hash(&nonce_key, &counter.to_le_bytes());
+
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce.copy_from_slice(&hash.as_bytes()[..NONCE_SIZE]);
+    nonce
+}
+
+/// Encrypt under a nonce derived from `counter`.
+///
+/// Output is deterministic for a given key, counter and plaintext.
+///
+/// # Format
+///
+/// Output: `format (1) || counter (8) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - `counter` must never have been used before with this key
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_seal_synthetic(
+    key: *const u8,
+    key_len: u32,
+    counter: u64,
+    plaintext: *const u8,
+    plaintext_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if plaintext.is_null() && plaintext_len != 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let cipher = match XChaCha20Poly1305::new_from_slice(key_slice) {
+            Ok(c) => c,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+        let nonce = synthetic_nonce(key_slice, counter);
+
+        let mut output = match output_buffer(SYNTHETIC_HEADER_SIZE + plaintext_slice.len() + TAG_SIZE) {
+            Ok(b) => Zeroizing::new(b),
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.push(FORMAT_SYNTHETIC);
+        output.extend_from_slice(&counter.to_le_bytes());
+        output.extend_from_slice(plaintext_slice);
+
+        let (header, body) = output.split_at_mut(SYNTHETIC_HEADER_SIZE);
+        let tag = match cipher.encrypt_in_place_detached(XNonce::from_slice(&nonce), header, body) {
+            Ok(t) => t,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+        output.extend_from_slice(&tag);
+
+        VaultBuffer::success(mem::take(&mut *output))
+    })
+}
+
+/// Decrypt data sealed with `vault_seal_synthetic`.
+///
+/// The counter is read from the blob and authenticated, so a blob whose
+/// counter was altered fails as `ERR_DECRYPT_FAILED`. Rejecting stale
+/// counters (replay) is up to the caller, which can read the counter from
+/// bytes 1..9 before unsealing.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_unseal_synthetic(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = SYNTHETIC_HEADER_SIZE + TAG_SIZE;
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, body) = sealed_slice.split_at(SYNTHETIC_HEADER_SIZE);
+        if header[0] != FORMAT_SYNTHETIC {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+        let mut counter_bytes = [0u8; 8];
+        counter_bytes.copy_from_slice(&header[1..]);
+        let counter = u64::from_le_bytes(counter_bytes);
+
+        let cipher = match XChaCha20Poly1305::new_from_slice(key_slice) {
+            Ok(c) => c,
+            Err(_) => return VaultBuffer::error(ERR_INVALID_INPUT),
+        };
+        let nonce = synthetic_nonce(key_slice, counter);
+
+        let (ciphertext, tag) = body.split_at(body.len() - TAG_SIZE);
+        let mut plaintext = match Secret::copy_of(ciphertext) {
+            Ok(p) => p,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        if cipher
+            .decrypt_in_place_detached(XNonce::from_slice(&nonce), header, &mut plaintext, Tag::from_slice(tag))
+            .is_err()
+        {
+            return VaultBuffer::error(ERR_DECRYPT_FAILED);
+        }
+
+        VaultBuffer::success(plaintext.into_inner())
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn seal(key: &[u8], counter: u64, plaintext: &[u8]) -> Vec<u8> {
+        let result = vault_seal_synthetic(key.as_ptr(), 32, counter, plaintext.as_ptr(), plaintext.len() as u32);
+        assert_eq!(result.error, 0);
+        let sealed = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+        vault_free(result.data, result.len);
+        sealed
+    }
+
+    #[test]
+    fn test_synthetic_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintext = b"message 7";
+
+        unsafe {
+            let sealed = seal(&key, 7, plaintext);
+            assert_eq!(sealed.len(), SYNTHETIC_HEADER_SIZE + plaintext.len() + TAG_SIZE);
+
+            let result = vault_unseal_synthetic(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32);
+            assert_eq!(result.error, 0);
+            assert_eq!(slice::from_raw_parts(result.data, result.len as usize), plaintext);
+            vault_free(result.data, result.len);
+        }
+    }
+
+    #[test]
+    fn test_synthetic_deterministic() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            assert_eq!(seal(&key, 7, b"payload"), seal(&key, 7, b"payload"));
+            assert_ne!(seal(&key, 7, b"payload"), seal(&key, 8, b"payload"));
+            assert_ne!(synthetic_nonce(&key, 7), synthetic_nonce(&[0x43u8; 32], 7));
+        }
+    }
+
+    #[test]
+    fn test_synthetic_counter_altered() {
+        let key = [0x42u8; 32];
+
+        unsafe {
+            let mut sealed = seal(&key, 7, b"payload");
+            sealed[1..9].copy_from_slice(&8u64.to_le_bytes());
+
+            let result = vault_unseal_synthetic(key.as_ptr(), 32, sealed.as_ptr(), sealed.len() as u32);
+            assert_eq!(result.error, ERR_DECRYPT_FAILED);
+        }
+    }
+}
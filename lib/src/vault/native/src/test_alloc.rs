@@ -0,0 +1,134 @@
+//! Test Allocator
+//!
+//! A global allocator for the test build that can inspect memory as it is
+//! freed. A test arms a 16-byte needle on its own thread; every deallocation
+//! on that thread is scanned for it, so a secret that reaches the allocator
+//! without being wiped is counted. Realloc goes through the default
+//! alloc/copy/dealloc path so the old block is scanned too.
+//!
+//! Separately, `Secret` buffers register their allocation while a test is
+//! watching, and each one must be all zeros by the time it is freed.
+//!
+//! A test can also make allocations above a size fail on its thread, to
+//! check that running out of memory is reported rather than aborting.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Length of the byte pattern a test watches for
+pub(crate) const NEEDLE_SIZE: usize = 16;
+
+/// Live `Secret` allocations tracked per thread (extras go unchecked)
+const WATCH_SLOTS: usize = 32;
+
+struct ScanningAllocator;
+
+thread_local! {
+    static NEEDLE: Cell<Option<[u8; NEEDLE_SIZE]>> = const { Cell::new(None) };
+    static HITS: Cell<usize> = const { Cell::new(0) };
+    static WATCHING: Cell<bool> = const { Cell::new(false) };
+    static WATCHED: Cell<[usize; WATCH_SLOTS]> = const { Cell::new([0; WATCH_SLOTS]) };
+    static DIRTY: Cell<usize> = const { Cell::new(0) };
+    static FAIL_FROM: Cell<usize> = const { Cell::new(usize::MAX) };
+}
+
+/// Whether an allocation of `layout` should be refused on this thread.
+fn refused(layout: Layout) -> bool {
+    FAIL_FROM.try_with(Cell::get).is_ok_and(|min| layout.size() >= min)
+}
+
+/// Remove `ptr` from the watch list, returning whether it was there.
+fn take_watched(ptr: *const u8) -> bool {
+    WATCHED
+        .try_with(|w| {
+            let mut slots = w.get();
+            let found = slots.iter_mut().find(|slot| **slot == ptr as usize);
+            let hit = found.map(|slot| *slot = 0).is_some();
+            w.set(slots);
+            hit
+        })
+        .unwrap_or(false)
+}
+
+unsafe impl GlobalAlloc for ScanningAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if refused(layout) {
+            return std::ptr::null_mut();
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        if refused(layout) {
+            return std::ptr::null_mut();
+        }
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // `try_with` because thread-locals may already be gone at thread exit
+        if let Ok(Some(needle)) = NEEDLE.try_with(Cell::get) {
+            let freed = std::slice::from_raw_parts(ptr, layout.size());
+            if freed.windows(NEEDLE_SIZE).any(|w| w == needle) {
+                let _ = HITS.try_with(|h| h.set(h.get() + 1));
+            }
+        }
+        if take_watched(ptr) {
+            let freed = std::slice::from_raw_parts(ptr, layout.size());
+            if freed.iter().any(|&b| b != 0) {
+                let _ = DIRTY.try_with(|d| d.set(d.get() + 1));
+            }
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: ScanningAllocator = ScanningAllocator;
+
+/// Run `f` with `needle` armed and return how many freed blocks contained it.
+pub(crate) fn leaked_copies(needle: [u8; NEEDLE_SIZE], f: impl FnOnce()) -> usize {
+    HITS.with(|h| h.set(0));
+    NEEDLE.with(|n| n.set(Some(needle)));
+    f();
+    NEEDLE.with(|n| n.set(None));
+    HITS.with(Cell::get)
+}
+
+/// Check the allocation at `ptr` when it is freed (no-op unless watching).
+pub(crate) fn watch(ptr: *const u8) {
+    if !WATCHING.with(Cell::get) {
+        return;
+    }
+    WATCHED.with(|w| {
+        let mut slots = w.get();
+        if let Some(slot) = slots.iter_mut().find(|slot| **slot == 0) {
+            *slot = ptr as usize;
+        }
+        w.set(slots);
+    });
+}
+
+/// Stop checking `ptr`; its owner has handed it on.
+pub(crate) fn unwatch(ptr: *const u8) {
+    take_watched(ptr);
+}
+
+/// Run `f` and return how many `Secret` allocations were freed non-zero.
+pub(crate) fn dirty_secret_frees(f: impl FnOnce()) -> usize {
+    DIRTY.with(|d| d.set(0));
+    WATCHED.with(|w| w.set([0; WATCH_SLOTS]));
+    WATCHING.with(|w| w.set(true));
+    f();
+    WATCHING.with(|w| w.set(false));
+    WATCHED.with(|w| w.set([0; WATCH_SLOTS]));
+    DIRTY.with(Cell::get)
+}
+
+/// Run `f` with every allocation of `min_size` bytes or more failing.
+pub(crate) fn with_failing_alloc<T>(min_size: usize, f: impl FnOnce() -> T) -> T {
+    FAIL_FROM.with(|m| m.set(min_size));
+    let result = f();
+    FAIL_FROM.with(|m| m.set(usize::MAX));
+    result
+}
@@ -0,0 +1,244 @@
+//! Time-Lock Sealing
+//!
+//! Argon2 makes each passphrase guess memory-hard; this adds a tunable
+//! time-hard gate on top for exported backups. The encryption subkey is the
+//! end of a sequential BLAKE3 hash chain of `difficulty` steps. Each step
+//! depends on the previous one, so the work cannot be parallelized, and
+//! every decryption attempt (including every guess at the key) must redo it.
+//!
+//! The difficulty is chosen by whoever seals the blob, and unsealing always
+//! pays it. Only unseal blobs from sources you would accept that cost from.
+//! The header is read before anything is authenticated, so the difficulty is
+//! capped at `MAX_TIMELOCK_DIFFICULTY` (about a minute of hashing); a larger
+//! value is rejected as corrupt before any work is done.
+//!
+//! ## Format
+//!
+//! `format (1, 0x03) || difficulty (8, LE) || salt (16) || nonce (24) || ciphertext || tag (16)`
+//!
+//! The header (format, difficulty, salt) is authenticated as associated data.
+
+use std::slice;
+
+use super::*;
+
+/// Size of the authenticated header: format || difficulty || salt
+const TIMELOCK_HEADER_SIZE: usize = FORMAT_HEADER_SIZE + 8 + SALT_SIZE;
+
+const TIMELOCK_CONTEXT: &str = "vault_core 2025-01 timelock chain";
+
+/// Largest difficulty accepted when sealing or unsealing
+pub(crate) const MAX_TIMELOCK_DIFFICULTY: u64 = 1 << 30;
+
+/// Read the difficulty from a time-lock header, refusing zero and anything
+/// above `MAX_TIMELOCK_DIFFICULTY`.
+pub(crate) fn header_difficulty(header: &[u8]) -> VaultResult<u64> {
+    let mut difficulty_bytes = [0u8; 8];
+    difficulty_bytes.copy_from_slice(&header[FORMAT_HEADER_SIZE..FORMAT_HEADER_SIZE + 8]);
+    let difficulty = u64::from_le_bytes(difficulty_bytes);
+    if difficulty == 0 || difficulty > MAX_TIMELOCK_DIFFICULTY {
+        return Err(error_detail(ERR_CORRUPT_DATA, format_args!("time-lock difficulty {difficulty} out of range")));
+    }
+    Ok(difficulty)
+}
+
+/// Walk the sequential chain from `key` and `salt` for `difficulty` steps.
+fn timelock_subkey(key: &[u8], salt: &[u8], difficulty: u64) -> Zeroizing<[u8; KEY_SIZE]> {
+    let mut hasher = blake3::Hasher::new_derive_key(TIMELOCK_CONTEXT);
+    hasher.update(key);
+    hasher.update(salt);
+    let mut state = Zeroizing::new(*hasher.finalize().as_bytes());
+
+    for _ in 0..difficulty {
+        *state = *blake3::hash(state.as_ref()).as_bytes();
+    }
+    state
+}
+
+/// Seal with a sequential-work gate on the encryption subkey.
+///
+/// # Format
+///
+/// Output: `format (1) || difficulty (8) || salt (16) || nonce (24) || ciphertext || tag (16)`
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `plaintext` must be valid for `plaintext_len` bytes
+/// - `difficulty` must be at least 1 and at most `MAX_TIMELOCK_DIFFICULTY`
+/// - Returned buffer must be freed with `vault_free`
+#[no_mangle]
+pub unsafe extern "C" fn vault_timelock_seal(
+    key: *const u8,
+    key_len: u32,
+    plaintext: *const u8,
+    plaintext_len: u32,
+    difficulty: u64,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if (plaintext.is_null() && plaintext_len != 0) || difficulty == 0 || difficulty > MAX_TIMELOCK_DIFFICULTY {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let plaintext_slice: &[u8] = if plaintext_len == 0 { &[] } else { slice::from_raw_parts(plaintext, plaintext_len as usize) };
+
+        let mut salt = [0u8; SALT_SIZE];
+        if let Err(code) = random_bytes(&mut salt) {
+            return VaultBuffer::error(code);
+        }
+
+        let mut header = Vec::with_capacity(TIMELOCK_HEADER_SIZE);
+        header.push(FORMAT_TIMELOCK);
+        header.extend_from_slice(&difficulty.to_le_bytes());
+        header.extend_from_slice(&salt);
+
+        let subkey = timelock_subkey(key_slice, &salt, difficulty);
+        let sealed = match xchacha_seal(subkey.as_ref(), plaintext_slice, &header) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+
+        let mut output = match output_buffer(header.len() + sealed.len()) {
+            Ok(o) => o,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        output.extend_from_slice(&header);
+        output.extend_from_slice(&sealed);
+        VaultBuffer::success(output)
+    })
+}
+
+/// Decrypt data sealed with `vault_timelock_seal`, redoing the sequential work.
+///
+/// # Safety
+///
+/// - `key` must point to exactly 32 bytes (`key_len` must be 32)
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_CORRUPT_DATA` for a difficulty
+/// of zero or above `MAX_TIMELOCK_DIFFICULTY`, or `ERR_DECRYPT_FAILED`
+#[no_mangle]
+pub unsafe extern "C" fn vault_timelock_unseal(
+    key: *const u8,
+    key_len: u32,
+    sealed: *const u8,
+    sealed_len: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        let min_len = TIMELOCK_HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+        if sealed.is_null() {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        if (sealed_len as usize) < min_len {
+            return VaultBuffer::error(ERR_CORRUPT_DATA);
+        }
+        let key_slice = match key_arg(key, key_len) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let (header, body) = sealed_slice.split_at(TIMELOCK_HEADER_SIZE);
+        if header[0] != FORMAT_TIMELOCK {
+            return VaultBuffer::error(ERR_UNSUPPORTED_VERSION);
+        }
+        let difficulty = match header_difficulty(header) {
+            Ok(d) => d,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let salt = &header[9..];
+
+        let subkey = timelock_subkey(key_slice, salt, difficulty);
+        match xchacha_open(subkey.as_ref(), body, header) {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_timelock_roundtrip() {
+        let key = [0x42u8; 32];
+        let plaintext = b"exported backup";
+
+        for difficulty in [1u64, 100, 10_000] {
+            unsafe {
+                let sealed = vault_timelock_seal(key.as_ptr(), 32, plaintext.as_ptr(), plaintext.len() as u32, difficulty);
+                assert_eq!(sealed.error, 0);
+
+                let unsealed = vault_timelock_unseal(key.as_ptr(), 32, sealed.data, sealed.len);
+                assert_eq!(unsealed.error, 0);
+                assert_eq!(slice::from_raw_parts(unsealed.data, unsealed.len as usize), plaintext);
+
+                // The difficulty is authenticated (flipping bit 0 of 1 would give the
+                // out-of-range zero, which is refused earlier)
+                *sealed.data.add(1) ^= 2;
+                let tampered = vault_timelock_unseal(key.as_ptr(), 32, sealed.data, sealed.len);
+                assert_eq!(tampered.error, ERR_DECRYPT_FAILED);
+
+                vault_free(sealed.data, sealed.len);
+                vault_free(unsealed.data, unsealed.len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_timelock_difficulty_costs_time() {
+        let key = [0x42u8; 32];
+        let salt = [0u8; SALT_SIZE];
+
+        let start = Instant::now();
+        timelock_subkey(&key, &salt, 1_000);
+        let easy = start.elapsed();
+
+        let start = Instant::now();
+        timelock_subkey(&key, &salt, 1_000_000);
+        let hard = start.elapsed();
+
+        assert!(hard > easy * 10, "easy {easy:?}, hard {hard:?}");
+        assert_ne!(*timelock_subkey(&key, &salt, 1), *timelock_subkey(&key, &salt, 2));
+    }
+
+    #[test]
+    fn test_timelock_difficulty_capped() {
+        let key = [0x42u8; 32];
+        let plaintext = b"exported backup";
+
+        unsafe {
+            let too_hard = vault_timelock_seal(key.as_ptr(), 32, plaintext.as_ptr(), 15, MAX_TIMELOCK_DIFFICULTY + 1);
+            assert_eq!(too_hard.error, ERR_INVALID_INPUT);
+
+            // A forged header is refused before any hashing
+            let sealed = vault_timelock_seal(key.as_ptr(), 32, plaintext.as_ptr(), 15, 1);
+            assert_eq!(sealed.error, 0);
+            let mut forged = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+            for difficulty in [u64::MAX, MAX_TIMELOCK_DIFFICULTY + 1, 0] {
+                forged[1..9].copy_from_slice(&difficulty.to_le_bytes());
+                let start = Instant::now();
+                let result = vault_timelock_unseal(key.as_ptr(), 32, forged.as_ptr(), forged.len() as u32);
+                assert_eq!(result.error, ERR_CORRUPT_DATA);
+                assert!(start.elapsed().as_secs() < 1);
+                assert_eq!(vault_validate_sealed(forged.as_ptr(), forged.len() as u32), ERR_CORRUPT_DATA);
+            }
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+}
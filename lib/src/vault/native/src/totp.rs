@@ -0,0 +1,251 @@
+//! TOTP Second Factor
+//!
+//! Time-based one-time passwords (RFC 6238) from an authenticator app,
+//! checked at unlock and optionally mixed into key derivation. Codes are
+//! 6 digits over HMAC-SHA256; enroll the app with `algorithm=SHA256` in its
+//! `otpauth://` URI, as apps that only do HMAC-SHA1 produce other codes.
+//!
+//! The library reads no clock. Callers pass the time step, normally
+//! `unix_seconds / 30`, so tests and devices with a trusted time source
+//! agree on it.
+//!
+//! **What the factor protects.** Every code follows from the TOTP secret,
+//! so a key bound to a code is only as safe as wherever `totp_secret` is
+//! kept; the phone is not involved once the secret is on this device. A
+//! code has 10^6 values, so with the passphrase known it costs at most that
+//! many Argon2 runs to find.
+//!
+//! **Keys change every step.** `vault_derive_key_totp` gives a different key
+//! for each step, so anything sealed under it opens only by deriving for the
+//! same step. It suits short-lived seals (an unlock handing a secret to the
+//! next screen), not data at rest; for that, bind a device secret that does
+//! not rotate with `vault_derive_key_2fa`.
+
+use std::slice;
+
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+
+use crate::kdf::default_argon2_params;
+
+use super::*;
+
+/// Digits in a code
+const TOTP_DIGITS: usize = 6;
+
+/// `10^TOTP_DIGITS`
+const TOTP_MODULUS: u32 = 1_000_000;
+
+/// Smallest TOTP secret accepted (RFC 4226 requires at least 128 bits)
+const MIN_TOTP_SECRET_SIZE: usize = 16;
+
+/// Steps either side of the given one that `vault_totp_verify` accepts
+const TOTP_SKEW_STEPS: u64 = 1;
+
+/// Borrow a TOTP secret argument.
+unsafe fn totp_secret_arg<'a>(secret: *const u8, secret_len: u32) -> VaultResult<&'a [u8]> {
+    if secret.is_null() || (secret_len as usize) < MIN_TOTP_SECRET_SIZE {
+        return Err(ERR_INVALID_INPUT);
+    }
+    Ok(slice::from_raw_parts(secret, secret_len as usize))
+}
+
+/// The code for time step `step` (RFC 4226 dynamic truncation).
+fn totp_code(secret: &[u8], step: u64) -> VaultResult<u32> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).map_err(|_| ERR_INVALID_INPUT)?;
+    mac.update(&step.to_be_bytes());
+    let digest = Secret::new(<[u8; 32]>::from(mac.finalize().into_bytes()));
+
+    let digest = digest.as_ref();
+    let offset = (digest[digest.len() - 1] & 0x0F) as usize;
+    let mut word = [0u8; 4];
+    word.copy_from_slice(&digest[offset..offset + 4]);
+    Ok((u32::from_be_bytes(word) & 0x7FFF_FFFF) % TOTP_MODULUS)
+}
+
+/// Check a code entered by the user against the steps around `time_step`.
+///
+/// Accepts the code for `time_step` and for one step either side, for
+/// clock skew and codes typed just as they rolled over. All three are
+/// computed and compared in constant time whichever matches.
+///
+/// # Safety
+///
+/// - `totp_secret` must be valid for `totp_secret_len` bytes (at least 16)
+/// - `out_step` must be writable, or null to skip
+///
+/// # Returns
+///
+/// 0 on match, with the step whose code matched written to `out_step`,
+/// `ERR_DECRYPT_FAILED` on mismatch, or `ERR_INVALID_INPUT` for a short or
+/// null secret or a code over 6 digits
+#[no_mangle]
+pub unsafe extern "C" fn vault_totp_verify(
+    totp_secret: *const u8,
+    totp_secret_len: u32,
+    code: u32,
+    time_step: u64,
+    out_step: *mut u64,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        let secret_slice = match totp_secret_arg(totp_secret, totp_secret_len) {
+            Ok(s) => s,
+            Err(code) => return code,
+        };
+        if code >= TOTP_MODULUS {
+            return ERR_INVALID_INPUT;
+        }
+
+        let first = time_step.saturating_sub(TOTP_SKEW_STEPS);
+        let last = time_step.saturating_add(TOTP_SKEW_STEPS);
+        let mut matched = None;
+        for step in first..=last {
+            let expected = match totp_code(secret_slice, step) {
+                Ok(c) => c,
+                Err(code) => return code,
+            };
+            if ct_eq(&expected.to_be_bytes(), &code.to_be_bytes()) && matched.is_none() {
+                matched = Some(step);
+            }
+        }
+
+        match matched {
+            Some(step) => {
+                if !out_step.is_null() {
+                    *out_step = step;
+                }
+                0
+            }
+            None => ERR_DECRYPT_FAILED,
+        }
+    })
+}
+
+/// Derive a key from a passphrase and the TOTP code for one time step.
+///
+/// Runs Argon2id at the default costs, as `vault_derive_key` does, with the
+/// 6-digit code (as ASCII) as Argon2's secret input, so every guess at the
+/// code costs a full derivation. Read the module documentation first: the
+/// key is different for every step.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `totp_secret` must be valid for `totp_secret_len` bytes (at least 16)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte key, or `ERR_INVALID_INPUT` for a null
+/// pointer, an empty passphrase or a short secret
+#[no_mangle]
+pub unsafe extern "C" fn vault_derive_key_totp(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    totp_secret: *const u8,
+    totp_secret_len: u32,
+    time_step: u64,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let secret_slice = match totp_secret_arg(totp_secret, totp_secret_len) {
+            Ok(s) => s,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+
+        let code = match totp_code(secret_slice, time_step) {
+            Ok(c) => c,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        let mut digits = Secret::new([0u8; TOTP_DIGITS]);
+        let mut rest = code;
+        for digit in digits.as_mut().iter_mut().rev() {
+            *digit = b'0' + (rest % 10) as u8;
+            rest /= 10;
+        }
+
+        let (m_cost, t_cost, p_cost) = default_argon2_params();
+        match argon2_key_with(passphrase_slice, salt_slice, digits.as_ref(), m_cost, t_cost, p_cost, Algorithm::Argon2id, true) {
+            Ok(key) => VaultBuffer::success(key.to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 6238 appendix B secret for HMAC-SHA256
+    const RFC_SECRET: &[u8] = b"12345678901234567890123456789012";
+
+    #[test]
+    fn test_totp_rfc6238_sha256() {
+        // Appendix B, last 6 of the 8 digits
+        for (unix_time, code) in [(59u64, 119246), (1111111109, 84774), (1234567890, 819424), (2000000000, 698825)] {
+            assert_eq!(totp_code(RFC_SECRET, unix_time / 30).unwrap(), code, "T = {unix_time}");
+        }
+    }
+
+    #[test]
+    fn test_totp_verify_skew() {
+        let step = 1234567890 / 30;
+        let verify = |code, step, out: *mut u64| unsafe { vault_totp_verify(RFC_SECRET.as_ptr(), 32, code, step, out) };
+
+        let mut matched = 0u64;
+        assert_eq!(verify(819424, step, &mut matched), 0);
+        assert_eq!(matched, step);
+        assert_eq!(verify(819424, step + 1, &mut matched), 0);
+        assert_eq!(matched, step);
+        assert_eq!(verify(819424, step - 1, ptr::null_mut()), 0);
+        assert_eq!(verify(819424, step + 2, ptr::null_mut()), ERR_DECRYPT_FAILED);
+        assert_eq!(verify(819425, step, ptr::null_mut()), ERR_DECRYPT_FAILED);
+
+        assert_eq!(verify(TOTP_MODULUS, step, ptr::null_mut()), ERR_INVALID_INPUT);
+        assert_eq!(unsafe { vault_totp_verify(RFC_SECRET.as_ptr(), 15, 819424, step, ptr::null_mut()) }, ERR_INVALID_INPUT);
+    }
+
+    #[test]
+    fn test_derive_key_totp() {
+        let _defaults = crate::kdf::lock_default_params();
+        let passphrase = b"time-bound";
+        let salt = [0x07u8; SALT_SIZE];
+        let step = 1234567890 / 30;
+
+        let derive = |step| unsafe {
+            let result = vault_derive_key_totp(passphrase.as_ptr(), 10, salt.as_ptr(), RFC_SECRET.as_ptr(), 32, step);
+            assert_eq!(result.error, 0);
+            let key = slice::from_raw_parts(result.data, result.len as usize).to_vec();
+            vault_free(result.data, result.len);
+            key
+        };
+
+        // Stable for a step, different for the next
+        let key = derive(step);
+        assert_eq!(key.len(), KEY_SIZE);
+        assert_eq!(derive(step), key);
+        assert_ne!(derive(step + 1), key);
+
+        // The code is Argon2's secret input
+        let (m_cost, t_cost, p_cost) = default_argon2_params();
+        let expected = argon2_key(passphrase, &salt, b"819424", m_cost, t_cost, p_cost, Algorithm::Argon2id).unwrap();
+        assert_eq!(key, expected.as_ref());
+
+        unsafe {
+            let short = vault_derive_key_totp(passphrase.as_ptr(), 10, salt.as_ptr(), RFC_SECRET.as_ptr(), 15, step);
+            assert_eq!(short.error, ERR_INVALID_INPUT);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Timing-Padded Unlock
+//!
+//! Deriving a key and then failing to unseal returns sooner than a
+//! successful unlock that goes on to copy out the plaintext, and the
+//! difference is visible to anyone timing the UI. `vault_unlock` does both
+//! steps and then sleeps until a caller-chosen minimum has elapsed, so
+//! success and failure are indistinguishable as long as the minimum exceeds
+//! the real work. Pick it above the slowest expected Argon2 run on the
+//! device (e.g. from calibration).
+//!
+//! Only one `vault_unlock` runs at a time per process. A second call made
+//! while one is in progress (say from a widget and the main app at once)
+//! returns `ERR_BUSY` immediately instead of allocating a second 64 MiB of
+//! Argon2 memory; retry once the first has finished.
+
+use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::kdf::argon2id_default;
+
+use super::*;
+
+/// Set while a `vault_unlock` is running
+static UNLOCK_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// The single unlock slot; released when dropped, including on unwind.
+struct UnlockSlot;
+
+impl UnlockSlot {
+    fn acquire() -> Option<Self> {
+        UNLOCK_IN_PROGRESS
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| Self)
+    }
+}
+
+impl Drop for UnlockSlot {
+    fn drop(&mut self) {
+        UNLOCK_IN_PROGRESS.store(false, Ordering::Release);
+    }
+}
+
+/// Derive a key from a passphrase and unseal a `vault_seal` blob with it,
+/// taking at least `min_millis` milliseconds whatever the outcome.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `sealed` must be valid for `sealed_len` bytes
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the plaintext, `ERR_DECRYPT_FAILED` for a wrong
+/// passphrase, or `ERR_BUSY` (without waiting for `min_millis`) if another
+/// unlock is in progress
+#[no_mangle]
+pub unsafe extern "C" fn vault_unlock(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    sealed: *const u8,
+    sealed_len: u32,
+    min_millis: u32,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || sealed.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+        let _slot = match UnlockSlot::acquire() {
+            Some(slot) => slot,
+            None => return VaultBuffer::error(ERR_BUSY),
+        };
+        let start = Instant::now();
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+
+        let result = argon2id_default(passphrase_slice, salt_slice)
+            .and_then(|key| open_blob(key.as_ref(), sealed_slice));
+
+        let floor = Duration::from_millis(min_millis as u64);
+        if let Some(remaining) = floor.checked_sub(start.elapsed()) {
+            thread::sleep(remaining);
+        }
+
+        match result {
+            Ok(plaintext) => VaultBuffer::success(plaintext),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_MILLIS: u32 = 1500;
+
+    #[test]
+    fn test_unlock_pads_success_and_failure() {
+        let _defaults = crate::kdf::lock_default_params();
+        let passphrase = b"unlock me";
+        let salt = [6u8; SALT_SIZE];
+        let plaintext = b"vault contents";
+
+        unsafe {
+            let key = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            assert_eq!(key.error, 0);
+            let sealed = vault_seal(key.data, plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            vault_free(key.data, key.len);
+
+            for (attempt, expected) in [(&b"unlock me"[..], 0), (&b"wrong one"[..], ERR_DECRYPT_FAILED)] {
+                let start = Instant::now();
+                let result = vault_unlock(
+                    attempt.as_ptr(),
+                    attempt.len() as u32,
+                    salt.as_ptr(),
+                    sealed.data,
+                    sealed.len,
+                    MIN_MILLIS,
+                );
+                assert!(start.elapsed() >= Duration::from_millis(MIN_MILLIS as u64));
+                assert_eq!(result.error, expected);
+                vault_free(result.data, result.len);
+            }
+
+            vault_free(sealed.data, sealed.len);
+        }
+    }
+
+    #[test]
+    fn test_unlock_rejects_concurrent_attempt() {
+        let _defaults = crate::kdf::lock_default_params();
+        let passphrase = b"unlock me";
+        let salt = [6u8; SALT_SIZE];
+
+        let sealed = unsafe {
+            let key = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            let sealed = vault_seal(key.data, b"x".as_ptr(), 1);
+            vault_free(key.data, key.len);
+            slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec()
+        };
+
+        let barrier = std::sync::Barrier::new(2);
+        let mut codes: Vec<i32> = thread::scope(|scope| {
+            let attempts: Vec<_> = (0..2)
+                .map(|_| {
+                    scope.spawn(|| unsafe {
+                        barrier.wait();
+                        let result = vault_unlock(
+                            passphrase.as_ptr(),
+                            passphrase.len() as u32,
+                            salt.as_ptr(),
+                            sealed.as_ptr(),
+                            sealed.len() as u32,
+                            MIN_MILLIS,
+                        );
+                        vault_free(result.data, result.len);
+                        result.error
+                    })
+                })
+                .collect();
+            attempts.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+
+        codes.sort();
+        assert_eq!(codes, [ERR_BUSY, 0]);
+
+        // The slot is free again afterwards
+        assert!(UnlockSlot::acquire().is_some());
+    }
+}
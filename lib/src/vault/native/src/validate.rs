@@ -0,0 +1,222 @@
+//! Sealed-Blob Validation
+//!
+//! Structural checks that need no key, so storage corruption can be reported
+//! as such before an Argon2 derivation or a decrypt is attempted, instead of
+//! surfacing later as an indistinct `ERR_DECRYPT_FAILED`.
+
+use std::slice;
+
+use crate::timelock::header_difficulty;
+
+use super::*;
+
+/// Check a sealed blob's framing without the key.
+///
+/// Verifies the blob is long enough to hold its header, nonce and tag for
+/// the format named by its first byte, and that any header fields are in
+/// range. This does not authenticate anything: a blob that passes can still
+/// fail to unseal, but one that fails is certainly unreadable.
+///
+/// # Safety
+///
+/// - `sealed` must be valid for `sealed_len` bytes
+///
+/// # Returns
+///
+/// 0 if the framing is well-formed, `ERR_UNSUPPORTED_VERSION` for an unknown
+/// format byte, or `ERR_CORRUPT_DATA` for a truncated or malformed blob
+#[no_mangle]
+pub unsafe extern "C" fn vault_validate_sealed(sealed: *const u8, sealed_len: u32) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        if sealed_len == 0 {
+            return ERR_CORRUPT_DATA;
+        }
+
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+        match validate_framing(sealed_slice) {
+            Ok(()) => 0,
+            Err(code) => code,
+        }
+    })
+}
+
+/// Check the framing of a non-empty blob against its format's layout.
+fn validate_framing(sealed: &[u8]) -> VaultResult<()> {
+    let min_len = match sealed[0] {
+        FORMAT_XCHACHA => FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE,
+        f if f == FORMAT_XCHACHA | FORMAT_COMPRESSED => FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE + 4,
+        FORMAT_SIV => FORMAT_HEADER_SIZE + TAG_SIZE,
+        FORMAT_TIMELOCK => FORMAT_HEADER_SIZE + 8 + SALT_SIZE + NONCE_SIZE + TAG_SIZE,
+        FORMAT_EXPIRING => FORMAT_HEADER_SIZE + 8 + NONCE_SIZE + TAG_SIZE,
+        FORMAT_STREAM => FORMAT_HEADER_SIZE + STREAM_NONCE_PREFIX_SIZE + 4 + TAG_SIZE,
+        FORMAT_HINTED => FORMAT_HEADER_SIZE + 2 + 2 * (NONCE_SIZE + TAG_SIZE),
+        FORMAT_SYNTHETIC => FORMAT_HEADER_SIZE + 8 + TAG_SIZE,
+        FORMAT_LOG => FORMAT_HEADER_SIZE + 4 + NONCE_SIZE + TAG_SIZE + 32,
+        FORMAT_IETF => FORMAT_HEADER_SIZE + 12 + TAG_SIZE,
+        FORMAT_BOUND | FORMAT_DOMAIN | FORMAT_COUNTER => FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE,
+        FORMAT_HEADED => FORMAT_HEADER_SIZE + 2 + NONCE_SIZE + TAG_SIZE,
+        _ => return Err(ERR_UNSUPPORTED_VERSION),
+    };
+    if sealed.len() < min_len {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    // The sealer never produces a time-lock difficulty out of range
+    if sealed[0] == FORMAT_TIMELOCK {
+        header_difficulty(sealed)?;
+    }
+
+    Ok(())
+}
+
+/// Report the exact plaintext length of a sealed blob without decrypting it.
+///
+/// The length follows from the blob's framing alone, so no key is needed
+/// and nothing is authenticated: a tampered blob can report a length and
+/// still fail to unseal. Compressed blobs keep their original length inside
+/// the ciphertext and are rejected.
+///
+/// # Safety
+///
+/// - `sealed` must be valid for `sealed_len` bytes
+///
+/// # Returns
+///
+/// The plaintext length in bytes, or a negative error code:
+/// `ERR_UNSUPPORTED_VERSION` for an unknown or compressed format, or
+/// `ERR_CORRUPT_DATA` for a truncated or malformed blob
+#[no_mangle]
+pub unsafe extern "C" fn vault_plaintext_len(sealed: *const u8, sealed_len: u32) -> i64 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if sealed.is_null() {
+            return ERR_INVALID_INPUT as i64;
+        }
+        if sealed_len == 0 {
+            return ERR_CORRUPT_DATA as i64;
+        }
+
+        let sealed_slice = slice::from_raw_parts(sealed, sealed_len as usize);
+        match plaintext_len(sealed_slice) {
+            Ok(len) => len as i64,
+            Err(code) => code as i64,
+        }
+    })
+}
+
+/// Compute the plaintext length of a non-empty blob from its framing.
+fn plaintext_len(sealed: &[u8]) -> VaultResult<usize> {
+    validate_framing(sealed)?;
+
+    let len = sealed.len();
+    let overhead = match sealed[0] {
+        FORMAT_XCHACHA | FORMAT_BOUND | FORMAT_DOMAIN | FORMAT_COUNTER => FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE,
+        FORMAT_SIV => FORMAT_HEADER_SIZE + TAG_SIZE,
+        FORMAT_TIMELOCK => FORMAT_HEADER_SIZE + 8 + SALT_SIZE + NONCE_SIZE + TAG_SIZE,
+        FORMAT_EXPIRING => FORMAT_HEADER_SIZE + 8 + NONCE_SIZE + TAG_SIZE,
+        FORMAT_SYNTHETIC => FORMAT_HEADER_SIZE + 8 + TAG_SIZE,
+        FORMAT_IETF => FORMAT_HEADER_SIZE + 12 + TAG_SIZE,
+        FORMAT_HEADED => {
+            let header_len = u16::from_le_bytes([sealed[1], sealed[2]]) as usize;
+            FORMAT_HEADER_SIZE + 2 + header_len + NONCE_SIZE + TAG_SIZE
+        }
+        FORMAT_HINTED => {
+            let hint_len = u16::from_le_bytes([sealed[1], sealed[2]]) as usize;
+            FORMAT_HEADER_SIZE + 2 + (NONCE_SIZE + hint_len + TAG_SIZE) + NONCE_SIZE + TAG_SIZE
+        }
+        FORMAT_LOG => {
+            // The length field covers a single entry; anything after its
+            // chain hash belongs to the next entry
+            let ciphertext_len = u32::from_le_bytes([sealed[1], sealed[2], sealed[3], sealed[4]]) as usize;
+            if len < FORMAT_HEADER_SIZE + 4 + NONCE_SIZE + ciphertext_len + TAG_SIZE + 32 {
+                return Err(ERR_CORRUPT_DATA);
+            }
+            return Ok(ciphertext_len);
+        }
+        FORMAT_STREAM => return stream_plaintext_len(sealed),
+        // Compressed: the original length is encrypted with the payload
+        _ => return Err(ERR_UNSUPPORTED_VERSION),
+    };
+    len.checked_sub(overhead).ok_or(ERR_CORRUPT_DATA)
+}
+
+/// Plaintext length of a stream blob: every chunk carries its own tag.
+fn stream_plaintext_len(sealed: &[u8]) -> VaultResult<usize> {
+    let header_size = FORMAT_HEADER_SIZE + STREAM_NONCE_PREFIX_SIZE + 4;
+    let size_field = &sealed[header_size - 4..header_size];
+    let chunk_size = u32::from_le_bytes([size_field[0], size_field[1], size_field[2], size_field[3]]) as usize;
+    if chunk_size == 0 || chunk_size > STREAM_CHUNK_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+
+    let body = sealed.len() - header_size;
+    let sealed_chunk = chunk_size + TAG_SIZE;
+    let (full, last) = (body / sealed_chunk, body % sealed_chunk);
+    if last != 0 && last < TAG_SIZE {
+        return Err(ERR_CORRUPT_DATA);
+    }
+    let chunks = full + usize::from(last != 0);
+    Ok(body - chunks * TAG_SIZE)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_sealed() {
+        let key = [0x42u8; 32];
+        let plaintext = b"framing";
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            let blob = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+            vault_free(sealed.data, sealed.len);
+
+            assert_eq!(vault_validate_sealed(blob.as_ptr(), blob.len() as u32), 0);
+
+            // Truncated below nonce + tag
+            let short = &blob[..FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE - 1];
+            assert_eq!(vault_validate_sealed(short.as_ptr(), short.len() as u32), ERR_CORRUPT_DATA);
+
+            // Unknown format byte
+            let mut unknown = blob.clone();
+            unknown[0] = 0x7F;
+            assert_eq!(vault_validate_sealed(unknown.as_ptr(), unknown.len() as u32), ERR_UNSUPPORTED_VERSION);
+        }
+    }
+
+    #[test]
+    fn test_plaintext_len() {
+        let key = [0x42u8; 32];
+        let plaintext = b"exact length please";
+
+        unsafe {
+            let sealed = vault_seal(key.as_ptr(), plaintext.as_ptr(), plaintext.len() as u32);
+            assert_eq!(sealed.error, 0);
+            let blob = slice::from_raw_parts(sealed.data, sealed.len as usize).to_vec();
+            vault_free(sealed.data, sealed.len);
+
+            assert_eq!(vault_plaintext_len(blob.as_ptr(), blob.len() as u32), plaintext.len() as i64);
+
+            let mut stream = Vec::new();
+            let long = vec![0x5Au8; STREAM_CHUNK_SIZE * 2 + 100];
+            crate::stream::stream_seal_to(&key, &long, &mut stream).unwrap();
+            assert_eq!(vault_plaintext_len(stream.as_ptr(), stream.len() as u32), long.len() as i64);
+
+            // Truncated below nonce + tag
+            let short = &blob[..FORMAT_HEADER_SIZE + NONCE_SIZE + TAG_SIZE - 1];
+            assert_eq!(vault_plaintext_len(short.as_ptr(), short.len() as u32), ERR_CORRUPT_DATA as i64);
+            assert_eq!(vault_plaintext_len(ptr::null(), 0), ERR_INVALID_INPUT as i64);
+        }
+    }
+}
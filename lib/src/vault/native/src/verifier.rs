@@ -0,0 +1,156 @@
+//! Passphrase Verifiers
+//!
+//! A stored value that answers "is this the right passphrase?" without
+//! unsealing anything. The verifier is Argon2id over the passphrase under a
+//! salt derived from the vault salt with a distinct context, so it is an
+//! independent Argon2 output: knowing it reveals nothing about the
+//! encryption key, though it does allow offline guessing at the same
+//! Argon2 cost as the sealed data itself.
+
+use std::slice;
+
+use super::*;
+
+/// Verifier length in bytes
+const VERIFIER_SIZE: usize = KEY_SIZE;
+
+const VERIFIER_SALT_CONTEXT: &str = "vault_core 2025-01 passphrase verifier salt";
+
+/// Derive the verifier for `passphrase` under the vault `salt`.
+fn derive_verifier(passphrase: &[u8], salt: &[u8]) -> VaultResult<Secret<[u8; KEY_SIZE]>> {
+    let verifier_salt = blake3::derive_key(VERIFIER_SALT_CONTEXT, salt);
+    argon2id(
+        passphrase,
+        &verifier_salt[..SALT_SIZE],
+        ARGON2_M_COST,
+        ARGON2_T_COST,
+        ARGON2_P_COST,
+    )
+}
+
+/// Derive a passphrase verifier to store alongside the vault salt.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes (the vault's key salt)
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte verifier, or error code
+#[no_mangle]
+pub unsafe extern "C" fn vault_make_verifier(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+) -> VaultBuffer {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null() || salt.is_null() || passphrase_len == 0 {
+            return VaultBuffer::error(ERR_INVALID_INPUT);
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+
+        match derive_verifier(passphrase_slice, salt_slice) {
+            Ok(verifier) => VaultBuffer::success(verifier.to_vec()),
+            Err(code) => VaultBuffer::error(code),
+        }
+    })
+}
+
+/// Check a candidate passphrase against a stored verifier.
+///
+/// Re-derives the verifier and compares in constant time.
+///
+/// # Safety
+///
+/// - `passphrase` must be valid for `passphrase_len` bytes
+/// - `salt` must point to exactly 16 bytes
+/// - `verifier` must be valid for `verifier_len` bytes (`verifier_len` must be 32)
+///
+/// # Returns
+///
+/// 0 on match, `ERR_DECRYPT_FAILED` on mismatch, or another error code
+#[no_mangle]
+pub unsafe extern "C" fn vault_check_verifier(
+    passphrase: *const u8,
+    passphrase_len: u32,
+    salt: *const u8,
+    verifier: *const u8,
+    verifier_len: u32,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if passphrase.is_null()
+            || salt.is_null()
+            || verifier.is_null()
+            || passphrase_len == 0
+            || verifier_len as usize != VERIFIER_SIZE
+        {
+            return ERR_INVALID_INPUT;
+        }
+
+        let passphrase_slice = slice::from_raw_parts(passphrase, passphrase_len as usize);
+        let salt_slice = slice::from_raw_parts(salt, SALT_SIZE);
+        let verifier_slice = slice::from_raw_parts(verifier, VERIFIER_SIZE);
+
+        match derive_verifier(passphrase_slice, salt_slice) {
+            Ok(candidate) if ct_eq(candidate.as_ref(), verifier_slice) => 0,
+            Ok(_) => ERR_DECRYPT_FAILED,
+            Err(code) => code,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verifier_check() {
+        let passphrase = b"correct horse";
+        let wrong = b"battery staple";
+        let salt = [4u8; SALT_SIZE];
+
+        unsafe {
+            let verifier = vault_make_verifier(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            assert_eq!(verifier.error, 0);
+            assert_eq!(verifier.len as usize, VERIFIER_SIZE);
+
+            let rc = vault_check_verifier(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr(), verifier.data, verifier.len);
+            assert_eq!(rc, 0);
+            let rc = vault_check_verifier(wrong.as_ptr(), wrong.len() as u32, salt.as_ptr(), verifier.data, verifier.len);
+            assert_eq!(rc, ERR_DECRYPT_FAILED);
+
+            vault_free(verifier.data, verifier.len);
+        }
+    }
+
+    #[test]
+    fn test_verifier_differs_from_key() {
+        let passphrase = b"correct horse";
+        let salt = [4u8; SALT_SIZE];
+
+        unsafe {
+            let verifier = vault_make_verifier(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            let key = vault_derive_key(passphrase.as_ptr(), passphrase.len() as u32, salt.as_ptr());
+            assert_eq!(verifier.error, 0);
+            assert_eq!(key.error, 0);
+
+            assert_ne!(
+                slice::from_raw_parts(verifier.data, verifier.len as usize),
+                slice::from_raw_parts(key.data, key.len as usize)
+            );
+
+            vault_free(verifier.data, verifier.len);
+            vault_free(key.data, key.len);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! WebAssembly Bindings
+//!
+//! Thin `wasm-bindgen` wrappers so the web build runs the same crypto core.
+//! JavaScript passes and receives `Uint8Array`s; failures throw an `Error`
+//! carrying the vault error code. The C-ABI functions are unchanged and
+//! remain the interface for native builds.
+//!
+//! Build with `wasm-pack build --features wasm`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::account::account_id_bytes;
+use crate::base32::base32_encode;
+use crate::kdf::argon2id_default;
+
+use super::*;
+
+/// Convert an internal result into a thrown JavaScript error.
+fn to_js<T>(result: VaultResult<T>) -> Result<T, JsError> {
+    result.map_err(|code| JsError::new(&format!("vault_core error {code}: {}", error_text(code).to_string_lossy())))
+}
+
+/// Derive a 32-byte key from a passphrase using Argon2id.
+///
+/// `salt` must be exactly 16 bytes.
+#[wasm_bindgen(js_name = deriveKey)]
+pub fn derive_key(passphrase: &[u8], salt: &[u8]) -> Result<Vec<u8>, JsError> {
+    if passphrase.is_empty() {
+        return to_js(Err(ERR_INVALID_INPUT));
+    }
+    if salt.len() != SALT_SIZE {
+        return to_js(Err(ERR_BAD_SALT_SIZE));
+    }
+    let key = to_js(argon2id_default(passphrase, salt))?;
+    Ok(key.to_vec())
+}
+
+/// Encrypt with XChaCha20-Poly1305 (same format as `vault_seal`).
+#[wasm_bindgen]
+pub fn seal(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, JsError> {
+    if key.len() != KEY_SIZE {
+        return to_js(Err(ERR_BAD_KEY_SIZE));
+    }
+    to_js(seal_blob(key, plaintext))
+}
+
+/// Decrypt data from `seal` or `vault_seal`.
+#[wasm_bindgen]
+pub fn unseal(key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, JsError> {
+    to_js(unseal_safe(key, sealed).map_err(|err| err.code()))
+}
+
+/// Generate `len` cryptographically secure random bytes.
+#[wasm_bindgen]
+pub fn random(len: u32) -> Result<Vec<u8>, JsError> {
+    let mut out = to_js(output_buffer(len as usize))?;
+    out.resize(len as usize, 0);
+    to_js(random_bytes(&mut out))?;
+    Ok(out)
+}
+
+/// Checksummed account identifier for a public key (same as `vault_account_id`).
+#[wasm_bindgen(js_name = accountId)]
+pub fn account_id(pubkey: &[u8]) -> Result<String, JsError> {
+    if pubkey.is_empty() {
+        return to_js(Err(ERR_INVALID_INPUT));
+    }
+    Ok(to_js(base32_encode(&account_id_bytes(pubkey)))?.into_iter().map(char::from).collect())
+}
@@ -0,0 +1,263 @@
+//! X25519 Key Agreement
+//!
+//! Diffie–Hellman between devices for end-to-end encrypted wallet sync.
+//! Each device generates a keypair behind a key handle and publishes only
+//! the 32-byte public key; the agreed secret is run through HKDF-SHA256
+//! straight into a new handle that seals and unseals with
+//! `vault_seal_with_handle`. Neither the private key nor the raw shared
+//! secret is ever returned.
+//!
+//! ## Sealing Key
+//!
+//! ```text
+//! HKDF-SHA256(ikm = X25519(private, peer), salt = empty,
+//!             info = "vault_core 2025-01 x25519 seal" || low pk || high pk || info)
+//! ```
+//!
+//! The two public keys are ordered bytewise, so both devices derive the
+//! same key without agreeing on roles, and the key is bound to this pair.
+//! A peer key of small order, which would make the secret all zero, is
+//! rejected.
+
+use std::slice;
+
+use curve25519_dalek::montgomery::MontgomeryPoint;
+
+use crate::keyhandle::{insert_key, key_for, KeyPurpose};
+use crate::subkey::hkdf_sha256;
+
+use super::*;
+
+/// X25519 public key size
+const X25519_PUBLIC_KEY_SIZE: usize = 32;
+
+/// HKDF info prefix for sealing keys
+const X25519_SEAL_INFO: &[u8] = b"vault_core 2025-01 x25519 seal";
+
+/// The public key for a private key.
+fn x25519_public_key(private_key: &[u8; KEY_SIZE]) -> [u8; X25519_PUBLIC_KEY_SIZE] {
+    MontgomeryPoint::mul_base_clamped(*private_key).to_bytes()
+}
+
+/// The raw shared secret, refusing the all-zero result of a small-order peer.
+fn shared_secret(private_key: &[u8; KEY_SIZE], peer: &[u8; X25519_PUBLIC_KEY_SIZE]) -> VaultResult<Secret<[u8; 32]>> {
+    let shared = Secret::new(MontgomeryPoint(*peer).mul_clamped(*private_key).to_bytes());
+    if ct_eq(shared.as_ref(), &[0u8; 32]) {
+        return Err(error_detail(ERR_INVALID_INPUT, format_args!("peer public key has small order")));
+    }
+    Ok(shared)
+}
+
+/// The sealing key both sides of the exchange derive.
+fn sealing_key(private_key: &[u8; KEY_SIZE], peer: &[u8; X25519_PUBLIC_KEY_SIZE], info: &[u8]) -> VaultResult<Zeroizing<Vec<u8>>> {
+    let shared = shared_secret(private_key, peer)?;
+    let own = x25519_public_key(private_key);
+    let (low, high) = if own <= *peer { (&own, peer) } else { (peer, &own) };
+
+    let mut full_info = Vec::with_capacity(X25519_SEAL_INFO.len() + 2 * X25519_PUBLIC_KEY_SIZE + info.len());
+    full_info.extend_from_slice(X25519_SEAL_INFO);
+    full_info.extend_from_slice(low);
+    full_info.extend_from_slice(high);
+    full_info.extend_from_slice(info);
+    Ok(Zeroizing::new(hkdf_sha256(shared.as_ref(), &[], &full_info, KEY_SIZE)?))
+}
+
+/// Generate an X25519 keypair and keep the private key behind a handle.
+///
+/// Publish the public key from `vault_x25519_public_key`.
+///
+/// # Safety
+///
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_RNG_FAILED`, or `ERR_INVALID_INPUT` for a null pointer
+#[no_mangle]
+pub unsafe extern "C" fn vault_x25519_keypair(out_handle: *mut u64) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if out_handle.is_null() {
+            return ERR_INVALID_INPUT;
+        }
+        let mut private_key = Secret::new([0u8; KEY_SIZE]);
+        if let Err(code) = random_bytes(private_key.as_mut()) {
+            return code;
+        }
+
+        *out_handle = insert_key(private_key.as_ref(), KeyPurpose::X25519, None);
+        0
+    })
+}
+
+/// The X25519 public key of the private key behind `key_handle`.
+///
+/// Any 32-byte key is a valid X25519 private key, so one imported with
+/// `vault_key_import_for` (purpose 3) works too.
+///
+/// # Safety
+///
+/// - Returned buffer must be freed with `vault_free`
+///
+/// # Returns
+///
+/// VaultBuffer containing the 32-byte public key, or `ERR_INVALID_INPUT`
+/// for an unknown handle or one that is not an X25519 key
+#[no_mangle]
+pub unsafe extern "C" fn vault_x25519_public_key(key_handle: u64) -> VaultBuffer {
+    ffi_boundary(|| {
+        let private_key = match key_for(key_handle, KeyPurpose::X25519) {
+            Ok(k) => k,
+            Err(code) => return VaultBuffer::error(code),
+        };
+        VaultBuffer::success(x25519_public_key(&private_key).to_vec())
+    })
+}
+
+/// Agree on a sealing key with a peer and keep it behind a new handle.
+///
+/// Both devices call this with their own handle and the other's public key
+/// (and the same `info`) and get handles to the same 32-byte key; see the
+/// module documentation for the derivation. `info` separates keys for
+/// different purposes, such as `"sync"`.
+///
+/// # Safety
+///
+/// - `peer_public_key` must point to exactly 32 bytes (`peer_public_key_len`
+///   must be 32)
+/// - `info` must be valid for `info_len` bytes (may be null when `info_len`
+///   is 0)
+/// - `out_handle` must be writable
+/// - The handle must be released with `vault_key_destroy`
+///
+/// # Returns
+///
+/// 0 on success, `ERR_BAD_KEY_SIZE`, or `ERR_INVALID_INPUT` for a null
+/// pointer, an unknown handle or one that is not an X25519 key, or a
+/// small-order peer key
+#[no_mangle]
+pub unsafe extern "C" fn vault_x25519_shared_secret(
+    key_handle: u64,
+    peer_public_key: *const u8,
+    peer_public_key_len: u32,
+    info: *const u8,
+    info_len: u32,
+    out_handle: *mut u64,
+) -> i32 {
+    ffi_boundary(|| {
+        // Validate inputs
+        if peer_public_key.is_null() || out_handle.is_null() || (info.is_null() && info_len != 0) {
+            return ERR_INVALID_INPUT;
+        }
+        if peer_public_key_len as usize != X25519_PUBLIC_KEY_SIZE {
+            return ERR_BAD_KEY_SIZE;
+        }
+        let mut peer = [0u8; X25519_PUBLIC_KEY_SIZE];
+        peer.copy_from_slice(slice::from_raw_parts(peer_public_key, X25519_PUBLIC_KEY_SIZE));
+        let info_slice: &[u8] = if info_len == 0 { &[] } else { slice::from_raw_parts(info, info_len as usize) };
+        let private_key = match key_for(key_handle, KeyPurpose::X25519) {
+            Ok(k) => k,
+            Err(code) => return code,
+        };
+
+        match sealing_key(&private_key, &peer, info_slice) {
+            Ok(key) => {
+                *out_handle = insert_key(&key, KeyPurpose::Seal, None);
+                0
+            }
+            Err(code) => code,
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    unsafe fn take(buffer: VaultBuffer) -> Vec<u8> {
+        assert_eq!(buffer.error, 0);
+        let bytes = slice::from_raw_parts(buffer.data, buffer.len as usize).to_vec();
+        vault_free(buffer.data, buffer.len);
+        bytes
+    }
+
+    #[test]
+    fn test_x25519_rfc7748_vector() {
+        // RFC 7748 section 6.1
+        let alice = hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob_public = hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+
+        let mut private_key = [0u8; 32];
+        private_key.copy_from_slice(&alice);
+        let mut peer = [0u8; 32];
+        peer.copy_from_slice(&bob_public);
+        assert_eq!(x25519_public_key(&private_key).to_vec(), hex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a"));
+        assert_eq!(
+            shared_secret(&private_key, &peer).unwrap().to_vec(),
+            hex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")
+        );
+    }
+
+    #[test]
+    fn test_x25519_devices_agree() {
+        let plaintext = b"wallet sync";
+
+        unsafe {
+            let (mut alice, mut bob) = (0u64, 0u64);
+            assert_eq!(vault_x25519_keypair(&mut alice), 0);
+            assert_eq!(vault_x25519_keypair(&mut bob), 0);
+            let alice_public = take(vault_x25519_public_key(alice));
+            let bob_public = take(vault_x25519_public_key(bob));
+            assert_ne!(alice_public, bob_public);
+
+            let (mut alice_key, mut bob_key, mut other_key) = (0u64, 0u64, 0u64);
+            assert_eq!(vault_x25519_shared_secret(alice, bob_public.as_ptr(), 32, b"sync".as_ptr(), 4, &mut alice_key), 0);
+            assert_eq!(vault_x25519_shared_secret(bob, alice_public.as_ptr(), 32, b"sync".as_ptr(), 4, &mut bob_key), 0);
+            assert_eq!(vault_x25519_shared_secret(bob, alice_public.as_ptr(), 32, ptr::null(), 0, &mut other_key), 0);
+
+            // Sealed by one device, opened by the other; another info is another key
+            let sealed = take(vault_seal_with_handle(alice_key, plaintext.as_ptr(), 11));
+            assert_eq!(take(vault_unseal_with_handle(bob_key, sealed.as_ptr(), sealed.len() as u32)), plaintext);
+            assert_eq!(vault_unseal_with_handle(other_key, sealed.as_ptr(), sealed.len() as u32).error, ERR_DECRYPT_FAILED);
+
+            for handle in [alice, bob, alice_key, bob_key, other_key] {
+                assert_eq!(vault_key_destroy(handle), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_x25519_rejections() {
+        unsafe {
+            let mut handle = 0u64;
+            assert_eq!(vault_x25519_keypair(&mut handle), 0);
+
+            // Small-order points give an all-zero secret
+            let mut out = 0u64;
+            let zero = [0u8; 32];
+            let mut one = [0u8; 32];
+            one[0] = 1;
+            assert_eq!(vault_x25519_shared_secret(handle, zero.as_ptr(), 32, ptr::null(), 0, &mut out), ERR_INVALID_INPUT);
+            assert_eq!(vault_x25519_shared_secret(handle, one.as_ptr(), 32, ptr::null(), 0, &mut out), ERR_INVALID_INPUT);
+
+            let peer = [0x09u8; 32];
+            assert_eq!(vault_x25519_shared_secret(handle, peer.as_ptr(), 31, ptr::null(), 0, &mut out), ERR_BAD_KEY_SIZE);
+            assert_eq!(vault_x25519_shared_secret(handle, peer.as_ptr(), 32, ptr::null(), 4, &mut out), ERR_INVALID_INPUT);
+            assert_eq!(vault_x25519_shared_secret(handle, peer.as_ptr(), 32, ptr::null(), 0, ptr::null_mut()), ERR_INVALID_INPUT);
+            assert_eq!(vault_x25519_keypair(ptr::null_mut()), ERR_INVALID_INPUT);
+
+            assert_eq!(vault_key_destroy(handle), 0);
+            assert_eq!(vault_x25519_shared_secret(handle, peer.as_ptr(), 32, ptr::null(), 0, &mut out), ERR_INVALID_INPUT);
+            assert_eq!(vault_x25519_public_key(handle).error, ERR_INVALID_INPUT);
+        }
+    }
+}
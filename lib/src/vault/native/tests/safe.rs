@@ -0,0 +1,37 @@
+//! The safe Rust API, used as another crate would use it.
+//!
+//! Run with `cargo test --test safe`.
+
+#![forbid(unsafe_code)]
+
+use vault_core::safe::{derive_key, seal, unseal};
+use vault_core::VaultError;
+
+const SALT: [u8; 16] = [0x07; 16];
+
+#[test]
+fn safe_roundtrip() {
+    let key = derive_key(b"correct horse battery staple", &SALT).unwrap();
+    assert_eq!(*key, *derive_key(b"correct horse battery staple", &SALT).unwrap());
+
+    let sealed = seal(&key, b"no unsafe here").unwrap();
+    assert_eq!(unseal(&key, &sealed).unwrap().as_slice(), b"no unsafe here");
+
+    let empty = seal(&key, b"").unwrap();
+    assert!(unseal(&key, &empty).unwrap().is_empty());
+}
+
+#[test]
+fn safe_errors() {
+    let key = [0x42u8; 32];
+    let sealed = seal(&key, b"secret").unwrap();
+
+    assert_eq!(unseal(&[0x43u8; 32], &sealed).unwrap_err(), VaultError::DecryptFailed);
+    assert_eq!(unseal(&key, &sealed[..10]).unwrap_err(), VaultError::CorruptData);
+
+    let mut unknown_format = sealed.clone();
+    unknown_format[0] = 0x7F;
+    assert_eq!(unseal(&key, &unknown_format).unwrap_err(), VaultError::UnsupportedVersion);
+
+    assert_eq!(derive_key(b"", &SALT).unwrap_err(), VaultError::InvalidInput);
+}
@@ -0,0 +1,22 @@
+//! Headless browser tests for the `wasm` feature.
+//!
+//! Run with `wasm-pack test --headless --firefox -- --features wasm`.
+
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use vault_core::wasm::{random, seal, unseal};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn seal_unseal_roundtrip() {
+    let key = random(32).unwrap();
+    let plaintext = b"Hello from the browser";
+
+    let sealed = seal(&key, plaintext).unwrap();
+    assert!(sealed.len() > plaintext.len());
+
+    let unsealed = unseal(&key, &sealed).unwrap();
+    assert_eq!(unsealed, plaintext);
+}
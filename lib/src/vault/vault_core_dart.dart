@@ -23,6 +23,21 @@ class VaultError {
   static const invalidInput = -1;
   static const decryptFailed = -2;
   static const kdfFailed = -3;
+  static const lockedOut = -4;
+  static const unsupportedVersion = -5;
+  static const corruptData = -6;
+  static const expired = -7;
+  static const io = -8;
+  static const badKeySize = -9;
+  static const badNonceSize = -10;
+  static const badSaltSize = -11;
+  static const outOfMemory = -12;
+  static const wipeFailed = -13;
+  static const rngFailed = -14;
+  static const busy = -15;
+  static const weakKey = -16;
+  static const timingLeak = -17;
+  static const internalPanic = -18;
 }
 
 /// Exception thrown by vault operations
@@ -41,6 +56,21 @@ class VaultException implements Exception {
       VaultError.decryptFailed =>
         VaultException(code, 'Decryption failed (wrong key or corrupted data)'),
       VaultError.kdfFailed => VaultException(code, 'Key derivation failed'),
+      VaultError.lockedOut => VaultException(code, 'Too many failed attempts'),
+      VaultError.unsupportedVersion => VaultException(code, 'Unsupported sealed data format'),
+      VaultError.corruptData => VaultException(code, 'Sealed data is truncated or malformed'),
+      VaultError.expired => VaultException(code, 'Sealed data has expired'),
+      VaultError.io => VaultException(code, 'File could not be read or written'),
+      VaultError.badKeySize => VaultException(code, 'Key has the wrong length'),
+      VaultError.badNonceSize => VaultException(code, 'Nonce has the wrong length'),
+      VaultError.badSaltSize => VaultException(code, 'Salt has the wrong length'),
+      VaultError.outOfMemory => VaultException(code, 'Not enough memory for key derivation'),
+      VaultError.wipeFailed => VaultException(code, 'Memory did not read back as zero after wiping'),
+      VaultError.rngFailed => VaultException(code, 'The system random number generator failed'),
+      VaultError.busy => VaultException(code, 'Another unlock is already in progress'),
+      VaultError.weakKey => VaultException(code, 'Key is a single repeated byte (likely uninitialized)'),
+      VaultError.timingLeak => VaultException(code, 'Timing self-check found input-dependent timing'),
+      VaultError.internalPanic => VaultException(code, 'Internal error (a panic was caught)'),
       _ => VaultException(code, 'Unknown error'),
     };
   }
@@ -84,6 +114,10 @@ class VaultCore {
   // XChaCha20-Poly1305 AEAD
   final _cipher = Xchacha20.poly1305Aead();
 
+  // Sealed blob format byte (matches FORMAT_XCHACHA in the Rust core).
+  // Authenticated as associated data.
+  static const _formatXChaCha = 0x01;
+
   // ===========================================================================
   // Public API
   // ===========================================================================
@@ -119,7 +153,7 @@ class VaultCore {
   /// - [key]: 32-byte key from [deriveKey]
   /// - [plaintext]: Data to encrypt
   ///
-  /// Returns: Sealed data (nonce || ciphertext || tag)
+  /// Returns: Sealed data (format || nonce || ciphertext || tag)
   Future<Uint8List> seal(Uint8List key, Uint8List plaintext) async {
     if (key.length != 32) {
       throw ArgumentError('Key must be exactly 32 bytes');
@@ -133,11 +167,13 @@ class VaultCore {
         plaintext,
         secretKey: secretKey,
         nonce: nonce,
+        aad: const [_formatXChaCha],
       );
 
-      // Format: nonce (24) || ciphertext || tag (16)
-      final result = Uint8List(nonce.length + secretBox.cipherText.length + secretBox.mac.bytes.length);
-      var offset = 0;
+      // Format: format (1) || nonce (24) || ciphertext || tag (16)
+      final result = Uint8List(1 + nonce.length + secretBox.cipherText.length + secretBox.mac.bytes.length);
+      result[0] = _formatXChaCha;
+      var offset = 1;
 
       // Copy nonce
       result.setAll(offset, nonce);
@@ -168,23 +204,27 @@ class VaultCore {
       throw ArgumentError('Key must be exactly 32 bytes');
     }
 
-    // XChaCha20 nonce is 24 bytes, MAC is 16 bytes
+    // Format byte, then XChaCha20 nonce (24 bytes), MAC is 16 bytes
+    const headerLen = 1;
     const nonceLen = 24;
     const macLen = 16;
-    final minLen = nonceLen + macLen;
+    final minLen = headerLen + nonceLen + macLen;
 
     if (sealed.length < minLen) {
       throw VaultException.fromCode(VaultError.invalidInput);
     }
+    if (sealed[0] != _formatXChaCha) {
+      throw VaultException.fromCode(VaultError.unsupportedVersion);
+    }
 
     try {
       final secretKey = SecretKey(key);
 
-      // Extract nonce (first 24 bytes)
-      final nonce = sealed.sublist(0, nonceLen);
+      // Extract nonce (24 bytes after the format byte)
+      final nonce = sealed.sublist(headerLen, headerLen + nonceLen);
 
       // Extract ciphertext (middle)
-      final cipherText = sealed.sublist(nonceLen, sealed.length - macLen);
+      final cipherText = sealed.sublist(headerLen + nonceLen, sealed.length - macLen);
 
       // Extract MAC (last 16 bytes)
       final mac = Mac(sealed.sublist(sealed.length - macLen));
@@ -198,6 +238,7 @@ class VaultCore {
       final plaintext = await _cipher.decrypt(
         secretBox,
         secretKey: secretKey,
+        aad: const [_formatXChaCha],
       );
 
       return Uint8List.fromList(plaintext);
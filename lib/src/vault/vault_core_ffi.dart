@@ -86,6 +86,21 @@ class VaultError {
   static const invalidInput = -1;
   static const decryptFailed = -2;
   static const kdfFailed = -3;
+  static const lockedOut = -4;
+  static const unsupportedVersion = -5;
+  static const corruptData = -6;
+  static const expired = -7;
+  static const io = -8;
+  static const badKeySize = -9;
+  static const badNonceSize = -10;
+  static const badSaltSize = -11;
+  static const outOfMemory = -12;
+  static const wipeFailed = -13;
+  static const rngFailed = -14;
+  static const busy = -15;
+  static const weakKey = -16;
+  static const timingLeak = -17;
+  static const internalPanic = -18;
 }
 
 /// Exception thrown by vault operations
@@ -103,6 +118,21 @@ class VaultException implements Exception {
       VaultError.invalidInput => VaultException(code, 'Invalid input'),
       VaultError.decryptFailed => VaultException(code, 'Decryption failed (wrong key or corrupted data)'),
       VaultError.kdfFailed => VaultException(code, 'Key derivation failed'),
+      VaultError.lockedOut => VaultException(code, 'Too many failed attempts'),
+      VaultError.unsupportedVersion => VaultException(code, 'Unsupported sealed data format'),
+      VaultError.corruptData => VaultException(code, 'Sealed data is truncated or malformed'),
+      VaultError.expired => VaultException(code, 'Sealed data has expired'),
+      VaultError.io => VaultException(code, 'File could not be read or written'),
+      VaultError.badKeySize => VaultException(code, 'Key has the wrong length'),
+      VaultError.badNonceSize => VaultException(code, 'Nonce has the wrong length'),
+      VaultError.badSaltSize => VaultException(code, 'Salt has the wrong length'),
+      VaultError.outOfMemory => VaultException(code, 'Not enough memory for key derivation'),
+      VaultError.wipeFailed => VaultException(code, 'Memory did not read back as zero after wiping'),
+      VaultError.rngFailed => VaultException(code, 'The system random number generator failed'),
+      VaultError.busy => VaultException(code, 'Another unlock is already in progress'),
+      VaultError.weakKey => VaultException(code, 'Key is a single repeated byte (likely uninitialized)'),
+      VaultError.timingLeak => VaultException(code, 'Timing self-check found input-dependent timing'),
+      VaultError.internalPanic => VaultException(code, 'Internal error (a panic was caught)'),
       _ => VaultException(code, 'Unknown error'),
     };
   }
@@ -226,7 +256,7 @@ class VaultCore {
   /// - [key]: 32-byte key from [deriveKey]
   /// - [plaintext]: Data to encrypt
   ///
-  /// Returns: Sealed data (nonce || ciphertext || tag)
+  /// Returns: Sealed data (format || nonce || ciphertext || tag)
   Uint8List seal(Uint8List key, Uint8List plaintext) {
     if (key.length != 32) {
       throw ArgumentError('Key must be exactly 32 bytes');
@@ -322,7 +352,10 @@ class VaultCore {
   }
 
   /// Copy data from native buffer and free it
+  ///
+  /// An empty result has a null `data` pointer and `len` 0.
   Uint8List _copyAndFree(VaultBuffer buffer) {
+    if (buffer.len == 0) return Uint8List(0);
     final data = Uint8List.fromList(
       buffer.data.asTypedList(buffer.len),
     );